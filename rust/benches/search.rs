@@ -0,0 +1,79 @@
+// Sequential against parallel search on the challenge puzzle, and both against brute
+// force to show what the deductions buy; the two branchings over a batch of generated
+// puzzles; and solving a corpus full of shuffled copies with and without the cache.
+// Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sudokusolver::{
+    Branching, CachingSolver, GeneratorOptions, Solver, SolverConfig, SolverRng, Sudoku,
+    UnsolvedSudoku,
+};
+
+fn bench_search(c: &mut Criterion) {
+    let s = include_str!("../../challenge.txt")
+        .parse::<Sudoku>()
+        .unwrap();
+    let u = UnsolvedSudoku::from(s);
+    c.bench_function("dynamic_solve", |b| b.iter(|| u.solved()));
+    c.bench_function("dynamic_solve_parallel", |b| {
+        b.iter(|| u.dynamic_solve_parallel())
+    });
+    c.bench_function("brute_force", |b| b.iter(|| u.brute_force_counting()));
+}
+
+fn bench_branching(c: &mut Criterion) {
+    let mut rng = SolverRng::new(11);
+    let puzzles: Vec<Sudoku> = (0..40)
+        .map(|_| Sudoku::generate_puzzle_with_rng(&mut rng, GeneratorOptions::default()))
+        .collect();
+    for (name, branching) in [
+        ("fewest_candidates", Branching::FewestCandidates),
+        ("rarest_candidates", Branching::RarestCandidates),
+    ] {
+        let mut config = SolverConfig::default();
+        config.branching = branching;
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                for &p in &puzzles {
+                    Solver::with_config(p.into(), config).solve();
+                }
+            })
+        });
+    }
+}
+
+fn bench_cache(c: &mut Criterion) {
+    // Ten puzzles, each in ten shuffled copies, as a batch of user submissions might be;
+    // and ten copies of the challenge puzzle, the hardest search at hand.
+    let mut rng = SolverRng::new(12);
+    let generated: Vec<Sudoku> = (0..10)
+        .flat_map(|_| {
+            let p = Sudoku::generate_puzzle_with_rng(&mut rng, GeneratorOptions::default());
+            return (0..10).map(move |seed| p.shuffle(seed)).collect::<Vec<_>>();
+        })
+        .collect();
+    let challenge = include_str!("../../challenge.txt")
+        .parse::<Sudoku>()
+        .unwrap();
+    let hard: Vec<Sudoku> = (0..10).map(|seed| challenge.shuffle(seed)).collect();
+    for (name, puzzles) in [("near_duplicates", generated), ("hard_duplicates", hard)] {
+        c.bench_function(&format!("{}_uncached", name), |b| {
+            b.iter(|| {
+                for p in &puzzles {
+                    let _ = p.solve();
+                }
+            })
+        });
+        c.bench_function(&format!("{}_cached", name), |b| {
+            b.iter(|| {
+                let mut solver = CachingSolver::new();
+                for p in &puzzles {
+                    let _ = solver.solve(p);
+                }
+            })
+        });
+    }
+}
+
+criterion_group!(benches, bench_search, bench_branching, bench_cache);
+criterion_main!(benches);
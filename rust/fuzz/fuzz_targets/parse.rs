@@ -0,0 +1,13 @@
+// Any input either parses or is rejected with an error; it never panics.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sudokusolver::Sudoku;
+
+fuzz_target!(|data: &[u8]| {
+    let from_reader = Sudoku::from_reader(data);
+    if let Ok(text) = std::str::from_utf8(data) {
+        let parsed = text.parse::<Sudoku>();
+        assert_eq!(parsed.ok(), from_reader.ok());
+    }
+});
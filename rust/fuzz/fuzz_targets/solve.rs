@@ -0,0 +1,38 @@
+// Any solution the solver returns is a valid complete grid that keeps the puzzle's
+// clues. A consistent puzzle always has a solution. Sparse grids can need an enormous
+// number of guesses, so each search gets a budget and may give up.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sudokusolver::{ConsistentPuzzle, Sudoku, Unsolveable, UnsolvedSudoku};
+
+const BUDGET: u64 = 2000;
+
+fn check(puzzle: &Sudoku, solution: &Sudoku) {
+    assert!(puzzle.verifies(solution));
+}
+
+fuzz_target!(|input: (Sudoku, ConsistentPuzzle)| {
+    let (s, consistent) = input;
+    match UnsolvedSudoku::from(s).budgeted_solve(BUDGET).0 {
+        Some(Ok(solution)) => check(&s, &solution),
+        Some(Err(Unsolveable::MultipleSolutions(a, b))) => {
+            assert_ne!(a, b);
+            check(&s, &a);
+            check(&s, &b);
+        }
+        _ => {}
+    }
+
+    let puzzle = consistent.puzzle;
+    match UnsolvedSudoku::from(puzzle).budgeted_solve(BUDGET).0 {
+        Some(Ok(solution)) => assert_eq!(solution, consistent.solution),
+        Some(Err(Unsolveable::MultipleSolutions(a, b))) => {
+            assert_ne!(a, b);
+            check(&puzzle, &a);
+            check(&puzzle, &b);
+        }
+        Some(Err(e)) => panic!("consistent puzzle failed: {}", e),
+        None => {}
+    }
+});
@@ -0,0 +1,169 @@
+// Generating many puzzles in one run. Each puzzle is written out as soon as it is made
+// and only tallied in the report, so a large batch never sits in memory.
+//
+// Puzzles are written one per line in the SDM layout: 81 digits in row-major order,
+// 0 for blanks.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::generate::generate_counting;
+use crate::{rate, Difficulty, GeneratorOptions, Seed, SolverRng, Sudoku};
+
+// A summary of a generate_batch run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct GenerationReport {
+    pub puzzles: usize,
+    // How many puzzles had each number of clues.
+    pub clue_counts: BTreeMap<usize, usize>,
+    // How many puzzles were rated in each band.
+    pub difficulties: BTreeMap<Difficulty, usize>,
+    // Clue removals tried over all puzzles, each costing a uniqueness check.
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl GenerationReport {
+    pub fn mean_clues(&self) -> f64 {
+        let total: usize = self.clue_counts.iter().map(|(n, k)| n * k).sum();
+        return total as f64 / self.puzzles.max(1) as f64;
+    }
+
+    pub fn mean_attempts(&self) -> f64 {
+        return self.attempts as f64 / self.puzzles.max(1) as f64;
+    }
+}
+
+impl fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} puzzles in {:.2?}", self.puzzles, self.elapsed)?;
+        writeln!(f, "clues (mean {:.1}):", self.mean_clues())?;
+        for (clues, n) in &self.clue_counts {
+            writeln!(f, "  {:2}: {}", clues, n)?;
+        }
+        writeln!(f, "difficulty:")?;
+        for (difficulty, n) in &self.difficulties {
+            writeln!(f, "  {}: {}", difficulty, n)?;
+        }
+        return write!(f, "removals tried per puzzle: {:.1}", self.mean_attempts());
+    }
+}
+
+// Generate count puzzles from one SolverRng made from the seed, writing each to out as
+// an SDM line, and report on them. The same seed and options give the same puzzles
+// and, apart from the time taken, the same report.
+pub fn generate_batch(
+    seed: impl Into<Seed>,
+    count: usize,
+    options: GeneratorOptions,
+    mut out: impl io::Write,
+) -> io::Result<GenerationReport> {
+    let start = Instant::now();
+    let mut rng = SolverRng::new(seed);
+    let mut report = GenerationReport::default();
+    for _ in 0..count {
+        let (puzzle, attempts) = generate_counting(&mut rng, options);
+        write_sdm_line(&mut out, &puzzle)?;
+        let rating = rate(&puzzle).expect("generated puzzles have one solution");
+        report.puzzles += 1;
+        *report.clue_counts.entry(puzzle.clue_count()).or_default() += 1;
+        *report.difficulties.entry(rating.difficulty()).or_default() += 1;
+        report.attempts += attempts as u64;
+    }
+    out.flush()?;
+    report.elapsed = start.elapsed();
+    return Ok(report);
+}
+
+fn write_sdm_line(out: &mut impl io::Write, s: &Sudoku) -> io::Result<()> {
+    let line: String = s.cells().map(|(_, v)| char::from(b'0' + v)).collect();
+    return writeln!(out, "{}", line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Symmetry;
+
+    #[test]
+    fn test_report_snapshot() {
+        let mut out = Vec::new();
+        let mut report = generate_batch(7, 20, GeneratorOptions::default(), &mut out).unwrap();
+        report.elapsed = Duration::ZERO;
+        let expected = "\
+20 puzzles in 0.00ns
+clues (mean 24.6):
+  23: 2
+  24: 8
+  25: 7
+  26: 2
+  28: 1
+difficulty:
+  easy: 2
+  medium: 8
+  expert: 10
+removals tried per puzzle: 81.0";
+        assert_eq!(report.to_string(), expected);
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 20);
+        // The lines are the puzzles the rng makes, in order.
+        let mut rng = SolverRng::new(7);
+        for line in lines {
+            let expected = Sudoku::generate_puzzle_with_rng(&mut rng, GeneratorOptions::default());
+            let digits: String = expected
+                .cells()
+                .map(|(_, v)| char::from(b'0' + v))
+                .collect();
+            assert_eq!(line, digits);
+        }
+
+        let mut again = generate_batch(7, 20, GeneratorOptions::default(), io::sink()).unwrap();
+        again.elapsed = Duration::ZERO;
+        assert_eq!(again, report);
+    }
+
+    #[test]
+    fn test_attempts() {
+        // With no target and no symmetry every clue is tried once.
+        let plain = generate_batch(7, 5, GeneratorOptions::default(), io::sink()).unwrap();
+        assert_eq!(plain.attempts, 5 * 81);
+        // A target stops the removals early, after however many it took to get there,
+        // and under symmetry a pair that can't go is tried again from its other cell, so
+        // either way the count depends on the puzzle.
+        let attempts = |options| {
+            return (0..6)
+                .map(|seed| {
+                    generate_batch(seed, 1, options, io::sink())
+                        .unwrap()
+                        .attempts
+                })
+                .collect::<Vec<u64>>();
+        };
+        let target = GeneratorOptions {
+            target_clues: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(attempts(target), [53, 60, 55, 51, 62, 53]);
+        let symmetric = GeneratorOptions {
+            symmetry: Symmetry::Rotational,
+            ..Default::default()
+        };
+        assert_eq!(attempts(symmetric), [54, 55, 55, 54, 54, 55]);
+        let report = generate_batch(0, 6, symmetric, io::sink()).unwrap();
+        assert!(report
+            .to_string()
+            .ends_with("removals tried per puzzle: 53.8"));
+    }
+
+    #[test]
+    fn test_empty_batch() {
+        let report = generate_batch(0, 0, GeneratorOptions::default(), io::sink()).unwrap();
+        assert_eq!(report.puzzles, 0);
+        assert_eq!((report.mean_clues(), report.mean_attempts()), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,165 @@
+// The bench subcommand: time solving a whole file of puzzles several times over, for
+// comparing machines, builds, and search engines on puzzles of the user's choosing.
+// The criterion benches in benches/ are for working on the solver itself; this needs
+// nothing but the binary. Every iteration solves the same puzzles the same way, so
+// only the times differ between them.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use sudokusolver::{solve_each, text_lines, CorpusStats, SolverConfig, Sudoku};
+
+use crate::cli::{BenchArgs, ENGINES};
+use crate::{duration_text, read_text};
+
+// What a run found, as the text and --json both report it, the JSON fields in this
+// order. The outcomes and guesses are those of one iteration.
+// Without the json feature the text reads only some of the fields.
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+#[cfg_attr(not(feature = "json"), allow(dead_code))]
+struct Summary<'a> {
+    file: &'a str,
+    puzzles: usize,
+    iterations: u32,
+    warmup: u32,
+    engine: &'static str,
+    fastest_ns: u64,
+    mean_ns: u64,
+    stddev_ns: u64,
+    unique: usize,
+    multiple: usize,
+    no_solution: usize,
+    guesses: u64,
+    max_guesses: u64,
+}
+
+pub fn bench(args: BenchArgs) -> Result<(), io::Error> {
+    let puzzles = read_corpus(&read_text(&args.file)?)?;
+    let mut config = SolverConfig::default();
+    config.branching = args.branching;
+    for _ in 0..args.warmup {
+        solve_each(&puzzles, config);
+    }
+    let runs: Vec<CorpusStats> = (0..args.iterations)
+        .map(|_| solve_each(&puzzles, config))
+        .collect();
+    let times: Vec<Duration> = runs.iter().map(|r| r.elapsed).collect();
+    let mut total = CorpusStats::default();
+    for r in &runs {
+        total.merge(r);
+    }
+    let n = args.iterations as usize;
+    let (fastest, mean, stddev) = spread(&times);
+    let summary = Summary {
+        file: &args.file,
+        puzzles: puzzles.len(),
+        iterations: args.iterations,
+        warmup: args.warmup,
+        engine: ENGINES
+            .iter()
+            .find(|&&(_, b)| b == args.branching)
+            .map_or("fewest", |&(name, _)| name),
+        fastest_ns: fastest.as_nanos() as u64,
+        mean_ns: mean.as_nanos() as u64,
+        stddev_ns: stddev.as_nanos() as u64,
+        unique: total.unique / n,
+        multiple: total.multiple / n,
+        no_solution: total.unsolvable / n,
+        guesses: total.total_guesses / n as u64,
+        max_guesses: total.max_guesses,
+    };
+    let mut out = io::stdout().lock();
+    // Without the json feature, parsing refuses --json.
+    #[cfg(feature = "json")]
+    if args.json {
+        serde_json::to_writer(&mut out, &summary)?;
+        return writeln!(out);
+    }
+    writeln!(
+        out,
+        "{} puzzles, {} iterations after {} warm-up, engine {}",
+        summary.puzzles, summary.iterations, summary.warmup, summary.engine
+    )?;
+    writeln!(
+        out,
+        "time: fastest {}, mean {}, stddev {}",
+        duration_text(fastest),
+        duration_text(mean),
+        duration_text(stddev)
+    )?;
+    return writeln!(
+        out,
+        "each iteration: {} unique, {} multiple solutions, {} no solution; {} guesses, at most {} on one puzzle",
+        summary.unique,
+        summary.multiple,
+        summary.no_solution,
+        summary.guesses,
+        summary.max_guesses
+    );
+}
+
+// One puzzle per non-blank line, as 81 cells.
+fn read_corpus(text: &str) -> Result<Vec<Sudoku>, io::Error> {
+    let puzzles = text_lines(text)
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(ix, l)| {
+            return Sudoku::try_from(l.trim().as_bytes()).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("line {}: {}", ix + 1, e),
+                )
+            });
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if puzzles.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No puzzles to time.",
+        ));
+    }
+    return Ok(puzzles);
+}
+
+// The fastest time, the mean, and the population standard deviation.
+fn spread(times: &[Duration]) -> (Duration, Duration, Duration) {
+    let secs: Vec<f64> = times.iter().map(Duration::as_secs_f64).collect();
+    let n = secs.len() as f64;
+    let mean = secs.iter().sum::<f64>() / n;
+    let variance = secs.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n;
+    return (
+        times.iter().min().copied().unwrap_or_default(),
+        Duration::from_secs_f64(mean),
+        Duration::from_secs_f64(variance.sqrt()),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread() {
+        let ms = Duration::from_millis;
+        let (fastest, mean, stddev) = spread(&[ms(4), ms(2), ms(6)]);
+        assert_eq!(fastest, ms(2));
+        assert!(mean.abs_diff(ms(4)) < Duration::from_nanos(10));
+        // sqrt(8/3) ms.
+        assert!(stddev.abs_diff(Duration::from_micros(1633)) < Duration::from_micros(1));
+        assert_eq!(spread(&[ms(3)]).2, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_read_corpus() {
+        let line = "0".repeat(81);
+        assert_eq!(
+            read_corpus(&format!("{}\n\n {} \n", line, line))
+                .unwrap()
+                .len(),
+            2
+        );
+        let err = read_corpus(&format!("{}\n12\n", line)).unwrap_err();
+        assert!(err.to_string().starts_with("line 2: "));
+        assert!(read_corpus("\n").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+// A solver that remembers what it has solved, for batch jobs full of puzzles that are
+// the same up to the moves in transform.rs. Each puzzle is looked up by its canonical
+// form, so a turned, shuffled, or relabelled copy of an earlier puzzle costs only the
+// canonicalizing, not a search; the stored solution is carried back to the copy's own
+// layout.
+//
+// Canonicalizing costs about a millisecond, though, which is several times what this
+// solver's search takes on a typical puzzle or even the challenge one (see the
+// near_duplicates and hard_duplicates benches). The cache only pays off where a
+// search is dearer than that.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+
+use crate::{Sudoku, Unsolveable};
+
+#[derive(Clone, Debug, Default)]
+pub struct CachingSolver {
+    // How solving each canonical form seen so far came out, keyed by its rows.
+    solved: BTreeMap<[[u8; 9]; 9], Result<Sudoku, Unsolveable>>,
+    hits: usize,
+}
+
+impl CachingSolver {
+    pub fn new() -> CachingSolver {
+        return CachingSolver::default();
+    }
+
+    // The same answer as puzzle.solve(), searched for only if no equivalent puzzle has
+    // been solved before. For a puzzle with several solutions, the two reported may
+    // differ from the ones solve finds.
+    pub fn solve(&mut self, puzzle: &Sudoku) -> Result<Sudoku, Unsolveable> {
+        let (canonical, map) = puzzle.canonical_form_with_map();
+        let result = match self.solved.get(&canonical.rows) {
+            Some(result) => {
+                self.hits += 1;
+                result.clone()
+            }
+            None => {
+                let result = canonical.solve();
+                self.solved.insert(canonical.rows, result.clone());
+                result
+            }
+        };
+        return match result {
+            Ok(solution) => Ok(map.undo(&solution)),
+            Err(Unsolveable::MultipleSolutions(a, b)) => Err(Unsolveable::MultipleSolutions(
+                Box::new(map.undo(&a)),
+                Box::new(map.undo(&b)),
+            )),
+            Err(e) => Err(e),
+        };
+    }
+
+    // How many puzzles, counting equivalent ones once, have been solved.
+    pub fn len(&self) -> usize {
+        return self.solved.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.solved.is_empty();
+    }
+
+    // How many solve calls were answered from the cache.
+    pub fn hits(&self) -> usize {
+        return self.hits;
+    }
+
+    // Forget every puzzle solved so far, and reset the hit count.
+    pub fn clear(&mut self) {
+        self.solved.clear();
+        self.hits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_equivalent_puzzles() {
+        let s = moderate();
+        let mut solver = CachingSolver::new();
+        assert!(solver.is_empty());
+        assert_eq!(solver.solve(&s), s.solve());
+        let copies = [
+            s.rotate180(),
+            s.mirror_horizontal().relabel([9, 8, 7, 6, 5, 4, 3, 2, 1]),
+            s.swap_bands(1, 3)
+                .swap_rows_within_band(2, 1, 2)
+                .transpose(),
+        ];
+        for t in copies {
+            let solution = solver.solve(&t).unwrap();
+            assert_eq!(solution, t.solve().unwrap(), "{:?}", t);
+        }
+        assert_eq!(solver.len(), 1);
+        assert_eq!(solver.hits(), 3);
+
+        let challenge: Sudoku = include_str!("../../challenge.txt").parse().unwrap();
+        assert_eq!(solver.solve(&challenge), challenge.solve());
+        assert_eq!(solver.len(), 2);
+        solver.clear();
+        assert!(solver.is_empty());
+        assert_eq!(solver.hits(), 0);
+    }
+
+    #[test]
+    fn test_unsolvable_puzzles() {
+        let mut solver = CachingSolver::new();
+        // Clearing clues from the top until there is more than one solution.
+        let mut open = moderate();
+        while open.count_solutions(2) < 2 {
+            let (c, _) = open.cells().find(|&(_, v)| v != 0).unwrap();
+            open.clear(c);
+        }
+        for t in [open, open.rotate90().relabel([2, 3, 4, 5, 6, 7, 8, 9, 1])] {
+            match solver.solve(&t) {
+                Err(Unsolveable::MultipleSolutions(a, b)) => {
+                    assert_ne!(a, b);
+                    assert!(t.verifies(&a) && t.verifies(&b), "{:?}", t);
+                }
+                other => panic!("expected two solutions, got {:?}", other),
+            }
+        }
+        assert_eq!(solver.hits(), 1);
+
+        let mut clash = Sudoku::empty();
+        clash.rows[0][0] = 5;
+        clash.rows[0][8] = 5;
+        assert_eq!(solver.solve(&clash), Err(Unsolveable::NoSolution));
+        assert_eq!(
+            solver.solve(&clash.transpose()),
+            Err(Unsolveable::NoSolution)
+        );
+        assert_eq!(solver.hits(), 2);
+    }
+}
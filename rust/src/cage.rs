@@ -0,0 +1,311 @@
+// Killer sudoku cages: groups of cells whose values must add up to a given sum, with no
+// value repeated inside the cage. A cage displays as its sum, a colon, and its cells,
+// like "15: r1c1 r1c2 r2c1", which is also how an .sdk file's [Cages] section lists
+// them.
+//
+// Solving prunes a cage's candidates by the sets of distinct digits with its sum: a
+// value stays only if some such set holds it and still has a value for every cell.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{CandidateSet, Coord, GridError};
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Cage {
+    sum: u8,
+    // In the order given.
+    cells: Vec<Coord>,
+}
+
+impl Cage {
+    // A cage of distinct cells, 1-9 of them, with a sum that some set of that many
+    // distinct digits adds up to.
+    pub fn new(sum: u8, cells: Vec<Coord>) -> Result<Cage, GridError> {
+        for (ix, &c) in cells.iter().enumerate() {
+            if cells[..ix].contains(&c) {
+                return Err(GridError::RepeatedCell(c));
+            }
+        }
+        if digit_sets(cells.len(), sum).next().is_none() {
+            return Err(GridError::CageSumOutOfRange {
+                sum,
+                cells: cells.len(),
+            });
+        }
+        return Ok(Cage { sum, cells });
+    }
+
+    pub fn sum(&self) -> u8 {
+        return self.sum;
+    }
+
+    pub fn cells(&self) -> &[Coord] {
+        return &self.cells;
+    }
+
+    // Whether the cage's values so far, with 0 for blanks, could still be completed:
+    // none repeated, and room left for the blanks, each at least 1, within the sum. A
+    // full cage must add up to the sum exactly.
+    pub(crate) fn consistent(&self, value: impl Fn(Coord) -> u8) -> bool {
+        let mut seen = CandidateSet::empty();
+        let mut total = 0u32;
+        let mut blanks = 0u32;
+        for &c in &self.cells {
+            match value(c) {
+                0 => blanks += 1,
+                v if seen.contains(v) => return false,
+                v => {
+                    seen.insert(v);
+                    total += v as u32;
+                }
+            }
+        }
+        return match blanks {
+            0 => total == self.sum as u32,
+            _ => total + blanks <= self.sum as u32,
+        };
+    }
+
+    // Every value any of the cage's cells could take, given the values each could hold
+    // so far in the cage's order (just its own for a fixed cell): the union of the sets
+    // of distinct digits with the cage's sum that have a value for every cell. Empty if
+    // the cage can't be filled at all.
+    pub(crate) fn allowed(&self, candidates: &[CandidateSet]) -> CandidateSet {
+        let all = candidates
+            .iter()
+            .fold(CandidateSet::empty(), |acc, ps| CandidateSet {
+                bits: acc.bits | ps.bits,
+            });
+        let mut allowed = CandidateSet::empty();
+        for set in digit_sets(self.cells.len(), self.sum) {
+            if set.bits & !all.bits == 0 && candidates.iter().all(|ps| ps.bits & set.bits != 0) {
+                allowed.bits |= set.bits;
+            }
+        }
+        return allowed;
+    }
+}
+
+impl fmt::Display for Cage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.sum)?;
+        for c in &self.cells {
+            write!(f, " {}", c)?;
+        }
+        return Ok(());
+    }
+}
+
+// Every set of n distinct digits 1-9 adding up to sum, smallest bitmask first.
+fn digit_sets(n: usize, sum: u8) -> impl Iterator<Item = CandidateSet> {
+    return (0..512u16)
+        .map(|m| CandidateSet { bits: m << 1 })
+        .filter(move |set| set.len() == n && set.iter().map(u32::from).sum::<u32>() == sum as u32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SdkFile, Sudoku, Unsolveable, UnsolvedSudoku};
+
+    fn c(row: u8, col: u8) -> Coord {
+        return Coord::new(row, col).unwrap();
+    }
+
+    // The only solution of the pure killer fixture.
+    const KILLER_SOLUTION: &str =
+        "571963824482157963963248715146892537827435196359716248798324651614589372235671489";
+
+    fn killer() -> SdkFile {
+        let text = include_str!("../tests/fixtures/variants/killer.sdk");
+        return text.parse().unwrap();
+    }
+
+    #[test]
+    fn test_new() {
+        let cage = Cage::new(15, vec![c(1, 1), c(1, 2), c(2, 1)]).unwrap();
+        assert_eq!(cage.sum(), 15);
+        assert_eq!(cage.cells(), [c(1, 1), c(1, 2), c(2, 1)]);
+        assert_eq!(cage.to_string(), "15: r1c1 r1c2 r2c1");
+        assert!(Cage::new(45, Coord::all().take(9).collect()).is_ok());
+        assert_eq!(
+            Cage::new(3, vec![c(1, 1), c(1, 1)]),
+            Err(GridError::RepeatedCell(c(1, 1)))
+        );
+        for (sum, n) in [(2, 2), (18, 2), (5, 0), (45, 10), (0, 1), (10, 1)] {
+            let cells: Vec<Coord> = Coord::all().take(n).collect();
+            assert_eq!(
+                Cage::new(sum, cells),
+                Err(GridError::CageSumOutOfRange { sum, cells: n }),
+                "{} in {}",
+                sum,
+                n
+            );
+        }
+        assert_eq!(
+            GridError::CageSumOutOfRange { sum: 2, cells: 2 }.to_string(),
+            "no 2 distinct digits add up to 2"
+        );
+    }
+
+    #[test]
+    fn test_digit_sets() {
+        let sets: Vec<Vec<u8>> = digit_sets(3, 7).map(|s| s.iter().collect()).collect();
+        assert_eq!(sets, [vec![1, 2, 4]]);
+        assert_eq!(digit_sets(2, 10).count(), 4);
+        assert_eq!(digit_sets(9, 45).count(), 1);
+        assert_eq!(digit_sets(1, 9).count(), 1);
+    }
+
+    #[test]
+    fn test_consistent() {
+        let cage = Cage::new(10, vec![c(1, 1), c(1, 2), c(1, 3)]).unwrap();
+        let values = |vs: [u8; 3]| move |cell: Coord| vs[cell.col() as usize - 1];
+        assert!(cage.consistent(values([0, 0, 0])));
+        assert!(cage.consistent(values([7, 0, 0])));
+        assert!(cage.consistent(values([1, 2, 7])));
+        // Too much for the blank to be at least 1.
+        assert!(!cage.consistent(values([8, 0, 2])));
+        assert!(!cage.consistent(values([1, 2, 6])));
+        assert!(!cage.consistent(values([4, 4, 2])));
+    }
+
+    #[test]
+    fn test_allowed() {
+        let set = |vs: &[u8]| vs.iter().cloned().collect::<CandidateSet>();
+        let pair = Cage::new(3, vec![c(1, 1), c(1, 2)]).unwrap();
+        let full = [CandidateSet::full(), CandidateSet::full()];
+        assert_eq!(pair.allowed(&full), set(&[1, 2]));
+
+        let cage = Cage::new(10, vec![c(1, 1), c(1, 2), c(1, 3)]).unwrap();
+        let full = [CandidateSet::full(); 3];
+        assert_eq!(cage.allowed(&full), set(&[1, 2, 3, 4, 5, 6, 7]));
+        // Of the sets adding up to 10, only {1, 3, 6} holds a fixed 6.
+        assert_eq!(
+            cage.allowed(&[set(&[6]), CandidateSet::full(), CandidateSet::full()]),
+            set(&[1, 3, 6])
+        );
+        // Every set with a 7 needs a 1 and 2 as well, which neither blank can hold.
+        assert_eq!(
+            cage.allowed(&[set(&[7]), set(&[3, 4, 5]), set(&[3, 4, 5])]),
+            CandidateSet::empty()
+        );
+        assert_eq!(
+            cage.allowed(&[set(&[]), CandidateSet::full(), CandidateSet::full()]),
+            CandidateSet::empty()
+        );
+    }
+
+    #[test]
+    fn test_pure_killer() {
+        let f = killer();
+        assert_eq!(f.givens, Sudoku::empty());
+        assert_eq!(f.cages.len(), 30);
+        assert_eq!(f.grid.cages().count(), 30);
+        let solutions: Vec<Sudoku> = f.grid.solutions().take(2).collect();
+        let expected = Sudoku::try_from(KILLER_SOLUTION.as_bytes()).unwrap();
+        assert_eq!(solutions, [expected]);
+        assert_eq!(f.grid.solved(), Ok(expected));
+        for cage in &f.cages {
+            let total: u8 = cage.cells().iter().map(|&c| expected[c]).sum();
+            assert_eq!(total, cage.sum(), "{}", cage);
+        }
+    }
+
+    // Wikipedia's example killer, drawn with its solution in the article.
+    #[test]
+    fn test_published_killer() {
+        let f: SdkFile = include_str!("../tests/fixtures/variants/killer-wikipedia.sdk")
+            .parse()
+            .unwrap();
+        assert_eq!(f.givens, Sudoku::empty());
+        assert_eq!(f.cages.len(), 29);
+        let expected = Sudoku::try_from(
+            &b"215647398368952174794381652586274931142593867973816425821739546659428713437165289"[..],
+        )
+        .unwrap();
+        let solutions: Vec<Sudoku> = f.grid.solutions().take(2).collect();
+        assert_eq!(solutions, [expected]);
+        assert_eq!(f.grid.solved(), Ok(expected));
+    }
+
+    #[test]
+    fn test_solving_with_cages() {
+        // Only 1 and 2 add up to 3; once r1c1 is 2, r1c2 must be 1.
+        let mut u = UnsolvedSudoku::empty();
+        u.add_cage(Cage::new(3, vec![c(1, 1), c(1, 2)]).unwrap());
+        u.simple_solve();
+        assert_eq!(
+            u.candidates(c(1, 2)).unwrap().iter().collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert!(u.place(1, 1, 2).is_ok());
+        assert_eq!(u.fixed_value(c(1, 2)), Some(1));
+        assert!(!u.can_place(1, 3, 1));
+
+        // A cage adding up to the wrong total makes the grid invalid once it is full.
+        let mut u = UnsolvedSudoku::empty();
+        u.add_cage(Cage::new(4, vec![c(1, 1), c(2, 1)]).unwrap());
+        let mut given = Sudoku::empty();
+        given[c(1, 1)] = 1;
+        given[c(2, 1)] = 2;
+        u.add_givens(&given);
+        assert!(!u.valid());
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+
+        // Brute force, which doesn't prune, checks the cages as it fills them in.
+        let solution = killer().grid.solved().unwrap();
+        let mut holes = solution;
+        for &cell in killer().cages[0]
+            .cells()
+            .iter()
+            .chain(killer().cages[1].cells())
+        {
+            holes.clear(cell);
+        }
+        let mut u = UnsolvedSudoku::empty();
+        for cage in killer().cages {
+            u.add_cage(cage);
+        }
+        u.add_givens(&holes);
+        assert_eq!(u.brute_force_solve(), Ok(solution));
+    }
+
+    #[test]
+    fn test_innies() {
+        // Row 1 holds cages adding up to 3 and 30, leaving 12 for r1c4 and r1c5, which
+        // two distinct digits can only make as 3 + 9, 4 + 8, or 5 + 7. Column 1 and
+        // square 1 leave cells over too; row 2 holds no cage.
+        let mut u = UnsolvedSudoku::empty();
+        u.add_cage(Cage::new(3, vec![c(1, 1), c(1, 2)]).unwrap());
+        let right = vec![c(1, 3), c(1, 6), c(1, 7), c(1, 8), c(1, 9)];
+        u.add_cage(Cage::new(30, right).unwrap());
+        u.add_cage(Cage::new(17, vec![c(2, 1), c(3, 1)]).unwrap());
+        let column = vec![
+            c(1, 1),
+            c(4, 1),
+            c(5, 1),
+            c(6, 1),
+            c(7, 1),
+            c(8, 1),
+            c(9, 1),
+        ];
+        let square = vec![c(1, 3), c(2, 2), c(2, 3), c(3, 2), c(3, 3)];
+        assert_eq!(
+            u.layout.innies,
+            [
+                Cage::new(12, vec![c(1, 4), c(1, 5)]).unwrap(),
+                Cage::new(28, column).unwrap(),
+                Cage::new(25, square).unwrap(),
+            ]
+        );
+        u.simple_solve();
+        let ps: Vec<u8> = u.candidates(c(1, 4)).unwrap().iter().collect();
+        assert_eq!(ps, [3, 4, 5, 7, 8, 9]);
+
+        // A cell in two cages turns the rule off.
+        u.add_cage(Cage::new(10, vec![c(1, 1), c(2, 2)]).unwrap());
+        assert_eq!(u.layout.innies, []);
+    }
+}
@@ -0,0 +1,346 @@
+// A canonical representative for each class of equivalent puzzles. Two grids are
+// equivalent when one can be turned into the other by the moves in transform.rs:
+// reordering bands, rows within a band, stacks, and columns within a stack, transposing,
+// and relabelling the digits. The canonical form is the equivalent grid that comes
+// first when read in row-major order with blanks as 0.
+//
+// Trying all 3.4 million layouts for each grid is far too slow, so the form is built one
+// row at a time instead. Each layout is a choice of column order and transpose, then of
+// a source row for each output row in turn, and for a fixed choice the smallest digit
+// labelling is simply first come, first numbered. At each row, only the layouts that tie
+// for the smallest rows so far are kept, and layouts whose unplaced rows would come out
+// the same are merged.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::Sudoku;
+
+// A layout chosen up to some row.
+#[derive(Clone, Copy)]
+struct Partial {
+    // 0 for the grid as given, 1 for its transpose.
+    source: usize,
+    // The source column of each output column.
+    cols: [u8; 9],
+    // The source rows of the output rows placed so far.
+    rows: [u8; 9],
+    placed: usize,
+    // labels[d] is the new digit for source digit d, or 0 until d first appears.
+    labels: [u8; 10],
+    next_label: u8,
+}
+
+impl Partial {
+    fn uses(&self, row: u8) -> bool {
+        return self.rows[..self.placed].contains(&row);
+    }
+
+    // The source rows that may come next: the rest of the current band, or at the start
+    // of a band, any row of a band not yet used.
+    fn next_rows(&self) -> impl Iterator<Item = u8> + '_ {
+        let band = match self.placed % 3 {
+            0 => None,
+            _ => Some(self.rows[self.placed - 1] / 3),
+        };
+        return (0..9u8).filter(move |&r| match band {
+            Some(b) => r / 3 == b && !self.uses(r),
+            None => (0..self.placed).all(|i| self.rows[i] / 3 != r / 3),
+        });
+    }
+
+    // Place source row `row` next, returning it as it appears in the output.
+    fn place(&mut self, grid: &[[u8; 9]; 9], row: u8) -> [u8; 9] {
+        let mut out = [0; 9];
+        for (o, &c) in out.iter_mut().zip(self.cols.iter()) {
+            let d = grid[row as usize][c as usize] as usize;
+            if d != 0 && self.labels[d] == 0 {
+                self.labels[d] = self.next_label;
+                self.next_label += 1;
+            }
+            *o = self.labels[d];
+        }
+        self.rows[self.placed] = row;
+        self.placed += 1;
+        return out;
+    }
+
+    // Everything the rest of the form depends on: the unplaced rows as they would appear,
+    // grouped as the row order allows, with digits not yet labelled kept distinct from
+    // labelled ones. Layouts with the same key finish the same way.
+    fn key(&self, grid: &[[u8; 9]; 9]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(100);
+        key.push(self.next_label);
+        let push_row = |key: &mut Vec<u8>, r: u8| {
+            for &c in &self.cols {
+                let d = grid[r as usize][c as usize] as usize;
+                key.push(match (d, self.labels[d]) {
+                    (0, _) => 0,
+                    (_, 0) => 10 + d as u8,
+                    (_, l) => l,
+                });
+            }
+        };
+        if !self.placed.is_multiple_of(3) {
+            for r in self.next_rows() {
+                push_row(&mut key, r);
+            }
+            key.push(u8::MAX);
+        }
+        for r in 0..9u8 {
+            let band_used = (0..self.placed).any(|i| self.rows[i] / 3 == r / 3);
+            if !band_used {
+                push_row(&mut key, r);
+            }
+        }
+        return key;
+    }
+}
+
+// All 1296 column orders that keep each stack of three together.
+fn column_orders() -> Vec<[u8; 9]> {
+    const PERMS: [[u8; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut orders = Vec::with_capacity(1296);
+    for stacks in PERMS {
+        for a in PERMS {
+            for b in PERMS {
+                for c in PERMS {
+                    let mut cols = [0; 9];
+                    for (i, within) in [a, b, c].iter().enumerate() {
+                        for (j, &w) in within.iter().enumerate() {
+                            cols[i * 3 + j] = stacks[i] * 3 + w;
+                        }
+                    }
+                    orders.push(cols);
+                }
+            }
+        }
+    }
+    return orders;
+}
+
+impl Sudoku {
+    // The first grid, in row-major order with blanks as 0, among all those this one can
+    // be turned into by reordering bands, stacks, and the rows and columns within them,
+    // transposing, and relabelling digits. Equivalent puzzles have the same canonical
+    // form, and the form is equivalent to the original, so it keeps the same number of
+    // clues and solutions.
+    pub fn canonical_form(&self) -> Sudoku {
+        return self.canonical_form_with_map().0;
+    }
+
+    // The canonical form, along with the layout that turns this grid into it.
+    pub(crate) fn canonical_form_with_map(&self) -> (Sudoku, CanonicalMap) {
+        let grids = [self.rows, self.transpose().rows];
+        let mut tied: Vec<Partial> = Vec::new();
+        for cols in column_orders() {
+            for source in 0..2 {
+                tied.push(Partial {
+                    source,
+                    cols,
+                    rows: [0; 9],
+                    placed: 0,
+                    labels: [0; 10],
+                    next_label: 1,
+                });
+            }
+        }
+
+        let mut out = Sudoku::empty();
+        for out_row in out.rows.iter_mut() {
+            // Above any real row, so the first candidate always wins.
+            let mut best = [u8::MAX; 9];
+            let mut next = Vec::new();
+            for p in &tied {
+                for row in p.next_rows() {
+                    let mut q = *p;
+                    let line = q.place(&grids[q.source], row);
+                    if line < best {
+                        best = line;
+                        next.clear();
+                    }
+                    if line == best {
+                        next.push(q);
+                    }
+                }
+            }
+            *out_row = best;
+            let merged: BTreeMap<Vec<u8>, Partial> = next
+                .into_iter()
+                .map(|p| (p.key(&grids[p.source]), p))
+                .collect();
+            tied = merged.into_values().collect();
+        }
+        // Every layout left gives the same form; digits the grid lacks get the labels
+        // left over, in order.
+        let p = tied[0];
+        let mut labels = p.labels;
+        let unlabelled = labels[1..].iter_mut().filter(|l| **l == 0);
+        for (next_label, label) in (p.next_label..).zip(unlabelled) {
+            *label = next_label;
+        }
+        let map = CanonicalMap {
+            transposed: p.source == 1,
+            rows: p.rows,
+            cols: p.cols,
+            labels,
+        };
+        return (out, map);
+    }
+}
+
+// How canonical_form_with_map laid a grid out: which of its rows and columns, or its
+// transpose's, went to each place, and what each digit became.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CanonicalMap {
+    transposed: bool,
+    rows: [u8; 9],
+    cols: [u8; 9],
+    labels: [u8; 10],
+}
+
+impl CanonicalMap {
+    // Carry a grid in the canonical layout, like the canonical form's solution, back to
+    // the original grid's layout and digits.
+    pub(crate) fn undo(&self, grid: &Sudoku) -> Sudoku {
+        let mut digits = [0; 10];
+        for d in 1..10u8 {
+            digits[self.labels[d as usize] as usize] = d;
+        }
+        let mut source = Sudoku::empty();
+        for (&r, row) in self.rows.iter().zip(grid.rows.iter()) {
+            for (&c, &v) in self.cols.iter().zip(row.iter()) {
+                source.rows[r as usize][c as usize] = digits[v as usize];
+            }
+        }
+        return match self.transposed {
+            true => source.transpose(),
+            false => source,
+        };
+    }
+}
+
+// Whether one grid can be turned into the other by the moves canonical_form allows.
+pub fn are_isomorphic(a: &Sudoku, b: &Sudoku) -> bool {
+    return a.canonical_form() == b.canonical_form();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coord;
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_transformed_copies() {
+        let s = moderate();
+        let canonical = s.canonical_form();
+        let copies = [
+            s.rotate90(),
+            s.mirror_vertical().relabel([9, 8, 7, 6, 5, 4, 3, 2, 1]),
+            s.swap_bands(1, 3)
+                .swap_stacks(2, 3)
+                .swap_rows_within_band(2, 1, 3)
+                .swap_cols_within_stack(1, 1, 2)
+                .transpose()
+                .relabel([2, 7, 1, 8, 9, 3, 6, 4, 5]),
+        ];
+        for t in copies {
+            assert_eq!(t.canonical_form(), canonical, "{:?}", t);
+            assert!(are_isomorphic(&s, &t));
+        }
+        assert_eq!(canonical.canonical_form(), canonical);
+        assert_eq!(canonical.clue_count(), s.clue_count());
+        assert_eq!(canonical.count_solutions(2), 1);
+
+        let solution = s.solve().unwrap();
+        assert!(are_isomorphic(&solution, &solution.rotate270()));
+        assert_eq!(
+            solution.canonical_form().to_string().lines().next(),
+            Some("123456789")
+        );
+    }
+
+    #[test]
+    fn test_random_transforms() {
+        // A small xorshift, so the test doesn't need a random number crate.
+        let mut x: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = |n: u64| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            return (x % n) as u8;
+        };
+        for seed in 0..4 {
+            let s = Sudoku::generate_puzzle(seed, Default::default());
+            let mut t = s;
+            for _ in 0..20 {
+                t = match next(5) {
+                    0 => t.transpose(),
+                    1 => t.swap_bands(next(3) + 1, next(3) + 1),
+                    2 => t.swap_stacks(next(3) + 1, next(3) + 1),
+                    3 => t.swap_rows_within_band(next(3) + 1, next(3) + 1, next(3) + 1),
+                    _ => t.swap_cols_within_stack(next(3) + 1, next(3) + 1, next(3) + 1),
+                };
+            }
+            let t = t.relabel([4, 9, 2, 3, 5, 7, 8, 1, 6]);
+            assert_eq!(s.canonical_form(), t.canonical_form(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn test_undo() {
+        let s = moderate();
+        let copies = [
+            s,
+            s.rotate90().relabel([3, 1, 4, 5, 9, 2, 6, 8, 7]),
+            s.transpose()
+                .swap_bands(1, 2)
+                .swap_cols_within_stack(3, 1, 3),
+        ];
+        for t in copies {
+            let (canonical, map) = t.canonical_form_with_map();
+            assert_eq!(map.undo(&canonical), t);
+            let back = map.undo(&canonical.solve().unwrap());
+            assert!(t.verifies(&back), "{:?}", t);
+            assert_eq!(back, t.solve().unwrap());
+        }
+        // A grid missing some digits still undoes to itself.
+        let mut one = Sudoku::empty();
+        one.set(Coord::new(2, 5).unwrap(), 7).unwrap();
+        let (canonical, map) = one.canonical_form_with_map();
+        assert_eq!(map.undo(&canonical), one);
+        assert_eq!(map.undo(&Sudoku::empty()), Sudoku::empty());
+    }
+
+    #[test]
+    fn test_different_puzzles() {
+        let challenge: Sudoku = include_str!("../../challenge.txt").parse().unwrap();
+        assert!(!are_isomorphic(&moderate(), &challenge));
+        // One clue fewer is never equivalent.
+        let mut fewer = moderate();
+        fewer.clear(Coord::new(9, 5).unwrap());
+        assert!(!are_isomorphic(&moderate(), &fewer));
+    }
+
+    #[test]
+    fn test_sparse_grids() {
+        assert_eq!(Sudoku::empty().canonical_form(), Sudoku::empty());
+        // A lone clue goes as late as possible, and becomes a 1.
+        let mut one = Sudoku::empty();
+        one.set(Coord::new(2, 5).unwrap(), 7).unwrap();
+        let mut expected = Sudoku::empty();
+        expected.set(Coord::new(9, 9).unwrap(), 1).unwrap();
+        assert_eq!(one.canonical_form(), expected);
+    }
+}
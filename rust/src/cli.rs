@@ -0,0 +1,1906 @@
+// The sudokusolver program's command line. This only turns the arguments into a
+// Command, checking them as it goes; main.rs carries the Command out. Each subcommand
+// takes its own options, and anything not understood is an error that comes with the
+// subcommand's usage, never silently ignored.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use sudokusolver::{BoxShape, Branching, Difficulty, GeneratorOptions, Symmetry, Variant};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Subcommand {
+    Solve,
+    Check,
+    Generate,
+    Rate,
+    Convert,
+    Hint,
+    Mask,
+    Interactive,
+    Bench,
+    Compare,
+    Transform,
+    Completions,
+}
+
+impl Subcommand {
+    pub const ALL: [Subcommand; 12] = [
+        Subcommand::Solve,
+        Subcommand::Check,
+        Subcommand::Generate,
+        Subcommand::Rate,
+        Subcommand::Convert,
+        Subcommand::Hint,
+        Subcommand::Mask,
+        Subcommand::Interactive,
+        Subcommand::Bench,
+        Subcommand::Compare,
+        Subcommand::Transform,
+        Subcommand::Completions,
+    ];
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            Subcommand::Solve => "solve",
+            Subcommand::Check => "check",
+            Subcommand::Generate => "generate",
+            Subcommand::Rate => "rate",
+            Subcommand::Convert => "convert",
+            Subcommand::Hint => "hint",
+            Subcommand::Mask => "mask",
+            Subcommand::Interactive => "interactive",
+            Subcommand::Bench => "bench",
+            Subcommand::Compare => "compare",
+            Subcommand::Transform => "transform",
+            Subcommand::Completions => "completions",
+        };
+    }
+
+    // edit is an older name for interactive, kept so scripts using it still work.
+    fn from_name(name: &str) -> Option<Subcommand> {
+        if name == "edit" {
+            return Some(Subcommand::Interactive);
+        }
+        return Subcommand::ALL.into_iter().find(|s| s.name() == name);
+    }
+
+    // One line on what the subcommand does, for completions and the full usage.
+    pub fn summary(self) -> &'static str {
+        return match self {
+            Subcommand::Solve => "solve puzzles",
+            Subcommand::Check => "check a grid against the rules",
+            Subcommand::Generate => "make new puzzles",
+            Subcommand::Rate => "rate how hard puzzles are",
+            Subcommand::Convert => "print puzzles in another format",
+            Subcommand::Hint => "list the cells singles force",
+            Subcommand::Mask => "make a puzzle with clues in a pattern",
+            Subcommand::Interactive => "work on a puzzle interactively",
+            Subcommand::Bench => "time solving a file of puzzles",
+            Subcommand::Compare => "check solutions against expected grids",
+            Subcommand::Transform => "rotate, relabel, or shuffle a puzzle",
+            Subcommand::Completions => "print a shell completion script",
+        };
+    }
+
+    // Every option the subcommand takes besides -h and --help. Parsing refuses any
+    // other, and the usage and completions are made from this, so none of the three
+    // can have an option the others lack.
+    pub fn flags(self) -> &'static [Flag] {
+        return match self {
+            Subcommand::Solve => SOLVE_FLAGS,
+            Subcommand::Check => CHECK_FLAGS,
+            Subcommand::Generate => GENERATE_FLAGS,
+            Subcommand::Convert => CONVERT_FLAGS,
+            Subcommand::Mask => MASK_FLAGS,
+            Subcommand::Bench => BENCH_FLAGS,
+            Subcommand::Compare => COMPARE_FLAGS,
+            Subcommand::Transform => TRANSFORM_FLAGS,
+            Subcommand::Rate
+            | Subcommand::Hint
+            | Subcommand::Interactive
+            | Subcommand::Completions => &[],
+        };
+    }
+
+    fn flag(self, name: &str) -> Option<&'static Flag> {
+        return self
+            .flags()
+            .iter()
+            .find(|f| f.name == name || f.short == Some(name));
+    }
+
+    // How to call the subcommand, one line for each way.
+    fn synopsis(self) -> &'static str {
+        return match self {
+            Subcommand::Solve => {
+                "\
+Usage: sudokusolver [solve] [-v] [--minimize] [--canonical] [--oneline] [--time] [--recursive] [--index N] [--max-guesses N] [--progress] [--variant V] [--regions FILE] [--extra-regions FILE] [--log-level LEVEL] file...
+       sudokusolver [solve] --all [--max-solutions N] [-v] [--oneline] file...
+       sudokusolver [solve] --status-only [--recursive] [--index N] [--max-guesses N] [--variant V] [--regions FILE] [--extra-regions FILE] file...
+       sudokusolver [solve] --size N [--box RxC] [--hex] [--oneline] [--status-only] file...
+       sudokusolver [solve] --dedupe file...
+       sudokusolver [solve] --watch [--interval MS] file"
+            }
+            Subcommand::Check => "Usage: sudokusolver check [--solvable] file",
+            Subcommand::Generate => {
+                "\
+Usage: sudokusolver generate [--solved] [--clues N] [--symmetry S] [--seed N]
+       sudokusolver generate --count N [--out FILE] [--clues N] [--symmetry S] [--seed N]
+       sudokusolver generate --daily YYYY-MM-DD [--difficulty D]"
+            }
+            Subcommand::Rate => "Usage: sudokusolver rate file...",
+            Subcommand::Convert => "Usage: sudokusolver convert [--from FORMAT] --to FORMAT file",
+            Subcommand::Hint => "Usage: sudokusolver hint file",
+            Subcommand::Mask => "Usage: sudokusolver mask --pattern FILE [--seed N] [--tries N]",
+            Subcommand::Interactive => "Usage: sudokusolver interactive [file]",
+            Subcommand::Bench => {
+                "Usage: sudokusolver bench [--iterations N] [--warmup N] [--engine HOW] [--json] file"
+            }
+            Subcommand::Compare => "Usage: sudokusolver compare [--suffix SUFFIX] path...",
+            Subcommand::Transform => {
+                "Usage: sudokusolver transform [--shuffle] [--rotate DEGREES] [--mirror SIDE] [--transpose] [--relabel DIGITS] [--seed N] file"
+            }
+            Subcommand::Completions => "Usage: sudokusolver completions bash|zsh|fish",
+        };
+    }
+
+    // What follows the options in the usage.
+    fn notes(self) -> &'static str {
+        return match self {
+            Subcommand::Solve => {
+                "\
+A file of - reads a puzzle from stdin. A directory, or a pattern with * or ? in its last
+part, stands for the .txt, .sdk, and .sdm files in it, in order of name.
+Exit status with --status-only: 0 one solution, 2 no solution, 3 several solutions,
+4 the file couldn't be read or parsed, 5 the search gave up at --max-guesses."
+            }
+            Subcommand::Check => {
+                "\
+Check a filled or partly filled grid against the rules, without solving it.
+Exit status, as for solve --status-only: 0 complete and valid, 2 breaking the rules,
+3 incomplete but consistent, 4 the file couldn't be read or parsed. With --solvable an
+incomplete grid is solved, giving 0 for one completion, 2 for none, and 3 for several."
+            }
+            Subcommand::Rate => {
+                "Print how hard each puzzle is for a person, by the techniques it needs."
+            }
+            Subcommand::Convert => {
+                "\
+Formats:
+  grid        nine lines of nine cells, '-' for blanks
+  line        one puzzle per line, 81 cells with '.' for blanks
+  sdm         one puzzle per line, 81 digits with 0 for blanks (.sdm)
+  csv         nine lines of nine comma-separated digits, 0 for blanks
+  sdk         SadMan Sudoku, nine lines with '.' for blanks (.sdk)
+  opensudoku  an OpenSudoku XML collection (.xml, .opensudoku)
+A file of - reads from stdin."
+            }
+            Subcommand::Hint => {
+                "Print every cell that singles force right now, and what forces it."
+            }
+            Subcommand::Interactive => {
+                "\
+Work on a puzzle, or an empty grid, with commands from stdin; help lists them. Cells
+are named like r4c7, as in hints. edit is another name for interactive."
+            }
+            Subcommand::Bench => {
+                "\
+The file holds one puzzle per line, as line or sdm, or - for stdin. Puzzles are solved
+one at a time on one thread, so results compare across machines and builds. It prints
+the fastest, mean, and standard deviation of the iterations' times, and the outcomes
+and guesses of one iteration, which are the same in every one. The engine is the
+guessing search, with fewest or rarest choosing where it guesses; there is no dlx
+engine. Logging would skew the times, so bench refuses -v and --log-level, and --json
+is refused by builds without the json feature."
+            }
+            Subcommand::Compare => {
+                "\
+Solve puzzles and check each solution against an expected grid, printing PASS or FAIL
+for each, the cells that differ, and a count; the run fails if any puzzle does. A
+directory, or a pattern with * or ? in its last part, pairs each puzzle file in it,
+like p1.txt, with the expected file named by swapping its extension for the suffix,
+p1.expected.txt. Any other path is a puzzle file, and the path after it its expected
+file. Either file may be in any format convert reads, judged by its extension."
+            }
+            Subcommand::Transform => {
+                "\
+Prints the transformed puzzle, which has the same number of solutions as the original.
+The transforms apply in the order above, whatever order they're given in. A file of -
+reads a puzzle from stdin."
+            }
+            Subcommand::Completions => {
+                "\
+Print a script that completes this program's subcommands and options in that shell.
+For bash, add `source <(sudokusolver completions bash)` to ~/.bashrc; for zsh, save
+the script as _sudokusolver in a directory on $fpath; for fish, save it as
+~/.config/fish/completions/sudokusolver.fish."
+            }
+            Subcommand::Generate | Subcommand::Mask => "",
+        };
+    }
+
+    fn example(self) -> &'static str {
+        return match self {
+            Subcommand::Solve => "sudokusolver --oneline puzzles/",
+            Subcommand::Check => "sudokusolver check --solvable partial.txt",
+            Subcommand::Generate => "sudokusolver generate --clues 26 --symmetry rotational",
+            Subcommand::Rate => "sudokusolver rate puzzles/*.txt",
+            Subcommand::Convert => "sudokusolver convert --to line puzzle.sdk",
+            Subcommand::Hint => "sudokusolver hint puzzle.txt",
+            Subcommand::Mask => "sudokusolver mask --pattern heart.txt --seed 3",
+            Subcommand::Interactive => "sudokusolver interactive puzzle.txt",
+            Subcommand::Bench => "sudokusolver bench --iterations 10 corpus.sdm",
+            Subcommand::Compare => "sudokusolver compare regression/",
+            Subcommand::Transform => {
+                "sudokusolver transform --rotate 90 --relabel random puzzle.txt"
+            }
+            Subcommand::Completions => "sudokusolver completions fish",
+        };
+    }
+
+    // The usage: how it's called, each option in flags, any notes, and an example.
+    pub fn usage(self) -> String {
+        let mut text = String::from(self.synopsis());
+        let flags: Vec<(String, &str)> = self
+            .flags()
+            .iter()
+            .map(|f| (f.usage_name(), f.help))
+            .collect();
+        let width = flags.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, help) in flags {
+            text.push_str(&format!("\n  {:width$}", name, width = width));
+            let indent = 2 + width + 2;
+            let mut column = indent;
+            for (ix, word) in help.split_whitespace().enumerate() {
+                if ix == 0 {
+                    text.push_str("  ");
+                } else if column + 1 + word.len() > USAGE_WIDTH {
+                    text.push_str(&format!("\n{:indent$}", "", indent = indent));
+                    column = indent;
+                } else {
+                    text.push(' ');
+                    column += 1;
+                }
+                text.push_str(word);
+                column += word.len();
+            }
+        }
+        if !self.notes().is_empty() {
+            text.push('\n');
+            text.push_str(self.notes());
+        }
+        text.push_str("\nExample: ");
+        text.push_str(self.example());
+        return text;
+    }
+}
+
+const SOLVE_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--verbose",
+        "print clue counts, and two solutions if there are several",
+    )
+    .short("-v"),
+    Flag::new(
+        "--minimize",
+        "print the puzzle with every redundant clue removed",
+    ),
+    Flag::new(
+        "--canonical",
+        "print the puzzle's canonical form, the same for all equivalent puzzles",
+    ),
+    Flag::new(
+        "--oneline",
+        "print each grid on one line, prefixed by its file if there are several",
+    ),
+    Flag::new(
+        "--time",
+        "print how long each puzzle took to parse and solve, and with several \
+             files the total, mean, median, and longest solve",
+    ),
+    Flag::new(
+        "--recursive",
+        "also solve the puzzle files in directories below a directory given",
+    ),
+    Flag::new(
+        "--all",
+        "print every solution, up to --max-solutions, and their count",
+    ),
+    Flag::new(
+        "--max-solutions",
+        "the most solutions --all prints (default 10)",
+    )
+    .value("N"),
+    Flag::new(
+        "--index",
+        "take each file as one puzzle per line, and solve only line N, counting \
+             from 0 and skipping blank lines",
+    )
+    .value("N"),
+    Flag::new(
+        "--max-guesses",
+        "give up on a puzzle once the search has made N guesses",
+    )
+    .value("N"),
+    Flag::new(
+        "--progress",
+        "show the guesses so far on stderr during a long solve, if it's a terminal",
+    ),
+    Flag::new(
+        "--status-only",
+        "print nothing, and exit with the status below; with several puzzles, \
+             exit with the highest and print a count of each on stderr",
+    ),
+    Flag::new(
+        "--log-level",
+        "print solver events at this level: error, warn, info, debug, or trace",
+    )
+    .value("LEVEL")
+    .choices(&["error", "warn", "info", "debug", "trace"]),
+    Flag::new(
+        "--variant",
+        "solve by the rules of classic sudoku, of X-sudoku (x), whose diagonals also \
+         hold 1-9, of hyper sudoku (hyper), whose four windows do, or with no repeats a \
+         knight's or king's move apart (anti-knight, anti-king), or with no consecutive \
+         digits side by side (non-consecutive); name several with commas to combine \
+         them; by default an .sdk file's #variant: line says, else classic",
+    )
+    .value("V")
+    .choices(&[
+        "classic",
+        "x",
+        "hyper",
+        "anti-knight",
+        "anti-king",
+        "non-consecutive",
+    ]),
+    Flag::new(
+        "--regions",
+        "solve as jigsaw sudoku, with the regions in this file, nine lines of nine \
+         letters A-I, in place of the squares; by default an .sdk file's [Regions] \
+         section says",
+    )
+    .value("FILE"),
+    Flag::new(
+        "--extra-regions",
+        "also solve with the regions in this file as units, each nine cells like r3c7 on \
+         a line of their own, or nine lines of nine with a letter for each region's cells \
+         and '.' elsewhere, as for asterisk sudoku",
+    )
+    .value("FILE"),
+    Flag::new(
+        "--size",
+        "solve grids N cells on a side, with boxes as near square as they go: 4 for \
+         2x2 boxes, 6 for 2x3, 8 for 2x4, 12 for 3x4, or 16 for 4x4, with the digits \
+         past 9 written A-G",
+    )
+    .value("N"),
+    Flag::new(
+        "--box",
+        "solve grids with boxes R rows tall and C columns wide, like 3x2 for a 6x6 \
+         grid in tall boxes; with --size, the two must agree",
+    )
+    .value("RxC"),
+    Flag::new(
+        "--hex",
+        "with --size or --box, write digits one lower, 0-9 and then A-F, as 16x16 \
+         puzzles often are",
+    ),
+    Flag::new(
+        "--dedupe",
+        "print each file whose puzzle isn't equivalent to an earlier one's",
+    ),
+    Flag::new(
+        "--watch",
+        "solve the file again each time it changes, until interrupted, showing whether it \
+         has one solution and, if singles don't finish it, its candidates",
+    ),
+    Flag::new(
+        "--interval",
+        "with --watch, check the file every MS milliseconds (default 500)",
+    )
+    .value("MS"),
+];
+
+const CHECK_FLAGS: &[Flag] = &[Flag::new(
+    "--solvable",
+    "for a consistent incomplete grid, also say whether it can be completed",
+)];
+
+const GENERATE_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--solved",
+        "print a random complete grid instead of a puzzle",
+    ),
+    Flag::new(
+        "--count",
+        "make N puzzles, one 81-digit line each, and summarize them",
+    )
+    .value("N"),
+    Flag::new(
+        "--out",
+        "write the --count puzzles to FILE instead of printing them",
+    )
+    .value("FILE"),
+    Flag::new("--clues", "stop removing clues once N are left").value("N"),
+    Flag::new(
+        "--symmetry",
+        "keep the clues symmetric: none, rotational, or mirror",
+    )
+    .value("S")
+    .choices(&["none", "rotational", "mirror"]),
+    Flag::new("--seed", "use seed N, so the same grid comes out each time").value("N"),
+    Flag::new(
+        "--daily",
+        "print the puzzle of the day, the same for everyone",
+    )
+    .value("YYYY-MM-DD"),
+    Flag::new(
+        "--difficulty",
+        "how hard the --daily puzzle is: easy, medium (the default), hard, or expert",
+    )
+    .value("D")
+    .choices(&["easy", "medium", "hard", "expert"]),
+];
+
+const CONVERT_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--from",
+        "the input's format; by default from the file's extension, else grid",
+    )
+    .value("FORMAT")
+    .choices(&FORMATS),
+    Flag::new("--to", "the format to print")
+        .value("FORMAT")
+        .choices(&FORMATS),
+];
+
+const MASK_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--pattern",
+        "nine lines of nine cells, '#' for a clue and '.' for a blank",
+    )
+    .value("FILE"),
+    Flag::new(
+        "--seed",
+        "use seed N, so the same puzzle comes out each time",
+    )
+    .value("N"),
+    Flag::new("--tries", "give up after N grids (default 100)").value("N"),
+];
+
+const BENCH_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--iterations",
+        "time solving the whole file N times (default 5)",
+    )
+    .value("N"),
+    Flag::new(
+        "--warmup",
+        "solve it N times first without timing it (default 1)",
+    )
+    .value("N"),
+    Flag::new(
+        "--engine",
+        "where the search guesses: fewest (default) or rarest candidates",
+    )
+    .value("HOW")
+    .choices(&["fewest", "rarest"]),
+    Flag::new("--branching", "another name for --engine")
+        .value("HOW")
+        .choices(&["fewest", "rarest"]),
+    Flag::new(
+        "--json",
+        "print the results as one JSON object; only with the json feature",
+    ),
+];
+
+const COMPARE_FLAGS: &[Flag] = &[Flag::new(
+    "--suffix",
+    "how expected files are named (default .expected.txt)",
+)
+.value("SUFFIX")];
+
+const TRANSFORM_FLAGS: &[Flag] = &[
+    Flag::new(
+        "--shuffle",
+        "scramble the puzzle at random: bands, stacks, rows, columns, and digits",
+    ),
+    Flag::new("--rotate", "turn it clockwise by 90, 180, or 270 degrees")
+        .value("DEGREES")
+        .choices(&["90", "180", "270"]),
+    Flag::new(
+        "--mirror",
+        "reflect it, swapping left and right (horizontal) or top and bottom \
+             (vertical)",
+    )
+    .value("SIDE")
+    .choices(&["horizontal", "vertical"]),
+    Flag::new("--transpose", "swap rows and columns"),
+    Flag::new(
+        "--relabel",
+        "write 1-9 as these nine digits, like 342918765, or random",
+    )
+    .value("DIGITS")
+    .choices(&["random"]),
+    Flag::new(
+        "--seed",
+        "use seed N for --shuffle and --relabel random, so the same puzzle comes \
+             out each time",
+    )
+    .value("N"),
+];
+
+// Usage lines wrap to fit in this many columns.
+const USAGE_WIDTH: usize = 88;
+
+const FORMATS: [&str; 6] = ["grid", "line", "sdm", "csv", "sdk", "opensudoku"];
+
+// One option a subcommand takes, as listed in Subcommand::flags.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Flag {
+    pub name: &'static str,
+    // A one-letter spelling, like -v for --verbose.
+    pub short: Option<&'static str>,
+    // What the option's value is called, if it takes one.
+    pub value: Option<&'static str>,
+    // The values it takes, if there's a fixed set to complete from.
+    pub choices: &'static [&'static str],
+    pub help: &'static str,
+}
+
+impl Flag {
+    const fn new(name: &'static str, help: &'static str) -> Flag {
+        return Flag {
+            name,
+            short: None,
+            value: None,
+            choices: &[],
+            help,
+        };
+    }
+
+    const fn short(self, short: &'static str) -> Flag {
+        return Flag {
+            short: Some(short),
+            ..self
+        };
+    }
+
+    const fn value(self, value: &'static str) -> Flag {
+        return Flag {
+            value: Some(value),
+            ..self
+        };
+    }
+
+    const fn choices(self, choices: &'static [&'static str]) -> Flag {
+        return Flag { choices, ..self };
+    }
+
+    // The option as the usage lists it, like "-v, --verbose" or "--seed N".
+    fn usage_name(&self) -> String {
+        let mut name = match self.short {
+            Some(short) => format!("{}, {}", short, self.name),
+            None => self.name.to_string(),
+        };
+        if let Some(value) = self.value {
+            name.push(' ');
+            name.push_str(value);
+        }
+        return name;
+    }
+}
+
+// The usage of every subcommand.
+pub fn full_usage() -> String {
+    let mut text =
+        String::from("Usage: sudokusolver [SUBCOMMAND] [OPTIONS] [FILE...]\nSubcommands:\n");
+    for s in Subcommand::ALL {
+        text.push_str(&format!("  {:12} {}", s.name(), s.summary()));
+        if s == Subcommand::Solve {
+            text.push_str(" (the default)");
+        }
+        text.push('\n');
+    }
+    text.push_str("sudokusolver help SUBCOMMAND describes one.\n");
+    for s in Subcommand::ALL {
+        text.push('\n');
+        text.push_str(&s.usage());
+        text.push('\n');
+    }
+    return text;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Grid,
+    Line,
+    Sdm,
+    Csv,
+    Sdk,
+    OpenSudoku,
+}
+
+impl Format {
+    // The format a file's extension suggests, if it suggests one.
+    pub fn from_extension(path: &str) -> Option<Format> {
+        let (_, ext) = path.rsplit_once('.')?;
+        return match ext {
+            "sdm" => Some(Format::Sdm),
+            "sdk" => Some(Format::Sdk),
+            "csv" => Some(Format::Csv),
+            "xml" | "opensudoku" => Some(Format::OpenSudoku),
+            _ => None,
+        };
+    }
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Format, ()> {
+        return match name {
+            "grid" => Ok(Format::Grid),
+            "line" => Ok(Format::Line),
+            "sdm" => Ok(Format::Sdm),
+            "csv" => Ok(Format::Csv),
+            "sdk" => Ok(Format::Sdk),
+            "opensudoku" => Ok(Format::OpenSudoku),
+            _ => Err(()),
+        };
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SolveArgs {
+    pub files: Vec<String>,
+    pub verbose: bool,
+    pub minimize: bool,
+    pub canonical: bool,
+    pub oneline: bool,
+    pub time: bool,
+    pub recursive: bool,
+    // With --all, the most solutions to print.
+    pub all: Option<usize>,
+    // Solve only this puzzle line of each file.
+    pub index: Option<usize>,
+    pub max_guesses: Option<u64>,
+    pub progress: bool,
+    pub status_only: bool,
+    pub log_level: Option<String>,
+    // Solve against these variants' rules rather than the file's; empty for classic.
+    pub variants: Option<Vec<Variant>>,
+    // Solve with the region map in this file in place of the puzzle's regions.
+    pub regions: Option<String>,
+    // Add the regions in this file as units of their own.
+    pub extra_regions: Option<String>,
+    // Solve grids of this shape with SizedSudoku instead of the 9x9 solver.
+    pub shape: Option<BoxShape>,
+    // With shape, the digits are written as Alphabet::Hex.
+    pub hex: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WatchArgs {
+    pub file: String,
+    // How long to wait between looks at the file.
+    pub interval: Duration,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GenerateArgs {
+    pub solved: bool,
+    pub count: Option<usize>,
+    pub out: Option<String>,
+    // None for a seed from the clock.
+    pub seed: Option<u64>,
+    pub options: GeneratorOptions,
+    // A date for the puzzle of the day, with its difficulty; nothing else applies then.
+    pub daily: Option<(String, Difficulty)>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckArgs {
+    pub file: String,
+    pub solvable: bool,
+}
+
+// The file to convert, its format, and the format to print.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConvertArgs {
+    pub file: String,
+    pub from: Format,
+    pub to: Format,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BenchArgs {
+    pub file: String,
+    pub iterations: u32,
+    pub warmup: u32,
+    pub branching: Branching,
+    pub json: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mirror {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Relabel {
+    Random,
+    // The digit each of 1-9 becomes, in order.
+    To([u8; 9]),
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TransformArgs {
+    pub file: String,
+    pub shuffle: bool,
+    // Clockwise, in degrees: 90, 180, or 270.
+    pub rotate: Option<u16>,
+    pub mirror: Option<Mirror>,
+    pub transpose: bool,
+    pub relabel: Option<Relabel>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    pub fn name(self) -> &'static str {
+        return match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        };
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompareArgs {
+    pub paths: Vec<String>,
+    pub suffix: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MaskArgs {
+    pub pattern: String,
+    pub seed: Option<u64>,
+    pub tries: u32,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    Solve(SolveArgs),
+    // The files whose puzzles aren't equivalent to an earlier file's.
+    Dedupe(Vec<String>),
+    Watch(WatchArgs),
+    Check(CheckArgs),
+    Generate(GenerateArgs),
+    Rate(Vec<String>),
+    Convert(ConvertArgs),
+    Hint(String),
+    Mask(MaskArgs),
+    Interactive(Option<String>),
+    Bench(BenchArgs),
+    Compare(CompareArgs),
+    Transform(TransformArgs),
+    Completions(Shell),
+    // Print the usage of one subcommand, or of them all.
+    Help(Option<Subcommand>),
+}
+
+// Arguments that don't make a Command: what was wrong, and for which subcommand, so
+// its usage can be shown.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageError {
+    pub subcommand: Option<Subcommand>,
+    pub message: String,
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+// The arguments after the program name. Without a subcommand name first, they are
+// the solve subcommand's.
+pub fn parse_args(args: &[String]) -> Result<Command, UsageError> {
+    let (sub, rest) = match args.first().map(String::as_str) {
+        None => {
+            return Err(UsageError {
+                subcommand: None,
+                message: "Needs at least one input.".to_string(),
+            })
+        }
+        Some("help") | Some("-h") | Some("--help") => {
+            return match args.get(1) {
+                None => Ok(Command::Help(None)),
+                Some(name) => match Subcommand::from_name(name) {
+                    Some(s) => Ok(Command::Help(Some(s))),
+                    None => Err(UsageError {
+                        subcommand: None,
+                        message: format!("Unknown subcommand {:?}.", name),
+                    }),
+                },
+            };
+        }
+        Some(first) => match Subcommand::from_name(first) {
+            Some(s) => (s, &args[1..]),
+            None => (Subcommand::Solve, args),
+        },
+    };
+    let mut args = Args {
+        sub,
+        rest: rest.iter(),
+        files: Vec::new(),
+    };
+    if rest.iter().any(|a| a == "-h" || a == "--help") {
+        return Ok(Command::Help(Some(sub)));
+    }
+    return match sub {
+        Subcommand::Solve => parse_solve(args),
+        Subcommand::Check => {
+            let mut solvable = false;
+            while let Some(a) = args.next_option()? {
+                match a {
+                    "--solvable" => solvable = true,
+                    _ => return Err(args.unknown(a)),
+                }
+            }
+            let file = args.only_file()?;
+            Ok(Command::Check(CheckArgs { file, solvable }))
+        }
+        Subcommand::Generate => parse_generate(args),
+        Subcommand::Rate => Ok(Command::Rate(args.files(1)?)),
+        Subcommand::Convert => parse_convert(args),
+        Subcommand::Hint => Ok(Command::Hint(args.only_file()?)),
+        Subcommand::Mask => parse_mask(args),
+        Subcommand::Interactive => Ok(Command::Interactive(args.optional_file()?)),
+        Subcommand::Bench => parse_bench(args),
+        Subcommand::Compare => {
+            let mut suffix = DEFAULT_EXPECTED_SUFFIX.to_string();
+            while let Some(a) = args.next_option()? {
+                match a {
+                    "--suffix" => suffix = args.value(a)?,
+                    _ => return Err(args.unknown(a)),
+                }
+            }
+            if suffix.is_empty() {
+                return Err(args.error("--suffix can't be empty."));
+            }
+            let paths = args.files(1)?;
+            Ok(Command::Compare(CompareArgs { paths, suffix }))
+        }
+        Subcommand::Transform => parse_transform(args),
+        Subcommand::Completions => {
+            let name = args.only_file()?;
+            match Shell::ALL.into_iter().find(|s| s.name() == name) {
+                Some(shell) => Ok(Command::Completions(shell)),
+                None => Err(args.error(&format!("Unknown shell {:?}.", name))),
+            }
+        }
+    };
+}
+
+// How many solutions --all prints without --max-solutions.
+pub const DEFAULT_MAX_SOLUTIONS: usize = 10;
+
+// How often --watch looks at the file without --interval, in milliseconds.
+pub const DEFAULT_WATCH_INTERVAL: u64 = 500;
+
+fn parse_solve(mut args: Args) -> Result<Command, UsageError> {
+    let mut solve = SolveArgs::default();
+    let mut dedupe = false;
+    let mut all = false;
+    let mut max_solutions = None;
+    let mut watch = false;
+    let mut interval = None;
+    let mut size = None;
+    let mut box_shape = None;
+    while let Some(a) = args.next_option()? {
+        match a {
+            "-v" | "--verbose" => solve.verbose = true,
+            "--minimize" => solve.minimize = true,
+            "--canonical" => solve.canonical = true,
+            "--oneline" => solve.oneline = true,
+            "--time" => solve.time = true,
+            "--recursive" => solve.recursive = true,
+            "--all" => all = true,
+            "--max-solutions" => max_solutions = Some(args.number(a)?),
+            "--max-guesses" => solve.max_guesses = Some(args.number(a)?),
+            "--index" => solve.index = Some(args.number(a)?),
+            "--status-only" => solve.status_only = true,
+            "--progress" => solve.progress = true,
+            "--dedupe" => dedupe = true,
+            "--watch" => watch = true,
+            "--interval" => interval = Some(args.number(a)?),
+            "--log-level" => solve.log_level = Some(args.value(a)?),
+            "--regions" => solve.regions = Some(args.value(a)?),
+            "--extra-regions" => solve.extra_regions = Some(args.value(a)?),
+            "--size" => size = Some(args.number(a)?),
+            "--box" => {
+                let v = args.value(a)?;
+                box_shape = match parse_box_shape(&v) {
+                    Some(shape) => Some(shape),
+                    None => return Err(args.error(&format!("Unknown box shape {:?}.", v))),
+                }
+            }
+            "--hex" => solve.hex = true,
+            "--variant" => {
+                let name = args.value(a)?;
+                solve.variants = match Variant::from_names(&name) {
+                    Some(vs) => Some(vs),
+                    None => return Err(args.error(&format!("Unknown variant {:?}.", name))),
+                }
+            }
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    solve.shape = match (size, box_shape) {
+        (None, shape) => shape,
+        (Some(size), None) => match BoxShape::for_size(size) {
+            Ok(shape) => Some(shape),
+            Err(e) => return Err(args.error(&format!("--size {}: {}.", size, e))),
+        },
+        (Some(size), Some(shape)) if shape.size() == size => Some(shape),
+        (Some(size), Some(shape)) => {
+            return Err(args.error(&format!(
+                "--box {}x{} makes a grid of size {}, not {}.",
+                shape.rows(),
+                shape.cols(),
+                shape.size(),
+                size
+            )));
+        }
+    };
+    solve.files = args.files(1)?;
+    if watch {
+        let given = SolveArgs {
+            files: solve.files.clone(),
+            ..Default::default()
+        };
+        if solve != given || all || max_solutions.is_some() || dedupe {
+            return Err(args.error("--watch takes no other options but --interval."));
+        }
+        let file = match solve.files.as_slice() {
+            [f] if f == "-" => return Err(args.error("--watch needs a file, not stdin.")),
+            [f] => f.clone(),
+            _ => return Err(args.error("--watch takes exactly one file.")),
+        };
+        let interval = match interval.unwrap_or(DEFAULT_WATCH_INTERVAL) {
+            0 => return Err(args.error("--interval must be at least 1.")),
+            ms => Duration::from_millis(ms),
+        };
+        return Ok(Command::Watch(WatchArgs { file, interval }));
+    }
+    if interval.is_some() {
+        return Err(args.error("--interval needs --watch."));
+    }
+    if dedupe {
+        let others = [
+            solve.verbose,
+            solve.minimize,
+            solve.canonical,
+            solve.oneline,
+            solve.time,
+            solve.recursive,
+            all,
+            solve.max_guesses.is_some(),
+            solve.status_only,
+            solve.progress,
+            solve.index.is_some(),
+            solve.variants.is_some(),
+            solve.regions.is_some(),
+            solve.extra_regions.is_some(),
+            solve.shape.is_some(),
+            solve.hex,
+        ];
+        if others.contains(&true) {
+            return Err(args.error("--dedupe takes no other options."));
+        }
+        return Ok(Command::Dedupe(solve.files));
+    }
+    if let Some(shape) = solve.shape {
+        let given = SolveArgs {
+            files: solve.files.clone(),
+            oneline: solve.oneline,
+            status_only: solve.status_only,
+            shape: Some(shape),
+            hex: solve.hex,
+            ..Default::default()
+        };
+        if solve != given || all || max_solutions.is_some() {
+            return Err(
+                args.error("--size and --box take only --hex, --oneline, and --status-only.")
+            );
+        }
+    } else if solve.hex {
+        return Err(args.error("--hex needs --size or --box."));
+    }
+    if solve.time && (solve.minimize || solve.canonical) {
+        return Err(args.error("--time only times solving."));
+    }
+    if solve.max_guesses.is_some() && (solve.minimize || solve.canonical || all) {
+        return Err(args.error("--max-guesses only limits solving."));
+    }
+    if solve.progress && (solve.minimize || solve.canonical || all) {
+        return Err(args.error("--progress only shows solving."));
+    }
+    if solve.variants.is_some() && (solve.minimize || solve.canonical || all) {
+        return Err(args.error("--variant only applies to solving."));
+    }
+    if solve.regions.is_some() && (solve.minimize || solve.canonical || all) {
+        return Err(args.error("--regions only applies to solving."));
+    }
+    if solve.extra_regions.is_some() && (solve.minimize || solve.canonical || all) {
+        return Err(args.error("--extra-regions only applies to solving."));
+    }
+    if solve.status_only {
+        let others = [
+            solve.verbose,
+            solve.minimize,
+            solve.canonical,
+            solve.oneline,
+            solve.time,
+            all,
+            solve.progress,
+        ];
+        if others.contains(&true) {
+            return Err(args.error(
+                "--status-only takes only --recursive, --index, --max-guesses, --variant, --regions, \
+                 --extra-regions, and --log-level.",
+            ));
+        }
+    }
+    match (all, max_solutions) {
+        (false, Some(_)) => return Err(args.error("--max-solutions needs --all.")),
+        (true, Some(0)) => return Err(args.error("--max-solutions must be at least 1.")),
+        (true, _) if solve.minimize || solve.canonical || solve.time => {
+            return Err(
+                args.error("--all can't be combined with --minimize, --canonical, or --time.")
+            );
+        }
+        (true, max) => solve.all = Some(max.unwrap_or(DEFAULT_MAX_SOLUTIONS)),
+        (false, None) => {}
+    }
+    return Ok(Command::Solve(solve));
+}
+
+// A box shape written as rows, 'x', and columns, like "2x3".
+fn parse_box_shape(text: &str) -> Option<BoxShape> {
+    let (rows, cols) = text.split_once(['x', 'X'])?;
+    return BoxShape::new(rows.parse().ok()?, cols.parse().ok()?).ok();
+}
+
+fn parse_generate(mut args: Args) -> Result<Command, UsageError> {
+    let mut generate = GenerateArgs::default();
+    let mut daily = None;
+    let mut difficulty = None;
+    while let Some(a) = args.next_option()? {
+        match a {
+            "--solved" => generate.solved = true,
+            "--count" => generate.count = Some(args.number(a)?),
+            "--out" => generate.out = Some(args.value(a)?),
+            "--seed" => generate.seed = Some(args.number(a)?),
+            "--clues" => generate.options.target_clues = Some(args.number(a)?),
+            "--symmetry" => {
+                generate.options.symmetry = match args.value(a)?.as_str() {
+                    "none" => Symmetry::None,
+                    "rotational" => Symmetry::Rotational,
+                    "mirror" => Symmetry::Mirror,
+                    s => return Err(args.error(&format!("Unknown symmetry {:?}.", s))),
+                }
+            }
+            "--daily" => daily = Some(args.value(a)?),
+            "--difficulty" => {
+                difficulty = Some(match args.value(a)?.as_str() {
+                    "easy" => Difficulty::Easy,
+                    "medium" => Difficulty::Medium,
+                    "hard" => Difficulty::Hard,
+                    "expert" => Difficulty::Expert,
+                    d => return Err(args.error(&format!("Unknown difficulty {:?}.", d))),
+                })
+            }
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    args.no_files()?;
+    if generate.out.is_some() && generate.count.is_none() {
+        return Err(args.error("--out needs --count."));
+    }
+    if generate.solved && generate.count.is_some() {
+        return Err(args.error("--solved can't be used with --count."));
+    }
+    match daily {
+        Some(date) => {
+            if generate != GenerateArgs::default() {
+                return Err(args.error("--daily takes no options but --difficulty."));
+            }
+            generate.daily = Some((date, difficulty.unwrap_or(Difficulty::Medium)));
+        }
+        None if difficulty.is_some() => return Err(args.error("--difficulty needs --daily.")),
+        None => {}
+    }
+    return Ok(Command::Generate(generate));
+}
+
+fn parse_convert(mut args: Args) -> Result<Command, UsageError> {
+    let mut from = None;
+    let mut to = None;
+    while let Some(a) = args.next_option()? {
+        match a {
+            "--from" => from = Some(args.format(a)?),
+            "--to" => to = Some(args.format(a)?),
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    let file = args.only_file()?;
+    let to = to.ok_or_else(|| args.error("Needs --to."))?;
+    let from = from
+        .or_else(|| Format::from_extension(&file))
+        .unwrap_or(Format::Grid);
+    return Ok(Command::Convert(ConvertArgs { file, from, to }));
+}
+
+fn parse_mask(mut args: Args) -> Result<Command, UsageError> {
+    let mut pattern = None;
+    let mut seed = None;
+    let mut tries = 100;
+    while let Some(a) = args.next_option()? {
+        match a {
+            "--pattern" => pattern = Some(args.value(a)?),
+            "--seed" => seed = Some(args.number(a)?),
+            "--tries" => tries = args.number(a)?,
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    args.no_files()?;
+    let pattern = pattern.ok_or_else(|| args.error("Needs --pattern."))?;
+    return Ok(Command::Mask(MaskArgs {
+        pattern,
+        seed,
+        tries,
+    }));
+}
+
+// How compare names the expected file for a puzzle without --suffix.
+pub const DEFAULT_EXPECTED_SUFFIX: &str = ".expected.txt";
+
+// The names --engine takes.
+pub const ENGINES: [(&str, Branching); 2] = [
+    ("fewest", Branching::FewestCandidates),
+    ("rarest", Branching::RarestCandidates),
+];
+
+fn parse_bench(mut args: Args) -> Result<Command, UsageError> {
+    let mut iterations = 5;
+    let mut warmup = 1;
+    let mut branching = Branching::FewestCandidates;
+    let mut json = false;
+    // Logging each event would be timed along with the solving.
+    if let Some(a) = args
+        .rest
+        .clone()
+        .find(|a| ["-v", "--verbose", "--log-level"].contains(&a.as_str()))
+    {
+        return Err(args.error(&format!("{} would skew the timings.", a)));
+    }
+    while let Some(a) = args.next_option()? {
+        match a {
+            "--iterations" => iterations = args.number(a)?,
+            "--warmup" => warmup = args.number(a)?,
+            "--engine" | "--branching" => {
+                let name = args.value(a)?;
+                branching = match ENGINES.iter().find(|(n, _)| *n == name) {
+                    Some(&(_, b)) => b,
+                    None if name == "dlx" => {
+                        return Err(args.error(
+                            "The dlx engine isn't available; --engine takes fewest or rarest.",
+                        ))
+                    }
+                    None => {
+                        return Err(args.error(&format!(
+                            "Unknown engine {:?}; --engine takes fewest or rarest.",
+                            name
+                        )))
+                    }
+                }
+            }
+            "--json" if cfg!(feature = "json") => json = true,
+            "--json" => return Err(args.error("--json needs the json feature.")),
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    if iterations == 0 {
+        return Err(args.error("--iterations must be at least 1."));
+    }
+    let file = args.only_file()?;
+    return Ok(Command::Bench(BenchArgs {
+        file,
+        iterations,
+        warmup,
+        branching,
+        json,
+    }));
+}
+
+fn parse_transform(mut args: Args) -> Result<Command, UsageError> {
+    let mut transform = TransformArgs::default();
+    while let Some(a) = args.next_option()? {
+        match a {
+            "--shuffle" => transform.shuffle = true,
+            "--rotate" => {
+                transform.rotate = match args.number(a)? {
+                    d @ (90 | 180 | 270) => Some(d),
+                    d => {
+                        return Err(
+                            args.error(&format!("--rotate takes 90, 180, or 270, not {}.", d))
+                        )
+                    }
+                }
+            }
+            "--mirror" => {
+                transform.mirror = match args.value(a)?.as_str() {
+                    "horizontal" => Some(Mirror::Horizontal),
+                    "vertical" => Some(Mirror::Vertical),
+                    m => return Err(args.error(&format!("Unknown mirror {:?}.", m))),
+                }
+            }
+            "--transpose" => transform.transpose = true,
+            "--relabel" => {
+                let v = args.value(a)?;
+                transform.relabel = match (v.as_str(), parse_permutation(&v)) {
+                    ("random", _) => Some(Relabel::Random),
+                    (_, Some(p)) => Some(Relabel::To(p)),
+                    (_, None) => {
+                        return Err(args.error(&format!(
+                            "--relabel takes random or the digits 1-9 in some order, not {:?}.",
+                            v
+                        )))
+                    }
+                };
+            }
+            "--seed" => transform.seed = Some(args.number(a)?),
+            _ => return Err(args.unknown(a)),
+        }
+    }
+    transform.file = args.only_file()?;
+    let random = transform.shuffle || transform.relabel == Some(Relabel::Random);
+    if transform.seed.is_some() && !random {
+        return Err(args.error("--seed needs --shuffle or --relabel random."));
+    }
+    let given = TransformArgs {
+        file: transform.file.clone(),
+        ..Default::default()
+    };
+    if transform == given {
+        return Err(args.error("Needs at least one transform."));
+    }
+    return Ok(Command::Transform(transform));
+}
+
+// Nine digits holding each of 1-9 once.
+fn parse_permutation(text: &str) -> Option<[u8; 9]> {
+    let digits: Vec<u8> = text.bytes().map(|b| b.wrapping_sub(b'0')).collect();
+    let mut permutation = [0; 9];
+    if digits.len() != 9 || (1..10).any(|d| !digits.contains(&d)) {
+        return None;
+    }
+    permutation.copy_from_slice(&digits);
+    return Some(permutation);
+}
+
+// A subcommand's arguments. An option is anything starting with '-' except "-" alone,
+// which names stdin; everything else is a file, and files and options may be mixed.
+struct Args<'a> {
+    sub: Subcommand,
+    rest: std::slice::Iter<'a, String>,
+    files: Vec<String>,
+}
+
+impl<'a> Args<'a> {
+    fn error(&self, message: &str) -> UsageError {
+        return UsageError {
+            subcommand: Some(self.sub),
+            message: message.to_string(),
+        };
+    }
+
+    fn unknown(&self, option: &str) -> UsageError {
+        return self.error(&format!(
+            "Unknown option {} for {}.",
+            option,
+            self.sub.name()
+        ));
+    }
+
+    // The next option, collecting any files before it. An option not in the
+    // subcommand's flags is an error here, before its parser sees it.
+    fn next_option(&mut self) -> Result<Option<&'a str>, UsageError> {
+        for a in self.rest.by_ref() {
+            if a.starts_with('-') && a != "-" {
+                if self.sub.flag(a).is_none() {
+                    return Err(self.unknown(a));
+                }
+                return Ok(Some(a));
+            }
+            self.files.push(a.clone());
+        }
+        return Ok(None);
+    }
+
+    // The argument after option, which it needs.
+    fn value(&mut self, option: &str) -> Result<String, UsageError> {
+        return match self.rest.next() {
+            Some(v) => Ok(v.clone()),
+            None => Err(self.error(&format!("{} needs a value.", option))),
+        };
+    }
+
+    fn number<T: FromStr>(&mut self, option: &str) -> Result<T, UsageError> {
+        let v = self.value(option)?;
+        return v
+            .parse()
+            .map_err(|_| self.error(&format!("{} needs a number, not {:?}.", option, v)));
+    }
+
+    fn format(&mut self, option: &str) -> Result<Format, UsageError> {
+        let v = self.value(option)?;
+        return v
+            .parse()
+            .map_err(|_| self.error(&format!("Unknown format {:?}.", v)));
+    }
+
+    // The files, once every option has been read; at least `min` of them.
+    fn files(&mut self, min: usize) -> Result<Vec<String>, UsageError> {
+        if let Some(a) = self.next_option()? {
+            return Err(self.unknown(a));
+        }
+        if self.files.len() < min {
+            return Err(self.error("Needs at least one input."));
+        }
+        return Ok(self.files.clone());
+    }
+
+    fn no_files(&self) -> Result<(), UsageError> {
+        return match self.files.first() {
+            Some(f) => Err(self.error(&format!("Unexpected argument {:?}.", f))),
+            None => Ok(()),
+        };
+    }
+
+    fn only_file(&mut self) -> Result<String, UsageError> {
+        if let Some(a) = self.next_option()? {
+            return Err(self.unknown(a));
+        }
+        return match self.files.as_slice() {
+            [f] => Ok(f.clone()),
+            _ => Err(self.error("Needs exactly one input.")),
+        };
+    }
+
+    // One file or none, after checking there are no options.
+    fn optional_file(&mut self) -> Result<Option<String>, UsageError> {
+        let mut files = self.files(0)?;
+        if files.len() > 1 {
+            return Err(self.error("Takes at most one file."));
+        }
+        return Ok(files.pop());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, UsageError> {
+        let args: Vec<String> = args.split_whitespace().map(String::from).collect();
+        return parse_args(&args);
+    }
+
+    fn error_for(args: &str) -> Option<Subcommand> {
+        return parse(args).unwrap_err().subcommand;
+    }
+
+    #[test]
+    fn test_solve() {
+        let expected = SolveArgs {
+            files: vec!["a.txt".to_string(), "-".to_string()],
+            verbose: true,
+            oneline: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse("-v a.txt --oneline -"),
+            Ok(Command::Solve(expected.clone()))
+        );
+        assert_eq!(
+            parse("solve a.txt - --verbose --oneline"),
+            Ok(Command::Solve(expected))
+        );
+        assert_eq!(
+            parse("--log-level debug a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                log_level: Some("debug".to_string()),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--dedupe a b"),
+            Ok(Command::Dedupe(vec!["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(
+            parse("--watch a.txt"),
+            Ok(Command::Watch(WatchArgs {
+                file: "a.txt".to_string(),
+                interval: Duration::from_millis(DEFAULT_WATCH_INTERVAL),
+            }))
+        );
+        assert_eq!(
+            parse("solve --interval 50 --watch a.txt"),
+            Ok(Command::Watch(WatchArgs {
+                file: "a.txt".to_string(),
+                interval: Duration::from_millis(50),
+            }))
+        );
+        for bad in [
+            "--watch a b",
+            "--watch -v a",
+            "--watch --all a",
+            "--watch --interval 0 a",
+            "--interval 50 a",
+        ] {
+            assert_eq!(error_for(bad), Some(Subcommand::Solve), "{}", bad);
+        }
+        assert_eq!(
+            parse("--time a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                time: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("puzzles --recursive"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["puzzles".to_string()],
+                recursive: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--status-only --max-guesses 50 a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                status_only: true,
+                max_guesses: Some(50),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            error_for("--time --minimize a.txt"),
+            Some(Subcommand::Solve)
+        );
+        assert_eq!(
+            error_for("--status-only --oneline a.txt"),
+            Some(Subcommand::Solve)
+        );
+        assert_eq!(
+            error_for("--max-guesses 5 --canonical a.txt"),
+            Some(Subcommand::Solve)
+        );
+        assert_eq!(
+            parse("--progress a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                progress: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(error_for("--progress --all a.txt"), Some(Subcommand::Solve));
+        assert_eq!(
+            parse("--index 3 --oneline a.sdm"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.sdm".to_string()],
+                index: Some(3),
+                oneline: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--variant X a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                variants: Some(vec![Variant::Diagonal]),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--variant anti-knight,classic,anti-king a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                variants: Some(vec![Variant::AntiKnight, Variant::AntiKing]),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--regions map.txt a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                regions: Some("map.txt".to_string()),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--status-only --extra-regions star.txt a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                status_only: true,
+                extra_regions: Some("star.txt".to_string()),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--size 16 --hex --oneline a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                oneline: true,
+                shape: Some(BoxShape::new(4, 4).unwrap()),
+                hex: true,
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("--size 4 --status-only a.txt"),
+            Ok(Command::Solve(SolveArgs {
+                files: vec!["a.txt".to_string()],
+                status_only: true,
+                shape: Some(BoxShape::new(2, 2).unwrap()),
+                ..Default::default()
+            }))
+        );
+        let six = |rows, cols| SolveArgs {
+            files: vec!["a.txt".to_string()],
+            shape: Some(BoxShape::new(rows, cols).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(parse("--size 6 a.txt"), Ok(Command::Solve(six(2, 3))));
+        assert_eq!(parse("--box 3x2 a.txt"), Ok(Command::Solve(six(3, 2))));
+        assert_eq!(
+            parse("--size 6 --box 3X2 a.txt"),
+            Ok(Command::Solve(six(3, 2)))
+        );
+        assert_eq!(
+            parse("--box 2x3 --size 6 a.txt"),
+            Ok(Command::Solve(six(2, 3)))
+        );
+        for bad in [
+            "--size 5 a",
+            "--size 6 --box 3x3 a",
+            "--box 2 a",
+            "--box 2x a",
+            "--box 4x5 a",
+            "--box 0x3 a",
+            "--box 2x3 --minimize a",
+            "--size 17 a",
+            "--size 4 -v a",
+            "--size 4 --all a",
+            "--size 4 --variant x a",
+            "--hex a",
+            "--variant jigsaw a",
+            "--regions a",
+            "--regions map.txt --canonical a",
+            "--regions map.txt --dedupe a",
+            "--extra-regions a",
+            "--extra-regions star.txt --minimize a",
+            "--extra-regions star.txt --dedupe a",
+            "--extra-regions star.txt --size 4 a",
+            "--variant x,,hyper a",
+            "--variant x --all 2 a",
+            "--variant x --minimize a",
+        ] {
+            assert_eq!(error_for(bad), Some(Subcommand::Solve), "{}", bad);
+        }
+        assert_eq!(error_for("--index -1 a.sdm"), Some(Subcommand::Solve));
+        assert_eq!(error_for("--dedupe -v a"), Some(Subcommand::Solve));
+        assert_eq!(error_for("--frobnicate a.txt"), Some(Subcommand::Solve));
+        assert_eq!(error_for("solve"), Some(Subcommand::Solve));
+        assert_eq!(error_for("a.txt --log-level"), Some(Subcommand::Solve));
+        assert_eq!(error_for(""), None);
+    }
+
+    #[test]
+    fn test_help() {
+        assert_eq!(parse("help"), Ok(Command::Help(None)));
+        assert_eq!(parse("--help"), Ok(Command::Help(None)));
+        assert_eq!(
+            parse("help rate"),
+            Ok(Command::Help(Some(Subcommand::Rate)))
+        );
+        assert_eq!(
+            parse("generate --seed 3 -h"),
+            Ok(Command::Help(Some(Subcommand::Generate)))
+        );
+        assert_eq!(error_for("help frobnicate"), None);
+        for s in Subcommand::ALL {
+            assert!(s.usage().starts_with("Usage: sudokusolver"));
+            assert!(full_usage().contains(&s.usage()));
+        }
+    }
+
+    // Each option in the table is one its subcommand's parser takes, and the usage
+    // describes it and what values it takes.
+    #[test]
+    fn test_flags() {
+        for s in Subcommand::ALL {
+            let usage = s.usage();
+            for f in s.flags() {
+                assert!(usage.contains(&f.usage_name()), "{} {}", s.name(), f.name);
+                for c in f.choices {
+                    assert!(usage.contains(c), "{} {} {}", s.name(), f.name, c);
+                }
+                let value = f.choices.first().copied().unwrap_or("1");
+                for name in f.short.iter().chain([&f.name]) {
+                    let args = match f.value {
+                        Some(_) => format!("{} {} {}", s.name(), name, value),
+                        None => format!("{} {}", s.name(), name),
+                    };
+                    if let Err(e) = parse(&args) {
+                        assert!(!e.message.starts_with("Unknown option"), "{}", args);
+                    }
+                }
+            }
+        }
+        assert_eq!(
+            parse("rate --minimize a").unwrap_err().message,
+            "Unknown option --minimize for rate."
+        );
+        assert_eq!(
+            parse("completions fish"),
+            Ok(Command::Completions(Shell::Fish))
+        );
+        assert_eq!(error_for("completions tcsh"), Some(Subcommand::Completions));
+        assert_eq!(error_for("completions"), Some(Subcommand::Completions));
+    }
+
+    #[test]
+    fn test_one_file_commands() {
+        let check = |solvable| {
+            return Ok(Command::Check(CheckArgs {
+                file: "a".to_string(),
+                solvable,
+            }));
+        };
+        assert_eq!(parse("check a"), check(false));
+        assert_eq!(parse("check a --solvable"), check(true));
+        assert_eq!(parse("hint -"), Ok(Command::Hint("-".to_string())));
+        assert_eq!(error_for("check"), Some(Subcommand::Check));
+        assert_eq!(error_for("check a b"), Some(Subcommand::Check));
+        assert_eq!(error_for("hint --all a"), Some(Subcommand::Hint));
+        assert_eq!(
+            parse("rate a b"),
+            Ok(Command::Rate(vec!["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(error_for("rate --fast a"), Some(Subcommand::Rate));
+        assert_eq!(parse("edit"), Ok(Command::Interactive(None)));
+        assert_eq!(error_for("edit a b"), Some(Subcommand::Interactive));
+        assert_eq!(parse("interactive"), Ok(Command::Interactive(None)));
+        assert_eq!(
+            parse("interactive a"),
+            Ok(Command::Interactive(Some("a".to_string())))
+        );
+        assert_eq!(
+            error_for("interactive --solve a"),
+            Some(Subcommand::Interactive)
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            parse("compare puzzles"),
+            Ok(Command::Compare(CompareArgs {
+                paths: vec!["puzzles".to_string()],
+                suffix: ".expected.txt".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse("compare --suffix .sol a.txt a.sol"),
+            Ok(Command::Compare(CompareArgs {
+                paths: vec!["a.txt".to_string(), "a.sol".to_string()],
+                suffix: ".sol".to_string(),
+            }))
+        );
+        assert_eq!(error_for("compare"), Some(Subcommand::Compare));
+        assert_eq!(error_for("compare --suffix"), Some(Subcommand::Compare));
+    }
+
+    #[test]
+    fn test_transform() {
+        assert_eq!(
+            parse("transform --rotate 90 --relabel random --seed 5 puzzle.txt"),
+            Ok(Command::Transform(TransformArgs {
+                file: "puzzle.txt".to_string(),
+                rotate: Some(90),
+                relabel: Some(Relabel::Random),
+                seed: Some(5),
+                ..Default::default()
+            }))
+        );
+        assert_eq!(
+            parse("transform --shuffle --mirror vertical --transpose --relabel 342918765 -"),
+            Ok(Command::Transform(TransformArgs {
+                file: "-".to_string(),
+                shuffle: true,
+                mirror: Some(Mirror::Vertical),
+                transpose: true,
+                relabel: Some(Relabel::To([3, 4, 2, 9, 1, 8, 7, 6, 5])),
+                ..Default::default()
+            }))
+        );
+        for bad in [
+            "transform a",
+            "transform --rotate 45 a",
+            "transform --mirror diagonal a",
+            "transform --relabel 112345678 a",
+            "transform --relabel 12345678 a",
+            "transform --transpose --seed 3 a",
+            "transform --shuffle a b",
+        ] {
+            assert_eq!(error_for(bad), Some(Subcommand::Transform), "{}", bad);
+        }
+        assert_eq!(
+            parse_permutation("123456789"),
+            Some([1, 2, 3, 4, 5, 6, 7, 8, 9])
+        );
+        assert_eq!(parse_permutation("12345678x"), None);
+    }
+
+    #[test]
+    fn test_bench() {
+        assert_eq!(
+            parse("bench a.sdm"),
+            Ok(Command::Bench(BenchArgs {
+                file: "a.sdm".to_string(),
+                iterations: 5,
+                warmup: 1,
+                branching: Branching::FewestCandidates,
+                json: false,
+            }))
+        );
+        let json = parse("bench --iterations 3 --warmup 0 --engine rarest --json a.sdm");
+        if cfg!(feature = "json") {
+            assert_eq!(
+                json,
+                Ok(Command::Bench(BenchArgs {
+                    file: "a.sdm".to_string(),
+                    iterations: 3,
+                    warmup: 0,
+                    branching: Branching::RarestCandidates,
+                    json: true,
+                }))
+            );
+        } else {
+            assert_eq!(json.unwrap_err().message, "--json needs the json feature.");
+        }
+        for bad in [
+            "bench",
+            "bench --iterations 0 a",
+            "bench --engine dlx a",
+            "bench --log-level debug a",
+            "bench -v a",
+        ] {
+            assert_eq!(error_for(bad), Some(Subcommand::Bench));
+        }
+        assert_eq!(
+            parse("bench --log-level debug a").unwrap_err().message,
+            "--log-level would skew the timings."
+        );
+        assert_eq!(
+            parse("bench --branching rarest a"),
+            parse("bench --engine rarest a")
+        );
+        assert_eq!(
+            parse("bench --engine dlx a").unwrap_err().message,
+            "The dlx engine isn't available; --engine takes fewest or rarest."
+        );
+        assert_eq!(
+            parse("bench --engine fastest a").unwrap_err().message,
+            "Unknown engine \"fastest\"; --engine takes fewest or rarest."
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut expected = GenerateArgs {
+            count: Some(20),
+            seed: Some(7),
+            out: Some("out.sdm".to_string()),
+            ..Default::default()
+        };
+        expected.options.target_clues = Some(30);
+        expected.options.symmetry = Symmetry::Mirror;
+        assert_eq!(
+            parse("generate --count 20 --seed 7 --out out.sdm --clues 30 --symmetry mirror"),
+            Ok(Command::Generate(expected))
+        );
+        assert_eq!(
+            parse("generate --daily 2024-06-01 --difficulty hard"),
+            Ok(Command::Generate(GenerateArgs {
+                daily: Some(("2024-06-01".to_string(), Difficulty::Hard)),
+                ..Default::default()
+            }))
+        );
+        match parse("generate --daily 2024-06-01") {
+            Ok(Command::Generate(g)) => assert_eq!(g.daily.unwrap().1, Difficulty::Medium),
+            other => panic!("{:?}", other),
+        }
+        for bad in [
+            "generate --count many",
+            "generate --symmetry diagonal",
+            "generate --out x",
+            "generate --solved --count 2",
+            "generate --daily 2024-06-01 --seed 1",
+            "generate --difficulty easy",
+            "generate extra",
+            "generate --tries 3",
+        ] {
+            assert_eq!(error_for(bad), Some(Subcommand::Generate), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_convert_and_mask() {
+        assert_eq!(
+            parse("convert --to csv puzzles.sdm"),
+            Ok(Command::Convert(ConvertArgs {
+                file: "puzzles.sdm".to_string(),
+                from: Format::Sdm,
+                to: Format::Csv,
+            }))
+        );
+        assert_eq!(
+            parse("convert - --from line --to opensudoku"),
+            Ok(Command::Convert(ConvertArgs {
+                file: "-".to_string(),
+                from: Format::Line,
+                to: Format::OpenSudoku,
+            }))
+        );
+        match parse("convert --to sdk a.txt") {
+            Ok(Command::Convert(c)) => assert_eq!(c.from, Format::Grid),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(error_for("convert a.txt"), Some(Subcommand::Convert));
+        assert_eq!(
+            error_for("convert --to png a.txt"),
+            Some(Subcommand::Convert)
+        );
+
+        assert_eq!(
+            parse("mask --pattern heart.txt --tries 5"),
+            Ok(Command::Mask(MaskArgs {
+                pattern: "heart.txt".to_string(),
+                seed: None,
+                tries: 5,
+            }))
+        );
+        assert_eq!(error_for("mask --seed 1"), Some(Subcommand::Mask));
+    }
+}
@@ -0,0 +1,201 @@
+// Puzzle codes: a grid packed into bits and written in the URL-safe base64 alphabet
+// (A-Z, a-z, 0-9, '-', '_'), short enough to share in a link.
+//
+// The bits are, most significant first: 81 bits marking which cells are clues, in
+// row-major order, then 4 bits for the value of each clue in the same order, then zero
+// bits up to a whole number of characters. A puzzle with 25 clues takes 31 characters,
+// and even a full grid only 68, against 81 for the digit form. Like the seeds in rng.rs,
+// the code for a grid is part of the crate's interface and must not change between
+// releases, so old links keep working.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::Sudoku;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CodeError {
+    // A character outside the alphabet.
+    BadCharacter(char),
+    // Too few characters for the clues the mask marks, or too many.
+    BadLength,
+    // A clue's value is 0 or more than 9.
+    BadValue,
+    // The bits after the last clue aren't zero, so the code wasn't made by to_code.
+    BadPadding,
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodeError::BadCharacter(c) => write!(f, "{:?} can't appear in a puzzle code", c),
+            CodeError::BadLength => write!(f, "Puzzle code is the wrong length"),
+            CodeError::BadValue => write!(f, "Puzzle code has a clue outside 1-9"),
+            CodeError::BadPadding => write!(f, "Puzzle code has stray bits at the end"),
+        }
+    }
+}
+
+impl core::error::Error for CodeError {}
+
+// Bits written most significant first into whole bytes.
+struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u8, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if value >> i & 1 == 1 {
+                self.bytes[self.len / 8] |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+
+    // Six bits to a character, the last padded with zeros.
+    fn encode(&self) -> String {
+        return (0..self.len.div_ceil(6))
+            .map(|ch| {
+                let ix = (ch * 6..ch * 6 + 6).fold(0, |n, at| {
+                    let set = at < self.len && bit(&self.bytes, at) == 1;
+                    return n << 1 | set as usize;
+                });
+                return ALPHABET[ix] as char;
+            })
+            .collect();
+    }
+}
+
+fn bit(bytes: &[u8], ix: usize) -> u8 {
+    return bytes[ix / 8] >> (7 - ix % 8) & 1;
+}
+
+impl Sudoku {
+    // The grid as a puzzle code; from_code reads it back.
+    pub fn to_code(&self) -> String {
+        let mut bits = BitWriter {
+            bytes: Vec::new(),
+            len: 0,
+        };
+        for (_, v) in self.cells() {
+            bits.push((v != 0) as u8, 1);
+        }
+        for (_, v) in self.cells().filter(|&(_, v)| v != 0) {
+            bits.push(v, 4);
+        }
+        return bits.encode();
+    }
+
+    // The grid a code from to_code stands for. Every code is checked in full, so a
+    // mistyped or truncated one is refused rather than read as some other grid.
+    pub fn from_code(code: &str) -> Result<Sudoku, CodeError> {
+        let mut bits = BitWriter {
+            bytes: Vec::new(),
+            len: 0,
+        };
+        for c in code.chars() {
+            let ix = ALPHABET
+                .iter()
+                .position(|&a| a as char == c)
+                .ok_or(CodeError::BadCharacter(c))?;
+            bits.push(ix as u8, 6);
+        }
+        if bits.len < 81 {
+            return Err(CodeError::BadLength);
+        }
+        let clues = (0..81).filter(|&ix| bit(&bits.bytes, ix) == 1).count();
+        let used = 81 + 4 * clues;
+        if bits.len != used.div_ceil(6) * 6 {
+            return Err(CodeError::BadLength);
+        }
+        if (used..bits.len).any(|ix| bit(&bits.bytes, ix) == 1) {
+            return Err(CodeError::BadPadding);
+        }
+        let mut s = Sudoku::empty();
+        let mut at = 81;
+        for (ix, coord) in crate::Coord::all().enumerate() {
+            if bit(&bits.bytes, ix) == 0 {
+                continue;
+            }
+            let value = (at..at + 4).fold(0, |n, b| n << 1 | bit(&bits.bytes, b));
+            at += 4;
+            if !(1..=9).contains(&value) {
+                return Err(CodeError::BadValue);
+            }
+            s[coord] = value;
+        }
+        return Ok(s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let solution = moderate().solve().unwrap();
+        for s in [moderate(), solution, Sudoku::empty()] {
+            let code = s.to_code();
+            assert_eq!(Sudoku::from_code(&code), Ok(s));
+            assert!(code.len() < 81);
+            assert!(code
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+        }
+        assert_eq!(moderate().to_code().len(), 31);
+        assert_eq!(solution.to_code().len(), 68);
+        assert_eq!(Sudoku::empty().to_code(), "A".repeat(14));
+    }
+
+    // The same codes in every release; see the top of this file.
+    #[test]
+    fn test_golden_code() {
+        assert_eq!(moderate().to_code(), "FaEZIEREoToOCAnKw8o5qYssoai6QzA");
+    }
+
+    #[test]
+    fn test_refuses() {
+        let code = moderate().to_code();
+        assert_eq!(
+            Sudoku::from_code(&code.replacen('E', "*", 1)),
+            Err(CodeError::BadCharacter('*'))
+        );
+        assert_eq!(Sudoku::from_code(&code[..30]), Err(CodeError::BadLength));
+        assert_eq!(
+            Sudoku::from_code(&format!("{}A", code)),
+            Err(CodeError::BadLength)
+        );
+        assert_eq!(Sudoku::from_code(""), Err(CodeError::BadLength));
+        // 181 bits of a 186-bit code are used; setting the last one breaks it.
+        let mut padded = code.clone();
+        let last = padded.pop().unwrap();
+        let ix = ALPHABET.iter().position(|&a| a as char == last).unwrap();
+        padded.push(ALPHABET[ix | 1] as char);
+        assert_eq!(Sudoku::from_code(&padded), Err(CodeError::BadPadding));
+        // One clue in r1c1, with value 15.
+        let mut bits = BitWriter {
+            bytes: Vec::new(),
+            len: 0,
+        };
+        bits.push(1, 1);
+        for _ in 1..81 {
+            bits.push(0, 1);
+        }
+        bits.push(15, 4);
+        assert_eq!(Sudoku::from_code(&bits.encode()), Err(CodeError::BadValue));
+    }
+}
@@ -0,0 +1,149 @@
+// The compare subcommand: a regression harness that solves puzzles and checks each
+// answer against an expected grid kept beside it. A directory or pattern pairs each
+// puzzle with its expected file by name, and other paths come in explicit pairs; see
+// the compare usage.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use sudokusolver::{Coord, Sudoku, Unsolveable};
+
+use crate::cli::{CompareArgs, Format};
+use crate::{display_name, inputs, parse_puzzles, read_text};
+
+pub fn compare(args: CompareArgs) -> Result<(), io::Error> {
+    let pairs = pairs(&args.paths, &args.suffix)?;
+    let mut out = io::stdout().lock();
+    let mut failed = 0;
+    for (puzzle, expected) in &pairs {
+        let name = display_name(puzzle);
+        match check(puzzle, expected) {
+            Ok(diff) if diff.is_empty() => writeln!(out, "PASS {}", name)?,
+            Ok(diff) => {
+                failed += 1;
+                let cells = if diff.len() == 1 {
+                    "cell differs"
+                } else {
+                    "cells differ"
+                };
+                writeln!(out, "FAIL {}: {} {}", name, diff.len(), cells)?;
+                for (c, got, want) in diff {
+                    writeln!(out, "  {}: expected {}, got {}", c, want, got)?;
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                writeln!(out, "FAIL {}: {}", name, e)?;
+            }
+        }
+    }
+    writeln!(
+        out,
+        "{} compared: {} passed, {} failed",
+        pairs.len(),
+        pairs.len() - failed,
+        failed
+    )?;
+    if failed > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} of {} comparisons failed.", failed, pairs.len()),
+        ));
+    }
+    return Ok(());
+}
+
+// The (puzzle, expected) file pairs the paths stand for.
+fn pairs(paths: &[String], suffix: &str) -> Result<Vec<(String, String)>, io::Error> {
+    let mut pairs = Vec::new();
+    let mut paths = paths.iter();
+    while let Some(path) = paths.next() {
+        let expanded = inputs::expand(path, false)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path, e)))?;
+        if !expanded.many {
+            let expected = paths.next().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} needs an expected file after it.", path),
+                )
+            })?;
+            pairs.push((path.clone(), expected.clone()));
+            continue;
+        }
+        for puzzle in expanded.files.into_iter().filter(|f| !f.ends_with(suffix)) {
+            let expected = expected_name(&puzzle, suffix);
+            pairs.push((puzzle, expected));
+        }
+    }
+    return Ok(pairs);
+}
+
+// p1.txt's expected file, p1.expected.txt with the default suffix.
+fn expected_name(puzzle: &str, suffix: &str) -> String {
+    return format!(
+        "{}{}",
+        Path::new(puzzle).with_extension("").display(),
+        suffix
+    );
+}
+
+// Solve the puzzle and compare its solution with the expected grid, giving each
+// differing cell with the solution's value and then the expected one.
+fn check(puzzle: &str, expected: &str) -> Result<Vec<(Coord, u8, u8)>, String> {
+    let puzzle = read_one(puzzle)?;
+    let expected = read_one(expected)?;
+    let solution = puzzle.solve().map_err(|e| match e {
+        Unsolveable::MultipleSolutions(..) => "multiple solutions found".to_string(),
+        _ => "no solution found".to_string(),
+    })?;
+    return Ok(solution.diff(&expected));
+}
+
+// The one puzzle in a file of any format, judged by its extension. A file with no
+// extension convert knows may be a grid or a single line.
+fn read_one(filename: &str) -> Result<Sudoku, String> {
+    let name = display_name(filename);
+    let text = read_text(filename).map_err(|e| format!("{}: {}", name, e))?;
+    let puzzles = match Format::from_extension(filename) {
+        Some(format) => parse_puzzles(&text, format),
+        None => parse_puzzles(&text, Format::Grid).or_else(|e| {
+            return match text.trim().lines().count() {
+                1 => parse_puzzles(&text, Format::Line),
+                _ => Err(e),
+            };
+        }),
+    };
+    return match puzzles.map_err(|e| format!("{}: {}", name, e))?[..] {
+        [s] => Ok(s),
+        ref all => Err(format!("{} holds {} puzzles, not one", name, all.len())),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_name() {
+        assert_eq!(
+            expected_name("dir/p1.txt", ".expected.txt"),
+            "dir/p1.expected.txt"
+        );
+        assert_eq!(expected_name("p1.sdm", ".sol"), "p1.sol");
+        assert_eq!(expected_name("p1", ".sol"), "p1.sol");
+    }
+
+    #[test]
+    fn test_pairs() {
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            pairs(&strings(&["a.txt", "a.sol", "b.txt", "b.sol"]), ".sol").unwrap(),
+            [
+                ("a.txt".to_string(), "a.sol".to_string()),
+                ("b.txt".to_string(), "b.sol".to_string())
+            ]
+        );
+        let err = pairs(&strings(&["a.txt"]), ".sol").unwrap_err();
+        assert_eq!(err.to_string(), "a.txt needs an expected file after it.");
+    }
+}
@@ -0,0 +1,215 @@
+// The completions subcommand: scripts for bash, zsh, and fish that complete the
+// subcommands and their options. They're made from Subcommand::flags, the table the
+// parser checks options against, so they offer exactly what the parser takes.
+
+use crate::cli::{Flag, Shell, Subcommand};
+
+pub fn script(shell: Shell) -> String {
+    return match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+    };
+}
+
+fn names() -> Vec<&'static str> {
+    return Subcommand::ALL.iter().map(|s| s.name()).collect();
+}
+
+// Whether the option's value is a file, so the shell should offer file names.
+fn takes_file(flag: &Flag) -> bool {
+    return flag.value == Some("FILE");
+}
+
+fn bash() -> String {
+    let mut text = String::from(
+        "_sudokusolver() {\n    \
+            local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n    \
+            local sub=solve opts=\"\"\n",
+    );
+    text.push_str(&format!(
+        "    case \"${{COMP_WORDS[1]}}\" in\n        {}|help) sub=\"${{COMP_WORDS[1]}}\" ;;\n    esac\n",
+        names().join("|")
+    ));
+    text.push_str("    case \"$sub\" in\n");
+    for sub in Subcommand::ALL {
+        text.push_str(&format!("        {})\n", sub.name()));
+        let valued: Vec<&Flag> = sub.flags().iter().filter(|f| f.value.is_some()).collect();
+        if !valued.is_empty() {
+            text.push_str("            case \"$prev\" in\n");
+            for f in valued {
+                let reply = if !f.choices.is_empty() {
+                    format!(
+                        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                        f.choices.join(" ")
+                    )
+                } else if takes_file(f) {
+                    "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()
+                } else {
+                    "COMPREPLY=()".to_string()
+                };
+                text.push_str(&format!(
+                    "                {}) {}; return ;;\n",
+                    f.name, reply
+                ));
+            }
+            text.push_str("            esac\n");
+        }
+        let mut opts: Vec<&str> = vec!["--help"];
+        for f in sub.flags() {
+            opts.extend(f.short);
+            opts.push(f.name);
+        }
+        text.push_str(&format!("            opts=\"{}\"\n", opts.join(" ")));
+        if sub == Subcommand::Completions {
+            text.push_str(&format!(
+                "            if [[ $COMP_CWORD -eq 2 && \"$cur\" != -* ]]; then\n                \
+                    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return\n            fi\n",
+                Shell::ALL.map(|s| s.name()).join(" ")
+            ));
+        }
+        text.push_str("            ;;\n");
+    }
+    text.push_str(&format!(
+        "        help)\n            COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return ;;\n",
+        names().join(" ")
+    ));
+    text.push_str("    esac\n");
+    text.push_str(&format!(
+        "    if [[ \"$cur\" == -* ]]; then\n        \
+            COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n    \
+        elif [[ $COMP_CWORD -eq 1 ]]; then\n        \
+            COMPREPLY=($(compgen -W \"{} help\" -- \"$cur\") $(compgen -f -- \"$cur\"))\n    \
+        else\n        \
+            COMPREPLY=($(compgen -f -- \"$cur\"))\n    \
+        fi\n}}\n\ncomplete -o filenames -F _sudokusolver sudokusolver\n",
+        names().join(" ")
+    ));
+    return text;
+}
+
+// Text for inside a single-quoted zsh _arguments spec's description.
+fn zsh_escape(text: &str) -> String {
+    return text
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:");
+}
+
+fn zsh() -> String {
+    let mut text = String::from("#compdef sudokusolver\n\n_sudokusolver() {\n");
+    text.push_str("    local -a subcommands\n    subcommands=(\n");
+    for sub in Subcommand::ALL {
+        text.push_str(&format!(
+            "        '{}:{}'\n",
+            sub.name(),
+            zsh_escape(sub.summary())
+        ));
+    }
+    text.push_str("        'help:describe a subcommand'\n    )\n");
+    text.push_str(
+        "    if (( CURRENT == 2 )) && [[ $words[2] != -* ]]; then\n        \
+            _describe 'subcommand' subcommands\n        _files\n        return\n    fi\n",
+    );
+    text.push_str(&format!(
+        "    local sub=solve\n    case $words[2] in\n        {}|help)\n            \
+            sub=$words[2]\n            shift words\n            (( CURRENT-- ))\n            ;;\n    \
+        esac\n",
+        names().join("|")
+    ));
+    text.push_str("    case $sub in\n");
+    for sub in Subcommand::ALL {
+        text.push_str(&format!(
+            "        {})\n            _arguments \\\n                '--help[print the usage]' \\\n",
+            sub.name()
+        ));
+        for f in sub.flags() {
+            let help = zsh_escape(f.help);
+            let value = match f.value {
+                Some(v) if !f.choices.is_empty() => format!(":{}:({})", v, f.choices.join(" ")),
+                Some(v) if takes_file(f) => format!(":{}:_files", v),
+                Some(v) => format!(":{}: ", v),
+                None => String::new(),
+            };
+            for name in f.short.iter().chain([&f.name]) {
+                text.push_str(&format!(
+                    "                '{}[{}]{}' \\\n",
+                    name, help, value
+                ));
+            }
+        }
+        let rest = match sub {
+            Subcommand::Completions => {
+                format!("'1:shell:({})'", Shell::ALL.map(|s| s.name()).join(" "))
+            }
+            _ => "'*:file:_files'".to_string(),
+        };
+        text.push_str(&format!("                {}\n            ;;\n", rest));
+    }
+    text.push_str(&format!(
+        "        help)\n            _arguments '1:subcommand:({})'\n            ;;\n",
+        names().join(" ")
+    ));
+    text.push_str("    esac\n}\n\n_sudokusolver \"$@\"\n");
+    return text;
+}
+
+// Text for inside a single-quoted fish string.
+fn fish_escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('\'', "\\'");
+}
+
+fn fish() -> String {
+    let mut text = String::new();
+    for sub in Subcommand::ALL {
+        text.push_str(&format!(
+            "complete -c sudokusolver -n '__fish_use_subcommand' -a {} -d '{}'\n",
+            sub.name(),
+            fish_escape(sub.summary())
+        ));
+    }
+    text.push_str(
+        "complete -c sudokusolver -n '__fish_use_subcommand' -a help -d 'describe a subcommand'\n",
+    );
+    text.push_str(&format!(
+        "complete -c sudokusolver -n '__fish_seen_subcommand_from help' -x -a '{}'\n",
+        names().join(" ")
+    ));
+    text.push_str(&format!(
+        "complete -c sudokusolver -n '__fish_seen_subcommand_from completions' -x -a '{}'\n",
+        Shell::ALL.map(|s| s.name()).join(" ")
+    ));
+    let others: Vec<&str> = names().into_iter().filter(|&n| n != "solve").collect();
+    for sub in Subcommand::ALL {
+        // Solve's options also apply with no subcommand at all.
+        let condition = match sub {
+            Subcommand::Solve => format!("not __fish_seen_subcommand_from {}", others.join(" ")),
+            _ => format!("__fish_seen_subcommand_from {}", sub.name()),
+        };
+        let mut line = format!(
+            "complete -c sudokusolver -n '{}' -l help -d 'print the usage'\n",
+            condition
+        );
+        for f in sub.flags() {
+            line.push_str(&format!(
+                "complete -c sudokusolver -n '{}' -l {}",
+                condition,
+                f.name.trim_start_matches('-')
+            ));
+            if let Some(short) = f.short {
+                line.push_str(&format!(" -s {}", short.trim_start_matches('-')));
+            }
+            if !f.choices.is_empty() {
+                line.push_str(&format!(" -x -a '{}'", f.choices.join(" ")));
+            } else if takes_file(f) {
+                line.push_str(" -r -F");
+            } else if f.value.is_some() {
+                line.push_str(" -x");
+            }
+            line.push_str(&format!(" -d '{}'\n", fish_escape(f.help)));
+        }
+        text.push_str(&line);
+    }
+    return text;
+}
@@ -0,0 +1,174 @@
+// Solving many puzzles at once, for benchmarking against large datasets: in parallel
+// with solve_corpus, or one after another with solve_each, whose timings don't depend on
+// how many cores the machine has.
+
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::{SolverConfig, Sudoku, Unsolveable, UnsolvedSudoku};
+
+// Aggregate results from solve_corpus or solve_each.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct CorpusStats {
+    pub unique: usize,
+    pub multiple: usize,
+    pub unsolvable: usize,
+    // Puzzles that used up their guess budget before the search finished.
+    pub timed_out: usize,
+    pub total_guesses: u64,
+    // The most guesses made on any one puzzle.
+    pub max_guesses: u64,
+    // Wall time for the whole corpus, not the sum over puzzles.
+    pub elapsed: Duration,
+}
+
+impl CorpusStats {
+    pub fn puzzles(&self) -> usize {
+        return self.unique + self.multiple + self.unsolvable + self.timed_out;
+    }
+
+    // Fold in the stats of another run, as if the two corpora had been solved as one:
+    // counts, guesses, and elapsed time add up, and max_guesses is the larger.
+    pub fn merge(&mut self, other: &CorpusStats) {
+        self.unique += other.unique;
+        self.multiple += other.multiple;
+        self.unsolvable += other.unsolvable;
+        self.timed_out += other.timed_out;
+        self.total_guesses += other.total_guesses;
+        self.max_guesses = self.max_guesses.max(other.max_guesses);
+        self.elapsed += other.elapsed;
+    }
+
+    fn add(&mut self, result: Option<Result<Sudoku, Unsolveable>>, guesses: u64) {
+        match result {
+            Some(Ok(_)) => self.unique += 1,
+            Some(Err(Unsolveable::MultipleSolutions(..))) => self.multiple += 1,
+            Some(Err(Unsolveable::NoSolution)) => self.unsolvable += 1,
+            None => self.timed_out += 1,
+        }
+        self.total_guesses += guesses;
+        self.max_guesses = self.max_guesses.max(guesses);
+    }
+}
+
+// Solve every puzzle in parallel, allowing each at most `budget` guesses, and tally
+// the outcomes.
+#[cfg(feature = "rayon")]
+pub fn solve_corpus(puzzles: &[Sudoku], budget: u64) -> CorpusStats {
+    let start = Instant::now();
+    let results: Vec<_> = puzzles
+        .par_iter()
+        .map(|p| UnsolvedSudoku::from(*p).budgeted_solve(budget))
+        .collect();
+
+    let mut stats = CorpusStats::default();
+    for (result, guesses) in results {
+        stats.add(result, guesses);
+    }
+    stats.elapsed = start.elapsed();
+    return stats;
+}
+
+// Solve every puzzle in order on this thread, with the budget and branching of config,
+// and tally the outcomes.
+pub fn solve_each(puzzles: &[Sudoku], config: SolverConfig) -> CorpusStats {
+    let start = Instant::now();
+    let mut stats = CorpusStats::default();
+    for p in puzzles {
+        let (result, guesses) = UnsolvedSudoku::from(*p).configured_solve(config, None);
+        stats.add(result, guesses);
+    }
+    stats.elapsed = start.elapsed();
+    return stats;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Sudoku {
+        return Sudoku::from_reader(s.as_bytes()).unwrap();
+    }
+
+    fn corpus() -> Vec<Sudoku> {
+        let moderate = parse(include_str!("../../moderate.txt"));
+        let challenge = parse(include_str!("../../challenge.txt"));
+        let mut broken = moderate;
+        // Row 1 already has a 9 at (1, 8).
+        broken.rows[0][0] = 9;
+        return vec![
+            moderate,
+            challenge,
+            Sudoku::empty(),
+            broken,
+            moderate,
+            challenge,
+        ];
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_solve_corpus() {
+        let stats = solve_corpus(&corpus(), u64::MAX);
+        assert_eq!(stats.unique, 4);
+        assert_eq!(stats.multiple, 1);
+        assert_eq!(stats.unsolvable, 1);
+        assert_eq!(stats.timed_out, 0);
+        assert_eq!(stats.puzzles(), 6);
+        assert!(stats.max_guesses > 0);
+        assert!(stats.total_guesses >= stats.max_guesses);
+
+        // The totals don't depend on how the work was split between threads.
+        let again = solve_corpus(&corpus(), u64::MAX);
+        assert_eq!(again.total_guesses, stats.total_guesses);
+        assert_eq!(again.max_guesses, stats.max_guesses);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_solve_corpus_budget() {
+        let stats = solve_corpus(&corpus(), 0);
+        assert_eq!(stats.total_guesses, 0);
+        // The moderate puzzle needs no guesses at all; the challenge puzzle and the empty
+        // grid can't be finished without them.
+        assert_eq!(stats.unique, 2);
+        assert!(stats.timed_out >= 3);
+        assert_eq!(stats.puzzles(), 6);
+
+        let empty = solve_corpus(&[], 10);
+        assert_eq!(empty.puzzles(), 0);
+        assert_eq!(empty.total_guesses, 0);
+    }
+
+    #[test]
+    fn test_solve_each_and_merge() {
+        let stats = solve_each(&corpus(), SolverConfig::default());
+        assert_eq!(
+            (
+                stats.unique,
+                stats.multiple,
+                stats.unsolvable,
+                stats.timed_out
+            ),
+            (4, 1, 1, 0)
+        );
+        #[cfg(feature = "rayon")]
+        assert_eq!(
+            stats.total_guesses,
+            solve_corpus(&corpus(), u64::MAX).total_guesses
+        );
+
+        let mut merged = CorpusStats::default();
+        merged.merge(&stats);
+        merged.merge(&stats);
+        assert_eq!(merged.puzzles(), 12);
+        assert_eq!(merged.unique, 8);
+        assert_eq!(merged.total_guesses, 2 * stats.total_guesses);
+        assert_eq!(merged.max_guesses, stats.max_guesses);
+        assert_eq!(merged.elapsed, 2 * stats.elapsed);
+    }
+}
@@ -0,0 +1,144 @@
+// A puzzle of the day: everyone who asks for the same date and difficulty gets the
+// same puzzle, with nothing shared but the date.
+//
+// The date, written as an ISO 8601 calendar date (YYYY-MM-DD), is hashed into a Seed
+// with 64-bit FNV-1a over its ASCII bytes. Puzzles are then drawn from one SolverRng
+// made from that seed, with the default GeneratorOptions, and the first with the
+// requested difficulty is the day's puzzle. Every step is fixed by the seed, so like
+// the seeds in rng.rs the puzzle for a date is part of the crate's interface: the
+// golden puzzles in the tests must not change between releases.
+
+use core::fmt;
+
+use crate::{rate, Difficulty, GeneratorOptions, Seed, SolverRng, Sudoku};
+
+// How many puzzles to draw before giving up on one of the requested difficulty. Hard
+// puzzles are the rarest, about one in a hundred.
+pub const DAILY_TRIES: u32 = 2000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum GenError {
+    // The date isn't a real YYYY-MM-DD date.
+    BadDate,
+    // None of the DAILY_TRIES puzzles had this difficulty.
+    NoPuzzle(Difficulty),
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenError::BadDate => write!(f, "Date is not of the form YYYY-MM-DD"),
+            GenError::NoPuzzle(d) => write!(f, "No {} puzzle in {} tries", d, DAILY_TRIES),
+        }
+    }
+}
+
+impl core::error::Error for GenError {}
+
+// The seed for a date: FNV-1a over its bytes, once it is checked to be a real date so
+// that each day has exactly one spelling.
+pub fn daily_seed(date: &str) -> Result<Seed, GenError> {
+    if !is_iso_date(date) {
+        return Err(GenError::BadDate);
+    }
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for b in date.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    return Ok(Seed(hash));
+}
+
+// The puzzle of the day for the date and difficulty.
+pub fn generate_daily(date: &str, difficulty: Difficulty) -> Result<Sudoku, GenError> {
+    let mut rng = SolverRng::new(daily_seed(date)?);
+    for _ in 0..DAILY_TRIES {
+        let puzzle = Sudoku::generate_puzzle_with_rng(&mut rng, GeneratorOptions::default());
+        let rating = rate(&puzzle).expect("generated puzzles have one solution");
+        if rating.difficulty() == difficulty {
+            return Ok(puzzle);
+        }
+    }
+    return Err(GenError::NoPuzzle(difficulty));
+}
+
+fn is_iso_date(date: &str) -> bool {
+    let b = date.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return false;
+    }
+    let number = |digits: &[u8]| {
+        return digits.iter().try_fold(0u32, |n, &d| match d {
+            b'0'..=b'9' => Some(n * 10 + (d - b'0') as u32),
+            _ => None,
+        });
+    };
+    let (year, month, day) = match (number(&b[..4]), number(&b[5..7]), number(&b[8..])) {
+        (Some(y), Some(m), Some(d)) => (y, m, d),
+        _ => return false,
+    };
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    return (1..=days).contains(&day);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dates() {
+        for date in ["2024-06-01", "2024-02-29", "2000-02-29", "1999-12-31"] {
+            assert!(daily_seed(date).is_ok(), "{}", date);
+        }
+        for date in [
+            "2024-6-01",
+            "2024-06-1",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-04-31",
+            "2024/06/01",
+            "2024-06-01 ",
+            "",
+        ] {
+            assert_eq!(daily_seed(date), Err(GenError::BadDate), "{}", date);
+        }
+        assert_eq!(daily_seed("2024-06-01"), Ok(Seed(0xC825_9D60_1CCB_EED6)));
+        assert_ne!(daily_seed("2024-06-01"), daily_seed("2024-06-02"));
+    }
+
+    // The same puzzles in every release; see the top of this file.
+    #[test]
+    fn test_golden_dailies() {
+        assert_eq!(
+            format!("{:?}", generate_daily("2024-06-01", Difficulty::Easy).unwrap()),
+            "Sudoku(.3...58....1..3..2.....9.6154.29.....9...8....7..6.......3.7......8..75...3....2.)"
+        );
+        assert_eq!(
+            format!("{:?}", generate_daily("2025-01-01", Difficulty::Expert).unwrap()),
+            "Sudoku(9.34...686............2.......9.......8..34...7...29.672...9......8..5.1.......3.)"
+        );
+    }
+
+    #[test]
+    fn test_difficulty() {
+        for d in [Difficulty::Easy, Difficulty::Medium, Difficulty::Expert] {
+            let puzzle = generate_daily("2024-06-01", d).unwrap();
+            assert_eq!(rate(&puzzle).unwrap().difficulty(), d);
+            assert_eq!(generate_daily("2024-06-01", d), Ok(puzzle));
+        }
+        assert_eq!(
+            generate_daily("June 1st", Difficulty::Easy),
+            Err(GenError::BadDate)
+        );
+    }
+}
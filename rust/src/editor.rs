@@ -0,0 +1,236 @@
+// Editing a puzzle clue by clue, with feedback on whether it still has one solution.
+// Sudoku::with_clue and without_clue make the edits; a PuzzleEditor also keeps a
+// solution found earlier, since most edits leave it valid and it tells a lot without
+// searching again.
+
+use crate::{Conflict, Coord, GridError, Sudoku, UnsolvedSudoku};
+
+// Counting stops here when reporting how many solutions an edited puzzle has.
+pub const EDITOR_SOLUTION_LIMIT: usize = 100;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SolutionStatus {
+    Unique,
+    // At least two solutions: this many, counting no further than EDITOR_SOLUTION_LIMIT.
+    Multiple(usize),
+    NoSolution,
+}
+
+impl Sudoku {
+    // A copy with value as a clue at coord. Refuses a value outside 1-9, a cell that
+    // already holds a different clue, and a value another clue in the same row, column,
+    // or box already has. Setting a clue that is already there is fine.
+    pub fn with_clue(&self, coord: Coord, value: u8) -> Result<Sudoku, Conflict> {
+        let (row, col) = (coord.row(), coord.col());
+        if !(1..=9).contains(&value) {
+            return Err(GridError::ValueOutOfRange(value).into());
+        }
+        match self.get(coord) {
+            0 => {}
+            v if v == value => return Ok(*self),
+            v => return Err(Conflict::AlreadyFilled { row, col, value: v }),
+        }
+        if coord.peers().any(|p| self.get(p) == value) {
+            return Err(Conflict::NotPossible { row, col, value });
+        }
+        let mut s = *self;
+        s[coord] = value;
+        return Ok(s);
+    }
+
+    // A copy with coord blank.
+    pub fn without_clue(&self, coord: Coord) -> Sudoku {
+        let mut s = *self;
+        s.clear(coord);
+        return s;
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PuzzleEditor {
+    puzzle: Sudoku,
+    // Some solution of the current puzzle, if one has been found and no edit has ruled
+    // it out.
+    solution: Option<Sudoku>,
+    // The status of the current puzzle, if known.
+    status: Option<SolutionStatus>,
+}
+
+impl PuzzleEditor {
+    pub fn new(puzzle: Sudoku) -> PuzzleEditor {
+        return PuzzleEditor {
+            puzzle,
+            solution: None,
+            status: None,
+        };
+    }
+
+    pub fn puzzle(&self) -> Sudoku {
+        return self.puzzle;
+    }
+
+    // A solution of the current puzzle, if the last status check found one.
+    pub fn known_solution(&self) -> Option<Sudoku> {
+        return self.solution;
+    }
+
+    // Add a clue, as Sudoku::with_clue. A clue that agrees with the known solution
+    // keeps it, and keeps a unique puzzle unique without searching again.
+    pub fn add_clue(&mut self, coord: Coord, value: u8) -> Result<(), Conflict> {
+        let edited = self.puzzle.with_clue(coord, value)?;
+        if edited == self.puzzle {
+            return Ok(());
+        }
+        self.puzzle = edited;
+        match self.solution {
+            Some(s) if s.get(coord) == value => {
+                if self.status != Some(SolutionStatus::Unique) {
+                    self.status = None;
+                }
+            }
+            _ => {
+                self.solution = None;
+                // More clues can't make an unsolvable puzzle solvable.
+                if self.status != Some(SolutionStatus::NoSolution) {
+                    self.status = None;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // Blank a cell. The known solution still solves the puzzle with fewer clues.
+    pub fn remove_clue(&mut self, coord: Coord) {
+        if self.puzzle.is_empty(coord) {
+            return;
+        }
+        self.puzzle = self.puzzle.without_clue(coord);
+        self.status = None;
+    }
+
+    // Whether the current puzzle has one solution, several, or none. This only searches
+    // when an edit since the last call could have changed the answer.
+    pub fn solution_status(&mut self) -> SolutionStatus {
+        if let Some(status) = self.status {
+            return status;
+        }
+        let mut solutions = UnsolvedSudoku::from(self.puzzle).solutions();
+        let first = solutions.next();
+        let count = match first {
+            None => 0,
+            Some(_) => 1 + solutions.take(EDITOR_SOLUTION_LIMIT - 1).count(),
+        };
+        self.solution = first;
+        let status = match count {
+            0 => SolutionStatus::NoSolution,
+            1 => SolutionStatus::Unique,
+            n => SolutionStatus::Multiple(n),
+        };
+        self.status = Some(status);
+        return status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(row: u8, col: u8) -> Coord {
+        return Coord::new(row, col).unwrap();
+    }
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_with_clue() {
+        let s = moderate();
+        // (1, 1) is blank; row 1 already has a 1 and column 1 a 6.
+        assert_eq!(s.with_clue(c(1, 1), 2).unwrap().get(c(1, 1)), 2);
+        assert_eq!(
+            s.with_clue(c(1, 1), 1),
+            Err(Conflict::NotPossible {
+                row: 1,
+                col: 1,
+                value: 1
+            })
+        );
+        assert!(s.with_clue(c(1, 1), 6).is_err());
+        assert_eq!(
+            s.with_clue(c(1, 4), 2),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 4,
+                value: 1
+            })
+        );
+        assert_eq!(s.with_clue(c(1, 4), 1), Ok(s));
+        assert_eq!(
+            s.with_clue(c(1, 1), 0),
+            Err(Conflict::OutOfRange(GridError::ValueOutOfRange(0)))
+        );
+        assert_eq!(s.without_clue(c(1, 4)).get(c(1, 4)), 0);
+        assert_eq!(s.without_clue(c(1, 4)).with_clue(c(1, 4), 1), Ok(s));
+    }
+
+    #[test]
+    fn test_status() {
+        let mut editor = PuzzleEditor::new(moderate());
+        assert_eq!(editor.solution_status(), SolutionStatus::Unique);
+        let solution = editor.known_solution().unwrap();
+        assert_eq!(Ok(solution), moderate().solve());
+
+        // Filling in from the solution keeps it unique without a search.
+        editor.add_clue(c(1, 1), solution.get(c(1, 1))).unwrap();
+        assert_eq!(editor.status, Some(SolutionStatus::Unique));
+        assert_eq!(editor.solution_status(), SolutionStatus::Unique);
+
+        // Moderate is minimal, so taking one of its clues away loses uniqueness.
+        editor.remove_clue(c(1, 1));
+        editor.remove_clue(c(9, 5));
+        assert_eq!(editor.solution_status(), SolutionStatus::Multiple(2));
+
+        // A clue against the known solution may still leave another one.
+        let known = editor.known_solution().unwrap();
+        let other = UnsolvedSudoku::from(editor.puzzle())
+            .solutions()
+            .find(|&s| s != known)
+            .unwrap();
+        let diff = Coord::all()
+            .find(|&p| known.get(p) != other.get(p))
+            .unwrap();
+        editor.add_clue(diff, other.get(diff)).unwrap();
+        assert_eq!(editor.known_solution(), None);
+        assert_eq!(editor.solution_status(), SolutionStatus::Unique);
+        assert_eq!(editor.known_solution(), Some(other));
+
+        let mut empty = PuzzleEditor::new(Sudoku::empty());
+        assert_eq!(
+            empty.solution_status(),
+            SolutionStatus::Multiple(EDITOR_SOLUTION_LIMIT)
+        );
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let mut editor = PuzzleEditor::new(moderate());
+        // Allowed by the clues, but not by the solution, and nothing else fits.
+        let solution = moderate().solve().unwrap();
+        let wrong = (1..10)
+            .find(|&v| v != solution.get(c(1, 1)) && moderate().with_clue(c(1, 1), v).is_ok())
+            .unwrap();
+        editor.add_clue(c(1, 1), wrong).unwrap();
+        assert_eq!(editor.solution_status(), SolutionStatus::NoSolution);
+        // Further clues keep it unsolvable without a search.
+        let more = (1..10)
+            .find(|&v| editor.puzzle().with_clue(c(1, 2), v).is_ok())
+            .unwrap();
+        editor.add_clue(c(1, 2), more).unwrap();
+        assert_eq!(editor.status, Some(SolutionStatus::NoSolution));
+        editor.remove_clue(c(1, 2));
+        editor.remove_clue(c(1, 1));
+        assert_eq!(editor.solution_status(), SolutionStatus::Unique);
+    }
+}
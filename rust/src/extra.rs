@@ -0,0 +1,304 @@
+// Extra regions: nine-cell units a puzzle adds on top of its rows, columns, and boxes,
+// as asterisk and center-dot sudoku do, read from a file in one of two forms. A list
+// has a region per line, as nine cells written like "r3c7":
+//
+//     r2c5 r3c3 r3c7 r5c2 r5c5 r5c8 r7c3 r7c7 r8c5
+//
+// A mask is nine lines of nine characters, a letter for each cell in a region and '.'
+// for the rest, each letter marking the nine cells of one region:
+//
+//     .........
+//     ....A....
+//     ..A...A..
+//     ...
+//
+// Either form skips blank lines and lines starting with '#'. A file whose first other
+// line has a digit in it is a list, and anything else a mask.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::sdk::parse_coord;
+use crate::{text_lines, Coord, Unit};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ExtraRegionError {
+    // On this line, counting from 1, this isn't a cell r1c1-r9c9.
+    Cell { line: usize, text: String },
+    // This line lists some number of cells other than nine.
+    Count { line: usize, cells: usize },
+    // This line lists the cell more than once.
+    Repeated { line: usize, cell: Coord },
+    // The mask has this many rows rather than nine.
+    RowCount(usize),
+    // This row of the mask, counting from 1, doesn't have nine cells.
+    RowLength(u8),
+    // This cell of the mask is something other than a letter or '.'.
+    Label { row: u8, col: u8, label: char },
+    // This letter marks some number of cells other than nine.
+    Size { label: char, cells: usize },
+}
+
+impl fmt::Display for ExtraRegionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtraRegionError::Cell { line, text } => {
+                write!(f, "line {}: {:?} isn't a cell r1c1-r9c9", line, text)
+            }
+            ExtraRegionError::Count { line, cells } => {
+                write!(f, "line {}: region has {} cells, not 9", line, cells)
+            }
+            ExtraRegionError::Repeated { line, cell } => {
+                write!(f, "line {}: region lists {} twice", line, cell)
+            }
+            ExtraRegionError::RowCount(n) => write!(f, "region mask has {} rows, not 9", n),
+            ExtraRegionError::RowLength(row) => {
+                write!(f, "region mask row {} isn't 9 cells", row)
+            }
+            ExtraRegionError::Label { row, col, label } => {
+                write!(f, "{:?} at r{}c{} isn't a letter or '.'", label, row, col)
+            }
+            ExtraRegionError::Size { label, cells } => {
+                write!(f, "region {} has {} cells, not 9", label, cells)
+            }
+        }
+    }
+}
+
+impl core::error::Error for ExtraRegionError {}
+
+// The regions in the text, as custom units: a list's in the order given, each with its
+// cells in the order listed, or a mask's in alphabetical order, each in row-major
+// order. Lowercase letters in a mask mark the same regions as their capitals.
+pub fn parse_extra_regions(text: &str) -> Result<Vec<Unit>, ExtraRegionError> {
+    let lines: Vec<(usize, &str)> = text_lines(text)
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty() && !l.starts_with('#'))
+        .map(|(ix, l)| (ix + 1, l))
+        .collect();
+    return match lines.first() {
+        Some((_, first)) if !first.contains(|c: char| c.is_ascii_digit()) => parse_mask(&lines),
+        _ => lines
+            .iter()
+            .map(|&(ix, l)| parse_list_line(ix, l))
+            .collect(),
+    };
+}
+
+fn parse_list_line(line: usize, text: &str) -> Result<Unit, ExtraRegionError> {
+    let mut cells = Vec::new();
+    for field in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if field.is_empty() {
+            continue;
+        }
+        let cell = match parse_coord(field) {
+            Some(c) => c,
+            None => {
+                let text = field.to_string();
+                return Err(ExtraRegionError::Cell { line, text });
+            }
+        };
+        if cells.contains(&cell) {
+            return Err(ExtraRegionError::Repeated { line, cell });
+        }
+        cells.push(cell);
+    }
+    return match <[Coord; 9]>::try_from(&cells[..]) {
+        Ok(coords) => Ok(Unit::Custom(coords)),
+        Err(_) => Err(ExtraRegionError::Count {
+            line,
+            cells: cells.len(),
+        }),
+    };
+}
+
+fn parse_mask(lines: &[(usize, &str)]) -> Result<Vec<Unit>, ExtraRegionError> {
+    if lines.len() != 9 {
+        return Err(ExtraRegionError::RowCount(lines.len()));
+    }
+    // Each letter's cells, A first.
+    let mut regions: Vec<Vec<Coord>> = (0..26).map(|_| Vec::new()).collect();
+    for (rix, &(_, row)) in lines.iter().enumerate() {
+        if row.chars().count() != 9 {
+            return Err(ExtraRegionError::RowLength(rix as u8 + 1));
+        }
+        for (cix, ch) in row.chars().enumerate() {
+            let coord = Coord::from_index(rix, cix);
+            match ch.to_ascii_uppercase() {
+                '.' => {}
+                l @ 'A'..='Z' => regions[(l as u8 - b'A') as usize].push(coord),
+                _ => {
+                    return Err(ExtraRegionError::Label {
+                        row: coord.row(),
+                        col: coord.col(),
+                        label: ch,
+                    });
+                }
+            }
+        }
+    }
+    let mut units = Vec::new();
+    for (ix, cells) in regions.iter().enumerate() {
+        if cells.is_empty() {
+            continue;
+        }
+        match <[Coord; 9]>::try_from(&cells[..]) {
+            Ok(coords) => units.push(Unit::Custom(coords)),
+            Err(_) => {
+                return Err(ExtraRegionError::Size {
+                    label: (b'A' + ix as u8) as char,
+                    cells: cells.len(),
+                });
+            }
+        }
+    }
+    return Ok(units);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Sudoku, UnsolvedSudoku};
+
+    const ASTERISK: &str = include_str!("../tests/fixtures/variants/asterisk.txt");
+    const ASTERISK_REGIONS: &str = include_str!("../tests/fixtures/variants/asterisk-regions.txt");
+
+    fn c(row: u8, col: u8) -> Coord {
+        return Coord::new(row, col).unwrap();
+    }
+
+    fn asterisk() -> Unit {
+        let cells = [
+            c(2, 5),
+            c(3, 3),
+            c(3, 7),
+            c(5, 2),
+            c(5, 5),
+            c(5, 8),
+            c(7, 3),
+            c(7, 7),
+            c(8, 5),
+        ];
+        return Unit::Custom(cells);
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_extra_regions(ASTERISK_REGIONS), Ok(vec![asterisk()]));
+        let two = "# the asterisk and the first row\n\n\
+                   r2c5 r3c3 r3c7 r5c2 r5c5 r5c8 r7c3 r7c7 r8c5\n\
+                   r1c1, r1c2, r1c3, r1c4, r1c5, r1c6, r1c7, r1c8, r1c9\n";
+        let row: Vec<Coord> = (1..10).map(|col| c(1, col)).collect();
+        assert_eq!(
+            parse_extra_regions(two),
+            Ok(vec![asterisk(), Unit::Custom(row.try_into().unwrap())])
+        );
+        assert_eq!(parse_extra_regions("# nothing\n"), Ok(vec![]));
+
+        let eight = "r1c1 r1c2 r1c3 r1c4 r1c5 r1c6 r1c7 r1c8";
+        assert_eq!(
+            parse_extra_regions(&format!("\n{}\n", eight)),
+            Err(ExtraRegionError::Count { line: 2, cells: 8 })
+        );
+        let ten = format!("{} r1c9 r2c1", eight);
+        assert_eq!(
+            parse_extra_regions(&ten),
+            Err(ExtraRegionError::Count { line: 1, cells: 10 })
+        );
+        let repeated = format!("{} r1c1", eight);
+        assert_eq!(
+            parse_extra_regions(&repeated),
+            Err(ExtraRegionError::Repeated {
+                line: 1,
+                cell: c(1, 1)
+            })
+        );
+        let outside = format!("{} r1c10", eight);
+        let err = parse_extra_regions(&outside).unwrap_err();
+        assert_eq!(
+            err,
+            ExtraRegionError::Cell {
+                line: 1,
+                text: "r1c10".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "line 1: \"r1c10\" isn't a cell r1c1-r9c9");
+    }
+
+    #[test]
+    fn test_parse_mask() {
+        let mask = "\
+            ....A....\n\
+            ..A...A..\n\
+            .........\n\
+            bBBBBBBBB\n\
+            .A..A..A.\n\
+            .........\n\
+            ..A...A..\n\
+            ....A....\n\
+            .........\n";
+        let star = [
+            c(1, 5),
+            c(2, 3),
+            c(2, 7),
+            c(5, 2),
+            c(5, 5),
+            c(5, 8),
+            c(7, 3),
+            c(7, 7),
+            c(8, 5),
+        ];
+        let row = Unit::Custom(Unit::Row(4).coords());
+        assert_eq!(parse_extra_regions(mask), Ok(vec![Unit::Custom(star), row]));
+
+        let rows: Vec<&str> = mask.lines().collect();
+        assert_eq!(
+            parse_extra_regions(&rows[..8].join("\n")),
+            Err(ExtraRegionError::RowCount(8))
+        );
+        let mut short = rows.clone();
+        short[2] = "........";
+        assert_eq!(
+            parse_extra_regions(&short.join("\n")),
+            Err(ExtraRegionError::RowLength(3))
+        );
+        let mut bad = rows.clone();
+        bad[5] = "...*.....";
+        assert_eq!(
+            parse_extra_regions(&bad.join("\n")),
+            Err(ExtraRegionError::Label {
+                row: 6,
+                col: 4,
+                label: '*'
+            })
+        );
+        let mut small = rows.clone();
+        small[0] = ".........";
+        let err = parse_extra_regions(&small.join("\n")).unwrap_err();
+        assert_eq!(
+            err,
+            ExtraRegionError::Size {
+                label: 'A',
+                cells: 8
+            }
+        );
+        assert_eq!(err.to_string(), "region A has 8 cells, not 9");
+    }
+
+    #[test]
+    fn test_extra_region_makes_unique() {
+        let puzzle: Sudoku = ASTERISK.parse().unwrap();
+        assert!(puzzle.count_solutions(2) > 1);
+        let mut u = UnsolvedSudoku::from(puzzle);
+        for unit in parse_extra_regions(ASTERISK_REGIONS).unwrap() {
+            u.add_unit(unit).unwrap();
+        }
+        let solution = u.solved().unwrap();
+        let mut values: Vec<u8> = asterisk().coords().iter().map(|&c| solution[c]).collect();
+        values.sort();
+        assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    }
+}
@@ -0,0 +1,127 @@
+// Arbitrary grids for fuzzing and property tests. A plain Sudoku can be any pattern of
+// digits, conflicting or not; a ConsistentPuzzle always has at least one solution.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{Coord, Sudoku};
+
+impl<'a> Arbitrary<'a> for Sudoku {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Sudoku> {
+        let mut s = Sudoku::empty();
+        for c in Coord::all() {
+            s[c] = u.int_in_range(0..=9)?;
+        }
+        return Ok(s);
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        return (81, Some(81));
+    }
+}
+
+// Some clues of a valid solution, along with that solution.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ConsistentPuzzle {
+    pub puzzle: Sudoku,
+    pub solution: Sudoku,
+}
+
+impl<'a> Arbitrary<'a> for ConsistentPuzzle {
+    // Start from a fixed solution, shuffle it with moves that keep it valid (relabelling
+    // digits, swapping rows within a band and bands within the grid, the same for
+    // columns, and transposing), then blank out some cells.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<ConsistentPuzzle> {
+        let digits = permutation::<9>(u)?;
+        let rows = line_order(u)?;
+        let cols = line_order(u)?;
+        let transpose = bool::arbitrary(u)?;
+
+        let mut solution = Sudoku::empty();
+        for c in Coord::all() {
+            let (mut i, mut j) = (rows[c.row() as usize - 1], cols[c.col() as usize - 1]);
+            if transpose {
+                (i, j) = (j, i);
+            }
+            // A standard valid grid: each row is the one above shifted by three, and
+            // each band by one more.
+            let base = (3 * (i % 3) + i / 3 + j) % 9;
+            solution[c] = digits[base] as u8 + 1;
+        }
+
+        let mut puzzle = solution;
+        for c in Coord::all() {
+            if bool::arbitrary(u)? {
+                puzzle.clear(c);
+            }
+        }
+        return Ok(ConsistentPuzzle { puzzle, solution });
+    }
+}
+
+// A permutation of 0..N.
+fn permutation<const N: usize>(u: &mut Unstructured) -> Result<[usize; N]> {
+    let mut out: [usize; N] = core::array::from_fn(|i| i);
+    for i in (1..N).rev() {
+        let j = u.int_in_range(0..=i)?;
+        out.swap(i, j);
+    }
+    return Ok(out);
+}
+
+// An order for the nine rows (or columns) that keeps each band of three together.
+fn line_order(u: &mut Unstructured) -> Result<[usize; 9]> {
+    let bands = permutation::<3>(u)?;
+    let mut out = [0; 9];
+    for (b, &band) in bands.iter().enumerate() {
+        let within = permutation::<3>(u)?;
+        for (k, &w) in within.iter().enumerate() {
+            out[b * 3 + k] = band * 3 + w;
+        }
+    }
+    return Ok(out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnsolvedSudoku;
+
+    fn bytes(seed: u64) -> Vec<u8> {
+        // A small xorshift, so the tests don't need a random number crate.
+        let mut x = seed | 1;
+        return (0..512)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_sudoku() {
+        for seed in 0..20 {
+            let data = bytes(seed);
+            let s = Sudoku::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            assert!(Coord::all().all(|c| s.get(c) <= 9));
+        }
+        // Running out of bytes fills with zeros rather than failing.
+        let s = Sudoku::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(s, Sudoku::empty());
+    }
+
+    #[test]
+    fn test_consistent_puzzle() {
+        for seed in 0..20 {
+            let data = bytes(seed);
+            let p = ConsistentPuzzle::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            assert!(p.solution.is_complete());
+            assert!(UnsolvedSudoku::from(p.solution).valid());
+            for c in Coord::all() {
+                assert!(p.puzzle.get(c) == 0 || p.puzzle.get(c) == p.solution.get(c));
+            }
+            assert!(UnsolvedSudoku::from(p.puzzle).solutions().next().is_some());
+        }
+    }
+}
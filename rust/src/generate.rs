@@ -0,0 +1,397 @@
+// Making new grids. Randomness comes from a seed or a SolverRng rather than a random
+// number crate, so the same seed always gives the same grid.
+
+use alloc::vec::Vec;
+
+use crate::{Coord, Seed, SolverRng, Sudoku, UnsolvedSudoku};
+
+// Which cells must be blanked together, so the clues keep a pattern.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Symmetry {
+    #[default]
+    None,
+    // Unchanged by a half turn about the centre cell.
+    Rotational,
+    // Unchanged by swapping left and right.
+    Mirror,
+}
+
+impl Symmetry {
+    // The cell coord is paired with, which may be itself.
+    fn partner(self, coord: Coord) -> Coord {
+        let (r, c) = (coord.row(), coord.col());
+        let (r, c) = match self {
+            Symmetry::None => (r, c),
+            Symmetry::Rotational => (10 - r, 10 - c),
+            Symmetry::Mirror => (r, 10 - c),
+        };
+        return Coord::new(r, c).expect("reflections stay on the grid");
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct GeneratorOptions {
+    // Stop removing clues once this few are left. None keeps going until no clue (or
+    // symmetric pair) can be removed without losing uniqueness.
+    pub target_clues: Option<u8>,
+    pub symmetry: Symmetry,
+    // The most removals to try, each of which costs a uniqueness check.
+    pub max_attempts: u32,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> GeneratorOptions {
+        return GeneratorOptions {
+            target_clues: None,
+            symmetry: Symmetry::None,
+            max_attempts: 81,
+        };
+    }
+}
+
+impl Sudoku {
+    // A random complete, valid grid, the same for the same seed. It is found by the
+    // usual guess-and-deduce search over an empty grid, trying each cell's candidates in
+    // a random order, so it always succeeds.
+    pub fn generate_solution(seed: impl Into<Seed>) -> Sudoku {
+        return Sudoku::generate_solution_with_rng(&mut SolverRng::new(seed));
+    }
+
+    // Like generate_solution, drawing from rng.
+    pub fn generate_solution_with_rng(rng: &mut SolverRng) -> Sudoku {
+        return solution(rng);
+    }
+
+    // A random puzzle with exactly one solution, the same for the same seed and options.
+    // Starting from generate_solution(seed), clues are blanked in a random order,
+    // keeping each removal only if the puzzle stays unique. There may be more clues than
+    // the target if no further removal keeps it unique, or if max_attempts runs out.
+    pub fn generate_puzzle(seed: impl Into<Seed>, options: GeneratorOptions) -> Sudoku {
+        return Sudoku::generate_puzzle_with_rng(&mut SolverRng::new(seed), options);
+    }
+
+    // Like generate_puzzle, drawing from rng.
+    pub fn generate_puzzle_with_rng(rng: &mut SolverRng, options: GeneratorOptions) -> Sudoku {
+        return generate_counting(rng, options).0;
+    }
+
+    // A copy of this puzzle with clues removed until none can go without the puzzle
+    // losing its unique solution. Clues are tried in row-major order, or in a random
+    // order fixed by the seed; different orders can leave different minimal puzzles.
+    // A puzzle without exactly one solution is returned unchanged.
+    pub fn minimize(&self, seed: Option<u64>) -> Sudoku {
+        return match seed {
+            Some(seed) => self.minimize_with_rng(&mut SolverRng::new(seed)),
+            None => self.minimize_in_order(Coord::all().collect()),
+        };
+    }
+
+    // Like minimize, trying clues in an order drawn from rng.
+    pub fn minimize_with_rng(&self, rng: &mut SolverRng) -> Sudoku {
+        return self.minimize_in_order(shuffled_coords(rng));
+    }
+
+    fn minimize_in_order(&self, order: Vec<Coord>) -> Sudoku {
+        if self.count_solutions(2) != 1 {
+            return *self;
+        }
+        let options = GeneratorOptions {
+            max_attempts: u32::MAX,
+            ..Default::default()
+        };
+        return blank_clues(*self, &order, options).0;
+    }
+
+    // The clues, in row-major order, that could each be removed on its own without the
+    // puzzle losing its unique solution. Removing several at once may still lose it.
+    // Empty if the puzzle doesn't have exactly one solution to begin with.
+    pub fn redundant_clues(&self) -> Vec<Coord> {
+        if self.count_solutions(2) != 1 {
+            return Vec::new();
+        }
+        return Coord::all()
+            .filter(|&c| !self.is_empty(c))
+            .filter(|&c| {
+                let mut fewer = *self;
+                fewer.clear(c);
+                return fewer.count_solutions(2) == 1;
+            })
+            .collect();
+    }
+
+    // Whether this is a minimal (irreducible) puzzle: it has exactly one solution, and
+    // taking away any one clue would leave it with more. This stops at the first clue
+    // that could go, so it is quicker than checking redundant_clues is empty.
+    pub fn is_minimal(&self) -> bool {
+        if self.count_solutions(2) != 1 {
+            return false;
+        }
+        return self.cells().filter(|&(_, v)| v != 0).all(|(c, _)| {
+            let mut fewer = *self;
+            fewer.clear(c);
+            return fewer.count_solutions(2) > 1;
+        });
+    }
+
+    // Up to count minimal puzzles with this puzzle's solution, each a subset of its
+    // clues, from running minimize with removal orders drawn from one SolverRng made
+    // from the seed. Puzzles with the same canonical form count once, keeping the first
+    // found. Fewer come back if 4 * count tries turn up no more, and none if this puzzle
+    // doesn't have exactly one solution.
+    pub fn minimal_reductions(&self, seed: impl Into<Seed>, count: usize) -> Vec<Sudoku> {
+        let mut found: Vec<Sudoku> = Vec::new();
+        let mut forms: Vec<Sudoku> = Vec::new();
+        if count == 0 || self.count_solutions(2) != 1 {
+            return found;
+        }
+        let mut rng = SolverRng::new(seed);
+        for _ in 0..4 * count {
+            let m = self.minimize_with_rng(&mut rng);
+            let form = m.canonical_form();
+            if !forms.contains(&form) {
+                forms.push(form);
+                found.push(m);
+                if found.len() == count {
+                    break;
+                }
+            }
+        }
+        return found;
+    }
+}
+
+fn shuffled_coords(rng: &mut SolverRng) -> Vec<Coord> {
+    let mut order: Vec<Coord> = Coord::all().collect();
+    rng.shuffle(&mut order);
+    return order;
+}
+
+// Like generate_puzzle_with_rng, also returning how many removals it tried.
+pub(crate) fn generate_counting(rng: &mut SolverRng, options: GeneratorOptions) -> (Sudoku, u32) {
+    let puzzle = solution(rng);
+    return blank_clues(puzzle, &shuffled_coords(rng), options);
+}
+
+// Try blanking each clue (with its partner under the symmetry) in turn, keeping each
+// removal that leaves the puzzle unique. One pass is enough: a clue that can't go now
+// can't go later either, since later removals only allow more solutions. Also returns
+// the number of removals tried, each of which cost a uniqueness check.
+fn blank_clues(mut puzzle: Sudoku, order: &[Coord], options: GeneratorOptions) -> (Sudoku, u32) {
+    let target = options.target_clues.unwrap_or(0) as usize;
+    let mut attempts = 0;
+    for &c in order {
+        if puzzle.clue_count() <= target || attempts >= options.max_attempts {
+            break;
+        }
+        let partner = options.symmetry.partner(c);
+        // A blank cell went along with its partner earlier.
+        if puzzle.is_empty(c) {
+            continue;
+        }
+        let removed = if partner == c { 1 } else { 2 };
+        if puzzle.clue_count() < target + removed {
+            continue;
+        }
+        attempts += 1;
+        let mut fewer = puzzle;
+        fewer.clear(c);
+        fewer.clear(partner);
+        if fewer.count_solutions(2) == 1 {
+            puzzle = fewer;
+        }
+    }
+    return (puzzle, attempts);
+}
+
+fn solution(rng: &mut SolverRng) -> Sudoku {
+    return match fill(UnsolvedSudoku::empty(), rng) {
+        Some(s) => s,
+        None => unreachable!("an empty grid always has a solution"),
+    };
+}
+
+fn fill(mut u: UnsolvedSudoku, rng: &mut SolverRng) -> Option<Sudoku> {
+    u.simple_solve();
+    let (coord, ps) = match u.min_candidates_cell() {
+        None if u.valid() => return Some(u.finished()),
+        None => return None,
+        Some(v) => v,
+    };
+    let mut order: [u8; 9] = [0; 9];
+    let n = ps.len();
+    for (o, p) in order.iter_mut().zip(ps.iter()) {
+        *o = p;
+    }
+    rng.shuffle(&mut order[..n]);
+    for &p in &order[..n] {
+        let mut u2 = u.clone();
+        u2.set(coord.row(), coord.col(), p);
+        if let Some(s) = fill(u2, rng) {
+            return Some(s);
+        }
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_solution() {
+        let mut seen = Vec::new();
+        for seed in 0..20 {
+            let s = Sudoku::generate_solution(seed);
+            assert!(Sudoku::empty().verifies(&s), "seed {}: {:?}", seed, s);
+            assert_eq!(s, Sudoku::generate_solution(seed));
+            seen.push(s);
+        }
+        seen.sort_by_key(|s| s.to_string());
+        seen.dedup();
+        assert_eq!(seen.len(), 20);
+        assert_eq!(
+            Sudoku::generate_solution(u64::MAX),
+            Sudoku::generate_solution(u64::MAX)
+        );
+    }
+
+    // Seeds must make the same grids in every release; see rng.rs.
+    #[test]
+    fn test_golden_grids() {
+        assert_eq!(
+            format!("{:?}", Sudoku::generate_solution(0)),
+            "Sudoku(317246985564389217928571463671893524259164378483752691136427859795638142842915736)"
+        );
+        // One rng carried through several calls gives a new grid each time.
+        let mut rng = SolverRng::new(0);
+        let first = Sudoku::generate_solution_with_rng(&mut rng);
+        assert_eq!(first, Sudoku::generate_solution(0));
+        assert_ne!(Sudoku::generate_solution_with_rng(&mut rng), first);
+
+        let options = GeneratorOptions {
+            symmetry: Symmetry::Rotational,
+            ..Default::default()
+        };
+        assert_eq!(
+            format!("{:?}", Sudoku::generate_puzzle(1, options)),
+            "Sudoku(.893...2.2......1.3.5..27.....5......924.613......9.....67..9.1.1......8.4...827.)"
+        );
+    }
+
+    fn assert_symmetric(s: &Sudoku, symmetry: Symmetry) {
+        for c in Coord::all() {
+            assert_eq!(s.is_empty(c), s.is_empty(symmetry.partner(c)), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_generate_puzzle() {
+        for symmetry in [Symmetry::None, Symmetry::Rotational, Symmetry::Mirror] {
+            for seed in 0..4 {
+                let options = GeneratorOptions {
+                    symmetry,
+                    ..Default::default()
+                };
+                let s = Sudoku::generate_puzzle(seed, options);
+                assert_eq!(s.count_solutions(2), 1);
+                assert_eq!(s.solve(), Ok(Sudoku::generate_solution(seed)));
+                assert_symmetric(&s, symmetry);
+                assert_eq!(s, Sudoku::generate_puzzle(seed, options));
+
+                let options = GeneratorOptions {
+                    target_clues: Some(40),
+                    ..options
+                };
+                let s = Sudoku::generate_puzzle(seed, options);
+                assert_eq!(s.count_solutions(2), 1);
+                assert_symmetric(&s, symmetry);
+                // Forty clues is comfortably above where removals start to fail. A pair
+                // that would go below the target is skipped, which can leave one extra.
+                assert!((40..=41).contains(&s.clue_count()), "{:?}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimize() {
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert!(moderate.is_minimal());
+        // Moderate is already minimal, so pad it with some of its solution.
+        let solved = moderate.solve().unwrap();
+        let mut s = moderate;
+        for (r, c) in [(1, 1), (2, 1), (5, 5), (8, 2), (9, 9)] {
+            let c = Coord::new(r, c).unwrap();
+            s[c] = solved[c];
+        }
+        let redundant = s.redundant_clues();
+        assert!(!redundant.is_empty());
+        for seed in [None, Some(1), Some(2)] {
+            let m = s.minimize(seed);
+            assert_eq!(m.count_solutions(2), 1);
+            assert_eq!(m.solve(), s.solve());
+            assert!(s.cells().all(|(c, v)| m.get(c) == 0 || m.get(c) == v));
+            assert!(m.clue_count() < s.clue_count());
+            assert!(m.is_minimal());
+            assert_eq!(m.redundant_clues(), vec![]);
+            assert_eq!(m, s.minimize(seed));
+            // Whatever was removed was redundant on its own at the start.
+            assert!(s
+                .cells()
+                .filter(|&(c, v)| v != 0 && m.is_empty(c))
+                .all(|(c, _)| redundant.contains(&c)));
+        }
+
+        assert_eq!(moderate.minimize(None), moderate);
+        let mut ambiguous = moderate;
+        ambiguous.clear(Coord::new(9, 5).unwrap());
+        assert_eq!(ambiguous.minimize(None), ambiguous);
+        assert_eq!(ambiguous.redundant_clues(), vec![]);
+        // Minimal puzzles must be unique, so neither of these is one.
+        assert!(!ambiguous.is_minimal());
+        assert!(!s.is_minimal());
+        assert!(!Sudoku::empty().is_minimal());
+        assert!(solved.minimize(Some(3)).is_minimal());
+    }
+
+    #[test]
+    fn test_minimal_reductions() {
+        let solution = Sudoku::generate_solution(5);
+        let found = solution.minimal_reductions(0, 4);
+        assert_eq!(found.len(), 4);
+        for (i, m) in found.iter().enumerate() {
+            assert!(m.is_minimal());
+            assert_eq!(m.solve(), Ok(solution));
+            for other in &found[..i] {
+                assert!(!crate::are_isomorphic(m, other));
+            }
+        }
+        assert_eq!(found, solution.minimal_reductions(0, 4));
+        assert_eq!(found[..2], solution.minimal_reductions(0, 2)[..]);
+
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        // Already minimal, so every try gives the same puzzle back.
+        assert_eq!(moderate.minimal_reductions(0, 3), vec![moderate]);
+        assert_eq!(Sudoku::empty().minimal_reductions(0, 3), vec![]);
+        assert_eq!(solution.minimal_reductions(0, 0), vec![]);
+    }
+
+    #[test]
+    fn test_generate_puzzle_minimal() {
+        // With no target and enough attempts, no single clue can be removed.
+        assert!(Sudoku::generate_puzzle(3, GeneratorOptions::default()).is_minimal());
+
+        let options = GeneratorOptions {
+            max_attempts: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            Sudoku::generate_puzzle(3, options),
+            Sudoku::generate_solution(3)
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// Turning the paths on the command line into puzzle files. A plain file is taken as it
+// is, whatever its name. A directory stands for the puzzle files in it, and a pattern
+// with * or ? in its last part for the puzzle files it matches. Puzzle files are those
+// ending in one of PUZZLE_EXTENSIONS; names starting with '.' are always skipped. Files
+// from a directory or pattern come sorted by path, so the output is the same whatever
+// order the file system lists them in.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PUZZLE_EXTENSIONS: [&str; 3] = ["txt", "sdk", "sdm"];
+
+// What one command-line path stood for.
+#[derive(Debug)]
+pub struct Expanded {
+    pub files: Vec<String>,
+    // Whether the path named a directory or pattern rather than a single file.
+    pub many: bool,
+}
+
+pub fn expand(arg: &str, recursive: bool) -> io::Result<Expanded> {
+    if arg == "-" {
+        return Ok(Expanded {
+            files: vec![arg.to_string()],
+            many: false,
+        });
+    }
+    let path = Path::new(arg);
+    let files = if is_pattern(arg) {
+        glob(path)?
+    } else if path.is_dir() {
+        puzzle_files(path, recursive)?
+    } else {
+        return Ok(Expanded {
+            files: vec![arg.to_string()],
+            many: false,
+        });
+    };
+    if files.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No puzzle files found.",
+        ));
+    }
+    return Ok(Expanded {
+        files: files.iter().map(|p| p.display().to_string()).collect(),
+        many: true,
+    });
+}
+
+// The puzzle files in dir, and with recursive in every directory below it, sorted by
+// path.
+pub fn puzzle_files(dir: &Path, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    walk(dir, recursive, &mut found)?;
+    found.sort();
+    return Ok(found);
+}
+
+fn walk(dir: &Path, recursive: bool, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            if recursive {
+                walk(&path, recursive, found)?;
+            }
+        } else if is_puzzle_file(&path) {
+            found.push(path);
+        }
+    }
+    return Ok(());
+}
+
+// The puzzle files in the pattern's directory whose names match its last part.
+fn glob(pattern: &Path) -> io::Result<Vec<PathBuf>> {
+    let name = pattern
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let dir = match pattern.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    if dir.to_str().is_some_and(is_pattern) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Only the last part of a path can have * or ?.",
+        ));
+    }
+    let mut found: Vec<PathBuf> = puzzle_files(dir, false)?
+        .into_iter()
+        .filter(|p| {
+            let file = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            return matches(name.as_bytes(), file.as_bytes());
+        })
+        .collect();
+    found.sort();
+    return Ok(found);
+}
+
+fn is_pattern(arg: &str) -> bool {
+    return arg.contains(['*', '?']);
+}
+
+fn is_hidden(path: &Path) -> bool {
+    return path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'));
+}
+
+fn is_puzzle_file(path: &Path) -> bool {
+    return path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| PUZZLE_EXTENSIONS.contains(&e));
+}
+
+// Whether name matches pattern, where * is any run of characters and ? any one.
+fn matches(pattern: &[u8], name: &[u8]) -> bool {
+    return match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+        Some((&c, rest)) => name.first() == Some(&c) && matches(rest, &name[1..]),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Holds a.txt, b.sdk, c.sdm, notes.md, .hidden.txt, sub/d.txt, and
+    // sub/deeper/e.txt, all empty.
+    fn dir() -> PathBuf {
+        return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/inputs");
+    }
+
+    fn names(files: &[PathBuf]) -> Vec<String> {
+        let dir = dir();
+        return files
+            .iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+    }
+
+    #[test]
+    fn test_puzzle_files() {
+        assert_eq!(
+            names(&puzzle_files(&dir(), false).unwrap()),
+            ["a.txt", "b.sdk", "c.sdm"]
+        );
+        assert_eq!(
+            names(&puzzle_files(&dir(), true).unwrap()),
+            ["a.txt", "b.sdk", "c.sdm", "sub/d.txt", "sub/deeper/e.txt"]
+        );
+        assert!(puzzle_files(&dir().join("missing"), false).is_err());
+    }
+
+    #[test]
+    fn test_glob() {
+        assert_eq!(names(&glob(&dir().join("*.txt")).unwrap()), ["a.txt"]);
+        assert_eq!(
+            names(&glob(&dir().join("?.s*")).unwrap()),
+            ["b.sdk", "c.sdm"]
+        );
+        // Patterns only pick out puzzle files.
+        assert_eq!(names(&glob(&dir().join("*")).unwrap()).len(), 3);
+        assert!(glob(&dir().join("*/d.txt")).is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let dir = dir().display().to_string();
+        let e = expand(&dir, true).unwrap();
+        assert!(e.many);
+        assert_eq!(e.files.len(), 5);
+        assert!(e.files[0].ends_with("a.txt"));
+
+        let single = format!("{}/notes.md", dir);
+        let e = expand(&single, false).unwrap();
+        assert!(!e.many);
+        assert_eq!(e.files, [single]);
+        assert_eq!(expand("-", true).unwrap().files, ["-"]);
+
+        let none = expand(&format!("{}/*.xml", dir), false).unwrap_err();
+        assert_eq!(none.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(b"*.txt", b"a.txt"));
+        assert!(matches(b"*.txt", b".txt"));
+        assert!(!matches(b"*.txt", b"a.txt.bak"));
+        assert!(matches(b"p?-*", b"p1-easy"));
+        assert!(!matches(b"p?-*", b"p-easy"));
+        assert!(matches(b"*", b""));
+        assert!(!matches(b"?", b""));
+    }
+}
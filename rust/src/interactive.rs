@@ -0,0 +1,234 @@
+// The interactive subcommand, which edit also runs: a loop that reads one command per
+// line from stdin and works on a single puzzle. Cells are named as in hints, r4c7 for
+// row 4, column 7. Every command that changes the puzzle first saves it, so undo can
+// step back through them all. A command that can't be carried out says why and changes
+// nothing.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use sudokusolver::{parse_coord, Coord, PuzzleEditor, Sudoku, Unsolveable, UnsolvedSudoku};
+
+use crate::{print_status, read_puzzle};
+
+const HELP: &str = "\
+Commands:
+  set r4c7 3   put clue 3 in row 4, column 7
+  clear r4c7   remove the clue in row 4, column 7
+  show         print the puzzle
+  candidates   list what each blank cell can still hold
+  hint         name a cell that a single forces, and its value
+  solve        fill in the solution, if there is just one
+  undo         take back the last change
+  load FILE    start over with the puzzle in FILE
+  reset        go back to the puzzle as it was loaded
+  quit         stop";
+
+struct Session {
+    editor: PuzzleEditor,
+    // The puzzle as loaded, for reset.
+    start: Sudoku,
+    // The puzzle before each change, the latest last.
+    undo: Vec<Sudoku>,
+}
+
+impl Session {
+    // Carry out one command line. Returns false for quit.
+    fn run(&mut self, line: &str) -> Result<bool, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] => {}
+            ["set", cell, value] => {
+                let coord = parse_cell(cell)?;
+                let value = parse_value(value)?;
+                let before = self.editor.puzzle();
+                self.editor
+                    .add_clue(coord, value)
+                    .map_err(|e| e.to_string())?;
+                self.changed(before);
+            }
+            ["clear", cell] => {
+                let coord = parse_cell(cell)?;
+                if self.editor.puzzle().is_empty(coord) {
+                    return Err(format!("{} is already blank.", coord));
+                }
+                let before = self.editor.puzzle();
+                self.editor.remove_clue(coord);
+                self.changed(before);
+            }
+            ["show"] => println!("{:#}", self.editor.puzzle()),
+            ["candidates"] => self.candidates(),
+            ["hint"] => match self.editor.puzzle().forced_placements().first() {
+                Some((row, col, value, technique)) => {
+                    println!("r{}c{} = {} ({})", row, col, value, technique)
+                }
+                None => println!("No cell is forced by a single."),
+            },
+            ["solve"] => {
+                let before = self.editor.puzzle();
+                let solution = before.solve().map_err(|e| match e {
+                    Unsolveable::MultipleSolutions(..) => {
+                        "The puzzle has more than one solution.".to_string()
+                    }
+                    _ => "The puzzle has no solution.".to_string(),
+                })?;
+                self.editor = PuzzleEditor::new(solution);
+                self.undo.push(before);
+                println!("{:#}", solution);
+            }
+            ["undo"] => {
+                let previous = self.undo.pop().ok_or("Nothing to undo.")?;
+                self.editor = PuzzleEditor::new(previous);
+                print_status(self.editor.solution_status());
+            }
+            ["load", filename] => {
+                let puzzle = read_puzzle(filename).map_err(|e| format!("{}: {}", filename, e))?;
+                self.undo.push(self.editor.puzzle());
+                self.start = puzzle;
+                self.editor = PuzzleEditor::new(puzzle);
+                print_status(self.editor.solution_status());
+            }
+            ["reset"] => {
+                self.undo.push(self.editor.puzzle());
+                self.editor = PuzzleEditor::new(self.start);
+                print_status(self.editor.solution_status());
+            }
+            ["quit"] => return Ok(false),
+            ["help"] => println!("{}", HELP),
+            // A known command with the wrong words after it gets its line of the help.
+            [command, ..] => {
+                let usage = HELP
+                    .lines()
+                    .skip(1)
+                    .map(|l| l.trim().split("  ").next().unwrap_or_default())
+                    .find(|u| u.split(' ').next() == Some(command));
+                return Err(match usage {
+                    Some(u) => format!("Usage: {}", u),
+                    None => format!("Unknown command {:?}; help lists the commands.", command),
+                });
+            }
+        }
+        return Ok(true);
+    }
+
+    // Save the puzzle from before an edit for undo, unless the edit changed nothing, and
+    // report on the new one.
+    fn changed(&mut self, before: Sudoku) {
+        if self.editor.puzzle() != before {
+            self.undo.push(before);
+        }
+        print_status(self.editor.solution_status());
+    }
+
+    fn candidates(&self) {
+        let puzzle = self.editor.puzzle();
+        let u = UnsolvedSudoku::from(puzzle);
+        if !u.valid() {
+            println!("The clues conflict.");
+            return;
+        }
+        for c in Coord::all().filter(|&c| puzzle.is_empty(c)) {
+            let values: Vec<String> = match u.candidates(c) {
+                Some(ps) => ps.iter().map(|v| v.to_string()).collect(),
+                None => u
+                    .fixed_value(c)
+                    .into_iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            };
+            println!("{}: {}", c, values.join(" "));
+        }
+    }
+}
+
+pub fn interactive(file: Option<String>) -> Result<(), io::Error> {
+    let puzzle = match file {
+        Some(filename) => read_puzzle(&filename)?,
+        None => Sudoku::empty(),
+    };
+    let mut session = Session {
+        editor: PuzzleEditor::new(puzzle),
+        start: puzzle,
+        undo: Vec::new(),
+    };
+    print_status(session.editor.solution_status());
+    // Only prompt a person at a terminal, so a script's transcript is just the replies.
+    let prompt = io::stdin().is_terminal();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        match session.run(&line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("{}", e),
+        }
+    }
+    return Ok(());
+}
+
+// A cell named like r4c7, in either case.
+fn parse_cell(word: &str) -> Result<Coord, String> {
+    return parse_coord(&word.to_ascii_lowercase())
+        .ok_or_else(|| format!("Expected a cell like r4c7, not {:?}.", word));
+}
+
+fn parse_value(word: &str) -> Result<u8, String> {
+    return match word.parse() {
+        Ok(v @ 1..=9) => Ok(v),
+        _ => Err(format!("Expected a value from 1 to 9, not {:?}.", word)),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cell() {
+        assert_eq!(parse_cell("r4c7"), Ok(Coord::new(4, 7).unwrap()));
+        assert_eq!(parse_cell("R9C1"), Ok(Coord::new(9, 1).unwrap()));
+        for bad in ["r0c1", "r4c10", "4c7", "r4", "rxc7", "r4c7x", ""] {
+            assert_eq!(
+                parse_cell(bad),
+                Err(format!("Expected a cell like r4c7, not {:?}.", bad))
+            );
+        }
+        assert_eq!(parse_value("9"), Ok(9));
+        assert!(parse_value("0").is_err() && parse_value("x").is_err());
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut session = Session {
+            editor: PuzzleEditor::new(Sudoku::empty()),
+            start: Sudoku::empty(),
+            undo: Vec::new(),
+        };
+        assert_eq!(session.run("set r1c1 5"), Ok(true));
+        assert_eq!(session.run("set r1c1 5"), Ok(true));
+        assert_eq!(session.run("set r1c2 6"), Ok(true));
+        assert!(session.run("set r1c3 5").is_err());
+        assert!(session.run("clear r2c2").is_err());
+        assert_eq!(session.undo.len(), 2);
+        session.run("undo").unwrap();
+        assert_eq!(session.editor.puzzle().clue_count(), 1);
+        session.run("undo").unwrap();
+        assert_eq!(session.editor.puzzle(), Sudoku::empty());
+        assert_eq!(session.run("undo"), Err("Nothing to undo.".to_string()));
+        assert_eq!(
+            session.run("set r1c1"),
+            Err("Usage: set r4c7 3".to_string())
+        );
+        assert!(session
+            .run("frobnicate")
+            .unwrap_err()
+            .starts_with("Unknown command"));
+        assert_eq!(session.run("quit"), Ok(false));
+    }
+}
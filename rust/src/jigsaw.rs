@@ -0,0 +1,362 @@
+// Jigsaw sudoku, where nine irregular regions of nine cells take the place of the
+// squares. A region map is nine lines of nine letters A-I, each the region of its cell:
+//
+//     AAABBBCCC
+//     AADDBBCCC
+//     ...
+//
+// Region A stands in for square 1, B for square 2, and so on. Published puzzles' regions
+// are always connected, but nothing here depends on it, so that isn't checked.
+
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{text_lines, Coord, Sudoku, Unit};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Regions {
+    // Each cell's region, 0-8 for A-I.
+    labels: [[u8; 9]; 9],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RegionError {
+    // The map has this many rows rather than nine.
+    RowCount(usize),
+    // This row, counting from 1, doesn't have nine cells.
+    RowLength(u8),
+    // This cell is labelled with something other than a letter A-I.
+    Label { row: u8, col: u8, label: char },
+    // This region has some number of cells other than nine; a label the map never uses
+    // has none.
+    Size { label: char, cells: usize },
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegionError::RowCount(n) => write!(f, "region map has {} rows, not 9", n),
+            RegionError::RowLength(row) => write!(f, "region map row {} isn't 9 cells", row),
+            RegionError::Label { row, col, label } => {
+                write!(
+                    f,
+                    "region {:?} at r{}c{} isn't a letter A-I",
+                    label, row, col
+                )
+            }
+            RegionError::Size { label, cells } => {
+                write!(f, "region {} has {} cells, not 9", label, cells)
+            }
+        }
+    }
+}
+
+impl core::error::Error for RegionError {}
+
+impl Regions {
+    // The nine squares, labelled A-I left to right and then top to bottom: the regions
+    // of a classic grid.
+    pub fn classic() -> Regions {
+        let labels = core::array::from_fn(|r| core::array::from_fn(|c| (r / 3 * 3 + c / 3) as u8));
+        return Regions { labels };
+    }
+
+    // The region holding the cell, numbered 1-9 for A-I.
+    pub fn region(&self, coord: Coord) -> u8 {
+        let (r, c) = coord.index();
+        return self.labels[r][c] + 1;
+    }
+
+    // The regions A-I as custom units, each with its cells in row-major order.
+    pub fn units(&self) -> [Unit; 9] {
+        return core::array::from_fn(|ix| {
+            let mut cells = Coord::all().filter(|&c| self.region(c) == ix as u8 + 1);
+            return Unit::Custom(core::array::from_fn(|_| cells.next().unwrap()));
+        });
+    }
+
+    // The grid drawn with '.' for blanks in 19 lines of 37 characters, walled in and
+    // with walls between cells of different regions:
+    //
+    //     +---+---+---+---+---+---+---+---+---+
+    //     | 3   .   . | .   .   4   . | .   1 |
+    //     +   +---+   +---+   +   +---+   +   +
+    //     ...
+    pub fn bordered(&self, s: &Sudoku) -> String {
+        let label = |r: usize, c: usize| self.labels[r][c];
+        // A wall above row r, or below the last one, in column c.
+        let across = |r: usize, c: usize| r == 0 || r == 9 || label(r - 1, c) != label(r, c);
+        // A wall left of column c, or right of the last one, in row r.
+        let down = |r: usize, c: usize| c == 0 || c == 9 || label(r, c - 1) != label(r, c);
+        let mut out = String::new();
+        for r in 0..=9 {
+            for c in 0..=9 {
+                let joined = (c > 0 && across(r, c - 1))
+                    || (c < 9 && across(r, c))
+                    || (r > 0 && down(r - 1, c))
+                    || (r < 9 && down(r, c));
+                out.push(if joined { '+' } else { ' ' });
+                if c < 9 {
+                    out.push_str(if across(r, c) { "---" } else { "   " });
+                }
+            }
+            if r == 9 {
+                break;
+            }
+            out.push('\n');
+            for c in 0..=9 {
+                out.push(if down(r, c) { '|' } else { ' ' });
+                if c < 9 {
+                    let v = s.rows[r][c];
+                    out.push(' ');
+                    out.push(if v == 0 { '.' } else { (b'0' + v) as char });
+                    out.push(' ');
+                }
+            }
+            out.push('\n');
+        }
+        return out;
+    }
+}
+
+impl FromStr for Regions {
+    type Err = RegionError;
+
+    // Blank lines and spaces around each row are skipped, and lowercase labels read as
+    // their capitals.
+    fn from_str(text: &str) -> Result<Regions, RegionError> {
+        let rows: alloc::vec::Vec<&str> = text_lines(text)
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        if rows.len() != 9 {
+            return Err(RegionError::RowCount(rows.len()));
+        }
+        let mut labels = [[0; 9]; 9];
+        let mut sizes = [0; 9];
+        for (r, row) in rows.iter().enumerate() {
+            if row.chars().count() != 9 {
+                return Err(RegionError::RowLength(r as u8 + 1));
+            }
+            for (c, ch) in row.chars().enumerate() {
+                let label = match ch.to_ascii_uppercase() {
+                    l @ 'A'..='I' => l as u8 - b'A',
+                    _ => {
+                        return Err(RegionError::Label {
+                            row: r as u8 + 1,
+                            col: c as u8 + 1,
+                            label: ch,
+                        });
+                    }
+                };
+                labels[r][c] = label;
+                sizes[label as usize] += 1;
+            }
+        }
+        for (ix, &cells) in sizes.iter().enumerate() {
+            if cells != 9 {
+                let label = (b'A' + ix as u8) as char;
+                return Err(RegionError::Size { label, cells });
+            }
+        }
+        return Ok(Regions { labels });
+    }
+}
+
+impl fmt::Display for Regions {
+    // The map as FromStr reads it, without a newline at the end.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (r, row) in self.labels.iter().enumerate() {
+            if r > 0 {
+                writeln!(f)?;
+            }
+            for &label in row {
+                write!(f, "{}", (b'A' + label) as char)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cell, SdkFile, Strategy, Technique, Unsolveable, UnsolvedSudoku};
+
+    fn jigsaw() -> SdkFile {
+        let text = include_str!("../tests/fixtures/variants/jigsaw.sdk");
+        return text.parse().unwrap();
+    }
+
+    #[test]
+    fn test_classic() {
+        let classic = Regions::classic();
+        for c in Coord::all() {
+            assert_eq!(classic.region(c), c.square());
+        }
+        let squares: alloc::vec::Vec<Unit> = (1..10).map(Unit::Square).collect();
+        let coords: alloc::vec::Vec<_> = squares.iter().map(|u| u.coords()).collect();
+        assert_eq!(
+            classic.units().map(|u| u.coords()).to_vec(),
+            coords,
+            "each square's cells, in row-major order"
+        );
+        assert_eq!(classic.to_string().parse::<Regions>(), Ok(classic));
+        assert_eq!(classic.to_string().lines().next(), Some("AAABBBCCC"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let classic = Regions::classic().to_string();
+        let rows: alloc::vec::Vec<&str> = classic.lines().collect();
+        assert_eq!(
+            rows[..8].join("\n").parse::<Regions>(),
+            Err(RegionError::RowCount(8))
+        );
+        let mut short = rows.clone();
+        short[3] = "DDDEEEFF";
+        assert_eq!(
+            short.join("\n").parse::<Regions>(),
+            Err(RegionError::RowLength(4))
+        );
+        let mut bad = rows.clone();
+        bad[1] = "AAABBBCCJ";
+        assert_eq!(
+            bad.join("\n").parse::<Regions>(),
+            Err(RegionError::Label {
+                row: 2,
+                col: 9,
+                label: 'J'
+            })
+        );
+        // One cell moved from C to B leaves B with ten and C with eight.
+        let mut moved = rows.clone();
+        moved[0] = "AAABBBBCC";
+        assert_eq!(
+            moved.join("\n").parse::<Regions>(),
+            Err(RegionError::Size {
+                label: 'B',
+                cells: 10
+            })
+        );
+        // I relabelled as H leaves no I at all.
+        let missing = classic.replace('I', "H");
+        assert_eq!(
+            missing.parse::<Regions>(),
+            Err(RegionError::Size {
+                label: 'H',
+                cells: 18
+            })
+        );
+        let lower = classic.to_lowercase().replace('\n', "\r\n\n");
+        assert_eq!(lower.parse::<Regions>(), Ok(Regions::classic()));
+        assert_eq!(
+            RegionError::Size {
+                label: 'I',
+                cells: 0
+            }
+            .to_string(),
+            "region I has 0 cells, not 9"
+        );
+    }
+
+    #[test]
+    fn test_solve_jigsaw() {
+        let f = jigsaw();
+        let regions = f.regions.unwrap();
+        assert_ne!(regions, Regions::classic());
+        let units: alloc::vec::Vec<Unit> = f.grid.units().collect();
+        assert_eq!(units.len(), 27);
+        assert!(!units.iter().any(|u| matches!(u, Unit::Square(_))));
+        assert!(regions.units().iter().all(|u| units.contains(u)));
+
+        let solution = f.grid.solved().unwrap();
+        assert!(f.givens.count_solutions(2) > 1);
+        for unit in regions.units() {
+            let mut values: alloc::vec::Vec<u8> =
+                unit.coords().iter().map(|&c| solution[c]).collect();
+            values.sort();
+            assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        }
+
+        // Two of a value in one region is a conflict there, even in different squares.
+        let mut clash = Sudoku::empty();
+        let region_a = regions.units()[0].coords();
+        let a = region_a[0];
+        let b = *region_a.iter().find(|c| c.square() != a.square()).unwrap();
+        clash[a] = 4;
+        clash[b] = 4;
+        let mut u = UnsolvedSudoku::with_regions(&regions, &[]);
+        u.add_givens(&clash);
+        assert!(!u.valid());
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    #[test]
+    fn test_xyz_wing_regions() {
+        // On classic squares, pivot r1c1 {1, 2, 3} with arms r1c5 {1, 3} and r3c2 {2, 3}
+        // would take the 3 from r1c2 and r1c3. Here r3c2 is in region D, away from the
+        // pivot, so there is no wing, and a solution with r1c3 = 3 survives.
+        let regions = jigsaw().regions.unwrap();
+        let mut u = UnsolvedSudoku::with_regions(&regions, &[]);
+        for (r, c, keep) in [(1, 1, &[1, 2, 3][..]), (1, 5, &[1, 3]), (3, 2, &[2, 3])] {
+            for v in (1..10).filter(|v| !keep.contains(v)) {
+                u.eliminate(Coord::new(r, c).unwrap(), v).unwrap();
+            }
+        }
+        let mut probe = u.clone();
+        probe.set(1, 3, 3);
+        let fit = match probe.dynamic_solve() {
+            Ok(s) => s,
+            Err(Unsolveable::MultipleSolutions(a, _)) => *a,
+            Err(e) => panic!("{}", e),
+        };
+
+        assert_eq!(Technique::XyzWing.apply(&mut u), 0);
+        for c in Coord::all() {
+            let fits = match &u[c] {
+                Cell::Value(v) => *v == fit[c],
+                Cell::Possibilities(ps) => ps.contains(&fit[c]),
+            };
+            assert!(fits, "{} lost {}", c, fit[c]);
+        }
+    }
+
+    #[test]
+    fn test_bordered() {
+        let f = jigsaw();
+        let regions = f.regions.unwrap();
+        let text = regions.bordered(&f.givens);
+        let lines: alloc::vec::Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 19);
+        assert!(lines.iter().all(|l| l.len() == 37), "{}", text);
+        assert_eq!(lines[0], "+---+---+---+---+---+---+---+---+---+");
+        assert_eq!(lines[0], lines[18]);
+        // Every wall in the middle lines lies between two regions.
+        for r in 0..9u8 {
+            let line = lines[2 * r as usize + 1].as_bytes();
+            for col in 2..10u8 {
+                let wall = line[4 * (col as usize - 1)] == b'|';
+                let left = Coord::new(r + 1, col - 1).unwrap();
+                let right = Coord::new(r + 1, col).unwrap();
+                assert_eq!(wall, regions.region(left) != regions.region(right));
+            }
+        }
+        assert_eq!(
+            text.parse(),
+            Ok(f.givens),
+            "the grid reads back without its walls"
+        );
+        let classic = Regions::classic().bordered(&Sudoku::empty());
+        assert_eq!(
+            classic.lines().nth(2),
+            Some("+           +           +           +")
+        );
+        assert_eq!(
+            classic.lines().nth(1),
+            Some("| .   .   . | .   .   . | .   .   . |")
+        );
+    }
+}
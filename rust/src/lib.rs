@@ -0,0 +1,4746 @@
+// The grid types and the solver need only core and alloc. Reading from io::Read, the
+// parallel corpus, and serde support need the default "std" feature.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{Index, IndexMut};
+use core::str::FromStr;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "std")]
+use std::io;
+
+// Solver events go through the log facade with the "log" feature. Without it these
+// expand to nothing, and their arguments are never evaluated.
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => {};
+}
+#[cfg(feature = "log")]
+macro_rules! log_trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_trace {
+    ($($arg:tt)*) => {};
+}
+
+#[cfg(feature = "std")]
+mod batch;
+mod cache;
+mod cage;
+mod canonical;
+mod code;
+#[cfg(feature = "std")]
+mod corpus;
+mod daily;
+mod editor;
+mod extra;
+#[cfg(feature = "arbitrary")]
+mod fuzzing;
+mod generate;
+mod jigsaw;
+#[cfg(feature = "quick-xml")]
+mod opensudoku;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pattern;
+mod rating;
+mod rng;
+mod samurai;
+mod sdk;
+#[cfg(feature = "serde")]
+mod serialize;
+mod shape;
+mod solver;
+mod strategy;
+mod transform;
+mod unit;
+mod worksheet;
+
+#[cfg(feature = "std")]
+pub use batch::{generate_batch, GenerationReport};
+pub use cache::CachingSolver;
+pub use cage::Cage;
+pub use canonical::are_isomorphic;
+pub use code::CodeError;
+#[cfg(feature = "rayon")]
+pub use corpus::solve_corpus;
+#[cfg(feature = "std")]
+pub use corpus::{solve_each, CorpusStats};
+pub use daily::{daily_seed, generate_daily, GenError, DAILY_TRIES};
+pub use editor::{PuzzleEditor, SolutionStatus, EDITOR_SOLUTION_LIMIT};
+pub use extra::{parse_extra_regions, ExtraRegionError};
+#[cfg(feature = "arbitrary")]
+pub use fuzzing::ConsistentPuzzle;
+pub use generate::{GeneratorOptions, Symmetry};
+pub use jigsaw::{RegionError, Regions};
+#[cfg(feature = "quick-xml")]
+pub use opensudoku::{read_opensudoku_xml, write_opensudoku_xml};
+pub use pattern::{
+    apply_clue_pattern, check_clue_pattern, parse_clue_pattern, CluePattern, MaskReport,
+    PATTERN_SOLUTION_LIMIT,
+};
+pub use rating::{rate, Difficulty, Rating, Technique, GUESS_WEIGHT};
+pub use rng::{Seed, SolverRng};
+pub use samurai::{Samurai, SamuraiGrid, SamuraiUnsolveable};
+pub use sdk::{parse_coord, SdkError, SdkFile};
+pub use shape::{Alphabet, BoxShape, SizedSudoku, SizedUnsolveable};
+pub use solver::{Branching, SolveReport, Solver, SolverConfig};
+pub use strategy::Strategy;
+pub use unit::{Constraint, Unit, Variant};
+pub use worksheet::format_grid_row;
+
+pub mod prelude;
+
+use unit::Layout;
+
+// A plain grid of digits, 0 for blanks. It is only 81 bytes, so it is Copy; pass it
+// around by value freely.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Sudoku {
+    rows: [[u8; 9]; 9],
+}
+
+// Nine lines of nine cells with ' ' for blanks, or with {:#} the boxed grid of
+// Sudoku::bordered. Parsing reads either back.
+impl fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return f.write_str(&self.bordered());
+        }
+        for (n, r) in self.rows.iter().enumerate() {
+            if n > 0 {
+                writeln!(f)?;
+            }
+            for c in r {
+                match c {
+                    0 => write!(f, " ")?,
+                    _ => write!(f, "{}", c)?,
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+// A 1-based (row, column) position on the grid. Both parts are always in 1-9.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Coord {
+    row: u8,
+    col: u8,
+}
+
+impl Coord {
+    pub fn new(row: u8, col: u8) -> Result<Coord, GridError> {
+        if !(1..=9).contains(&row) {
+            return Err(GridError::RowOutOfRange(row));
+        }
+        if !(1..=9).contains(&col) {
+            return Err(GridError::ColumnOutOfRange(col));
+        }
+        return Ok(Coord { row, col });
+    }
+
+    // Build a Coord from 0-based array indices. Panics if either is above 8.
+    pub fn from_index(rix: usize, cix: usize) -> Coord {
+        assert!(
+            rix < 9 && cix < 9,
+            "index ({}, {}) out of range 0-8",
+            rix,
+            cix
+        );
+        return Coord {
+            row: (rix + 1) as u8,
+            col: (cix + 1) as u8,
+        };
+    }
+
+    pub fn row(self) -> u8 {
+        return self.row;
+    }
+
+    pub fn col(self) -> u8 {
+        return self.col;
+    }
+
+    // The 1-based index of the 3x3 square containing this cell, counting left to right
+    // and then top to bottom.
+    pub fn square(self) -> u8 {
+        return BoxShape::CLASSIC.box_of(self.row, self.col);
+    }
+
+    // 0-based (row, column) indices into a 9x9 array.
+    pub fn index(self) -> (usize, usize) {
+        return ((self.row - 1) as usize, (self.col - 1) as usize);
+    }
+
+    // All 81 coordinates in row-major order: (1, 1), (1, 2), ..., (1, 9), (2, 1), ...
+    pub fn all() -> impl Iterator<Item = Coord> {
+        return (0..81).map(|i| Coord::from_index(i / 9, i % 9));
+    }
+
+    // The 20 other cells sharing a row, column, or square with this one, in row-major
+    // order.
+    pub fn peers(self) -> impl Iterator<Item = Coord> {
+        return Coord::all().filter(move |&c| self.sees(c));
+    }
+
+    // Whether the two cells are different and share a row, column, or square, so they
+    // can't hold the same value.
+    pub fn sees(self, other: Coord) -> bool {
+        return other != self
+            && (other.row == self.row || other.col == self.col || other.square() == self.square());
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "r{}c{}", self.row, self.col)
+    }
+}
+
+trait SubArray {
+    fn name(&self) -> &'static str;
+    fn matrix_index(&self, index: u8) -> (u8, u8);
+
+    // The nine cells of this subarray, in order of their index within it.
+    fn coords(&self) -> [Coord; 9] {
+        let mut coords = [Coord { row: 1, col: 1 }; 9];
+        for (ix, c) in coords.iter_mut().enumerate() {
+            let (row, col) = self.matrix_index((ix + 1) as u8);
+            *c = Coord { row, col };
+        }
+        return coords;
+    }
+}
+
+fn check_unit_index(ix: u8) {
+    assert!((1..=9).contains(&ix), "unit index {} out of range 1-9", ix);
+}
+
+// The nine cells of row ix, left to right. Panics if ix is outside 1-9.
+pub fn row_coords(ix: u8) -> [Coord; 9] {
+    check_unit_index(ix);
+    return Row { index: ix }.coords();
+}
+
+// The nine cells of column ix, top to bottom. Panics if ix is outside 1-9.
+pub fn col_coords(ix: u8) -> [Coord; 9] {
+    check_unit_index(ix);
+    return Column { index: ix }.coords();
+}
+
+// The nine cells of square ix in reading order. Squares are numbered left to right and
+// then top to bottom. Panics if ix is outside 1-9.
+pub fn box_coords(ix: u8) -> [Coord; 9] {
+    check_unit_index(ix);
+    return Square { index: ix }.coords();
+}
+
+pub(crate) struct Row {
+    index: u8,
+}
+
+impl SubArray for Row {
+    fn name(&self) -> &'static str {
+        return "R";
+    }
+
+    fn matrix_index(&self, index: u8) -> (u8, u8) {
+        return (self.index, index);
+    }
+}
+
+pub(crate) struct Column {
+    index: u8,
+}
+
+impl SubArray for Column {
+    fn name(&self) -> &'static str {
+        return "C";
+    }
+
+    fn matrix_index(&self, index: u8) -> (u8, u8) {
+        return (index, self.index);
+    }
+}
+
+pub(crate) struct Square {
+    index: u8,
+}
+
+impl SubArray for Square {
+    fn name(&self) -> &'static str {
+        return "S";
+    }
+
+    fn matrix_index(&self, index: u8) -> (u8, u8) {
+        return BoxShape::CLASSIC.box_cell(self.index, index);
+    }
+}
+
+impl Default for Sudoku {
+    fn default() -> Sudoku {
+        return Sudoku::empty();
+    }
+}
+
+impl Sudoku {
+    // A grid with every cell blank.
+    pub fn empty() -> Sudoku {
+        return Sudoku { rows: [[0; 9]; 9] };
+    }
+
+    // A grid holding these clues, each a 1-based (row, col, value), and blank elsewhere.
+    // Each clue is checked against the ones before it: it can't be in a cell another
+    // already fills, even with the same value, or repeat a value in its row, column, or
+    // square. Nothing is solved, so clues that only conflict through what they imply are
+    // still placed; see UnsolvedSudoku::from_clues.
+    pub fn from_clues(clues: &[(u8, u8, u8)]) -> Result<Sudoku, Conflict> {
+        let mut s = Sudoku::empty();
+        for &(row, col, value) in clues {
+            let c = Coord::new(row, col).map_err(Conflict::OutOfRange)?;
+            if !(1..=9).contains(&value) {
+                return Err(Conflict::OutOfRange(GridError::ValueOutOfRange(value)));
+            }
+            if s[c] != 0 {
+                return Err(Conflict::AlreadyFilled {
+                    row,
+                    col,
+                    value: s[c],
+                });
+            }
+            for unit in [Unit::Row(row), Unit::Column(col), Unit::Square(c.square())] {
+                if let Some(&at) = unit.coords().iter().find(|&&other| s[other] == value) {
+                    return Err(Conflict::Taken {
+                        row,
+                        col,
+                        value,
+                        unit,
+                        at,
+                    });
+                }
+            }
+            s[c] = value;
+        }
+        return Ok(s);
+    }
+
+    // Whether every cell is blank.
+    pub fn is_empty_grid(&self) -> bool {
+        return self.clue_count() == 0;
+    }
+
+    // Read a grid as text; see the FromStr impl for the format.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: io::Read>(mut reader: R) -> io::Result<Sudoku> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        return text
+            .parse()
+            .map_err(|e: ParseError| io::Error::new(io::ErrorKind::InvalidInput, e));
+    }
+
+    // The puzzle on line n, counting from 0, of a file with one 81-cell puzzle per line
+    // as TryFrom<&[u8]> reads them, or None if there are no more than n. Blank lines
+    // don't count. Lines are read one at a time and only line n is parsed, so a corpus
+    // of millions of puzzles is never held in memory. A parse error comes back as an
+    // io::Error of kind InvalidInput, as from from_reader.
+    #[cfg(feature = "std")]
+    pub fn read_nth<R: io::Read>(reader: R, n: usize) -> io::Result<Option<Sudoku>> {
+        let mut reader = io::BufReader::new(reader);
+        let mut line = Vec::new();
+        let mut seen = 0;
+        loop {
+            line.clear();
+            if !read_line_bytes(&mut reader, &mut line)? {
+                return Ok(None);
+            }
+            let trimmed = line.trim_ascii();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if seen == n {
+                return Sudoku::try_from(trimmed)
+                    .map(Some)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+            }
+            seen += 1;
+        }
+    }
+}
+
+// Read up to the next '\n' or '\r' into line, without it, and return whether there was
+// anything left to read. The "\n" of a "\r\n" is left to end an empty line, which is
+// fine for read_nth as it skips blank lines.
+#[cfg(feature = "std")]
+fn read_line_bytes<R: io::BufRead>(reader: &mut R, line: &mut Vec<u8>) -> io::Result<bool> {
+    let mut any = false;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(any);
+        }
+        any = true;
+        match buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(ix) => {
+                line.extend_from_slice(&buf[..ix]);
+                reader.consume(ix + 1);
+                return Ok(true);
+            }
+            None => {
+                let len = buf.len();
+                line.extend_from_slice(buf);
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+// Why text could not be read as a grid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ParseError {
+    TooManyCharacters,
+    TooFewCharacters,
+    TooFewRows,
+    // A digit too large for the grid's size.
+    DigitOutOfRange,
+    // A field of a comma- or space-separated row that isn't a number.
+    BadField,
+    // The document around the grids is malformed, such as an XML collection that isn't
+    // well-formed XML.
+    Markup,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::TooManyCharacters => write!(f, "Too many characters"),
+            ParseError::TooFewCharacters => write!(f, "Too few characters"),
+            ParseError::TooFewRows => write!(f, "Too few rows"),
+            ParseError::DigitOutOfRange => write!(f, "Digit out of range for the grid"),
+            ParseError::BadField => write!(f, "Field is not a number"),
+            ParseError::Markup => write!(f, "Malformed document"),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+// The lines of text, like str::lines, but ending at any of "\n", "\r\n", or a lone
+// "\r", so files saved on any platform, or pasted together from several, split into
+// the same rows. A final line ending doesn't start another line.
+pub fn text_lines(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    return core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        let line = &rest[..end];
+        rest = &rest[end..];
+        rest = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix(['\r', '\n']))
+            .unwrap_or(rest);
+        return Some(line);
+    });
+}
+
+// Nine lines of nine cells each, split as text_lines does: digits 1-9, with '0', '.', '-',
+// or 'x' for blanks. Any other characters are skipped, so "123 456 789" is one row. A line
+// with commas, or with exactly nine whitespace-separated fields, is read field by field
+// instead, as numeric exports write it: each field must be a number 0-9 or a blank
+// marker, and an empty field between commas is blank too. This needs no allocation, so
+// it works without std.
+//
+// Grids drawn with borders, as Sudoku::bordered and Regions::bordered draw them, read
+// back too: a line of only '+', '-', '|', and spaces, with at least one '+', is a rule
+// between rows and holds no cells, and a '|' counts as a space between fields, so
+// "| 5 3 . | . 7 . | . . . |" is nine fields.
+impl FromStr for Sudoku {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Sudoku, ParseError> {
+        let mut s = Sudoku::empty();
+
+        let mut rix = 0;
+        let mut cix = 0;
+
+        for line in text_lines(text) {
+            let trimmed = line.trim();
+            if trimmed.contains('+') && trimmed.chars().all(|c| "+-| ".contains(c)) {
+                continue;
+            }
+            let fields = || {
+                trimmed
+                    .split(|c: char| c.is_whitespace() || c == '|')
+                    .filter(|f| !f.is_empty())
+            };
+
+            let mut put = |n: u8| {
+                if cix >= 9 || rix >= 9 {
+                    return Err(ParseError::TooManyCharacters);
+                }
+                s.rows[rix][cix] = n;
+                cix += 1;
+                return Ok(());
+            };
+
+            if trimmed.contains(',') {
+                // A trailing comma after the ninth field ends the row rather than
+                // starting a tenth, but after fewer it's an empty last field.
+                let fields = match trimmed.strip_suffix(',') {
+                    Some(rest) if trimmed.matches(',').count() == 9 => rest,
+                    _ => trimmed,
+                };
+                for field in fields.split(',') {
+                    put(parse_field(field.trim())?)?;
+                }
+            } else if fields().count() == 9 {
+                for field in fields() {
+                    put(parse_field(field)?)?;
+                }
+            } else {
+                for c in trimmed.chars() {
+                    let n = match c {
+                        '1'..='9' => c.to_digit(10).expect("This shouldn't happen"),
+                        '0' | '.' | '-' | 'x' => 0,
+                        _ => continue,
+                    };
+                    put(n as u8)?;
+                }
+            }
+
+            if cix < 9 {
+                return Err(ParseError::TooFewCharacters);
+            }
+
+            rix += 1;
+            cix = 0;
+        }
+
+        if rix < 9 {
+            return Err(ParseError::TooFewRows);
+        }
+
+        log_debug!("parsed puzzle with {} clues", s.clue_count());
+        return Ok(s);
+    }
+}
+
+// The same as parse, for code that is generic over TryFrom.
+impl TryFrom<&str> for Sudoku {
+    type Error = ParseError;
+
+    fn try_from(text: &str) -> Result<Sudoku, ParseError> {
+        return text.parse();
+    }
+}
+
+// Exactly 81 bytes, one per cell in row-major order, as in a line of an SDM file or
+// the Debug form: ASCII digits 1-9, with '0', '.', '-', or 'x' for blanks. Unlike text,
+// nothing is skipped, so any other byte is an error.
+impl TryFrom<&[u8]> for Sudoku {
+    type Error = ParseError;
+
+    fn try_from(bytes: &[u8]) -> Result<Sudoku, ParseError> {
+        if bytes.len() < 81 {
+            return Err(ParseError::TooFewCharacters);
+        }
+        if bytes.len() > 81 {
+            return Err(ParseError::TooManyCharacters);
+        }
+        let mut s = Sudoku::empty();
+        for (c, &b) in Coord::all().zip(bytes) {
+            s[c] = match b {
+                b'1'..=b'9' => b - b'0',
+                b'0' | b'.' | b'-' | b'x' => 0,
+                _ => return Err(ParseError::BadField),
+            };
+        }
+        return Ok(s);
+    }
+}
+
+// One field of a delimited row: a number 0-9, or empty or a blank marker for 0.
+fn parse_field(field: &str) -> Result<u8, ParseError> {
+    if matches!(field, "" | "-" | "x" | ".") {
+        return Ok(0);
+    }
+    if !field.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseError::BadField);
+    }
+    // Leading zeros are fine, but anything that is still two digits once they're gone
+    // is out of range, however long it is.
+    return match field.trim_start_matches('0') {
+        "" => Ok(0),
+        digits if digits.len() == 1 => Ok(digits.as_bytes()[0] - b'0'),
+        _ => Err(ParseError::DigitOutOfRange),
+    };
+}
+
+// The compact 81-character form, one digit per cell in row-major order with '.' for
+// blanks, which is much easier to compare in a failed assertion than nested arrays.
+impl fmt::Debug for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells: String = self
+            .rows
+            .iter()
+            .flatten()
+            .map(|&v| match v {
+                0 => '.',
+                _ => (b'0' + v) as char,
+            })
+            .collect();
+        return write!(f, "Sudoku({})", cells);
+    }
+}
+
+// Sudoku::certain_cells compares at most this many solutions directly.
+pub const CERTAIN_SOLUTION_LIMIT: usize = 100;
+
+impl Sudoku {
+    // The digit at coord, or 0 if it is blank.
+    pub fn get(&self, coord: Coord) -> u8 {
+        return self[coord];
+    }
+
+    // Set the digit at coord; 0 clears it. No check is made against the rest of the grid.
+    pub fn set(&mut self, coord: Coord, value: u8) -> Result<(), GridError> {
+        if value > 9 {
+            return Err(GridError::ValueOutOfRange(value));
+        }
+        self[coord] = value;
+        return Ok(());
+    }
+
+    pub fn clear(&mut self, coord: Coord) {
+        self[coord] = 0;
+    }
+
+    pub fn is_empty(&self, coord: Coord) -> bool {
+        return self[coord] == 0;
+    }
+
+    // The number of filled cells.
+    pub fn clue_count(&self) -> usize {
+        return self.cells().filter(|&(_, v)| v != 0).count();
+    }
+
+    // The number of blank cells.
+    pub fn empty_count(&self) -> usize {
+        return 81 - self.clue_count();
+    }
+
+    // Whether every cell is filled. This says nothing about whether the digits break the
+    // rules; see UnsolvedSudoku::valid for that.
+    pub fn is_complete(&self) -> bool {
+        return self.empty_count() == 0;
+    }
+
+    // Every cell and its value (0 for blank), in row-major order.
+    pub fn cells(&self) -> impl Iterator<Item = (Coord, u8)> + '_ {
+        return Coord::all().map(move |c| (c, self[c]));
+    }
+
+    // The digits, 0 for blank, row by row from the top. This is the grid itself, so
+    // reading it costs no copy.
+    pub fn rows(&self) -> &[[u8; 9]; 9] {
+        return &self.rows;
+    }
+
+    // The cells of row ix, column ix, or square ix, in the order given by row_coords,
+    // col_coords, and box_coords. These panic if ix is outside 1-9.
+    pub fn row_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, u8)> + '_ {
+        return row_coords(ix).into_iter().map(move |c| (c, self[c]));
+    }
+
+    pub fn col_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, u8)> + '_ {
+        return col_coords(ix).into_iter().map(move |c| (c, self[c]));
+    }
+
+    pub fn box_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, u8)> + '_ {
+        return box_coords(ix).into_iter().map(move |c| (c, self[c]));
+    }
+
+    // The square containing 1-based (rix, cix), numbered 1-9 left to right and then top
+    // to bottom, so (1, 1) is in square 1, (1, 4) in square 2, and (4, 1) in square 4.
+    // Panics if either index is outside 1-9.
+    pub fn box_of(rix: u8, cix: u8) -> u8 {
+        return match Coord::new(rix, cix) {
+            Ok(c) => c.square(),
+            Err(e) => panic!("box_of: {}", e),
+        };
+    }
+
+    // The 1-based (row, column) of each cell in square box_index, in reading order.
+    // Panics if box_index is outside 1-9.
+    pub fn cells_in_box(box_index: u8) -> [(u8, u8); 9] {
+        return box_coords(box_index).map(|c| (c.row(), c.col()));
+    }
+
+    // The values of each square in turn, 1 to 9, with each square's in the reading
+    // order of cells_in_box and 0 for blanks.
+    pub fn boxes(&self) -> impl Iterator<Item = [u8; 9]> + '_ {
+        return (1..10).map(move |b| {
+            return Sudoku::cells_in_box(b).map(|(r, c)| self.rows[r as usize - 1][c as usize - 1]);
+        });
+    }
+
+    // Solve a copy of this puzzle, leaving it untouched.
+    pub fn solve(&self) -> Result<Sudoku, Unsolveable> {
+        let mut u: UnsolvedSudoku = (*self).into();
+        return u.dynamic_solve();
+    }
+
+    // Whether solution is a correct answer to this puzzle: complete, breaking no rule,
+    // and keeping every given. For grading an answer; it doesn't check the puzzle has
+    // only one solution.
+    pub fn verifies(&self, solution: &Sudoku) -> bool {
+        if !solution.is_complete() || self.cells().any(|(c, v)| v != 0 && solution[c] != v) {
+            return false;
+        }
+        return Unit::classic().all(|unit| {
+            let mut seen = CandidateSet::empty();
+            return unit.coords().iter().all(|&c| {
+                let v = solution[c];
+                if !(1..=9).contains(&v) || seen.contains(v) {
+                    return false;
+                }
+                seen.insert(v);
+                return true;
+            });
+        });
+    }
+
+    // The cells, in row-major order, where user has a value other than the one in this
+    // puzzle's solution. Blank cells in user are not mistakes. Mistakes only make sense
+    // against a single answer, so this fails if the puzzle isn't uniquely solvable.
+    pub fn find_mistakes(&self, user: &Sudoku) -> Result<Vec<(u8, u8)>, Unsolveable> {
+        let solution = self.solve()?;
+        return Ok(user
+            .cells()
+            .filter(|&(c, v)| v != 0 && v != solution[c])
+            .map(|(c, _)| (c.row(), c.col()))
+            .collect());
+    }
+
+    // The value the unique solution has at (rix, cix), or None if the puzzle does not have
+    // exactly one solution or the coordinates are off the grid. Each call solves the
+    // puzzle again; to reveal many cells, call solve once instead.
+    pub fn solution_cell(&self, rix: u8, cix: u8) -> Option<u8> {
+        let c = Coord::new(rix, cix).ok()?;
+        return self.solve().ok().map(|s| s[c]);
+    }
+
+    // Like from_reader, but also rejects grids that don't have exactly one solution, so
+    // callers can take uniqueness for granted.
+    #[cfg(feature = "std")]
+    pub fn parse_strict<R: io::Read>(reader: R) -> Result<Sudoku, StrictError> {
+        let s = Sudoku::from_reader(reader)?;
+        let mut solutions = UnsolvedSudoku::from(s).solutions();
+        return match (solutions.next(), solutions.next()) {
+            (None, _) => Err(StrictError::Unsolveable(Unsolveable::NoSolution)),
+            (Some(_), None) => Ok(s),
+            (Some(a), Some(b)) => Err(StrictError::Unsolveable(Unsolveable::MultipleSolutions(
+                Box::new(a),
+                Box::new(b),
+            ))),
+        };
+    }
+
+    // The number of solutions, counting no further than limit. count_solutions(2) is
+    // enough to tell whether a puzzle is unique.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        return UnsolvedSudoku::from(*self).solutions().take(limit).count();
+    }
+
+    // The cells where two different solutions disagree, in row-major order, or nothing
+    // if the puzzle has at most one solution. These are where another clue would help
+    // make the puzzle unique, though one clue may not be enough.
+    pub fn ambiguous_cells(&self) -> Vec<(u8, u8)> {
+        let mut solutions = UnsolvedSudoku::from(*self).solutions();
+        let (first, second) = match (solutions.next(), solutions.next()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Vec::new(),
+        };
+        return Coord::all()
+            .filter(|&c| first[c] != second[c])
+            .map(|c| (c.row(), c.col()))
+            .collect();
+    }
+
+    // Every cell where other holds a different value, in row-major order, with this
+    // grid's value and then other's; 0 is a blank. Empty when the grids are the same.
+    pub fn diff(&self, other: &Sudoku) -> Vec<(Coord, u8, u8)> {
+        return Coord::all()
+            .filter(|&c| self[c] != other[c])
+            .map(|c| (c, self[c], other[c]))
+            .collect();
+    }
+
+    // The grid of cells that hold the same value in every solution, and blanks
+    // elsewhere: the clues, and whatever they settle even if the puzzle isn't unique. A
+    // puzzle with no solution gives the empty grid. The first CERTAIN_SOLUTION_LIMIT
+    // solutions are compared directly. If there are more, each cell they all agree on is
+    // then confirmed by trying every other value there, so a cell that merely happened
+    // to match in those solutions is still left blank.
+    pub fn certain_cells(&self) -> Sudoku {
+        let mut solutions = UnsolvedSudoku::from(*self).solutions();
+        let mut agreed = match solutions.next() {
+            Some(s) => s,
+            None => return Sudoku::empty(),
+        };
+        for s in solutions.by_ref().take(CERTAIN_SOLUTION_LIMIT - 1) {
+            for c in Coord::all() {
+                if agreed[c] != s[c] {
+                    agreed.clear(c);
+                }
+            }
+        }
+        if solutions.next().is_none() {
+            return agreed;
+        }
+        let unconfirmed: Vec<Coord> = Coord::all()
+            .filter(|&c| agreed[c] != 0 && self.is_empty(c))
+            .collect();
+        for c in unconfirmed {
+            let other = (1..10).filter(|&v| v != agreed[c]).any(|v| {
+                let mut tried = *self;
+                tried[c] = v;
+                return tried.count_solutions(1) > 0;
+            });
+            if other {
+                agreed.clear(c);
+            }
+        }
+        return agreed;
+    }
+
+    // Every pair of clues breaking the rules by sharing a row, column, or square and a
+    // value, by unit as in Unit::classic and then in the unit's order. Unlike
+    // UnsolvedSudoku::violations, only the clues themselves are compared, with nothing
+    // deduced from them.
+    pub fn violations(&self) -> Vec<Violation> {
+        let mut found = Vec::new();
+        for unit in Unit::classic() {
+            unit_violations(unit, &unit.coords(), |c| self[c], &mut found);
+        }
+        return found;
+    }
+
+    // Every (row, column, value) the rules force right now, with the technique forcing
+    // it: a blank cell where only one value fits is a naked single, and a value that
+    // fits in only one blank cell of a row, column, or square is a hidden single. Both
+    // are judged from the clues alone and nothing is placed, so this is every hint
+    // available at once. A placement forced both ways is listed once, as the naked
+    // single. In row-major order, and by value within a cell.
+    pub fn forced_placements(&self) -> Vec<(u8, u8, u8, &'static str)> {
+        let mut fits = [[CandidateSet::empty(); 9]; 9];
+        for c in Coord::all().filter(|&c| self.is_empty(c)) {
+            let (i, j) = c.index();
+            fits[i][j] = (1..10)
+                .filter(|&v| c.peers().all(|p| self[p] != v))
+                .collect();
+        }
+        let fits_at = |c: Coord| fits[c.index().0][c.index().1];
+
+        let mut forced = Vec::new();
+        for c in Coord::all() {
+            let fit = fits_at(c);
+            if fit.len() == 1 {
+                let v = fit.iter().next().expect("one candidate");
+                forced.push((c, v, Technique::NakedSingle));
+            }
+        }
+        for unit in Unit::classic() {
+            for v in 1..10 {
+                let coords = unit.coords();
+                let mut places = coords.iter().filter(|&&c| fits_at(c).contains(v));
+                if let (Some(&c), None) = (places.next(), places.next()) {
+                    if !forced.iter().any(|&(f, w, _)| (f, w) == (c, v)) {
+                        forced.push((c, v, Technique::HiddenSingle));
+                    }
+                }
+            }
+        }
+        forced.sort_by_key(|&(c, v, _)| (c, v));
+        return forced
+            .into_iter()
+            .map(|(c, v, t)| (c.row(), c.col(), v, t.name()))
+            .collect();
+    }
+}
+
+impl Index<Coord> for Sudoku {
+    type Output = u8;
+
+    fn index(&self, c: Coord) -> &u8 {
+        let (i, j) = c.index();
+        return &self.rows[i][j];
+    }
+}
+
+impl IndexMut<Coord> for Sudoku {
+    fn index_mut(&mut self, c: Coord) -> &mut u8 {
+        let (i, j) = c.index();
+        return &mut self.rows[i][j];
+    }
+}
+
+// A set of candidate values 1-9, stored as a bitmask.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct CandidateSet {
+    bits: u16,
+}
+
+impl CandidateSet {
+    pub fn empty() -> CandidateSet {
+        return CandidateSet { bits: 0 };
+    }
+
+    pub fn full() -> CandidateSet {
+        return CandidateSet {
+            bits: 0b11_1111_1110,
+        };
+    }
+
+    pub fn contains(self, value: u8) -> bool {
+        return (1..=9).contains(&value) && self.bits & (1 << value) != 0;
+    }
+
+    // Panics if value is outside 1-9.
+    pub fn insert(&mut self, value: u8) {
+        assert!(
+            (1..=9).contains(&value),
+            "candidate {} out of range 1-9",
+            value
+        );
+        self.bits |= 1 << value;
+    }
+
+    pub fn remove(&mut self, value: u8) {
+        if (1..=9).contains(&value) {
+            self.bits &= !(1 << value);
+        }
+    }
+
+    pub fn len(self) -> usize {
+        return self.bits.count_ones() as usize;
+    }
+
+    pub fn is_empty(self) -> bool {
+        return self.bits == 0;
+    }
+
+    // The candidates in increasing order.
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        return (1..10).filter(move |&v| self.contains(v));
+    }
+}
+
+impl FromIterator<u8> for CandidateSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> CandidateSet {
+        let mut set = CandidateSet::empty();
+        for v in iter {
+            set.insert(v);
+        }
+        return set;
+    }
+}
+
+impl fmt::Debug for CandidateSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Cell {
+    Value(u8),
+    Possibilities(collections::BTreeSet<u8>),
+}
+
+enum Removal {
+    Fixed(u8),
+    Removed,
+    NotFound,
+}
+
+// A candidate one of the techniques removed, like eliminate_fish_traced, and which
+// technique removed it. (Elimination below is the outcome of removing one by hand.)
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct Eliminated {
+    // (row, column), counting from 1.
+    pub cell: (u8, u8),
+    pub value: u8,
+    pub reason: Technique,
+}
+
+// The outcome of UnsolvedSudoku::eliminate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Elimination {
+    // The value was not a candidate (or the cell was already fixed); nothing changed.
+    NotCandidate,
+    // The value was removed, leaving two or more candidates.
+    Removed,
+    // The value was removed and the cell was fixed to the one remaining candidate,
+    // which was then removed from its row, column, and square.
+    Fixed(u8),
+}
+
+impl Cell {
+    // The candidates of an unsolved cell in increasing order, without allocating.
+    // Yields nothing for a fixed cell.
+    pub fn candidate_iter(&self) -> impl Iterator<Item = u8> + '_ {
+        let ps = match self {
+            Cell::Value(_) => None,
+            Cell::Possibilities(ps) => Some(ps),
+        };
+        return ps.into_iter().flatten().cloned();
+    }
+
+    fn remove_possibility(&mut self, value: u8) -> Removal {
+        let ps = match self {
+            Cell::Value(_) => return Removal::NotFound,
+            Cell::Possibilities(p) => p,
+        };
+        if !ps.contains(&value) {
+            return Removal::NotFound;
+        }
+        ps.remove(&value);
+        if ps.len() == 1 {
+            let &v = ps.iter().next().unwrap();
+            *self = Cell::Value(v);
+            return Removal::Fixed(v);
+        }
+        return Removal::Removed;
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        return Cell::Possibilities(collections::BTreeSet::from_iter(1..10));
+    }
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct UnsolvedSudoku {
+    rows: [[Cell; 9]; 9],
+    layout: Arc<Layout>,
+}
+
+#[derive(Clone, Copy)]
+enum PossibleLocations {
+    None,
+    Filled,
+    Single(u8, u8),
+    Many,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Unsolveable {
+    // Two of the solutions, which differ in at least one cell.
+    MultipleSolutions(Box<Sudoku>, Box<Sudoku>),
+    NoSolution,
+}
+
+impl fmt::Display for Unsolveable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unsolveable::MultipleSolutions(..) => write!(f, "puzzle has multiple solutions"),
+            Unsolveable::NoSolution => write!(f, "puzzle has no solution"),
+        }
+    }
+}
+
+impl core::error::Error for Unsolveable {}
+
+// Why a solve that can be stopped part way didn't return a solution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SolveError {
+    // The search finished, and the puzzle doesn't have exactly one solution.
+    Unsolveable(Unsolveable),
+    // The search was stopped before it finished.
+    Cancelled,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsolveable(e) => write!(f, "{}", e),
+            SolveError::Cancelled => write!(f, "solve was cancelled"),
+        }
+    }
+}
+
+impl core::error::Error for SolveError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        return match self {
+            SolveError::Unsolveable(e) => Some(e),
+            SolveError::Cancelled => None,
+        };
+    }
+}
+
+impl From<Unsolveable> for SolveError {
+    fn from(e: Unsolveable) -> SolveError {
+        return SolveError::Unsolveable(e);
+    }
+}
+
+// Why Sudoku::parse_strict rejected its input.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StrictError {
+    // The input could not be read or is not a grid; see Sudoku::from_reader.
+    Parse(io::Error),
+    // The grid was read, but does not have exactly one solution.
+    Unsolveable(Unsolveable),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for StrictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StrictError::Parse(e) => write!(f, "{}", e),
+            StrictError::Unsolveable(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for StrictError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        return match self {
+            StrictError::Parse(e) => Some(e),
+            StrictError::Unsolveable(e) => Some(e),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for StrictError {
+    fn from(e: io::Error) -> StrictError {
+        return StrictError::Parse(e);
+    }
+}
+
+// What a call to add_clue changed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SolveProgress {
+    // Every cell that became fixed, with its value, in row-major order. This includes
+    // the clue itself.
+    pub fixed: Vec<(Coord, u8)>,
+    // Candidates removed from cells that are still unsolved.
+    pub eliminated: usize,
+    // Whether the grid is now completely solved.
+    pub solved: bool,
+}
+
+// What a call to place changed.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct PlacementEffects {
+    // Every cell that became fixed, with its value, in row-major order: the placed cell
+    // and any that were left with one candidate in turn.
+    pub fixed: Vec<(Coord, u8)>,
+    // Whether the grid is now completely solved.
+    pub solved: bool,
+}
+
+// Bookkeeping for one run of the guessing search.
+struct Search<'a, 's> {
+    guesses: u64,
+    budget: u64,
+    // How many guesses deep the current branch is, and the deepest any branch went.
+    depth: u32,
+    max_depth: u32,
+    // The deductions that have filled a cell, easiest first.
+    techniques: Vec<Technique>,
+    // Called with the guess count after each guess; false stops the search.
+    on_guess: Option<&'a mut dyn FnMut(u64) -> bool>,
+    branching: Branching,
+    // Run to a fixpoint with the hidden singles before each guess.
+    strategies: &'s [Arc<dyn Strategy>],
+}
+
+impl<'a, 's> Search<'a, 's> {
+    fn new(budget: u64) -> Search<'a, 's> {
+        return Search {
+            guesses: 0,
+            budget,
+            depth: 0,
+            max_depth: 0,
+            techniques: Vec::new(),
+            on_guess: None,
+            branching: Branching::FewestCandidates,
+            strategies: &[],
+        };
+    }
+
+    fn used(&mut self, technique: Technique) {
+        if let Err(ix) = self.techniques.binary_search(&technique) {
+            self.techniques.insert(ix, technique);
+        }
+    }
+}
+
+enum SearchError {
+    Unsolveable(Unsolveable),
+    // The guess budget ran out, or the on_guess callback asked to stop.
+    Stopped,
+}
+
+impl From<Unsolveable> for SearchError {
+    fn from(e: Unsolveable) -> SearchError {
+        return SearchError::Unsolveable(e);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum GridError {
+    RowOutOfRange(u8),
+    ColumnOutOfRange(u8),
+    ValueOutOfRange(u8),
+    UnitOutOfRange(u8),
+    // A custom unit or cage listed the same cell twice.
+    RepeatedCell(Coord),
+    // Boxes must have at least one row and column, and at most 16 cells.
+    BoxShapeOutOfRange(u8, u8),
+    // No set of this many distinct digits 1-9 adds up to a cage's sum.
+    CageSumOutOfRange { sum: u8, cells: usize },
+    // No boxes of the supported shapes tile a grid this many cells on a side.
+    SizeOutOfRange(u8),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::RowOutOfRange(r) => write!(f, "row {} out of range 1-9", r),
+            GridError::ColumnOutOfRange(c) => write!(f, "column {} out of range 1-9", c),
+            GridError::ValueOutOfRange(v) => write!(f, "value {} out of range 1-9", v),
+            GridError::UnitOutOfRange(ix) => write!(f, "unit index {} out of range 1-9", ix),
+            GridError::RepeatedCell(c) => write!(f, "cell {} appears twice in a unit or cage", c),
+            GridError::BoxShapeOutOfRange(r, c) => {
+                write!(f, "box shape {}x{} out of range", r, c)
+            }
+            GridError::CageSumOutOfRange { sum, cells } => {
+                write!(f, "no {} distinct digits add up to {}", cells, sum)
+            }
+            GridError::SizeOutOfRange(n) => write!(f, "no box shape fits a {0}x{0} grid", n),
+        }
+    }
+}
+
+impl core::error::Error for GridError {}
+
+// A placement that can't be made on top of the current grid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Conflict {
+    OutOfRange(GridError),
+    // The cell already holds a different value.
+    AlreadyFilled {
+        row: u8,
+        col: u8,
+        value: u8,
+    },
+    // The value has already been ruled out for the cell by its row, column, or square.
+    NotPossible {
+        row: u8,
+        col: u8,
+        value: u8,
+    },
+    // The value is already fixed at another cell of a unit the cell is in.
+    Taken {
+        row: u8,
+        col: u8,
+        value: u8,
+        unit: Unit,
+        at: Coord,
+    },
+    // The placement itself was allowed, but propagating it left two equal values in a
+    // row, column, or square.
+    Inconsistent {
+        row: u8,
+        col: u8,
+        value: u8,
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Conflict::OutOfRange(e) => write!(f, "{}", e),
+            Conflict::AlreadyFilled { row, col, value } => {
+                write!(
+                    f,
+                    "cell ({}, {}) is already filled with {}",
+                    row, col, value
+                )
+            }
+            Conflict::NotPossible { row, col, value } => {
+                write!(f, "{} cannot go in cell ({}, {})", value, row, col)
+            }
+            Conflict::Taken {
+                value, unit, at, ..
+            } => write!(f, "{} is already in {} at {}", value, unit, at),
+            Conflict::Inconsistent { row, col, value } => write!(
+                f,
+                "placing {} in cell ({}, {}) leads to a contradiction",
+                value, row, col
+            ),
+        }
+    }
+}
+
+impl core::error::Error for Conflict {}
+
+impl From<GridError> for Conflict {
+    fn from(e: GridError) -> Conflict {
+        return Conflict::OutOfRange(e);
+    }
+}
+
+// Two cells of one unit holding the same value, breaking the rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Violation {
+    pub unit: Unit,
+    // In the unit's order.
+    pub cells: (Coord, Coord),
+    pub value: u8,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "{} and {} both hold {} in {}",
+            self.cells.0, self.cells.1, self.value, self.unit
+        );
+    }
+}
+
+// A value held by more than one cell of a unit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct UnitViolation {
+    pub unit: Unit,
+    pub value: u8,
+    // Every cell of the unit holding the value, in the unit's order.
+    pub cells: Vec<Coord>,
+}
+
+impl fmt::Display for UnitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is in {} {} times:",
+            self.value,
+            self.unit,
+            self.cells.len()
+        )?;
+        for c in &self.cells {
+            write!(f, " {}", c)?;
+        }
+        return Ok(());
+    }
+}
+
+// Every pair of cells in the unit with the same nonzero value, added to found.
+fn unit_violations(
+    unit: Unit,
+    coords: &[Coord; 9],
+    value: impl Fn(Coord) -> u8,
+    found: &mut Vec<Violation>,
+) {
+    for (i, &a) in coords.iter().enumerate() {
+        let v = value(a);
+        if v == 0 {
+            continue;
+        }
+        for &b in &coords[i + 1..] {
+            if value(b) == v {
+                found.push(Violation {
+                    unit,
+                    cells: (a, b),
+                    value: v,
+                });
+            }
+        }
+    }
+}
+
+impl Default for UnsolvedSudoku {
+    fn default() -> UnsolvedSudoku {
+        return UnsolvedSudoku::empty();
+    }
+}
+
+impl UnsolvedSudoku {
+    // A classic grid with every value still possible in every cell.
+    pub fn empty() -> UnsolvedSudoku {
+        return UnsolvedSudoku {
+            rows: Default::default(),
+            layout: Layout::classic(),
+        };
+    }
+
+    // A classic grid with these clues placed, without building a Sudoku first. Each clue
+    // is checked against the ones before it, along with what they imply, so clues that
+    // can't all hold are reported rather than placed. Repeating a clue is fine.
+    pub fn from_clues(clues: &[(Coord, u8)]) -> Result<UnsolvedSudoku, Conflict> {
+        let mut u = UnsolvedSudoku::empty();
+        for &(c, value) in clues {
+            if u.check_entry(c.row(), c.col(), value)? {
+                u.set(c.row(), c.col(), value);
+            }
+        }
+        return Ok(u);
+    }
+
+    // An empty grid solved against exactly these units instead of the classic 27. Pass
+    // Unit::classic() chained with extra units to build a variant on top of the usual
+    // rules.
+    pub fn with_units(units: impl IntoIterator<Item = Unit>) -> Result<UnsolvedSudoku, GridError> {
+        let units: Vec<Unit> = units.into_iter().collect();
+        for unit in &units {
+            unit.check()?;
+        }
+        return Ok(UnsolvedSudoku {
+            rows: Default::default(),
+            layout: Arc::new(Layout::new(units)),
+        });
+    }
+
+    // An empty grid with the variant's units and constraints, for add_givens to fill in.
+    pub fn with_variant(variant: Variant) -> UnsolvedSudoku {
+        return UnsolvedSudoku::with_variants(&[variant]);
+    }
+
+    // An empty grid following the rules of every variant at once, such as anti-knight
+    // and anti-king together. With none, it's classic.
+    pub fn with_variants(variants: &[Variant]) -> UnsolvedSudoku {
+        return UnsolvedSudoku::with_base_units(Unit::classic().collect(), variants);
+    }
+
+    // An empty jigsaw grid: the regions stand in for the squares, each after the row
+    // and column with its index as Unit::classic orders them, along with every
+    // variant's rules but its squares. Pass no variants for plain jigsaw. Square
+    // regions give the same grid as with_variants.
+    pub fn with_regions(regions: &Regions, variants: &[Variant]) -> UnsolvedSudoku {
+        if *regions == Regions::classic() {
+            return UnsolvedSudoku::with_variants(variants);
+        }
+        let units = (1..10)
+            .zip(regions.units())
+            .flat_map(|(ix, region)| [Unit::Row(ix), Unit::Column(ix), region])
+            .collect();
+        return UnsolvedSudoku::with_base_units(units, variants);
+    }
+
+    // An empty grid with the units, and the variants' units beyond the classic ones and
+    // their constraints.
+    fn with_base_units(mut units: Vec<Unit>, variants: &[Variant]) -> UnsolvedSudoku {
+        let classic: Vec<Unit> = Unit::classic().collect();
+        for unit in variants.iter().flat_map(|v| v.units()) {
+            if !classic.contains(&unit) && !units.contains(&unit) {
+                units.push(unit);
+            }
+        }
+        let mut u = match units == classic {
+            // Sharing the classic layout, like every other classic grid.
+            true => UnsolvedSudoku::empty(),
+            false => UnsolvedSudoku::with_units(units).expect("a variant's units are all valid"),
+        };
+        for constraint in variants.iter().flat_map(|v| v.constraints()) {
+            u.add_constraint(constraint);
+        }
+        return u;
+    }
+
+    // The units this grid is solved against, in the order they are checked.
+    pub fn units(&self) -> impl Iterator<Item = Unit> + '_ {
+        return self.layout.units.iter().cloned();
+    }
+
+    // Add a unit to those this grid is solved against. Values already fixed in the
+    // unit's cells are removed from the candidates of its other cells, just as if they
+    // had been set after adding it. The grid is unchanged if the unit is invalid.
+    pub fn add_unit(&mut self, unit: Unit) -> Result<(), GridError> {
+        unit.check()?;
+        let mut units = self.layout.units.clone();
+        units.push(unit);
+        self.layout = Arc::new(Layout::with_rules(
+            units,
+            self.layout.constraints.clone(),
+            self.layout.cages.clone(),
+        ));
+        for c in unit.coords() {
+            if let Cell::Value(v) = self[c] {
+                self.set(c.row(), c.col(), v);
+            }
+        }
+        return Ok(());
+    }
+
+    // The constraints this grid is solved against on top of its units.
+    pub fn constraints(&self) -> impl Iterator<Item = Constraint> + '_ {
+        return self.layout.constraints.iter().cloned();
+    }
+
+    // Add a constraint, removing values already fixed from the candidates of the cells
+    // it relates them to, as add_unit does. Adding a constraint twice changes nothing.
+    pub fn add_constraint(&mut self, constraint: Constraint) {
+        if self.layout.constraints.contains(&constraint) {
+            return;
+        }
+        let mut constraints = self.layout.constraints.clone();
+        constraints.push(constraint);
+        self.layout = Arc::new(Layout::with_rules(
+            self.layout.units.clone(),
+            constraints,
+            self.layout.cages.clone(),
+        ));
+        for c in Coord::all() {
+            if let Cell::Value(v) = self[c] {
+                self.set(c.row(), c.col(), v);
+            }
+        }
+    }
+
+    // The killer cages this grid is solved against, in the order added.
+    pub fn cages(&self) -> impl Iterator<Item = &Cage> + '_ {
+        return self.layout.cages.iter();
+    }
+
+    // Add a killer cage, removing values already fixed in it from its other cells as
+    // add_unit does. Candidates are pruned by the cage's sum while solving, not here.
+    // Adding a cage twice changes nothing.
+    pub fn add_cage(&mut self, cage: Cage) {
+        if self.layout.cages.contains(&cage) {
+            return;
+        }
+        let mut cages = self.layout.cages.clone();
+        let cells = cage.cells().to_vec();
+        cages.push(cage);
+        self.layout = Arc::new(Layout::with_rules(
+            self.layout.units.clone(),
+            self.layout.constraints.clone(),
+            cages,
+        ));
+        for c in cells {
+            if let Cell::Value(v) = self[c] {
+                self.set(c.row(), c.col(), v);
+            }
+        }
+    }
+
+    // Fix every filled cell of s, propagating each as set does.
+    pub fn add_givens(&mut self, s: &Sudoku) {
+        for (c, v) in s.cells() {
+            if v != 0 {
+                self.set(c.row(), c.col(), v);
+            }
+        }
+    }
+
+    // Get the cell at 1-based (rix, cix). Panics if either index is outside 1-9;
+    // see try_get for a checked version.
+    pub fn get(&self, rix: u8, cix: u8) -> &Cell {
+        return &self[Coord { row: rix, col: cix }];
+    }
+
+    // Returns None if either index is outside 1-9.
+    pub fn try_get(&self, rix: u8, cix: u8) -> Option<&Cell> {
+        return Some(&self[Coord::new(rix, cix).ok()?]);
+    }
+
+    // Panics if either index is outside 1-9.
+    pub fn get_mut(&mut self, rix: u8, cix: u8) -> &mut Cell {
+        return &mut self[Coord { row: rix, col: cix }];
+    }
+
+    // Checked version of set: returns an error instead of panicking if the coordinates
+    // or value are outside 1-9.
+    pub fn try_set(&mut self, rix: u8, cix: u8, value: u8) -> Result<(), GridError> {
+        Coord::new(rix, cix)?;
+        if !(1..=9).contains(&value) {
+            return Err(GridError::ValueOutOfRange(value));
+        }
+        self.set(rix, cix, value);
+        return Ok(());
+    }
+
+    // Returns a copy of this grid with the user's (row, column, value) entries placed on
+    // top of it, or the first entry that conflicts with the givens, earlier entries, or
+    // what follows from them. The result can be passed to dynamic_solve to check it is
+    // still solvable.
+    pub fn with_user_entries(&self, entries: &[(u8, u8, u8)]) -> Result<UnsolvedSudoku, Conflict> {
+        let mut u = self.clone();
+        for &(row, col, value) in entries {
+            if !u.check_entry(row, col, value)? {
+                continue;
+            }
+            u.set(row, col, value);
+            if !u.valid() {
+                return Err(Conflict::Inconsistent { row, col, value });
+            }
+        }
+        return Ok(u);
+    }
+
+    // Whether value can be entered at (row, col): Ok(true) if the cell is open to it,
+    // Ok(false) if the cell already holds it, and the conflict otherwise.
+    fn check_entry(&self, row: u8, col: u8, value: u8) -> Result<bool, Conflict> {
+        Coord::new(row, col)?;
+        if !(1..=9).contains(&value) {
+            return Err(GridError::ValueOutOfRange(value).into());
+        }
+        return match self.get(row, col) {
+            &Cell::Value(v) if v == value => Ok(false),
+            &Cell::Value(v) => Err(Conflict::AlreadyFilled { row, col, value: v }),
+            Cell::Possibilities(ps) if !ps.contains(&value) => {
+                Err(Conflict::NotPossible { row, col, value })
+            }
+            Cell::Possibilities(_) => Ok(true),
+        };
+    }
+
+    // Place value at (rix, cix) as a player would, and report the cells that became
+    // fixed. Unlike add_clue this makes no deductions beyond removing the value from the
+    // cell's peers, and fixing any cell that leaves with one candidate. The value must
+    // still be a candidate there: one already fixed in the same unit is reported as
+    // Taken with that unit, and one removed some other way as NotPossible. Placing the
+    // value a cell already holds changes nothing. On a conflict the grid is left as it
+    // was.
+    pub fn place(&mut self, rix: u8, cix: u8, value: u8) -> Result<PlacementEffects, Conflict> {
+        let entry = self.check_entry(rix, cix, value);
+        if let Err(Conflict::NotPossible { row, col, value }) = entry {
+            let coord = Coord { row, col };
+            let taken = self.units().filter(|u| u.contains(coord)).find_map(|unit| {
+                let at = unit
+                    .coords()
+                    .into_iter()
+                    .find(|&c| c != coord && self.fixed_value(c) == Some(value))?;
+                return Some(Conflict::Taken {
+                    row,
+                    col,
+                    value,
+                    unit,
+                    at,
+                });
+            });
+            return Err(taken.unwrap_or(Conflict::NotPossible { row, col, value }));
+        }
+        if !entry? {
+            return Ok(PlacementEffects {
+                solved: self.is_solved(),
+                ..Default::default()
+            });
+        }
+        let mut u = self.clone();
+        u.set(rix, cix, value);
+        // Propagating can fix two peers to the same value, or take a cell's last
+        // candidate without fixing it.
+        let emptied = Coord::all().any(|c| u.candidates(c).is_some_and(|ps| ps.is_empty()));
+        if emptied || !u.valid() {
+            return Err(Conflict::Inconsistent {
+                row: rix,
+                col: cix,
+                value,
+            });
+        }
+        let fixed = u
+            .cells()
+            .filter_map(|(c, cell)| match (&self[c], cell) {
+                (Cell::Possibilities(_), &Cell::Value(v)) => Some((c, v)),
+                _ => None,
+            })
+            .collect();
+        let effects = PlacementEffects {
+            fixed,
+            solved: u.is_solved(),
+        };
+        *self = u;
+        return Ok(effects);
+    }
+
+    // Add a clue to a grid that is already partly solved, then deduce what follows from
+    // it without starting over, and report what changed. Deductions made so far are kept,
+    // so revealing clues one at a time only ever does the new work. On a conflict,
+    // including a contradiction found while deducing, the grid is left as it was.
+    // Only deductions are made, not guesses, so a clue that rules out every solution may
+    // still be accepted if that can't be seen without searching.
+    pub fn add_clue(&mut self, rix: u8, cix: u8, value: u8) -> Result<SolveProgress, Conflict> {
+        if !self.check_entry(rix, cix, value)? {
+            return Ok(SolveProgress {
+                solved: self.is_solved(),
+                ..Default::default()
+            });
+        }
+        let mut u = self.clone();
+        u.set(rix, cix, value);
+        u.simple_solve();
+        if !u.valid() {
+            return Err(Conflict::Inconsistent {
+                row: rix,
+                col: cix,
+                value,
+            });
+        }
+
+        let mut progress = SolveProgress {
+            solved: u.is_solved(),
+            ..Default::default()
+        };
+        for (c, cell) in u.cells() {
+            match (&self[c], cell) {
+                (Cell::Possibilities(_), &Cell::Value(v)) => progress.fixed.push((c, v)),
+                (Cell::Possibilities(old), Cell::Possibilities(new)) => {
+                    progress.eliminated += old.len() - new.len();
+                }
+                _ => {}
+            }
+        }
+        *self = u;
+        return Ok(progress);
+    }
+
+    // Set the value at (rix, cix) with value, and recursively remove that possibility
+    // from all cells sharing a unit or cage with it, and from each cell a constraint
+    // pairs with it whatever values the constraint then rules out.
+    // Panics if the coordinates are outside 1-9.
+    fn set(&mut self, rix: u8, cix: u8, value: u8) {
+        let coord = match Coord::new(rix, cix) {
+            Ok(c) => c,
+            Err(e) => panic!("set: {}", e),
+        };
+        self[coord] = Cell::Value(value);
+        let layout = Arc::clone(&self.layout);
+        let units = layout.units_of(coord);
+
+        for ix in 0..9 {
+            for &u in units {
+                let c = layout.coords[u][ix];
+                match self[c].remove_possibility(value) {
+                    Removal::Fixed(w) => self.set(c.row(), c.col(), w),
+                    _ => continue,
+                }
+            }
+        }
+        for &(c, constraint) in layout.neighbors_of(coord) {
+            for ruled_out in (1..10).filter(|&w| !constraint.allows(value, w)) {
+                if let Removal::Fixed(w) = self[c].remove_possibility(ruled_out) {
+                    self.set(c.row(), c.col(), w);
+                }
+            }
+        }
+        for &k in layout.cages_of(coord) {
+            for &c in layout.cages[k].cells() {
+                if let Removal::Fixed(w) = self[c].remove_possibility(value) {
+                    self.set(c.row(), c.col(), w);
+                }
+            }
+        }
+    }
+
+    // The cells fixed so far, with 0 for cells that are still unsolved.
+    pub fn partial_sudoku(&self) -> Sudoku {
+        let mut s = Sudoku::empty();
+        for (c, cell) in self.cells() {
+            s[c] = match *cell {
+                Cell::Value(v) => v,
+                Cell::Possibilities(_) => 0,
+            };
+        }
+        return s;
+    }
+
+    // Every cell in row-major order.
+    pub fn cells(&self) -> impl Iterator<Item = (Coord, &Cell)> {
+        return Coord::all().map(move |c| (c, &self[c]));
+    }
+
+    // The cells of row ix, column ix, or square ix, in the order given by row_coords,
+    // col_coords, and box_coords. These panic if ix is outside 1-9.
+    pub fn row_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, &Cell)> {
+        return row_coords(ix).into_iter().map(move |c| (c, &self[c]));
+    }
+
+    pub fn col_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, &Cell)> {
+        return col_coords(ix).into_iter().map(move |c| (c, &self[c]));
+    }
+
+    pub fn box_cells(&self, ix: u8) -> impl Iterator<Item = (Coord, &Cell)> {
+        return box_coords(ix).into_iter().map(move |c| (c, &self[c]));
+    }
+
+    // Whether value could be entered at (rix, cix): the cell is unsolved, value is still
+    // one of its candidates, and no cell sharing a unit or cage with it already holds
+    // it. The last check means a stale candidate list can't give a wrong answer. Returns
+    // false for coordinates or values outside 1-9.
+    pub fn can_place(&self, rix: u8, cix: u8, value: u8) -> bool {
+        let coord = match Coord::new(rix, cix) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+        match &self[coord] {
+            Cell::Value(_) => return false,
+            Cell::Possibilities(ps) if !ps.contains(&value) => return false,
+            Cell::Possibilities(_) => {}
+        }
+        let paired = self
+            .layout
+            .neighbors_of(coord)
+            .iter()
+            .all(|&(p, constraint)| {
+                return self
+                    .fixed_value(p)
+                    .is_none_or(|w| constraint.allows(value, w));
+            });
+        let caged = self
+            .layout
+            .cages_of(coord)
+            .iter()
+            .flat_map(|&k| self.layout.cages[k].cells())
+            .all(|&p| p == coord || self[p] != Cell::Value(value));
+        return paired
+            && caged
+            && self
+                .layout
+                .units_of(coord)
+                .iter()
+                .flat_map(|&u| self.layout.coords[u])
+                .all(|p| p == coord || self[p] != Cell::Value(value));
+    }
+
+    // The value the cell at (rix, cix) must take, if there is only one. A cell left with a
+    // single candidate is fixed straight away, so this is the cell's value if it is fixed
+    // and None if it still has two or more candidates or the coordinates are off the grid.
+    pub fn must_place(&self, rix: u8, cix: u8) -> Option<u8> {
+        return match self.try_get(rix, cix)? {
+            &Cell::Value(v) => Some(v),
+            Cell::Possibilities(_) => None,
+        };
+    }
+
+    // The remaining candidates for an unsolved cell, or None if the cell is fixed.
+    pub fn candidates(&self, coord: Coord) -> Option<CandidateSet> {
+        return match &self[coord] {
+            Cell::Value(_) => None,
+            Cell::Possibilities(ps) => Some(ps.iter().cloned().collect()),
+        };
+    }
+
+    // Every unsolved cell with exactly two candidates, as (row, column, (smaller,
+    // larger)) in row-major order. These bivalue cells are what XY-wing, W-wing, and
+    // other chain techniques are built from.
+    pub fn bivalue_cells(&self) -> Vec<(u8, u8, (u8, u8))> {
+        return self
+            .cells()
+            .filter_map(|(c, cell)| match cell {
+                Cell::Possibilities(ps) if ps.len() == 2 => {
+                    let mut values = ps.iter();
+                    let (&a, &b) = (values.next()?, values.next()?);
+                    return Some((c.row(), c.col(), (a, b)));
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    // The value of a fixed cell, or None if it is still unsolved.
+    pub fn fixed_value(&self, coord: Coord) -> Option<u8> {
+        return match self[coord] {
+            Cell::Value(v) => Some(v),
+            Cell::Possibilities(_) => None,
+        };
+    }
+
+    // Remove value from the candidates at coord. If that leaves a single candidate, the
+    // cell is fixed to it and the fix is propagated just as set does, so the grid never
+    // holds a one-element candidate set. Fixed cells are left alone.
+    pub fn eliminate(&mut self, coord: Coord, value: u8) -> Result<Elimination, GridError> {
+        if !(1..=9).contains(&value) {
+            return Err(GridError::ValueOutOfRange(value));
+        }
+        return Ok(match self[coord].remove_possibility(value) {
+            Removal::NotFound => Elimination::NotCandidate,
+            Removal::Removed => Elimination::Removed,
+            Removal::Fixed(w) => {
+                self.set(coord.row(), coord.col(), w);
+                Elimination::Fixed(w)
+            }
+        });
+    }
+
+    // The number of fixed cells, givens included.
+    pub fn solved_count(&self) -> usize {
+        return self
+            .cells()
+            .filter(|(_, c)| matches!(c, Cell::Value(_)))
+            .count();
+    }
+
+    // The number of cells still unsolved.
+    pub fn remaining_count(&self) -> usize {
+        return 81 - self.solved_count();
+    }
+
+    // A grid of every cell's state, for debugging the solver rather than for showing to
+    // users. Solved cells are "=" and their value; unsolved cells are their candidates as
+    // a three-digit hex bitmask, bit d - 1 set for candidate d, so "1ff" is all nine and
+    // "005" is 1 and 3. Cells are separated by spaces and stacks by '|', and a last line
+    // gives the solved count. The format is meant to be snapshot-tested, so keep it
+    // stable.
+    pub fn debug_dump(&self) -> String {
+        let mut out = String::new();
+        for (rix, row) in self.rows.iter().enumerate() {
+            if rix > 0 && rix % 3 == 0 {
+                out.push_str("------------+-------------+------------\n");
+            }
+            for (cix, cell) in row.iter().enumerate() {
+                if cix > 0 {
+                    out.push_str(if cix % 3 == 0 { " | " } else { " " });
+                }
+                match cell {
+                    Cell::Value(v) => out.push_str(&format!(" ={}", v)),
+                    Cell::Possibilities(ps) => {
+                        let mask = ps.iter().fold(0u16, |m, &p| m | 1 << (p - 1));
+                        out.push_str(&format!("{:03x}", mask));
+                    }
+                }
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("solved {}/81\n", self.solved_count()));
+        return out;
+    }
+
+    // True once every cell holds a value. The values may still conflict; see valid.
+    pub fn is_solved(&self) -> bool {
+        for (_, c) in self.cells() {
+            match c {
+                Cell::Value(_) => continue,
+                Cell::Possibilities(_) => return false,
+            }
+        }
+        return true;
+    }
+
+    // Fill hidden singles and prune cages until neither makes progress. Returns how many
+    // cells the hidden singles filled; the naked singles those set off aren't counted.
+    fn simple_solve(&mut self) -> usize {
+        let mut hidden = 0;
+        loop {
+            let filled = self.hidden_singles();
+            hidden += filled;
+            if filled + self.cage_sums() == 0 {
+                break;
+            }
+        }
+        log_trace!(
+            "propagation settled with {} cells solved",
+            self.solved_count()
+        );
+        return hidden;
+    }
+
+    // One pass over every unit, setting each value that has only one place left in it.
+    // Returns how many were set; each set may fix further cells as naked singles.
+    fn hidden_singles(&mut self) -> usize {
+        let layout = Arc::clone(&self.layout);
+        let mut filled = 0;
+        for unit in &layout.coords {
+            let mut locs = [PossibleLocations::None; 9];
+            for &c in unit {
+                let ps = match &self[c] {
+                    Cell::Value(v) => {
+                        locs[(v - 1) as usize] = PossibleLocations::Filled;
+                        continue;
+                    }
+                    Cell::Possibilities(ps) => ps,
+                };
+
+                for p in ps {
+                    locs[(p - 1) as usize] = match locs[(p - 1) as usize] {
+                        PossibleLocations::None => PossibleLocations::Single(c.row(), c.col()),
+                        PossibleLocations::Single(..) => PossibleLocations::Many,
+                        _ => continue,
+                    }
+                }
+            }
+
+            for (ix, p) in locs.iter().enumerate() {
+                let v = (ix + 1) as u8;
+                if let PossibleLocations::Single(rix, cix) = *p {
+                    self.set(rix, cix, v);
+                    filled += 1;
+                }
+            }
+        }
+        return filled;
+    }
+
+    // One pass over every cage, and every cage the rule of 45 finds, removing each
+    // candidate that no set of distinct digits with the cage's sum has room for. A cage
+    // that can't be filled at all is left with its unsolved cells empty, so the search
+    // gives up on the grid. Returns how many candidates were removed.
+    fn cage_sums(&mut self) -> usize {
+        let layout = Arc::clone(&self.layout);
+        let mut removed = 0;
+        for cage in layout.cages.iter().chain(&layout.innies) {
+            let candidates: Vec<CandidateSet> = cage
+                .cells()
+                .iter()
+                .map(|&c| match &self[c] {
+                    &Cell::Value(v) => CandidateSet::from_iter([v]),
+                    Cell::Possibilities(ps) => ps.iter().cloned().collect(),
+                })
+                .collect();
+            let allowed = cage.allowed(&candidates);
+            for &c in cage.cells() {
+                let ruled_out: Vec<u8> = match &self[c] {
+                    Cell::Possibilities(ps) => ps
+                        .iter()
+                        .cloned()
+                        .filter(|&v| !allowed.contains(v))
+                        .collect(),
+                    Cell::Value(_) => continue,
+                };
+                removed += ruled_out.len();
+                if allowed.is_empty() {
+                    self[c] = Cell::Possibilities(collections::BTreeSet::new());
+                    continue;
+                }
+                for v in ruled_out {
+                    if let Removal::Fixed(w) = self[c].remove_possibility(v) {
+                        self.set(c.row(), c.col(), w);
+                    }
+                }
+            }
+        }
+        return removed;
+    }
+
+    // Apply the basic fish pattern of the given size (2 for X-Wing, 3 for swordfish, 4 for
+    // jellyfish): if a value's candidates in `size` rows all fall within `size` columns,
+    // then those rows must hold the value in those columns, so it can be removed from
+    // the rest of each column. The same is done with rows and columns swapped.
+    // Returns the number of candidates removed. Panics if size is not 2, 3, or 4.
+    pub fn eliminate_fish(&mut self, size: usize) -> usize {
+        return self.eliminate_fish_traced(size).len();
+    }
+
+    // Like eliminate_fish, but returns each candidate removed, in the order removed.
+    pub fn eliminate_fish_traced(&mut self, size: usize) -> Vec<Eliminated> {
+        assert!(
+            (2..=4).contains(&size),
+            "fish size {} out of range 2-4",
+            size
+        );
+        let reason = match size {
+            2 => Technique::XWing,
+            3 => Technique::Swordfish,
+            _ => Technique::Jellyfish,
+        };
+        let mut removed = Vec::new();
+        let orientations: [fn(u8) -> [Coord; 9]; 2] = [row_coords, col_coords];
+        for value in 1..10 {
+            for base_coords in &orientations {
+                let bases: Vec<[Coord; 9]> = (1..10).map(base_coords).collect();
+
+                // For each base unit, a bitmask of the positions along it where value
+                // is still a candidate.
+                let mut masks = [0u16; 9];
+                for (b, unit) in bases.iter().enumerate() {
+                    for (ix, &c) in unit.iter().enumerate() {
+                        if let Cell::Possibilities(ps) = &self[c] {
+                            if ps.contains(&value) {
+                                masks[b] |= 1 << ix;
+                            }
+                        }
+                    }
+                }
+
+                let candidates: Vec<usize> = (0..9)
+                    .filter(|&b| masks[b] != 0 && masks[b].count_ones() as usize <= size)
+                    .collect();
+                let mut fish = Vec::new();
+                combinations(&candidates, size, &mut |chosen| {
+                    let cover = chosen.iter().fold(0u16, |m, &b| m | masks[b]);
+                    if cover.count_ones() as usize == size {
+                        fish.push((chosen.to_vec(), cover));
+                    }
+                });
+
+                for (chosen, cover) in fish {
+                    for (b, unit) in bases.iter().enumerate() {
+                        if chosen.contains(&b) {
+                            continue;
+                        }
+                        for (ix, &c) in unit.iter().enumerate() {
+                            if cover & (1 << ix) == 0 {
+                                continue;
+                            }
+                            self.remove_traced(c, value, reason, &mut removed);
+                        }
+                    }
+                }
+            }
+        }
+        return removed;
+    }
+
+    // Apply XYZ-wing: a pivot cell with candidates {X, Y, Z}, seeing two cells with
+    // candidates {X, Z} and {Y, Z}. Whichever value the pivot takes, one of the three
+    // cells is Z, so Z can be removed from every other cell that sees all three. Cells
+    // see each other by sharing one of the grid's units, so jigsaw regions count and
+    // squares they replace don't. Returns the number of candidates removed.
+    pub fn eliminate_xyz_wing(&mut self) -> usize {
+        return self.eliminate_xyz_wing_traced().len();
+    }
+
+    // Like eliminate_xyz_wing, but returns each candidate removed, in the order removed.
+    pub fn eliminate_xyz_wing_traced(&mut self) -> Vec<Eliminated> {
+        // Every wing is found before any candidate is removed; a removal only follows
+        // from the grid, so it stays sound as others shrink it.
+        let layout = Arc::clone(&self.layout);
+        let mut wings = Vec::new();
+        for (pivot, cell) in self.cells() {
+            let xyz = match cell {
+                Cell::Possibilities(ps) if ps.len() == 3 => ps,
+                _ => continue,
+            };
+            let arms: Vec<(Coord, &collections::BTreeSet<u8>)> = layout
+                .peers(pivot)
+                .filter_map(|c| match &self[c] {
+                    Cell::Possibilities(ps) if ps.len() == 2 && ps.is_subset(xyz) => {
+                        return Some((c, ps));
+                    }
+                    _ => None,
+                })
+                .collect();
+            for (ix, &(a, a_ps)) in arms.iter().enumerate() {
+                for &(b, b_ps) in &arms[ix + 1..] {
+                    if a_ps == b_ps {
+                        continue;
+                    }
+                    // Two different pairs of the pivot's three values share just one, Z.
+                    let z = *a_ps.intersection(b_ps).next().unwrap();
+                    wings.push((pivot, a, b, z));
+                }
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (pivot, a, b, z) in wings {
+            let seen: Vec<Coord> = layout
+                .peers(pivot)
+                .filter(|&c| c != a && c != b && layout.sees(a, c) && layout.sees(b, c))
+                .collect();
+            for c in seen {
+                self.remove_traced(c, z, Technique::XyzWing, &mut removed);
+            }
+        }
+        return removed;
+    }
+
+    // Apply naked pairs: two cells of a unit left with the same two candidates hold those
+    // two values between them, so no other cell of the unit can hold either. Returns the
+    // number of candidates removed.
+    pub fn eliminate_naked_pairs(&mut self) -> usize {
+        return self.eliminate_naked_pairs_traced().len();
+    }
+
+    // Like eliminate_naked_pairs, but returns each candidate removed, in the order
+    // removed.
+    pub fn eliminate_naked_pairs_traced(&mut self) -> Vec<Eliminated> {
+        // As with XYZ-wings, every pair is found before any candidate is removed.
+        let layout = Arc::clone(&self.layout);
+        let mut found = Vec::new();
+        for coords in &layout.coords {
+            let pairs: Vec<(Coord, &collections::BTreeSet<u8>)> = coords
+                .iter()
+                .filter_map(|&c| match &self[c] {
+                    Cell::Possibilities(ps) if ps.len() == 2 => Some((c, ps)),
+                    _ => None,
+                })
+                .collect();
+            for (ix, &(a, ps)) in pairs.iter().enumerate() {
+                for &(b, _) in pairs[ix + 1..].iter().filter(|&&(_, other)| other == ps) {
+                    let values: Vec<u8> = ps.iter().cloned().collect();
+                    found.push((coords, a, b, values));
+                }
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (coords, a, b, values) in found {
+            for &c in coords.iter().filter(|&&c| c != a && c != b) {
+                for &v in &values {
+                    self.remove_traced(c, v, Technique::NakedPair, &mut removed);
+                }
+            }
+        }
+        return removed;
+    }
+
+    // Apply pointing: when a value's candidates in one unit all lie in a second unit, the
+    // value must go in the cells they share, so the second unit's other cells can't hold
+    // it. That covers a box pointing along a row or column, a row or column claiming a
+    // box, and any other units that overlap, as diagonals and extra regions do. A value
+    // with one candidate left in a unit is left for hidden singles. Returns the number of
+    // candidates removed.
+    pub fn eliminate_pointing(&mut self) -> usize {
+        return self.eliminate_pointing_traced().len();
+    }
+
+    // Like eliminate_pointing, but returns each candidate removed, in the order removed.
+    pub fn eliminate_pointing_traced(&mut self) -> Vec<Eliminated> {
+        let layout = Arc::clone(&self.layout);
+        let mut found = Vec::new();
+        for value in 1..10u8 {
+            for (ix, coords) in layout.coords.iter().enumerate() {
+                let holding: Vec<Coord> = coords
+                    .iter()
+                    .cloned()
+                    .filter(|&c| matches!(&self[c], Cell::Possibilities(ps) if ps.contains(&value)))
+                    .collect();
+                if holding.len() < 2 {
+                    continue;
+                }
+                for (other_ix, other) in layout.coords.iter().enumerate() {
+                    if other_ix != ix && holding.iter().all(|c| other.contains(c)) {
+                        found.push((value, other, holding.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (value, other, holding) in found {
+            for &c in other.iter().filter(|c| !holding.contains(c)) {
+                self.remove_traced(c, value, Technique::Pointing, &mut removed);
+            }
+        }
+        return removed;
+    }
+
+    // Remove value from the cell's candidates for a technique, noting it in removed if
+    // it was there, and fixing the cell if one candidate is left.
+    fn remove_traced(
+        &mut self,
+        c: Coord,
+        value: u8,
+        reason: Technique,
+        removed: &mut Vec<Eliminated>,
+    ) {
+        let fixed = match self[c].remove_possibility(value) {
+            Removal::NotFound => return,
+            Removal::Removed => None,
+            Removal::Fixed(w) => Some(w),
+        };
+        removed.push(Eliminated {
+            cell: (c.row(), c.col()),
+            value,
+            reason,
+        });
+        if let Some(w) = fixed {
+            self.set(c.row(), c.col(), w);
+        }
+    }
+
+    // dynamic_solve applies the rules of simple_solve, and then alternates a "guess and check" expansion approach with application of the simple_solve rules to either find a single solution or return no solution.
+    pub fn dynamic_solve(&mut self) -> Result<Sudoku, Unsolveable> {
+        let mut search = Search::new(u64::MAX);
+        return match self.search(&mut search) {
+            Ok(s) => Ok(s),
+            Err(SearchError::Unsolveable(e)) => Err(e),
+            Err(SearchError::Stopped) => unreachable!("unlimited search ran out of guesses"),
+        };
+    }
+
+    // Like dynamic_solve, but calls on_guess with the running guess count after every
+    // guess, so a caller can show progress. Returning false from on_guess abandons the
+    // search, and then the result is SolveError::Cancelled.
+    pub fn dynamic_solve_with_callback(
+        &mut self,
+        mut on_guess: impl FnMut(u64) -> bool,
+    ) -> Result<Sudoku, SolveError> {
+        let mut search = Search::new(u64::MAX);
+        search.on_guess = Some(&mut on_guess);
+        return match self.search(&mut search) {
+            Ok(s) => Ok(s),
+            Err(SearchError::Unsolveable(e)) => Err(SolveError::Unsolveable(e)),
+            Err(SearchError::Stopped) => Err(SolveError::Cancelled),
+        };
+    }
+
+    // Like dynamic_solve, but abandons the search soon after cancel is set, from any
+    // thread, and then returns SolveError::Cancelled. The flag is read after each guess;
+    // that is one relaxed load against the copy of the grid each guess makes, so it
+    // costs nothing measurable. A puzzle that needs no guesses is solved regardless.
+    pub fn dynamic_solve_cancellable(&mut self, cancel: &AtomicBool) -> Result<Sudoku, SolveError> {
+        return self.dynamic_solve_with_callback(|_| !cancel.load(Ordering::Relaxed));
+    }
+
+    // Like dynamic_solve, but works on a copy, so self keeps its candidates as they were
+    // before the solve.
+    pub fn solved(&self) -> Result<Sudoku, Unsolveable> {
+        return self.clone().dynamic_solve();
+    }
+
+    // Like dynamic_solve, but gives up once `budget` guesses have been made, returning
+    // None. Also returns the number of guesses made.
+    pub fn budgeted_solve(&mut self, budget: u64) -> (Option<Result<Sudoku, Unsolveable>>, u64) {
+        return self.configured_solve(
+            SolverConfig {
+                guess_budget: budget,
+                ..Default::default()
+            },
+            None,
+        );
+    }
+
+    // Like budgeted_solve, with the budget and branching of config, and calling on_guess
+    // if given as dynamic_solve_with_callback does.
+    pub(crate) fn configured_solve(
+        &mut self,
+        config: SolverConfig,
+        on_guess: Option<&mut dyn FnMut(u64) -> bool>,
+    ) -> (Option<Result<Sudoku, Unsolveable>>, u64) {
+        let report = self.configured_report(config, on_guess, &[]);
+        return (report.result, report.guesses);
+    }
+
+    // Solve a copy, as solved does, and report how the search went: its result, the
+    // guesses it made and how deep they nested, which deductions it used, and how long
+    // it took. Cells already fixed in self, such as naked singles set off by adding the
+    // givens, don't count towards the techniques.
+    pub fn solve_report(&self) -> SolveReport {
+        return self
+            .clone()
+            .configured_report(SolverConfig::default(), None, &[]);
+    }
+
+    // Like configured_solve, with everything the search kept track of, and running the
+    // strategies before each guess.
+    pub(crate) fn configured_report(
+        &mut self,
+        config: SolverConfig,
+        on_guess: Option<&mut dyn FnMut(u64) -> bool>,
+        strategies: &[Arc<dyn Strategy>],
+    ) -> SolveReport {
+        #[cfg(feature = "std")]
+        let start = std::time::Instant::now();
+        let mut search = Search::new(config.guess_budget);
+        search.branching = config.branching;
+        search.on_guess = on_guess;
+        search.strategies = strategies;
+        let result = match self.search(&mut search) {
+            Ok(s) => Some(Ok(s)),
+            Err(SearchError::Unsolveable(e)) => Some(Err(e)),
+            Err(SearchError::Stopped) => None,
+        };
+        return SolveReport {
+            result,
+            guesses: search.guesses,
+            max_depth: search.max_depth,
+            techniques_used: search.techniques,
+            #[cfg(feature = "std")]
+            elapsed: start.elapsed(),
+        };
+    }
+
+    // Solve by trying digits alone: fill the blank cell with the fewest digits that fit,
+    // trying each in ascending order, with no deductions in between. Much slower than
+    // dynamic_solve, but simple enough to check by eye, so it serves as a reference to
+    // test the solver against, and as a baseline for how much the deductions save.
+    // Leaves self unchanged.
+    pub fn brute_force_solve(&mut self) -> Result<Sudoku, Unsolveable> {
+        return self.brute_force_counting().0;
+    }
+
+    // Like brute_force_solve, also returning how many digits it tried, to compare with
+    // the guesses from budgeted_solve.
+    pub fn brute_force_counting(&self) -> (Result<Sudoku, Unsolveable>, u64) {
+        let mut found = Vec::new();
+        let mut tries = 0;
+        if self.valid() {
+            self.brute_force(&mut self.partial_sudoku(), &mut found, &mut tries);
+        }
+        let result = match found[..] {
+            [] => Err(Unsolveable::NoSolution),
+            [s] => Ok(s),
+            [a, b, ..] => Err(Unsolveable::MultipleSolutions(Box::new(a), Box::new(b))),
+        };
+        return (result, tries);
+    }
+
+    // Add each completion of grid to found, stopping once there are two.
+    fn brute_force(&self, grid: &mut Sudoku, found: &mut Vec<Sudoku>, tries: &mut u64) {
+        let mut best: Option<(Coord, Vec<u8>)> = None;
+        for c in Coord::all().filter(|&c| grid.is_empty(c)) {
+            let fits: Vec<u8> = (1..10).filter(|&v| self.fits(grid, c, v)).collect();
+            if best.as_ref().is_none_or(|(_, b)| fits.len() < b.len()) {
+                best = Some((c, fits));
+            }
+        }
+        let (coord, fits) = match best {
+            None => {
+                found.push(*grid);
+                return;
+            }
+            Some(b) => b,
+        };
+        for v in fits {
+            if found.len() >= 2 {
+                break;
+            }
+            *tries += 1;
+            grid[coord] = v;
+            self.brute_force(grid, found, tries);
+        }
+        grid.clear(coord);
+    }
+
+    // Whether v can go in the blank cell c of grid: it is still one of the cell's
+    // candidates here, no other cell sharing a unit holds it in grid, no constraint
+    // rules it out given the values of the cells it pairs with c, and every cage
+    // holding c could still be completed.
+    fn fits(&self, grid: &Sudoku, c: Coord, v: u8) -> bool {
+        if let Cell::Possibilities(ps) = &self[c] {
+            if !ps.contains(&v) {
+                return false;
+            }
+        }
+        return self.layout.units_of(c).iter().all(|&u| {
+            self.layout.coords[u]
+                .iter()
+                .all(|&o| o == c || grid[o] != v)
+        }) && self
+            .layout
+            .neighbors_of(c)
+            .iter()
+            .all(|&(o, constraint)| grid[o] == 0 || constraint.allows(v, grid[o]))
+            && self
+                .layout
+                .cages_of(c)
+                .iter()
+                .all(|&k| self.layout.cages[k].consistent(|o| if o == c { v } else { grid[o] }));
+    }
+
+    // The grid of a solved search. Panics if any cell is unsolved, which would be a bug
+    // in the solver rather than silently handing back a grid with holes.
+    fn finished(&self) -> Sudoku {
+        return match Sudoku::try_from(self) {
+            Ok(s) => s,
+            Err(e) => panic!("solver finished with an incomplete grid: {}", e),
+        };
+    }
+
+    // The first unsolved cell (in row-major order) with the fewest candidates, and those
+    // candidates: where dynamic_solve makes its next guess. None if every cell is fixed.
+    pub fn min_candidates_cell(&self) -> Option<(Coord, CandidateSet)> {
+        let mut to_expand: Option<(Coord, &collections::BTreeSet<u8>)> = None;
+        for (coord, c) in self.cells() {
+            let ps = match (c, &to_expand) {
+                (&Cell::Value(_), _) => continue,
+                (Cell::Possibilities(ps), None) => ps,
+                (Cell::Possibilities(ps), Some((_, other_ps))) if ps.len() < other_ps.len() => ps,
+                (&Cell::Possibilities(_), Some(_)) => continue,
+            };
+            to_expand = Some((coord, ps));
+        }
+        return to_expand.map(|(coord, ps)| (coord, ps.iter().cloned().collect()));
+    }
+
+    // Like min_candidates_cell, but breaks ties by rarity: each candidate scores one for
+    // every unsolved peer that could also take it, and the cell with the lowest total
+    // wins, with remaining ties going to the first in row-major order. Guessing where
+    // the values are in short supply settles more of the grid, which on hard puzzles
+    // tends to mean fewer guesses, at the cost of scoring the tied cells.
+    pub fn rarest_candidates_cell(&self) -> Option<(Coord, CandidateSet)> {
+        let (_, fewest) = self.min_candidates_cell()?;
+        let mut best: Option<(Coord, CandidateSet, usize)> = None;
+        for (coord, cell) in self.cells() {
+            let ps = match cell {
+                Cell::Possibilities(ps) if ps.len() == fewest.len() => ps,
+                _ => continue,
+            };
+            let mut peers: Vec<Coord> = self
+                .layout
+                .units_of(coord)
+                .iter()
+                .flat_map(|&u| self.layout.coords[u])
+                .chain(self.layout.neighbors_of(coord).iter().map(|&(n, _)| n))
+                .chain(
+                    self.layout
+                        .cages_of(coord)
+                        .iter()
+                        .flat_map(|&k| self.layout.cages[k].cells().iter().cloned()),
+                )
+                .filter(|&p| p != coord)
+                .collect();
+            peers.sort();
+            peers.dedup();
+            let score = peers
+                .iter()
+                .filter_map(|&p| self.candidates(p))
+                .map(|other| ps.iter().filter(|&&v| other.contains(v)).count())
+                .sum();
+            if best.as_ref().is_none_or(|&(_, _, b)| score < b) {
+                best = Some((coord, ps.iter().cloned().collect(), score));
+            }
+        }
+        return best.map(|(coord, ps, _)| (coord, ps));
+    }
+
+    // Every solution, found lazily in the same order the search tries its guesses. Take
+    // the first two to tell a unique puzzle from an ambiguous one without enumerating
+    // the rest, which for a sparse grid could take practically forever.
+    //
+    // The order is fixed by the grid alone, the same on every run and platform: each
+    // guess goes in min_candidates_cell, trying its candidates in ascending order and
+    // exploring each fully before the next. That is not lexicographic order, since the
+    // guessed cell needn't be the first blank one. count_solutions counts in this order,
+    // and the two solutions dynamic_solve reports for an ambiguous puzzle are the first
+    // two here.
+    pub fn solutions(&self) -> Solutions {
+        if !self.valid() {
+            return Solutions { stack: Vec::new() };
+        }
+        return Solutions {
+            stack: vec![self.clone()],
+        };
+    }
+
+    fn search(&mut self, search: &mut Search<'_, '_>) -> Result<Sudoku, SearchError> {
+        // Guessing never adds a conflict, so only the starting grid can have one. Catch
+        // it here: with few other clues, searching to rule it out could take forever.
+        if search.depth == 0 && !self.valid() {
+            return Err(Unsolveable::NoSolution.into());
+        }
+        let fixed = self.solved_count();
+        let mut hidden = self.simple_solve();
+        while !search.strategies.is_empty() && self.apply_strategies(search.strategies) > 0 {
+            hidden += self.simple_solve();
+        }
+        if hidden > 0 {
+            search.used(Technique::HiddenSingle);
+        }
+        let fixed_after = self.solved_count();
+        if fixed_after > fixed + hidden {
+            search.used(Technique::NakedSingle);
+        }
+        if self.is_solved() {
+            if self.valid() {
+                log_debug!(
+                    "solution found at depth {} after {} guesses",
+                    search.depth,
+                    search.guesses
+                );
+                return Ok(self.finished());
+            }
+            return Err(Unsolveable::NoSolution.into());
+        };
+
+        let branch = match search.branching {
+            Branching::FewestCandidates => self.min_candidates_cell(),
+            Branching::RarestCandidates => self.rarest_candidates_cell(),
+        };
+        let (coord, ps) = match branch {
+            None => return Ok(self.finished()),
+            Some(v) => v,
+        };
+
+        let mut found = None;
+        for p in ps.iter() {
+            if search.guesses >= search.budget {
+                return Err(SearchError::Stopped);
+            }
+            search.guesses += 1;
+            if let Some(on_guess) = &mut search.on_guess {
+                if !on_guess(search.guesses) {
+                    return Err(SearchError::Stopped);
+                }
+            }
+            search.depth += 1;
+            search.max_depth = search.max_depth.max(search.depth);
+            log_trace!("guess {} at {} (depth {})", p, coord, search.depth);
+            let mut u2 = self.clone();
+            u2.set(coord.row(), coord.col(), p);
+            // Any cell fixed besides the guessed one was left a naked single by it.
+            if u2.solved_count() > fixed_after + 1 {
+                search.used(Technique::NakedSingle);
+            }
+            let result = u2.search(search);
+            search.depth -= 1;
+            let solved = match result {
+                Err(SearchError::Unsolveable(Unsolveable::NoSolution)) => {
+                    log_trace!(
+                        "backtrack from {} at {} (depth {})",
+                        p,
+                        coord,
+                        search.depth + 1
+                    );
+                    continue;
+                }
+                // Report the first two solutions in search order, which may be this
+                // branch's first and one found before it.
+                Err(SearchError::Unsolveable(Unsolveable::MultipleSolutions(a, b))) => {
+                    let pair = match found {
+                        Some(first) => Unsolveable::MultipleSolutions(Box::new(first), a),
+                        None => Unsolveable::MultipleSolutions(a, b),
+                    };
+                    return Err(pair.into());
+                }
+                Err(e) => return Err(e),
+                Ok(s) => s,
+            };
+            found = match found {
+                None => Some(solved),
+                Some(first) => {
+                    return Err(
+                        Unsolveable::MultipleSolutions(Box::new(first), Box::new(solved)).into(),
+                    );
+                }
+            }
+        }
+
+        match found {
+            None => Err(Unsolveable::NoSolution.into()),
+            Some(s) => Ok(s),
+        }
+    }
+
+    // Whether no unit holds the same value in two fixed cells, no constraint is broken
+    // by two fixed cells, and no cage repeats a value or has fixed values that leave its
+    // sum out of reach.
+    pub fn valid(&self) -> bool {
+        let cages_hold = self
+            .layout
+            .cages
+            .iter()
+            .all(|cage| cage.consistent(|c| self.fixed_value(c).unwrap_or(0)));
+        let constraints_hold = Coord::all().all(|c| match self.fixed_value(c) {
+            None => true,
+            Some(v) => self.layout.neighbors_of(c).iter().all(|&(o, constraint)| {
+                self.fixed_value(o).is_none_or(|w| constraint.allows(v, w))
+            }),
+        });
+        return constraints_hold && cages_hold && self.violations().is_empty();
+    }
+
+    // Every unit with a value in more than one fixed cell, in the order of units and
+    // then by value, so a UI can mark each problem at once. Constraints are not checked
+    // here; without any, this is empty exactly when valid.
+    pub fn validity_report(&self) -> Vec<UnitViolation> {
+        let mut report = Vec::new();
+        for (unit, coords) in self.layout.units.iter().zip(&self.layout.coords) {
+            let mut holding: [Vec<Coord>; 9] = Default::default();
+            for &c in coords {
+                if let Some(v) = self.fixed_value(c) {
+                    holding[(v - 1) as usize].push(c);
+                }
+            }
+            for (ix, cells) in holding.into_iter().enumerate() {
+                if cells.len() > 1 {
+                    report.push(UnitViolation {
+                        unit: *unit,
+                        value: (ix + 1) as u8,
+                        cells,
+                    });
+                }
+            }
+        }
+        return report;
+    }
+
+    // Every pair of fixed cells holding the same value in a unit, unit by unit in the
+    // order of units, and then in the unit's order. A cell repeated three times in one
+    // unit makes three pairs. As with validity_report, constraints are not checked.
+    pub fn violations(&self) -> Vec<Violation> {
+        let mut found = Vec::new();
+        for (unit, coords) in self.layout.units.iter().zip(&self.layout.coords) {
+            let value = |c: Coord| self.fixed_value(c).unwrap_or(0);
+            unit_violations(*unit, coords, value, &mut found);
+        }
+        return found;
+    }
+}
+
+// Iterator over the solutions of a grid; see UnsolvedSudoku::solutions.
+pub struct Solutions {
+    // Grids still to explore, the next one on top.
+    stack: Vec<UnsolvedSudoku>,
+}
+
+impl Iterator for Solutions {
+    type Item = Sudoku;
+
+    fn next(&mut self) -> Option<Sudoku> {
+        while let Some(mut u) = self.stack.pop() {
+            u.simple_solve();
+            let (coord, ps) = match u.min_candidates_cell() {
+                Some(v) => v,
+                None if u.valid() => return Some(u.finished()),
+                None => continue,
+            };
+            // Pushed in reverse so the smallest candidate is explored first.
+            for p in (1..10).rev().filter(|&p| ps.contains(p)) {
+                let mut u2 = u.clone();
+                u2.set(coord.row(), coord.col(), p);
+                self.stack.push(u2);
+            }
+        }
+        return None;
+    }
+}
+
+// Call f with every size-element subset of items, each in the original order.
+fn combinations(items: &[usize], size: usize, f: &mut impl FnMut(&[usize])) {
+    fn recurse(
+        items: &[usize],
+        size: usize,
+        chosen: &mut Vec<usize>,
+        f: &mut impl FnMut(&[usize]),
+    ) {
+        if chosen.len() == size {
+            f(chosen);
+            return;
+        }
+        for (ix, &item) in items.iter().enumerate() {
+            chosen.push(item);
+            recurse(&items[ix + 1..], size, chosen, f);
+            chosen.pop();
+        }
+    }
+    recurse(items, size, &mut Vec::with_capacity(size), f);
+}
+
+// Rows separated by '/', with fixed cells as their digit and unsolved cells as their
+// candidates in brackets, e.g. "5[12]3...".
+impl fmt::Debug for UnsolvedSudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "UnsolvedSudoku(")?;
+        for (n, row) in self.rows.iter().enumerate() {
+            if n > 0 {
+                write!(f, "/")?;
+            }
+            for cell in row {
+                match cell {
+                    Cell::Value(v) => write!(f, "{}", v)?,
+                    Cell::Possibilities(ps) => {
+                        write!(f, "[")?;
+                        for p in ps {
+                            write!(f, "{}", p)?;
+                        }
+                        write!(f, "]")?;
+                    }
+                }
+            }
+        }
+        return write!(f, ")");
+    }
+}
+
+impl Index<Coord> for UnsolvedSudoku {
+    type Output = Cell;
+
+    fn index(&self, c: Coord) -> &Cell {
+        let (i, j) = c.index();
+        return &self.rows[i][j];
+    }
+}
+
+impl IndexMut<Coord> for UnsolvedSudoku {
+    fn index_mut(&mut self, c: Coord) -> &mut Cell {
+        let (i, j) = c.index();
+        return &mut self.rows[i][j];
+    }
+}
+
+impl From<Sudoku> for UnsolvedSudoku {
+    fn from(s: Sudoku) -> UnsolvedSudoku {
+        let mut u = UnsolvedSudoku::empty();
+        u.add_givens(&s);
+        return u;
+    }
+}
+
+// Lossy: unsolved cells become blanks. Use try_from to make sure the grid is complete.
+impl From<UnsolvedSudoku> for Sudoku {
+    fn from(u: UnsolvedSudoku) -> Sudoku {
+        return u.partial_sudoku();
+    }
+}
+
+// The error from converting an UnsolvedSudoku that still has unsolved cells.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Incomplete {
+    pub unsolved: Vec<Coord>,
+}
+
+impl fmt::Display for Incomplete {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} cells are unsolved:", self.unsolved.len())?;
+        for c in &self.unsolved {
+            write!(f, " {}", c)?;
+        }
+        return Ok(());
+    }
+}
+
+impl core::error::Error for Incomplete {}
+
+// Succeeds only if every cell has a value.
+impl TryFrom<&UnsolvedSudoku> for Sudoku {
+    type Error = Incomplete;
+
+    fn try_from(u: &UnsolvedSudoku) -> Result<Sudoku, Incomplete> {
+        let unsolved: Vec<Coord> = u
+            .cells()
+            .filter(|(_, cell)| matches!(cell, Cell::Possibilities(_)))
+            .map(|(c, _)| c)
+            .collect();
+        if !unsolved.is_empty() {
+            return Err(Incomplete { unsolved });
+        }
+        return Ok(u.partial_sudoku());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        for i in 1..10 {
+            let r = Row { index: i };
+            for j in 1..10 {
+                let (rix, cix) = r.matrix_index(j);
+                assert_eq!(rix, i);
+                assert_eq!(cix, j);
+
+                assert_eq!(rix, Coord::new(i, j).unwrap().row());
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_of() {
+        let expected = [
+            [1, 1, 1, 2, 2, 2, 3, 3, 3],
+            [1, 1, 1, 2, 2, 2, 3, 3, 3],
+            [1, 1, 1, 2, 2, 2, 3, 3, 3],
+            [4, 4, 4, 5, 5, 5, 6, 6, 6],
+            [4, 4, 4, 5, 5, 5, 6, 6, 6],
+            [4, 4, 4, 5, 5, 5, 6, 6, 6],
+            [7, 7, 7, 8, 8, 8, 9, 9, 9],
+            [7, 7, 7, 8, 8, 8, 9, 9, 9],
+            [7, 7, 7, 8, 8, 8, 9, 9, 9],
+        ];
+        for rix in 1..10u8 {
+            for cix in 1..10u8 {
+                let b = expected[rix as usize - 1][cix as usize - 1];
+                assert_eq!(Sudoku::box_of(rix, cix), b, "({}, {})", rix, cix);
+                assert!(Sudoku::cells_in_box(b).contains(&(rix, cix)));
+            }
+        }
+        assert_eq!(
+            Sudoku::cells_in_box(6),
+            [
+                (4, 7),
+                (4, 8),
+                (4, 9),
+                (5, 7),
+                (5, 8),
+                (5, 9),
+                (6, 7),
+                (6, 8),
+                (6, 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_boxes() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let boxes: Vec<[u8; 9]> = s.boxes().collect();
+        assert_eq!(boxes.len(), 9);
+        let top_left: Vec<u8> = s.rows()[..3].iter().flat_map(|r| r[..3].to_vec()).collect();
+        assert_eq!(boxes[0].to_vec(), top_left);
+        for (ix, values) in boxes.iter().enumerate() {
+            let cells: Vec<u8> = s.box_cells(ix as u8 + 1).map(|(_, v)| v).collect();
+            assert_eq!(values.to_vec(), cells);
+        }
+        let solved: Vec<[u8; 9]> = s.solve().unwrap().boxes().collect();
+        for mut values in solved {
+            values.sort();
+            assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        }
+        assert!(Sudoku::empty().boxes().all(|b| b == [0; 9]));
+    }
+
+    #[test]
+    #[should_panic(expected = "box_of: ")]
+    fn test_box_of_out_of_range() {
+        Sudoku::box_of(10, 1);
+    }
+
+    #[test]
+    fn test_squares() {
+        for i in 1..10 {
+            let q = Square { index: i };
+            for j in 1..10 {
+                let (rix, cix) = q.matrix_index(j);
+                let q2 = Coord::new(rix, cix).unwrap().square();
+                println!("Square ({}, {}) -> ({}, {}) -> {}", i, j, rix, cix, q2);
+                assert_eq!(i, q2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_coords() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let u: UnsolvedSudoku = s.into();
+        for i in 1..10 {
+            for j in 1..10 {
+                let c = Coord::new(i, j).unwrap();
+                assert_eq!((c.row(), c.col()), (i, j));
+                assert_eq!(c.index(), ((i - 1) as usize, (j - 1) as usize));
+                assert_eq!(Coord::from_index((i - 1) as usize, (j - 1) as usize), c);
+                assert_eq!(s[c], s.rows[(i - 1) as usize][(j - 1) as usize]);
+                assert!(std::ptr::eq(&u[c], u.get(i, j)));
+
+                // The square index agrees with the Square subarray containing this cell.
+                let q = Square { index: c.square() };
+                assert!((1..10).any(|k| q.matrix_index(k) == (i, j)));
+            }
+            assert_eq!(Coord::new(0, i), Err(GridError::RowOutOfRange(0)));
+            assert_eq!(Coord::new(10, i), Err(GridError::RowOutOfRange(10)));
+            assert_eq!(Coord::new(i, 0), Err(GridError::ColumnOutOfRange(0)));
+            assert_eq!(Coord::new(i, 10), Err(GridError::ColumnOutOfRange(10)));
+        }
+
+        let mut s2 = s;
+        let c = Coord::new(9, 8).unwrap();
+        s2[c] = 4;
+        assert_eq!(s2.rows[8][7], 4);
+        let mut u2 = u.clone();
+        u2[c] = Cell::Value(4);
+        assert_eq!(*u2.get(9, 8), Cell::Value(4));
+    }
+
+    #[test]
+    fn test_verifies() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let solved = s.solve().unwrap();
+        assert!(s.verifies(&solved));
+        assert!(Sudoku::empty().verifies(&solved));
+        assert!(solved.verifies(&solved));
+
+        // Swapping two non-given cells in a row keeps the row and the givens, but
+        // repeats digits in two columns.
+        let (a, b) = (Coord::new(1, 3).unwrap(), Coord::new(1, 5).unwrap());
+        assert!(s.is_empty(a) && s.is_empty(b));
+        let mut swapped = solved;
+        swapped[a] = solved[b];
+        swapped[b] = solved[a];
+        assert!(!s.verifies(&swapped));
+
+        // The other solution of the ambiguous puzzle is a valid grid, but it disagrees
+        // with moderate's (9, 5) clue.
+        let mut solutions = UnsolvedSudoku::from(ambiguous()).solutions();
+        let other = solutions.find(|t| *t != solved).unwrap();
+        assert!(ambiguous().verifies(&other));
+        assert!(!s.verifies(&other));
+
+        let mut incomplete = solved;
+        incomplete.clear(a);
+        assert!(!s.verifies(&incomplete));
+    }
+
+    #[test]
+    fn test_find_mistakes() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let solved = s.solve().unwrap();
+        assert_eq!(s.find_mistakes(&s), Ok(vec![]));
+        assert_eq!(s.find_mistakes(&solved), Ok(vec![]));
+        assert_eq!(s.find_mistakes(&Sudoku::empty()), Ok(vec![]));
+
+        // Two wrong entries among some right ones, and a blank that is ignored.
+        let mut user = s;
+        user[Coord::new(1, 1).unwrap()] = 2;
+        user[Coord::new(1, 2).unwrap()] = 7;
+        user[Coord::new(5, 5).unwrap()] = solved[Coord::new(5, 5).unwrap()];
+        user[Coord::new(9, 1).unwrap()] = 1;
+        user.clear(Coord::new(1, 4).unwrap());
+        assert_eq!(s.find_mistakes(&user), Ok(vec![(1, 2), (9, 1)]));
+
+        assert!(matches!(
+            ambiguous().find_mistakes(&user),
+            Err(Unsolveable::MultipleSolutions(..))
+        ));
+    }
+
+    #[test]
+    fn test_solution_cell() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert_eq!(s.solution_cell(1, 1), Some(2));
+        assert_eq!(s.solution_cell(1, 4), Some(1));
+        assert_eq!(s.solution_cell(9, 9), Some(4));
+        assert_eq!(s.solution_cell(0, 9), None);
+        assert_eq!(s.solution_cell(9, 10), None);
+        // Every cell agrees with the full solution.
+        let solved = s.solve().unwrap();
+        for i in 1..10 {
+            for j in 1..10 {
+                assert_eq!(
+                    s.solution_cell(i, j),
+                    Some(solved.rows[(i - 1) as usize][(j - 1) as usize])
+                );
+            }
+        }
+
+        // An empty grid has many solutions, so no single cell is determined.
+        assert_eq!(Sudoku::empty().solution_cell(5, 5), None);
+    }
+
+    // The moderate puzzle without its (9, 5) clue, which has exactly two solutions.
+    fn ambiguous() -> Sudoku {
+        let mut s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        s.clear(Coord::new(9, 5).unwrap());
+        return s;
+    }
+
+    // Found by fuzzing: conflicting givens above an otherwise empty grid used to send
+    // the search through the whole of the empty part before giving up.
+    #[test]
+    fn test_conflicting_givens() {
+        let mut s = Sudoku::empty();
+        for col in 1..10 {
+            s.set(Coord::new(2, col).unwrap(), 2).unwrap();
+        }
+        let u = UnsolvedSudoku::from(s);
+        assert_eq!(u.solved(), Err(Unsolveable::NoSolution));
+        assert_eq!(
+            u.clone().budgeted_solve(0),
+            (Some(Err(Unsolveable::NoSolution)), 0)
+        );
+        assert_eq!(u.solutions().next(), None);
+    }
+
+    #[test]
+    fn test_solutions() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let all: Vec<Sudoku> = UnsolvedSudoku::from(s).solutions().collect();
+        assert_eq!(all, vec![s.solve().unwrap()]);
+
+        let all: Vec<Sudoku> = UnsolvedSudoku::from(ambiguous()).solutions().collect();
+        assert_eq!(all.len(), 2);
+        // The search reports the same two, in the same order.
+        assert_eq!(
+            UnsolvedSudoku::from(ambiguous()).dynamic_solve(),
+            Err(Unsolveable::MultipleSolutions(
+                Box::new(all[0]),
+                Box::new(all[1])
+            ))
+        );
+        assert_ne!(all[0], all[1]);
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert!(all.contains(&moderate.solve().unwrap()));
+        for solution in &all {
+            assert!(UnsolvedSudoku::from(*solution).valid());
+        }
+
+        // Lazy, so even the empty grid hands back its first few solutions at once.
+        let some: Vec<Sudoku> = UnsolvedSudoku::empty().solutions().take(3).collect();
+        assert_eq!(some.len(), 3);
+
+        let mut broken = moderate;
+        broken.rows[0][0] = 9;
+        assert_eq!(UnsolvedSudoku::from(broken).solutions().next(), None);
+    }
+
+    #[test]
+    fn test_solution_order() {
+        // Order matters with many solutions, so take more clues away.
+        let mut s = ambiguous();
+        for (r, c) in [(1, 9), (3, 2), (4, 7), (7, 6)] {
+            s.clear(Coord::new(r, c).unwrap());
+        }
+        let first: Vec<Sudoku> = UnsolvedSudoku::from(s).solutions().take(3).collect();
+        assert_eq!(
+            first.iter().map(|s| format!("{:?}", s)).collect::<Vec<_>>()[..2],
+            [
+                "Sudoku(267143598183695742594287613452368971938471256671529384845936127329714865716852439)",
+                "Sudoku(267143598183695742594287613459368271738421956621579384945836127372914865816752439)",
+            ]
+        );
+        assert_eq!(
+            UnsolvedSudoku::from(s)
+                .solutions()
+                .take(3)
+                .collect::<Vec<_>>(),
+            first
+        );
+        match s.solve() {
+            Err(Unsolveable::MultipleSolutions(a, b)) => assert_eq!([*a, *b], first[..2]),
+            other => panic!("expected two solutions, got {:?}", other),
+        }
+
+        assert_eq!(
+            format!("{:?}", UnsolvedSudoku::empty().solutions().next().unwrap()),
+            "Sudoku(123456789456789123789123456231674895875912364694538217317265948542897631968341572)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_parse_strict() {
+        let text = include_str!("../../moderate.txt");
+        let s = Sudoku::parse_strict(text.as_bytes()).unwrap();
+        assert_eq!(s, Sudoku::from_reader(text.as_bytes()).unwrap());
+        assert_eq!(s.count_solutions(2), 1);
+        assert_eq!(ambiguous().count_solutions(2), 2);
+        assert_eq!(ambiguous().count_solutions(10), 2);
+        assert_eq!(ambiguous().count_solutions(1), 1);
+        assert_eq!(Sudoku::empty().count_solutions(5), 5);
+        assert_eq!(Sudoku::empty().count_solutions(0), 0);
+
+        let mut ambiguous_text = String::new();
+        for row in ambiguous().to_string().lines() {
+            ambiguous_text.push_str(&row.replace(' ', "0"));
+            ambiguous_text.push('\n');
+        }
+        match Sudoku::parse_strict(ambiguous_text.as_bytes()) {
+            Err(StrictError::Unsolveable(Unsolveable::MultipleSolutions(a, b))) => {
+                assert_ne!(a, b);
+                assert_eq!(a.ambiguous_cells(), vec![]);
+            }
+            other => panic!("expected multiple solutions, got {:?}", other),
+        }
+        // Row 1 already has a 9 at (1, 8).
+        let broken = text.replacen('-', "9", 1);
+        let err = Sudoku::parse_strict(broken.as_bytes()).unwrap_err();
+        assert!(matches!(
+            err,
+            StrictError::Unsolveable(Unsolveable::NoSolution)
+        ));
+        assert_eq!(err.to_string(), "puzzle has no solution");
+        let err = Sudoku::parse_strict("123".as_bytes()).unwrap_err();
+        assert!(matches!(err, StrictError::Parse(_)));
+    }
+
+    #[test]
+    fn test_ambiguous_cells() {
+        let cells = ambiguous().ambiguous_cells();
+        assert_eq!(cells.len(), 22);
+        assert_eq!(&cells[..3], &[(1, 1), (1, 3), (4, 1)]);
+        assert!(cells.contains(&(9, 5)));
+        assert_eq!(cells.last(), Some(&(9, 6)));
+
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert_eq!(s.ambiguous_cells(), vec![]);
+        let mut broken = s;
+        broken.rows[0][0] = 9;
+        assert_eq!(broken.ambiguous_cells(), vec![]);
+        assert!(!Sudoku::empty().ambiguous_cells().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let s = ambiguous();
+        let mut solutions = UnsolvedSudoku::from(s).solutions();
+        let (a, b) = (solutions.next().unwrap(), solutions.next().unwrap());
+        assert_eq!(a.diff(&a), vec![]);
+        let diff = a.diff(&b);
+        assert_eq!(diff.len(), s.ambiguous_cells().len());
+        for &(c, x, y) in &diff {
+            assert_eq!((x, y), (a[c], b[c]));
+            assert_ne!(x, y);
+        }
+        // A blank differs from every value.
+        assert_eq!(Sudoku::empty().diff(&a).len(), 81);
+    }
+
+    #[test]
+    fn test_certain_cells() {
+        let s = ambiguous();
+        let solutions: Vec<Sudoku> = UnsolvedSudoku::from(s).solutions().collect();
+        let certain = s.certain_cells();
+        assert_eq!(certain.clue_count(), 81 - s.ambiguous_cells().len());
+        for (c, v) in certain.cells().filter(|&(_, v)| v != 0) {
+            assert!(solutions.iter().all(|sol| sol[c] == v));
+        }
+        for (row, col) in s.ambiguous_cells() {
+            assert!(certain.is_empty(Coord::new(row, col).unwrap()));
+        }
+
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        assert_eq!(moderate.certain_cells(), moderate.solve().unwrap());
+        let mut broken = moderate;
+        broken.rows[0][0] = 9;
+        assert_eq!(broken.certain_cells(), Sudoku::empty());
+
+        // Far more solutions than the limit, and only the clues are certain.
+        assert_eq!(Sudoku::empty().certain_cells(), Sudoku::empty());
+        let mut one = Sudoku::empty();
+        one[Coord::new(5, 5).unwrap()] = 3;
+        assert_eq!(one.certain_cells(), one);
+    }
+
+    fn check_unit(coords: &[Coord], expected: &[(u8, u8)]) {
+        assert_eq!(coords.len(), 9);
+        let distinct: collections::BTreeSet<Coord> = coords.iter().cloned().collect();
+        assert_eq!(distinct.len(), 9);
+        let expected: Vec<Coord> = expected
+            .iter()
+            .map(|&(r, c)| Coord::new(r, c).unwrap())
+            .collect();
+        assert_eq!(coords, &expected[..]);
+    }
+
+    #[test]
+    fn test_unit_iterators() {
+        let u = moderate();
+        let s = u.partial_sudoku();
+        for ix in 1..10 {
+            let row: Vec<(u8, u8)> = (1..10).map(|j| (ix, j)).collect();
+            let col: Vec<(u8, u8)> = (1..10).map(|j| (j, ix)).collect();
+            let r0 = ((ix - 1) / 3) * 3;
+            let c0 = ((ix - 1) % 3) * 3;
+            let square: Vec<(u8, u8)> = (0..9).map(|j| (r0 + j / 3 + 1, c0 + j % 3 + 1)).collect();
+
+            check_unit(&row_coords(ix), &row);
+            check_unit(&col_coords(ix), &col);
+            check_unit(&box_coords(ix), &square);
+
+            let cs: Vec<Coord> = u.row_cells(ix).map(|(c, _)| c).collect();
+            check_unit(&cs, &row);
+            let cs: Vec<Coord> = u.col_cells(ix).map(|(c, _)| c).collect();
+            check_unit(&cs, &col);
+            let cs: Vec<Coord> = u.box_cells(ix).map(|(c, _)| c).collect();
+            check_unit(&cs, &square);
+
+            for (c, v) in s
+                .row_cells(ix)
+                .chain(s.col_cells(ix))
+                .chain(s.box_cells(ix))
+            {
+                assert_eq!(v, s[c]);
+            }
+            for (c, cell) in u
+                .row_cells(ix)
+                .chain(u.col_cells(ix))
+                .chain(u.box_cells(ix))
+            {
+                assert!(std::ptr::eq(cell, &u[c]));
+            }
+        }
+
+        // 27 distinct units, with every cell in exactly three of them.
+        let all: Vec<[Coord; 9]> = Unit::classic().map(|unit| unit.coords()).collect();
+        assert_eq!(all.len(), 27);
+        let distinct: collections::BTreeSet<[Coord; 9]> = all.iter().cloned().collect();
+        assert_eq!(distinct.len(), 27);
+        for c in Coord::all() {
+            assert_eq!(all.iter().filter(|unit| unit.contains(&c)).count(), 3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "unit index 10 out of range")]
+    fn test_unit_out_of_range() {
+        box_coords(10);
+    }
+
+    #[test]
+    fn test_cells() {
+        let u = moderate();
+        let s = u.partial_sudoku();
+        let coords: Vec<Coord> = s.cells().map(|(c, _)| c).collect();
+        assert_eq!(coords.len(), 81);
+        assert_eq!(coords[0], Coord::new(1, 1).unwrap());
+        assert_eq!(coords[1], Coord::new(1, 2).unwrap());
+        assert_eq!(coords[9], Coord::new(2, 1).unwrap());
+        assert!(coords.windows(2).all(|w| w[0] < w[1]));
+        for (c, v) in s.cells() {
+            assert_eq!(v, s.rows[c.index().0][c.index().1]);
+        }
+        let ucoords: Vec<Coord> = u.cells().map(|(c, _)| c).collect();
+        assert_eq!(coords, ucoords);
+    }
+
+    #[test]
+    fn test_rows_accessor() {
+        let s = moderate().partial_sudoku();
+        assert_eq!(s.rows().len(), 9);
+        for (rix, row) in s.rows().iter().enumerate() {
+            let expected: Vec<u8> = s.row_cells(rix as u8 + 1).map(|(_, v)| v).collect();
+            assert_eq!(&row[..], &expected[..]);
+        }
+        assert_eq!(Sudoku::empty().rows(), &[[0; 9]; 9]);
+    }
+
+    // An empty grid where value 5 in each listed row may only go in the listed columns.
+    fn fish_grid(rows: &[(u8, &[u8])]) -> UnsolvedSudoku {
+        let mut u = UnsolvedSudoku::empty();
+        for &(r, cols) in rows {
+            for c in 1..10 {
+                if !cols.contains(&c) {
+                    u.get_mut(r, c).remove_possibility(5);
+                }
+            }
+        }
+        return u;
+    }
+
+    fn has_candidate(u: &UnsolvedSudoku, rix: u8, cix: u8, value: u8) -> bool {
+        return match u.get(rix, cix) {
+            Cell::Possibilities(ps) => ps.contains(&value),
+            Cell::Value(_) => false,
+        };
+    }
+
+    #[test]
+    fn test_fish() {
+        let x_wing: &[(u8, &[u8])] = &[(1, &[2, 7]), (5, &[2, 7])];
+        let swordfish: &[(u8, &[u8])] = &[(1, &[2, 6]), (4, &[6, 9]), (8, &[2, 9])];
+        let jellyfish: &[(u8, &[u8])] = &[(1, &[1, 4]), (3, &[4, 7]), (6, &[7, 8]), (9, &[1, 8])];
+
+        for (size, pattern, reason) in [
+            (2, x_wing, Technique::XWing),
+            (3, swordfish, Technique::Swordfish),
+            (4, jellyfish, Technique::Jellyfish),
+        ] {
+            let mut u = fish_grid(pattern);
+            let cols: collections::BTreeSet<u8> = pattern
+                .iter()
+                .flat_map(|(_, cs)| cs.iter().cloned())
+                .collect();
+            assert_eq!(cols.len(), size);
+
+            // A smaller fish doesn't see the pattern.
+            if size > 2 {
+                assert_eq!(u.clone().eliminate_fish(size - 1), 0);
+            }
+
+            let removed = u.eliminate_fish_traced(size);
+            assert_eq!(removed.len(), (9 - size) * size);
+            for e in &removed {
+                assert_eq!((e.value, e.reason), (5, reason));
+                assert!(!pattern.iter().any(|&(r, _)| r == e.cell.0));
+                assert!(cols.contains(&e.cell.1));
+            }
+            for r in 1..10 {
+                let in_fish = pattern.iter().any(|&(pr, _)| pr == r);
+                for c in 1..10 {
+                    let expected = if in_fish || !cols.contains(&c) {
+                        has_candidate(&fish_grid(pattern), r, c, 5)
+                    } else {
+                        false
+                    };
+                    assert_eq!(has_candidate(&u, r, c, 5), expected);
+                    // Other values are untouched.
+                    assert!(has_candidate(&u, r, c, 4));
+                }
+            }
+            // A second pass finds nothing new.
+            assert_eq!(u.eliminate_fish(size), 0);
+        }
+
+        // Column-based fish: the transpose of the X-Wing.
+        let mut u = UnsolvedSudoku::empty();
+        for r in 1..10 {
+            if r != 3 && r != 8 {
+                u.get_mut(r, 4).remove_possibility(5);
+                u.get_mut(r, 6).remove_possibility(5);
+            }
+        }
+        assert_eq!(u.eliminate_fish(2), 14);
+        assert!(!has_candidate(&u, 3, 1, 5));
+        assert!(has_candidate(&u, 3, 4, 5));
+
+        // Fish eliminations never change the answer.
+        let mut u = moderate();
+        u.simple_solve();
+        for size in 2..5 {
+            u.eliminate_fish(size);
+        }
+        let expected = moderate().dynamic_solve().unwrap();
+        assert_eq!(u.dynamic_solve(), Ok(expected));
+    }
+
+    fn with_candidates(u: &mut UnsolvedSudoku, rix: u8, cix: u8, keep: &[u8]) {
+        for v in 1..10 {
+            if !keep.contains(&v) {
+                u.get_mut(rix, cix).remove_possibility(v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_xyz_wing() {
+        // Pivot r1c1 {1, 2, 3} with wings r1c5 {1, 3} and r2c2 {2, 3}: only r1c2 and r1c3
+        // see all three, so they lose the 3.
+        let mut u = UnsolvedSudoku::empty();
+        with_candidates(&mut u, 1, 1, &[1, 2, 3]);
+        with_candidates(&mut u, 1, 5, &[1, 3]);
+        with_candidates(&mut u, 2, 2, &[2, 3]);
+        let removed = |cell| Eliminated {
+            cell,
+            value: 3,
+            reason: Technique::XyzWing,
+        };
+        assert_eq!(
+            u.eliminate_xyz_wing_traced(),
+            [removed((1, 2)), removed((1, 3))]
+        );
+        for r in 1..10 {
+            for c in 1..10 {
+                let lost = r == 1 && (c == 2 || c == 3);
+                assert_eq!(has_candidate(&u, r, c, 3), !lost);
+            }
+        }
+        // Other values are untouched.
+        assert!(has_candidate(&u, 1, 2, 2) && has_candidate(&u, 1, 3, 1));
+        assert_eq!(u.eliminate_xyz_wing(), 0);
+
+        // Wings with the same pair, or with a value the pivot lacks, aren't a wing.
+        let mut u = UnsolvedSudoku::empty();
+        with_candidates(&mut u, 1, 1, &[1, 2, 3]);
+        with_candidates(&mut u, 1, 5, &[1, 3]);
+        with_candidates(&mut u, 2, 2, &[1, 3]);
+        with_candidates(&mut u, 3, 3, &[2, 4]);
+        assert_eq!(u.eliminate_xyz_wing(), 0);
+
+        // Singles and fish stall on this puzzle, and XYZ-wing is what gets them going
+        // again, all the way to the solution.
+        let puzzle =
+            Sudoku::try_from(&b"......4788.4..6.......2....9.2....5.67......91...896...53..........945...8.3..1.."[..])
+                .unwrap();
+        let mut u = UnsolvedSudoku::from(puzzle);
+        let stall = |u: &mut UnsolvedSudoku| {
+            while u.hidden_singles() > 0 || (2..5).any(|size| u.eliminate_fish(size) > 0) {}
+        };
+        stall(&mut u);
+        assert!(!u.is_solved());
+        while !u.is_solved() && u.eliminate_xyz_wing() > 0 {
+            stall(&mut u);
+        }
+        assert!(u.is_solved());
+        assert_eq!(u.dynamic_solve(), puzzle.solve());
+    }
+
+    #[test]
+    fn test_naked_pairs_and_pointing() {
+        // r1c1 and r1c2 share {1, 2}, so both leave the rest of row 1 and of box 1.
+        let mut u = UnsolvedSudoku::empty();
+        with_candidates(&mut u, 1, 1, &[1, 2]);
+        with_candidates(&mut u, 1, 2, &[1, 2]);
+        let removed = u.eliminate_naked_pairs_traced();
+        assert_eq!(removed.len(), 26);
+        assert_eq!(
+            removed[0],
+            Eliminated {
+                cell: (1, 3),
+                value: 1,
+                reason: Technique::NakedPair,
+            }
+        );
+        assert!(!has_candidate(&u, 3, 3, 2) && has_candidate(&u, 4, 1, 2));
+        assert_eq!(u.eliminate_naked_pairs(), 0);
+
+        // Box 1 has its 5s only in row 1, so the rest of row 1 loses them.
+        let mut u = UnsolvedSudoku::empty();
+        for c in Unit::Square(1).coords().into_iter().skip(2) {
+            u.eliminate(c, 5).unwrap();
+        }
+        let removed = u.eliminate_pointing_traced();
+        let cells: Vec<(u8, u8)> = removed.iter().map(|e| e.cell).collect();
+        assert_eq!(cells, [(1, 4), (1, 5), (1, 6), (1, 7), (1, 8), (1, 9)]);
+        assert!(removed
+            .iter()
+            .all(|e| e.value == 5 && e.reason == Technique::Pointing));
+        assert!(has_candidate(&u, 2, 4, 5));
+        // Row 1 now claims box 1's 5s, which the box has already lost.
+        assert_eq!(u.eliminate_pointing(), 0);
+    }
+
+    #[test]
+    fn test_traced_reasons() {
+        // The XYZ-wing of test_xyz_wing, and box 9 with its 7s only in row 9.
+        let mut u = UnsolvedSudoku::empty();
+        with_candidates(&mut u, 1, 1, &[1, 2, 3]);
+        with_candidates(&mut u, 1, 5, &[1, 3]);
+        with_candidates(&mut u, 2, 2, &[2, 3]);
+        for c in Unit::Square(9).coords().into_iter().take(6) {
+            u.eliminate(c, 7).unwrap();
+        }
+        let mut removed = u.eliminate_pointing_traced();
+        removed.extend(u.eliminate_xyz_wing_traced());
+        let eliminated = |cell, value, reason| Eliminated {
+            cell,
+            value,
+            reason,
+        };
+        assert_eq!(
+            removed,
+            [
+                eliminated((9, 1), 7, Technique::Pointing),
+                eliminated((9, 2), 7, Technique::Pointing),
+                eliminated((9, 3), 7, Technique::Pointing),
+                eliminated((9, 4), 7, Technique::Pointing),
+                eliminated((9, 5), 7, Technique::Pointing),
+                eliminated((9, 6), 7, Technique::Pointing),
+                eliminated((1, 2), 3, Technique::XyzWing),
+                eliminated((1, 3), 3, Technique::XyzWing),
+            ]
+        );
+        // Applied easiest first, the reasons come out in order, which is also the order
+        // of their weights.
+        assert!(removed.windows(2).all(|w| w[0].reason <= w[1].reason));
+        assert!(Technique::ALL
+            .windows(2)
+            .all(|w| w[0] < w[1] && w[0].weight() < w[1].weight()));
+    }
+
+    #[test]
+    #[should_panic(expected = "fish size 5 out of range")]
+    fn test_fish_size() {
+        UnsolvedSudoku::empty().eliminate_fish(5);
+    }
+
+    #[test]
+    fn test_candidate_set() {
+        let mut set = CandidateSet::empty();
+        assert!(set.is_empty());
+        set.insert(3);
+        set.insert(9);
+        set.insert(3);
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(3) && set.contains(9) && !set.contains(1));
+        assert!(!set.contains(0) && !set.contains(10));
+        assert_eq!(set.iter().collect::<Vec<u8>>(), vec![3, 9]);
+        set.remove(3);
+        set.remove(0);
+        assert_eq!(set, [9].iter().cloned().collect());
+        assert_eq!(
+            CandidateSet::full().iter().collect::<Vec<u8>>(),
+            (1..10).collect::<Vec<u8>>()
+        );
+        assert_eq!(format!("{:?}", set), "{9}");
+    }
+
+    #[test]
+    fn test_candidate_iter() {
+        let u = moderate();
+        for (c, cell) in u.cells() {
+            let listed: Vec<u8> = cell.candidate_iter().collect();
+            match u.candidates(c) {
+                Some(ps) => assert_eq!(listed, ps.iter().collect::<Vec<u8>>()),
+                None => assert!(listed.is_empty()),
+            }
+        }
+        assert_eq!(Cell::default().candidate_iter().count(), 9);
+        assert_eq!(Cell::Value(4).candidate_iter().next(), None);
+    }
+
+    #[test]
+    fn test_eliminate() {
+        let mut u = UnsolvedSudoku::empty();
+        let c = Coord::new(2, 3).unwrap();
+        assert_eq!(u.candidates(c), Some(CandidateSet::full()));
+        assert_eq!(u.fixed_value(c), None);
+
+        assert_eq!(u.eliminate(c, 0), Err(GridError::ValueOutOfRange(0)));
+        assert_eq!(u.eliminate(c, 10), Err(GridError::ValueOutOfRange(10)));
+        for v in 1..8 {
+            assert_eq!(u.eliminate(c, v), Ok(Elimination::Removed));
+        }
+        assert_eq!(u.eliminate(c, 1), Ok(Elimination::NotCandidate));
+        assert_eq!(u.candidates(c), Some([8, 9].iter().cloned().collect()));
+
+        // Taking away the second-last candidate fixes the cell and propagates.
+        assert_eq!(u.eliminate(c, 8), Ok(Elimination::Fixed(9)));
+        assert_eq!(u.candidates(c), None);
+        assert_eq!(u.fixed_value(c), Some(9));
+        assert_eq!(u.eliminate(c, 9), Ok(Elimination::NotCandidate));
+        for (peer, _) in u.row_cells(2).chain(u.col_cells(3)).chain(u.box_cells(1)) {
+            if peer != c {
+                assert!(!u.candidates(peer).unwrap().contains(9));
+            }
+        }
+        assert!(u.candidates(Coord::new(9, 9).unwrap()).unwrap().contains(9));
+    }
+
+    #[test]
+    fn test_eq_hash_debug() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert_eq!(
+            format!("{:?}", s),
+            "Sudoku(...1.3.95.8....7...94..7..3......5...3...1...6..5.9....4..351.7.....486.....6....)"
+        );
+
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(s));
+        assert!(!set.insert(s));
+        let solved = s.solve().unwrap();
+        assert_ne!(solved, s);
+        assert!(set.insert(solved));
+        assert_eq!(set.len(), 2);
+
+        let mut u = UnsolvedSudoku::empty();
+        u.set(1, 1, 5);
+        let debug = format!("{:?}", u);
+        assert!(debug.starts_with("UnsolvedSudoku(5[12346789][12346789]"));
+        assert!(debug.ends_with("[123456789])"));
+        assert_eq!(debug.matches('/').count(), 8);
+        assert_ne!(u, UnsolvedSudoku::empty());
+    }
+
+    #[test]
+    fn test_sudoku_accessors() {
+        let mut s = Sudoku::empty();
+        for c in Coord::all() {
+            assert!(s.is_empty(c));
+            assert_eq!(s.get(c), 0);
+        }
+
+        let c = Coord::new(4, 7).unwrap();
+        for v in 1..10 {
+            assert_eq!(s.set(c, v), Ok(()));
+            assert_eq!(s.get(c), v);
+            assert_eq!(s[c], v);
+            assert!(!s.is_empty(c));
+        }
+        for &bad in &[10, 11, 255] {
+            assert_eq!(s.set(c, bad), Err(GridError::ValueOutOfRange(bad)));
+            assert_eq!(s.get(c), 9);
+        }
+        assert_eq!(s.cells().filter(|&(_, v)| v != 0).count(), 1);
+
+        // Copies are independent.
+        let copy = s;
+        s.clear(c);
+        assert!(s.is_empty(c));
+        assert_eq!(copy.get(c), 9);
+
+        assert_eq!(s.set(c, 3), Ok(()));
+        assert_eq!(s.set(c, 0), Ok(()));
+        assert!(s.is_empty(c));
+        assert_eq!(s, Sudoku::empty());
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(Sudoku::default(), Sudoku::empty());
+        assert!(Sudoku::empty().is_empty_grid());
+        assert_eq!(Sudoku::empty().empty_count(), 81);
+        let mut s = Sudoku::empty();
+        s.set(Coord::new(5, 5).unwrap(), 3).unwrap();
+        assert!(!s.is_empty_grid());
+
+        let u = UnsolvedSudoku::default();
+        assert_eq!(u, UnsolvedSudoku::empty());
+        assert_eq!(u, UnsolvedSudoku::from(Sudoku::empty()));
+        for c in Coord::all() {
+            assert_eq!(u.candidates(c), Some(CandidateSet::full()));
+        }
+        assert_eq!(u.units().count(), 27);
+        assert_eq!(BoxShape::default(), BoxShape::CLASSIC);
+    }
+
+    #[test]
+    fn test_from_clues() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let clues: Vec<(Coord, u8)> = s.cells().filter(|&(_, v)| v != 0).collect();
+        assert_eq!(
+            UnsolvedSudoku::from_clues(&clues),
+            Ok(UnsolvedSudoku::from(s))
+        );
+        assert_eq!(UnsolvedSudoku::from_clues(&[]), Ok(UnsolvedSudoku::empty()));
+
+        let (a, b) = (Coord::new(1, 1).unwrap(), Coord::new(1, 9).unwrap());
+        assert!(UnsolvedSudoku::from_clues(&[(a, 4), (a, 4)]).is_ok());
+        assert_eq!(
+            UnsolvedSudoku::from_clues(&[(a, 4), (a, 5)]),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 1,
+                value: 4
+            })
+        );
+        assert_eq!(
+            UnsolvedSudoku::from_clues(&[(a, 4), (b, 4)]),
+            Err(Conflict::NotPossible {
+                row: 1,
+                col: 9,
+                value: 4
+            })
+        );
+        assert_eq!(
+            UnsolvedSudoku::from_clues(&[(a, 10)]),
+            Err(Conflict::OutOfRange(GridError::ValueOutOfRange(10)))
+        );
+    }
+
+    #[test]
+    fn test_sudoku_from_clues() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let clues: Vec<(u8, u8, u8)> = s
+            .cells()
+            .filter(|&(_, v)| v != 0)
+            .map(|(c, v)| (c.row(), c.col(), v))
+            .collect();
+        assert_eq!(Sudoku::from_clues(&clues), Ok(s));
+        assert_eq!(Sudoku::from_clues(&[]), Ok(Sudoku::empty()));
+        // A second clue for a cell is refused, whatever its value.
+        let e = Sudoku::from_clues(&[(2, 7, 5), (2, 7, 5)]).unwrap_err();
+        assert_eq!(
+            e,
+            Conflict::AlreadyFilled {
+                row: 2,
+                col: 7,
+                value: 5
+            }
+        );
+        assert_eq!(e.to_string(), "cell (2, 7) is already filled with 5");
+        assert_eq!(
+            Sudoku::from_clues(&[(1, 1, 4), (1, 1, 5)]),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 1,
+                value: 4
+            })
+        );
+        assert_eq!(
+            Sudoku::from_clues(&[(1, 1, 4), (3, 2, 4)]),
+            Err(Conflict::Taken {
+                row: 3,
+                col: 2,
+                value: 4,
+                unit: Unit::Square(1),
+                at: Coord::new(1, 1).unwrap()
+            })
+        );
+        let e = Sudoku::from_clues(&[(1, 1, 4), (9, 1, 4)]).unwrap_err();
+        assert_eq!(e.to_string(), "4 is already in C1 at r1c1");
+        for (clue, e) in [
+            ((0, 1, 1), GridError::RowOutOfRange(0)),
+            ((1, 10, 1), GridError::ColumnOutOfRange(10)),
+            ((1, 1, 0), GridError::ValueOutOfRange(0)),
+        ] {
+            assert_eq!(Sudoku::from_clues(&[clue]), Err(Conflict::OutOfRange(e)));
+        }
+        // With the rest of row 1 filled, r1c1 and r1c2 must hold 1 and 2, which leaves
+        // no room for a 1 at r2c2. Nothing repeats, so the grid is built all the same.
+        let implied = [
+            (1, 3, 3),
+            (1, 4, 4),
+            (1, 5, 5),
+            (1, 6, 6),
+            (1, 7, 7),
+            (1, 8, 8),
+            (1, 9, 9),
+            (2, 2, 1),
+        ];
+        assert_eq!(Sudoku::from_clues(&implied).unwrap().count_solutions(1), 0);
+    }
+
+    #[test]
+    fn test_parse_bordered() {
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let solution = moderate.solve().unwrap();
+        for s in [moderate, solution, Sudoku::empty()] {
+            let text = format!("{:#}", s);
+            assert_eq!(text, s.bordered());
+            assert_eq!(text.parse(), Ok(s), "{}", text);
+            assert_eq!(format!("{}\n", text).replace('\n', "\r\n").parse(), Ok(s));
+        }
+        assert_eq!(format!("{}", moderate).lines().count(), 9);
+
+        // A rule needs a '+', so a row of dashes is still nine blanks.
+        let text = format!("{:#}", moderate).replacen("| . . . | 1", "| - - - | 1", 1);
+        assert_eq!(text.parse(), Ok(moderate));
+        let dashes = "---------\n".repeat(9);
+        assert_eq!(dashes.parse(), Ok(Sudoku::empty()));
+        let short = format!("{:#}", moderate).replacen("| . 9 5 |", "| . 9 |", 1);
+        assert_eq!(short.parse::<Sudoku>(), Err(ParseError::TooFewCharacters));
+        let missing = format!("{:#}", moderate).replacen("| . . . | 1 . 3 | . 9 5 |\n", "", 1);
+        assert_eq!(missing.parse::<Sudoku>(), Err(ParseError::TooFewRows));
+    }
+
+    #[test]
+    fn test_parse_delimited() {
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let joined = |sep: &str, blank: &str| {
+            let mut text = String::new();
+            for row in 1..10 {
+                let fields: Vec<String> = (1..10)
+                    .map(|col| match moderate.get(Coord::new(row, col).unwrap()) {
+                        0 => blank.to_string(),
+                        v => v.to_string(),
+                    })
+                    .collect();
+                text.push_str(&fields.join(sep));
+                text.push('\n');
+            }
+            return text;
+        };
+        assert_eq!(joined(" ", "0").parse(), Ok(moderate));
+        assert_eq!(joined("\t", "-").parse(), Ok(moderate));
+        assert_eq!(joined(",", "0").parse(), Ok(moderate));
+        assert_eq!(joined(", ", "00").parse(), Ok(moderate));
+        assert_eq!(joined(",", "").parse(), Ok(moderate));
+        assert_eq!(joined(",", "").replace('\n', ",\n").parse(), Ok(moderate));
+
+        // A '.' is a blank however the line is read.
+        let dotted = joined("", ".");
+        assert_eq!(dotted.parse(), Ok(moderate));
+        assert_eq!(joined(" ", ".").parse(), Ok(moderate));
+        assert_eq!(joined(",", ".").parse(), Ok(moderate));
+        // Rows read in different ways can share a grid.
+        let layouts = [dotted, joined(",", "."), joined(" ", ".")];
+        let mixed: String = (0..9)
+            .map(|r| format!("{}\n", layouts[r % 3].lines().nth(r).unwrap()))
+            .collect();
+        assert_eq!(mixed.parse(), Ok(moderate));
+
+        // Packed digits in groups of three are still one cell per character.
+        let grouped: String = include_str!("../../moderate.txt")
+            .lines()
+            .map(|l| format!("{} {} {}\n", &l[..3], &l[3..6], &l[6..]))
+            .collect();
+        assert_eq!(grouped.parse(), Ok(moderate));
+
+        let row = "1,2,3,4,5,6,7,8,9\n";
+        assert_eq!(row.repeat(9).parse::<Sudoku>().map(|_| ()), Ok(()));
+        let ten = row.replace('9', "10").repeat(9);
+        assert_eq!(ten.parse::<Sudoku>(), Err(ParseError::DigitOutOfRange));
+        assert_eq!(
+            "1 2 3 4 5 6 7 8 10\n".repeat(9).parse::<Sudoku>(),
+            Err(ParseError::DigitOutOfRange)
+        );
+        assert_eq!(
+            row.replace('9', "a").repeat(9).parse::<Sudoku>(),
+            Err(ParseError::BadField)
+        );
+        assert_eq!(
+            "1,2,3\n".repeat(9).parse::<Sudoku>(),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(
+            "1,2,3,4,5,6,7,8,9,1\n".repeat(9).parse::<Sudoku>(),
+            Err(ParseError::TooManyCharacters)
+        );
+    }
+
+    #[test]
+    fn test_try_from() {
+        let text = include_str!("../../moderate.txt");
+        let moderate: Sudoku = text.parse().unwrap();
+        assert_eq!(Sudoku::try_from(text), Ok(moderate));
+        assert_eq!(Sudoku::try_from("123"), Err(ParseError::TooFewCharacters));
+
+        let debug = format!("{:?}", moderate);
+        let packed = &debug["Sudoku(".len()..debug.len() - 1];
+        assert_eq!(Sudoku::try_from(packed.as_bytes()), Ok(moderate));
+        let sdm = packed.replace('.', "0");
+        assert_eq!(Sudoku::try_from(sdm.as_bytes()), Ok(moderate));
+        assert_eq!(
+            Sudoku::try_from(&sdm.as_bytes()[1..]),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(
+            Sudoku::try_from(format!("{}0", sdm).as_bytes()),
+            Err(ParseError::TooManyCharacters)
+        );
+        assert_eq!(
+            Sudoku::try_from(sdm.replacen('0', " ", 1).as_bytes()),
+            Err(ParseError::BadField)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_read_nth() {
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        let challenge: Sudoku = include_str!("../../challenge.txt").parse().unwrap();
+        let line = |s: &Sudoku| {
+            let debug = format!("{:?}", s);
+            return debug["Sudoku(".len()..debug.len() - 1].to_string();
+        };
+        let corpus = format!(
+            "{}\n\n  {}  \r\nnot a puzzle\n{}",
+            line(&moderate),
+            line(&challenge),
+            line(&moderate)
+        );
+        let nth = |n| Sudoku::read_nth(corpus.as_bytes(), n);
+        assert_eq!(nth(0).unwrap(), Some(moderate));
+        assert_eq!(nth(1).unwrap(), Some(challenge));
+        assert_eq!(nth(3).unwrap(), Some(moderate));
+        assert_eq!(nth(4).unwrap(), None);
+        // Only the line asked for is parsed.
+        let err = nth(2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "Too few characters");
+        assert_eq!(Sudoku::read_nth(&b""[..], 0).unwrap(), None);
+
+        // Any line ending separates puzzles.
+        let line = line(&moderate);
+        for ending in ["\n", "\r\n", "\r"] {
+            let corpus = [line.as_str(); 3].join(ending);
+            let nth = |n| Sudoku::read_nth(corpus.as_bytes(), n).unwrap();
+            assert_eq!(nth(2), Some(moderate), "{:?}", ending);
+            assert_eq!(nth(3), None, "{:?}", ending);
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let lines: Vec<&str> = text_lines("a\nb\r\nc\rd\r\re\n").collect();
+        assert_eq!(lines, ["a", "b", "c", "d", "", "e"]);
+        assert_eq!(text_lines("").count(), 0);
+        assert_eq!(text_lines("\r\n").collect::<Vec<_>>(), [""]);
+        assert_eq!(text_lines("last").collect::<Vec<_>>(), ["last"]);
+
+        let text = include_str!("../../moderate.txt");
+        let moderate: Sudoku = text.parse().unwrap();
+        let rows: Vec<&str> = text.lines().collect();
+        for ending in ["\n", "\r\n", "\r"] {
+            let joined = rows.join(ending);
+            assert_eq!(joined.parse::<Sudoku>(), Ok(moderate), "{:?}", ending);
+            assert_eq!((joined + ending).parse::<Sudoku>(), Ok(moderate));
+        }
+        // Mixed endings, as from pasting rows together.
+        let mixed: String = rows
+            .iter()
+            .zip(["\r", "\n", "\r\n"].iter().cycle())
+            .map(|(row, ending)| format!("{}{}", row, ending))
+            .collect();
+        assert_eq!(mixed.parse::<Sudoku>(), Ok(moderate));
+        // An ending doubled up is a blank row, as it would be with "\n\n".
+        assert_eq!(
+            rows.join("\r\r").parse::<Sudoku>(),
+            Err(ParseError::TooFewCharacters)
+        );
+    }
+
+    #[test]
+    fn test_forced_placements() {
+        let solution: Sudoku = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap()
+            .solve()
+            .unwrap();
+        // A single blank is forced every way, but listed once.
+        let mut one = solution;
+        one.clear(Coord::new(4, 5).unwrap());
+        let v = solution.get(Coord::new(4, 5).unwrap());
+        assert_eq!(one.forced_placements(), vec![(4, 5, v, "naked single")]);
+        assert_eq!(solution.forced_placements(), vec![]);
+
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        let copy = moderate;
+        let forced = moderate.forced_placements();
+        assert_eq!(moderate, copy);
+        assert!(forced.iter().any(|f| f.3 == "hidden single"));
+        for &(r, c, v, _) in &forced {
+            assert_eq!(solution.get(Coord::new(r, c).unwrap()), v);
+        }
+        let mut sorted = forced.clone();
+        sorted.sort();
+        sorted.dedup_by_key(|f| (f.0, f.1, f.2));
+        assert_eq!(sorted.len(), forced.len());
+        // Each hidden single is in a cell where other values still fit.
+        for &(r, c, _, technique) in &forced {
+            let cell = Coord::new(r, c).unwrap();
+            let fits = (1..10).filter(|&w| cell.peers().all(|p| moderate.get(p) != w));
+            let expected = if fits.count() == 1 {
+                "naked single"
+            } else {
+                "hidden single"
+            };
+            assert_eq!(technique, expected);
+        }
+    }
+
+    #[test]
+    fn test_cancellable() {
+        let challenge: Sudoku = include_str!("../../challenge.txt").parse().unwrap();
+        let cancel = AtomicBool::new(false);
+        let solved = UnsolvedSudoku::from(challenge).dynamic_solve_cancellable(&cancel);
+        assert_eq!(solved, challenge.solve().map_err(SolveError::from));
+
+        cancel.store(true, Ordering::Relaxed);
+        let mut u = UnsolvedSudoku::from(challenge);
+        assert_eq!(
+            u.dynamic_solve_cancellable(&cancel),
+            Err(SolveError::Cancelled)
+        );
+        assert_eq!(SolveError::Cancelled.to_string(), "solve was cancelled");
+        // Without guessing there is nothing to cancel.
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        let solved = UnsolvedSudoku::from(moderate).dynamic_solve_cancellable(&cancel);
+        assert_eq!(solved, moderate.solve().map_err(SolveError::from));
+
+        // A puzzle with no solution says so, rather than looking cancelled.
+        let mut broken = moderate;
+        let given = Coord::all().find(|&c| moderate[c] != 0).unwrap();
+        let blank = Coord::all()
+            .find(|&c| c.row() == given.row() && moderate[c] == 0)
+            .unwrap();
+        broken[blank] = moderate[given];
+        cancel.store(false, Ordering::Relaxed);
+        assert_eq!(
+            UnsolvedSudoku::from(broken).dynamic_solve_cancellable(&cancel),
+            Err(SolveError::Unsolveable(Unsolveable::NoSolution))
+        );
+    }
+
+    #[test]
+    fn test_violations() {
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        let solution = moderate.solve().unwrap();
+        assert_eq!(moderate.violations(), vec![]);
+        assert_eq!(solution.violations(), vec![]);
+        let c = |r, c| Coord::new(r, c).unwrap();
+
+        // Swapping two cells of a row keeps the row right, but breaks both columns and,
+        // across squares, both squares.
+        let mut swapped = solution;
+        swapped[c(1, 1)] = solution[c(1, 4)];
+        swapped[c(1, 4)] = solution[c(1, 1)];
+        let found = swapped.violations();
+        assert_eq!(found.len(), 4);
+        assert!(found
+            .iter()
+            .all(|v| matches!(v.unit, Unit::Column(_) | Unit::Square(_))));
+        assert_eq!(
+            found[0],
+            Violation {
+                unit: Unit::Column(1),
+                cells: (c(1, 1), c(3, 1)),
+                value: 1,
+            }
+        );
+        assert_eq!(found[0].to_string(), "r1c1 and r3c1 both hold 1 in C1");
+
+        // Three of a kind in one unit are three pairs.
+        let mut row = Sudoku::empty();
+        for col in [2, 5, 9] {
+            row[c(5, col)] = 7;
+        }
+        let pairs: Vec<(Coord, Coord)> = row.violations().iter().map(|v| v.cells).collect();
+        assert_eq!(
+            pairs,
+            vec![(c(5, 2), c(5, 5)), (c(5, 2), c(5, 9)), (c(5, 5), c(5, 9))]
+        );
+
+        let u = UnsolvedSudoku::from(swapped);
+        assert!(!u.valid());
+        assert_eq!(u.violations(), found);
+        assert!(UnsolvedSudoku::from(moderate).valid());
+    }
+
+    #[test]
+    fn test_validity_report() {
+        let moderate: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        assert_eq!(UnsolvedSudoku::from(moderate).validity_report(), vec![]);
+        let c = |r, c| Coord::new(r, c).unwrap();
+
+        // Three 7s in row 5, two of them also sharing square 4, and a pair of 2s in
+        // column 9.
+        let mut u = UnsolvedSudoku::empty();
+        for (coord, v) in [
+            (c(5, 1), 7),
+            (c(5, 2), 7),
+            (c(5, 9), 7),
+            (c(1, 9), 2),
+            (c(8, 9), 2),
+        ] {
+            *u.get_mut(coord.row(), coord.col()) = Cell::Value(v);
+        }
+        let report = u.validity_report();
+        let summary: Vec<(Unit, u8, usize)> = report
+            .iter()
+            .map(|v| (v.unit, v.value, v.cells.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Unit::Square(4), 7, 2),
+                (Unit::Row(5), 7, 3),
+                (Unit::Column(9), 2, 2),
+            ]
+        );
+        assert_eq!(report[1].cells, vec![c(5, 1), c(5, 2), c(5, 9)]);
+        assert_eq!(report[1].to_string(), "7 is in R5 3 times: r5c1 r5c2 r5c9");
+        assert!(!u.valid());
+        // The same problems as pairs: three in the row, and one each in the others.
+        assert_eq!(u.violations().len(), 5);
+    }
+
+    #[test]
+    fn test_counts() {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert_eq!(s.clue_count() + s.empty_count(), 81);
+        assert_eq!(s.clue_count(), 25);
+        assert!(!s.is_complete());
+        assert_eq!(Sudoku::empty().clue_count(), 0);
+        assert_eq!(Sudoku::empty().empty_count(), 81);
+
+        let solution = s.solve().unwrap();
+        assert!(solution.is_complete());
+        // Complete but breaking the rules.
+        let mut wrong = solution;
+        wrong.rows[0][0] = wrong.rows[0][1];
+        assert!(wrong.is_complete());
+        assert!(!UnsolvedSudoku::from(wrong).valid());
+
+        let u = UnsolvedSudoku::empty();
+        assert_eq!((u.solved_count(), u.remaining_count()), (0, 81));
+        let mut u = UnsolvedSudoku::from(s);
+        // Setting the givens already fixes some cells by elimination.
+        assert!(u.solved_count() >= s.clue_count());
+        assert_eq!(u.solved_count() + u.remaining_count(), 81);
+        u.simple_solve();
+        assert_eq!((u.solved_count(), u.remaining_count()), (81, 0));
+    }
+
+    #[test]
+    fn test_bivalue_cells() {
+        let s: Sudoku = include_str!("../../challenge.txt").parse().unwrap();
+        let mut u = UnsolvedSudoku::from(s);
+        u.simple_solve();
+        let bivalue = u.bivalue_cells();
+        assert!(!bivalue.is_empty());
+        let expected: Vec<(u8, u8, (u8, u8))> = Coord::all()
+            .filter_map(|c| {
+                let ps: Vec<u8> = u.candidates(c)?.iter().collect();
+                return match ps[..] {
+                    [a, b] => Some((c.row(), c.col(), (a, b))),
+                    _ => None,
+                };
+            })
+            .collect();
+        assert_eq!(bivalue, expected);
+        assert!(bivalue.iter().all(|&(_, _, (a, b))| a < b));
+
+        assert_eq!(UnsolvedSudoku::empty().bivalue_cells(), vec![]);
+        let mut solved = UnsolvedSudoku::from(s.solve().unwrap());
+        solved.simple_solve();
+        assert_eq!(solved.bivalue_cells(), vec![]);
+    }
+
+    #[test]
+    fn test_dynamic_solve_with_callback() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let mut counts = Vec::new();
+        let result = UnsolvedSudoku::from(s).dynamic_solve_with_callback(|n| {
+            counts.push(n);
+            return true;
+        });
+        assert_eq!(result, s.solve().map_err(SolveError::from));
+        assert_eq!(counts, (1..=12).collect::<Vec<u64>>());
+
+        let mut calls = 0;
+        let result = UnsolvedSudoku::from(s).dynamic_solve_with_callback(|n| {
+            calls += 1;
+            return n < 5;
+        });
+        assert_eq!(result, Err(SolveError::Cancelled));
+        assert_eq!(calls, 5);
+
+        // No guesses, no calls.
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let result = UnsolvedSudoku::from(moderate).dynamic_solve_with_callback(|_| false);
+        assert_eq!(result, moderate.solve().map_err(SolveError::from));
+    }
+
+    #[test]
+    fn test_debug_dump() {
+        let mut u = UnsolvedSudoku::from(
+            include_str!("../../challenge.txt")
+                .parse::<Sudoku>()
+                .unwrap(),
+        );
+        u.simple_solve();
+        let expected = concat!(
+            " =6 014 011 |  =8  =4  =7 |  =9 015  =2\n",
+            "04f 046 04b |  =9  =6  =5 | 041 045  =8\n",
+            "0d0 1d0 1d0 |  =1  =2  =3 |  =4 050  =6\n",
+            "------------+-------------+------------\n",
+            "0c7 0c6 0c3 |  =5  =9 081 | 0c1  =6  =4\n",
+            "0d9 0d0 0d9 |  =2 0c1  =6 |  =3 041  =9\n",
+            " =9  =6 0c1 |  =3 0c1  =4 | 0c1  =2  =5\n",
+            "------------+-------------+------------\n",
+            "090  =1 190 |  =6  =3 180 |  =2  =4  =7\n",
+            "082  =4  =6 |  =7 081 083 |  =5  =9  =3\n",
+            "042 142  =3 |  =4  =5 102 |  =6  =8  =1\n",
+            "solved 47/81\n",
+        );
+        assert_eq!(u.debug_dump(), expected);
+        assert!(UnsolvedSudoku::empty()
+            .debug_dump()
+            .starts_with("1ff 1ff 1ff | 1ff"));
+    }
+
+    #[test]
+    fn test_brute_force() {
+        for text in [
+            include_str!("../../moderate.txt"),
+            include_str!("../../challenge.txt"),
+        ] {
+            let mut u = UnsolvedSudoku::from(text.parse::<Sudoku>().unwrap());
+            let before = u.clone();
+            let (result, tries) = u.brute_force_counting();
+            assert_eq!(result, u.solved());
+            assert_eq!(u.brute_force_solve(), result);
+            assert_eq!(u, before);
+            // Deductions save a great deal of trying.
+            let (_, guesses) = u.clone().budgeted_solve(u64::MAX);
+            assert!(
+                tries > guesses,
+                "{} tries against {} guesses",
+                tries,
+                guesses
+            );
+        }
+
+        let all: Vec<Sudoku> = UnsolvedSudoku::from(ambiguous()).solutions().collect();
+        match UnsolvedSudoku::from(ambiguous()).brute_force_solve() {
+            Err(Unsolveable::MultipleSolutions(a, b)) => {
+                assert_ne!(a, b);
+                assert!(all.contains(&a) && all.contains(&b));
+            }
+            other => panic!("expected two solutions, got {:?}", other),
+        }
+        let broken = include_str!("../../moderate.txt").replacen('-', "9", 1);
+        let mut u = UnsolvedSudoku::from(broken.parse::<Sudoku>().unwrap());
+        assert_eq!(u.brute_force_solve(), Err(Unsolveable::NoSolution));
+
+        // Eliminated candidates are respected: without 2 at (1, 1), moderate has no
+        // solution.
+        let mut u = moderate();
+        u.eliminate(Coord::new(1, 1).unwrap(), 2).unwrap();
+        assert_eq!(u.brute_force_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    #[test]
+    fn test_solved_keeps_original() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let u = UnsolvedSudoku::from(s);
+        let before = u.clone();
+        assert_eq!(u.solved(), s.solve());
+        assert_eq!(u, before);
+        assert!(!u.is_solved());
+        assert_eq!(
+            UnsolvedSudoku::from(ambiguous()).solved(),
+            UnsolvedSudoku::from(ambiguous()).dynamic_solve()
+        );
+        assert!(UnsolvedSudoku::from(s.solve().unwrap()).is_solved());
+    }
+
+    #[test]
+    fn test_min_candidates_cell() {
+        let mut u = UnsolvedSudoku::empty();
+        // Ties go to the first cell in row-major order.
+        assert_eq!(
+            u.min_candidates_cell(),
+            Some((Coord::new(1, 1).unwrap(), CandidateSet::full()))
+        );
+        for v in 4..10 {
+            u.eliminate(Coord::new(5, 5).unwrap(), v).unwrap();
+        }
+        u.eliminate(Coord::new(2, 8).unwrap(), 1).unwrap();
+        assert_eq!(
+            u.min_candidates_cell(),
+            Some((Coord::new(5, 5).unwrap(), [1, 2, 3].into_iter().collect()))
+        );
+
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let mut u = UnsolvedSudoku::from(s);
+        u.simple_solve();
+        let (coord, ps) = u.min_candidates_cell().unwrap();
+        assert_eq!(Some(ps), u.candidates(coord));
+        for (c, _) in u.cells() {
+            if let Some(other) = u.candidates(c) {
+                assert!(other.len() >= ps.len());
+            }
+        }
+
+        let mut solved = UnsolvedSudoku::from(s.solve().unwrap());
+        assert_eq!(solved.min_candidates_cell(), None);
+        solved.simple_solve();
+        assert_eq!(solved.min_candidates_cell(), None);
+    }
+
+    #[test]
+    fn test_rarest_candidates_cell() {
+        // r1c1 and r9c9 both have 1-3 left, but 1 and 2 are already gone from most of
+        // r9c9's peers, so its candidates are rarer there.
+        let mut u = UnsolvedSudoku::empty();
+        for c in [Coord::new(1, 1).unwrap(), Coord::new(9, 9).unwrap()] {
+            for v in 4..10 {
+                u.eliminate(c, v).unwrap();
+            }
+        }
+        for col in 1..9 {
+            u.eliminate(Coord::new(9, col).unwrap(), 1).unwrap();
+            u.eliminate(Coord::new(9, col).unwrap(), 2).unwrap();
+        }
+        assert_eq!(
+            u.min_candidates_cell().unwrap().0,
+            Coord::new(1, 1).unwrap()
+        );
+        assert_eq!(
+            u.rarest_candidates_cell(),
+            Some((Coord::new(9, 9).unwrap(), [1, 2, 3].into_iter().collect()))
+        );
+        // With nothing to tell them apart, it agrees with min_candidates_cell.
+        let empty = UnsolvedSudoku::empty();
+        assert_eq!(empty.rarest_candidates_cell(), empty.min_candidates_cell());
+        let solved = UnsolvedSudoku::from(
+            include_str!("../../moderate.txt")
+                .parse::<Sudoku>()
+                .unwrap()
+                .solve()
+                .unwrap(),
+        );
+        assert_eq!(solved.rarest_candidates_cell(), None);
+    }
+
+    #[test]
+    fn test_peers() {
+        let c = Coord::new(5, 5).unwrap();
+        let peers: Vec<Coord> = c.peers().collect();
+        assert_eq!(peers.len(), 20);
+        assert!(!peers.contains(&c));
+        assert!(peers.contains(&Coord::new(4, 6).unwrap()));
+        assert!(peers.contains(&Coord::new(1, 5).unwrap()));
+        assert!(peers.contains(&Coord::new(5, 9).unwrap()));
+        assert!(!peers.contains(&Coord::new(4, 7).unwrap()));
+        for c in Coord::all() {
+            assert_eq!(c.peers().count(), 20);
+        }
+    }
+
+    #[test]
+    fn test_can_place() {
+        let u = moderate();
+        // Row 1 is "---1-3-95".
+        assert!(u.can_place(1, 1, 2));
+        assert!(!u.can_place(1, 1, 9));
+        assert!(!u.can_place(1, 1, 1));
+        // Already filled.
+        assert!(!u.can_place(1, 4, 1));
+        assert!(!u.can_place(1, 4, 2));
+        // Off the grid.
+        assert!(!u.can_place(0, 1, 2));
+        assert!(!u.can_place(1, 10, 2));
+        assert!(!u.can_place(1, 1, 0));
+        assert!(!u.can_place(1, 1, 10));
+
+        // A stale candidate list is caught by checking the peers directly.
+        let mut stale = UnsolvedSudoku::empty();
+        stale.rows[0][8] = Cell::Value(7);
+        assert!(!stale.can_place(1, 1, 7));
+        assert!(stale.can_place(1, 1, 6));
+        assert!(stale.can_place(2, 1, 7));
+    }
+
+    #[test]
+    fn test_must_place() {
+        let mut u = UnsolvedSudoku::empty();
+        assert_eq!(u.must_place(1, 1), None);
+        for v in 1..9 {
+            u.get_mut(1, 1).remove_possibility(v);
+        }
+        assert_eq!(u.must_place(1, 1), Some(9));
+        assert_eq!(u.must_place(1, 2), None);
+        assert_eq!(u.must_place(0, 1), None);
+        assert_eq!(u.must_place(1, 10), None);
+
+        let u = moderate();
+        assert_eq!(u.must_place(1, 4), Some(1));
+        assert_eq!(u.must_place(1, 9), Some(5));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let mut u = moderate();
+        let partial = u.partial_sudoku();
+        let given = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        // Propagating the givens already fixes a few more cells.
+        assert!(given.cells().all(|(c, v)| v == 0 || partial[c] == v));
+        assert_eq!(partial.rows[8], [0, 0, 0, 0, 6, 0, 3, 5, 4]);
+        assert_eq!(Sudoku::from(u.clone()), partial);
+
+        let err = Sudoku::try_from(&u).unwrap_err();
+        let blanks: Vec<Coord> = partial
+            .cells()
+            .filter(|&(_, v)| v == 0)
+            .map(|(c, _)| c)
+            .collect();
+        assert_eq!(err.unsolved, blanks);
+        let expected = format!("{} cells are unsolved: r1c1 r1c2 r1c3 r1c5", blanks.len());
+        assert!(err.to_string().starts_with(&expected));
+
+        // moderate needs no guesses, so dynamic_solve leaves u itself solved.
+        let solved = u.dynamic_solve().unwrap();
+        assert_eq!(Sudoku::try_from(&u), Ok(solved));
+        assert_eq!(Sudoku::from(u), solved);
+    }
+
+    #[test]
+    fn test_try_get() {
+        let u = UnsolvedSudoku::empty();
+        for i in 1..10 {
+            for j in 1..10 {
+                assert!(u.try_get(i, j).is_some());
+            }
+            for &bad in &[0, 10, 255] {
+                assert!(u.try_get(bad, i).is_none());
+                assert!(u.try_get(i, bad).is_none());
+                assert!(u.try_get(bad, bad).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_set() {
+        let mut u = UnsolvedSudoku::empty();
+        for &bad in &[0, 10, 255] {
+            assert_eq!(u.try_set(bad, 1, 1), Err(GridError::RowOutOfRange(bad)));
+            assert_eq!(u.try_set(1, bad, 1), Err(GridError::ColumnOutOfRange(bad)));
+            assert_eq!(u.try_set(bad, bad, 1), Err(GridError::RowOutOfRange(bad)));
+            assert_eq!(u.try_set(1, 1, bad), Err(GridError::ValueOutOfRange(bad)));
+            assert_eq!(u.try_set(bad, 1, bad), Err(GridError::RowOutOfRange(bad)));
+        }
+        // None of the failures should have touched the grid.
+        assert_eq!(u, UnsolvedSudoku::empty());
+
+        assert_eq!(u.try_set(9, 9, 5), Ok(()));
+        assert_eq!(*u.get(9, 9), Cell::Value(5));
+    }
+
+    fn moderate() -> UnsolvedSudoku {
+        let s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        return s.into();
+    }
+
+    #[test]
+    fn test_with_user_entries() {
+        let u = moderate();
+        // (1, 1) is 2 in the solution, (1, 4) is the given 1.
+        let played = u.with_user_entries(&[(1, 1, 2), (1, 4, 1)]).unwrap();
+        assert_eq!(*played.get(1, 1), Cell::Value(2));
+        // The original is left alone.
+        assert!(*u.get(1, 1) != Cell::Value(2));
+        assert!(played.clone().dynamic_solve().is_ok());
+
+        assert_eq!(
+            u.with_user_entries(&[(1, 4, 2)]),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 4,
+                value: 1
+            })
+        );
+        // 9 is already in row 1.
+        assert_eq!(
+            u.with_user_entries(&[(1, 1, 9)]),
+            Err(Conflict::NotPossible {
+                row: 1,
+                col: 1,
+                value: 9
+            })
+        );
+        // Later entries are checked against earlier ones.
+        assert_eq!(
+            u.with_user_entries(&[(1, 1, 2), (1, 2, 2)]),
+            Err(Conflict::NotPossible {
+                row: 1,
+                col: 2,
+                value: 2
+            })
+        );
+        assert_eq!(
+            u.with_user_entries(&[(0, 1, 2)]),
+            Err(Conflict::OutOfRange(GridError::RowOutOfRange(0)))
+        );
+        assert_eq!(
+            u.with_user_entries(&[(1, 1, 0)]),
+            Err(Conflict::OutOfRange(GridError::ValueOutOfRange(0)))
+        );
+    }
+
+    #[test]
+    fn test_add_clue() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let solution = s.solve().unwrap();
+        let fixed_count = |u: &UnsolvedSudoku| {
+            u.cells()
+                .filter(|(c, _)| u.fixed_value(*c).is_some())
+                .count()
+        };
+
+        // Reveal the solution one cell at a time. Each step's report accounts for every
+        // newly fixed cell, and the solve finishes well before every cell is revealed.
+        let mut u = UnsolvedSudoku::from(s);
+        let mut reveals = 0;
+        for c in Coord::all() {
+            if u.fixed_value(c).is_some() {
+                continue;
+            }
+            let before = fixed_count(&u);
+            let progress = u.add_clue(c.row(), c.col(), solution[c]).unwrap();
+            reveals += 1;
+            assert!(progress.fixed.contains(&(c, solution[c])));
+            assert_eq!(fixed_count(&u), before + progress.fixed.len());
+            for &(f, v) in &progress.fixed {
+                assert_eq!(solution[f], v);
+            }
+            assert_eq!(progress.solved, u.is_solved());
+            if progress.solved {
+                break;
+            }
+        }
+        assert!(u.is_solved());
+        assert!(reveals < 10);
+        assert_eq!(u.partial_sudoku(), solution);
+
+        // A clue that is already there changes nothing.
+        assert_eq!(
+            u.add_clue(1, 1, solution[Coord::new(1, 1).unwrap()]),
+            Ok(SolveProgress {
+                fixed: vec![],
+                eliminated: 0,
+                solved: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_add_clue_conflicts() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let mut u = UnsolvedSudoku::from(s);
+        let before = u.clone();
+
+        // 3 is still a candidate at (1, 1), but deducing from it runs into a
+        // contradiction; the solution has 6 there.
+        assert_eq!(
+            u.add_clue(1, 1, 3),
+            Err(Conflict::Inconsistent {
+                row: 1,
+                col: 1,
+                value: 3
+            })
+        );
+        assert!(u == before);
+        assert_eq!(
+            u.add_clue(1, 4, 1),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 4,
+                value: 8
+            })
+        );
+        assert_eq!(
+            u.add_clue(1, 1, 8),
+            Err(Conflict::NotPossible {
+                row: 1,
+                col: 1,
+                value: 8
+            })
+        );
+        assert_eq!(
+            u.add_clue(10, 1, 1),
+            Err(Conflict::OutOfRange(GridError::RowOutOfRange(10)))
+        );
+        assert!(u == before);
+
+        let progress = u.add_clue(1, 1, 6).unwrap();
+        assert!(!progress.solved);
+        assert!(progress.eliminated > 0);
+        assert_eq!(progress.fixed[0], (Coord::new(1, 1).unwrap(), 6));
+    }
+
+    #[test]
+    fn test_place() {
+        let s = include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        let solution = s.solve().unwrap();
+        let mut u = UnsolvedSudoku::from(s);
+        let before = u.clone();
+        let c = |row, col| Coord::new(row, col).unwrap();
+
+        // Row 1 already has an 8, at (1, 4).
+        assert_eq!(
+            u.place(1, 1, 8),
+            Err(Conflict::Taken {
+                row: 1,
+                col: 1,
+                value: 8,
+                unit: Unit::Row(1),
+                at: c(1, 4)
+            })
+        );
+        assert_eq!(
+            u.place(1, 4, 1),
+            Err(Conflict::AlreadyFilled {
+                row: 1,
+                col: 4,
+                value: 8
+            })
+        );
+        assert_eq!(
+            u.place(1, 1, 0),
+            Err(Conflict::OutOfRange(GridError::ValueOutOfRange(0)))
+        );
+        assert!(u == before);
+
+        // Placing the whole solution fixes every cell exactly once.
+        let mut fixed = vec![];
+        for c in Coord::all() {
+            if u.fixed_value(c).is_some() {
+                continue;
+            }
+            let effects = u.place(c.row(), c.col(), solution[c]).unwrap();
+            assert_eq!(effects.fixed[0].0, c);
+            assert_eq!(effects.solved, u.is_solved());
+            fixed.extend(effects.fixed);
+        }
+        assert!(u.is_solved());
+        let open = Coord::all().filter(|&c| before.fixed_value(c).is_none());
+        assert_eq!(fixed.len(), open.count());
+        assert!(fixed.iter().all(|&(c, v)| solution[c] == v));
+        assert_eq!(
+            u.place(1, 1, solution[c(1, 1)]),
+            Ok(PlacementEffects {
+                fixed: vec![],
+                solved: true
+            })
+        );
+
+        // A candidate removed by hand is not possible, as no peer holds it.
+        let mut u = UnsolvedSudoku::empty();
+        u.eliminate(c(5, 5), 3).unwrap();
+        assert_eq!(
+            u.place(5, 5, 3),
+            Err(Conflict::NotPossible {
+                row: 5,
+                col: 5,
+                value: 3
+            })
+        );
+
+        // With (1, 1) and (1, 2) down to 1 and 2, a 1 elsewhere in row 1 fixes both to 2.
+        for col in [1, 2] {
+            for v in 3..=9 {
+                u.eliminate(c(1, col), v).unwrap();
+            }
+        }
+        let before = u.clone();
+        assert_eq!(
+            u.place(1, 9, 1),
+            Err(Conflict::Inconsistent {
+                row: 1,
+                col: 9,
+                value: 1
+            })
+        );
+        assert!(u == before);
+        let effects = u.place(1, 1, 1).unwrap();
+        assert_eq!(effects.fixed, vec![(c(1, 1), 1), (c(1, 2), 2)]);
+        assert!(!effects.solved);
+    }
+
+    #[test]
+    #[should_panic(expected = "row 0 out of range")]
+    fn test_set_zero() {
+        UnsolvedSudoku::empty().set(0, 1, 5);
+    }
+}
@@ -1,495 +1,971 @@
-use std::collections;
+use std::collections::HashSet;
 use std::env;
-use std::fmt;
 use std::fs::File;
-use std::io;
-use std::io::BufRead;
-use std::iter::FromIterator;
+use std::io::{self, Read, Write};
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use sudokusolver::{
+    generate_batch, generate_daily, parse_clue_pattern, parse_extra_regions, rate, text_lines,
+    Alphabet, BoxShape, Cage, Constraint, Regions, SdkFile, SizedSudoku, SizedUnsolveable,
+    SolutionStatus, Solver, SolverConfig, SolverRng, Sudoku, Unit, Unsolveable, UnsolvedSudoku,
+    Variant, EDITOR_SOLUTION_LIMIT, PATTERN_SOLUTION_LIMIT,
+};
+
+use cli::{
+    CheckArgs, Command, ConvertArgs, Format, GenerateArgs, MaskArgs, Mirror, Relabel, SolveArgs,
+    TransformArgs,
+};
+use status::Status;
+
+mod bench;
+mod cli;
+mod compare;
+mod completions;
+mod inputs;
+mod interactive;
+mod progress;
+mod status;
+mod watch;
 
-#[derive(Clone)]
-pub struct Sudoku {
-    rows: [[u8; 9]; 9],
-}
-
-impl fmt::Display for Sudoku {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (n, r) in self.rows.iter().enumerate() {
-            if n > 0 {
-                write!(f, "\n")?;
-            }
-            for c in r {
-                match c {
-                    0 => write!(f, "{}", " ")?,
-                    _ => write!(f, "{}", c)?,
-                }
+fn main() -> Result<(), io::Error> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n", e);
+            match e.subcommand {
+                Some(sub) => eprintln!("{}", sub.usage()),
+                None => eprint!("{}", cli::full_usage()),
             }
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e.message));
         }
-
-        return Ok(());
-    }
-}
-
-trait SubArray {
-    fn name(&self) -> &'static str;
-    fn matrix_index(&self, index: u8) -> (u8, u8);
+    };
+    return match command {
+        Command::Solve(
+            args @ SolveArgs {
+                shape: Some(shape), ..
+            },
+        ) => solve_sized(args, shape),
+        Command::Solve(args) if args.status_only => exit_with(status_only(args)?),
+        Command::Solve(args) => solve(args),
+        Command::Dedupe(files) => print_distinct(&files),
+        Command::Watch(args) => watch::watch(args),
+        Command::Check(args) => exit_with(check(args)?),
+        Command::Generate(args) => generate(args),
+        Command::Rate(files) => rate_files(&files),
+        Command::Convert(args) => convert(args),
+        Command::Hint(file) => hint(&file),
+        Command::Mask(args) => mask(args),
+        Command::Interactive(file) => interactive::interactive(file),
+        Command::Bench(args) => bench::bench(args),
+        Command::Compare(args) => compare::compare(args),
+        Command::Transform(args) => transform(args),
+        Command::Completions(shell) => {
+            print!("{}", completions::script(shell));
+            Ok(())
+        }
+        Command::Help(Some(sub)) => {
+            println!("{}", sub.usage());
+            Ok(())
+        }
+        Command::Help(None) => {
+            print!("{}", cli::full_usage());
+            Ok(())
+        }
+    };
 }
 
-pub struct Row {
-    index: u8,
+// End the program with the exit status, once stdout is flushed.
+fn exit_with(status: i32) -> Result<(), io::Error> {
+    if status != 0 {
+        io::stdout().flush()?;
+        process::exit(status);
+    }
+    return Ok(());
 }
 
-impl SubArray for Row {
-    fn name(&self) -> &'static str {
-        return "R";
+fn solve(args: SolveArgs) -> Result<(), io::Error> {
+    if let Some(level) = &args.log_level {
+        init_logging(level)?;
     }
-
-    fn matrix_index(&self, index: u8) -> (u8, u8) {
-        return (self.index, index);
+    // Paths that can't be expanded are reported straight away, and fail the run once
+    // the rest are done.
+    let mut files = Vec::new();
+    let mut many = args.files.len() > 1;
+    let mut unreadable = 0;
+    for arg in &args.files {
+        match inputs::expand(arg, args.recursive) {
+            Ok(e) => {
+                files.extend(e.files);
+                many |= e.many;
+            }
+            Err(e) => {
+                eprintln!("{}: {}", arg, e);
+                unreadable += 1;
+            }
+        }
     }
+    let regions = match &args.regions {
+        Some(filename) => Some(read_regions(filename)?),
+        None => None,
+    };
+    let extra_regions = match &args.extra_regions {
+        Some(filename) => read_extra_regions(filename)?,
+        None => Vec::new(),
+    };
+    let config = Config {
+        verbose: args.verbose,
+        minimize: args.minimize,
+        canonical: args.canonical,
+        oneline: args.oneline,
+        time: args.time,
+        all: args.all,
+        index: args.index,
+        max_guesses: args.max_guesses,
+        progress: args.progress,
+        variants: args.variants.clone(),
+        regions,
+        extra_regions,
+        headers: many,
+    };
+    let mut times = Vec::new();
+    let result = each_file(&files, |filename, out| {
+        return solve_file(filename, &config, out, &mut times);
+    });
+    if config.time && config.headers {
+        println!("{}", time_summary(&mut times));
+    }
+    if result.is_ok() && unreadable > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} of {} paths could not be read.",
+                unreadable,
+                args.files.len()
+            ),
+        ));
+    }
+    return result;
 }
 
-pub struct Column {
-    index: u8,
-}
-
-impl SubArray for Column {
-    fn name(&self) -> &'static str {
-        return "C";
+// Run `run` on each file in turn. One file keeps the plain output and reports its
+// failure as the program's error; with several, each failure is reported on stderr
+// and the rest still run, failing at the end if any did.
+fn each_file(
+    files: &[String],
+    mut run: impl FnMut(&str, &mut io::StdoutLock) -> Result<(), io::Error>,
+) -> Result<(), io::Error> {
+    let mut out = io::stdout().lock();
+    if let [filename] = files {
+        return run(filename, &mut out);
     }
-
-    fn matrix_index(&self, index: u8) -> (u8, u8) {
-        return (index, self.index);
+    let mut failed = 0;
+    for filename in files {
+        if let Err(e) = run(filename, &mut out) {
+            out.flush()?;
+            eprintln!("{}: {}", display_name(filename), e);
+            failed += 1;
+        }
     }
+    if failed > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} of {} puzzles failed.", failed, files.len()),
+        ));
+    }
+    return Ok(());
 }
 
-pub struct Square {
-    index: u8,
+// What to do with each puzzle file.
+#[derive(Clone, Debug, Default)]
+struct Config {
+    verbose: bool,
+    minimize: bool,
+    canonical: bool,
+    oneline: bool,
+    time: bool,
+    // Print every solution, up to this many, rather than requiring just one.
+    all: Option<usize>,
+    // Read only this puzzle line of each file.
+    index: Option<usize>,
+    // Give up on a puzzle after this many guesses.
+    max_guesses: Option<u64>,
+    // Show a spinner on stderr while searching, if it's a terminal.
+    progress: bool,
+    // Solve by these variants' rules, not the file's.
+    variants: Option<Vec<Variant>>,
+    // Solve with these regions, not the file's.
+    regions: Option<Regions>,
+    // Solve with these units on top of the puzzle's own.
+    extra_regions: Vec<Unit>,
+    // Label each result with its file: a header line before it, or with oneline a first
+    // column.
+    headers: bool,
 }
 
-impl SubArray for Square {
-    fn name(&self) -> &'static str {
-        return "S";
+// Solve the puzzle in the file, or "-" for stdin, and write the result (or with
+// minimize or canonical, that puzzle) to out. With config.time, the time the solve
+// took is added to times.
+fn solve_file(
+    filename: &str,
+    config: &Config,
+    out: &mut impl Write,
+    times: &mut Vec<Duration>,
+) -> Result<(), io::Error> {
+    let name = display_name(filename);
+    if config.headers && !config.oneline {
+        writeln!(out, "== {} ==", name)?;
     }
-
-    fn matrix_index(&self, index: u8) -> (u8, u8) {
-        let ii = (self.index - 1) / 3;
-        let ij = (self.index - 1) % 3;
-        let ji = (index - 1) / 3;
-        let jj = (index - 1) % 3;
-
-        return (ii * 3 + ji + 1, ij * 3 + jj + 1);
+    let ((s, mut rules), parse_time) = match config.index {
+        // Reading a line at a time, the time to find the line counts as parsing.
+        Some(n) => {
+            let start = Instant::now();
+            let s = read_nth_puzzle(filename, n)?;
+            ((s, Rules::default()), start.elapsed())
+        }
+        // Read first, so the parse time doesn't include waiting on the file.
+        None => {
+            let text = read_text(filename)?;
+            let start = Instant::now();
+            let parsed = parse_with_rules(filename, &text);
+            (parsed?, start.elapsed())
+        }
+    };
+    if let Some(variants) = &config.variants {
+        rules.variants = variants.clone();
     }
-}
-
-impl Sudoku {
-    fn empty() -> Sudoku {
-        Sudoku { rows: [[0; 9]; 9] }
+    if config.regions.is_some() {
+        rules.regions = config.regions;
     }
-
-    fn from_reader<R: io::Read>(reader: R) -> io::Result<Sudoku> {
-        let mut buf = io::BufReader::new(reader);
-
-        let mut s = Sudoku::empty();
-
-        let mut line = String::new();
-
-        let mut rix = 0;
-        let mut cix = 0;
-
-        loop {
-            match buf.read_line(&mut line) {
-                Ok(0) => break,
-                Ok(_) => {}
-                Err(e) => {
-                    return Err(e);
+    rules.units.extend(&config.extra_regions);
+    if !rules.is_classic() && (config.all.is_some() || config.minimize || config.canonical) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--all, --minimize, and --canonical only take classic puzzles.",
+        ));
+    }
+    if let Some(max) = config.all {
+        return print_solutions(&s, max, name, config, out);
+    }
+    let mut solve_time = Duration::ZERO;
+    if config.verbose {
+        eprintln!(
+            "{}: {} clues, {} empty",
+            name,
+            s.clue_count(),
+            s.empty_count()
+        );
+    }
+    let result = if config.minimize {
+        if s.count_solutions(2) != 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Only a puzzle with one solution can be minimized.",
+            ));
+        }
+        s.minimize(None)
+    } else if config.canonical {
+        s.canonical_form()
+    } else {
+        let grid = rules.grid(&s);
+        let mut solver = Solver::with_config(grid, solver_config(config.max_guesses));
+        let mut spinner = progress::Spinner::new(config.progress);
+        let report = solver.solve_with_callback(|guesses| {
+            spinner.tick(guesses);
+            return true;
+        });
+        spinner.clear();
+        solve_time = report.elapsed;
+        if config.time {
+            times.push(solve_time);
+        }
+        let Some(result) = report.result else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Gave up after {} guesses.", report.guesses),
+            ));
+        };
+        match result {
+            Ok(solution) => solution,
+            Err(Unsolveable::MultipleSolutions(a, b)) => {
+                if config.verbose {
+                    eprintln!("{}\n\nand\n\n{}", a, b);
                 }
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Multiple solutions found.",
+                ));
             }
-            let trimmed = line.trim();
-
-            for c in trimmed.chars() {
-                let n = match c {
-                    '1'...'9' => c.to_digit(10).expect("This shouldn't happen"),
-                    '0' | '-' | 'x' => 0,
-                    _ => continue,
-                };
-
-                if cix >= 9 || rix >= 9 {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::InvalidInput,
-                        "Too many characters",
-                    ));
-                }
-                s.rows[rix][cix] = n as u8;
-                cix += 1;
+            Err(Unsolveable::NoSolution) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "No solution found.",
+                ));
             }
-
-            if cix < 9 {
+            Err(e) => {
                 return Err(std::io::Error::new(
                     std::io::ErrorKind::InvalidInput,
-                    "Too few characters",
+                    e.to_string(),
                 ));
             }
-
-            rix += 1;
-            cix = 0;
-            line.clear();
         }
+    };
 
-        if rix < 9 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Too few rows",
-            ));
+    if config.oneline {
+        if config.headers {
+            write!(out, "{} ", name)?;
         }
-
-        return Ok(s);
+        writeln!(out, "{}", line_text(&result, '.'))?;
+    } else if config.minimize || config.canonical {
+        writeln!(out, "{}", puzzle_text(&result))?;
+    } else if let Some(regions) = &rules.regions {
+        // A jigsaw's solution can't be checked by eye without its regions drawn in.
+        writeln!(out, "{}", regions.bordered(&result))?;
+    } else {
+        writeln!(out, "{}", result)?;
+    }
+    if config.time {
+        writeln!(
+            out,
+            "time: parse {}, solve {}",
+            duration_text(parse_time),
+            duration_text(solve_time)
+        )?;
     }
+    return Ok(());
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub enum Cell {
-    Value(u8),
-    Possibilities(collections::BTreeSet<u8>),
+fn solver_config(max_guesses: Option<u64>) -> SolverConfig {
+    let mut config = SolverConfig::default();
+    if let Some(max) = max_guesses {
+        config.guess_budget = max;
+    }
+    return config;
 }
 
-enum Removal {
-    Fixed(u8),
-    Removed,
-    NotFound,
+// Solve each file's grid of the shape with SizedSudoku, printing the solutions in the
+// same alphabet as the puzzles, or with status_only, exiting with the worst status as
+// status_only does.
+fn solve_sized(args: SolveArgs, shape: BoxShape) -> Result<(), io::Error> {
+    let alphabet = match args.hex {
+        true => Alphabet::Hex,
+        false => Alphabet::Letters,
+    };
+    let solve = |filename: &str| -> Result<Result<SizedSudoku, SizedUnsolveable>, io::Error> {
+        let s = SizedSudoku::parse_with(shape, alphabet, &read_text(filename)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        return Ok(s.solve());
+    };
+    let headers = args.files.len() > 1;
+    if args.status_only {
+        let statuses: Vec<Status> = args
+            .files
+            .iter()
+            .map(|filename| match solve(filename) {
+                Ok(result) => Status::of_sized(&result),
+                Err(_) => Status::Unreadable,
+            })
+            .collect();
+        if headers {
+            eprintln!("{}", status::summary(&statuses));
+        }
+        return exit_with(status::worst(&statuses));
+    }
+    return each_file(&args.files, |filename, out| {
+        let solution = match solve(filename)? {
+            Ok(s) => s,
+            Err(SizedUnsolveable::MultipleSolutions(..)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Multiple solutions found.",
+                ));
+            }
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "No solution found.",
+                ));
+            }
+        };
+        let text = solution.to_text(alphabet);
+        let name = display_name(filename);
+        if args.oneline {
+            if headers {
+                write!(out, "{} ", name)?;
+            }
+            writeln!(out, "{}", text.replace('\n', ""))?;
+        } else {
+            if headers {
+                writeln!(out, "== {} ==", name)?;
+            }
+            writeln!(out, "{}", text)?;
+        }
+        return Ok(());
+    });
 }
 
-impl Cell {
-    fn remove_possibility(&mut self, value: u8) -> Removal {
-        let ps = match self {
-            Cell::Value(_) => return Removal::NotFound,
-            Cell::Possibilities(p) => p,
+// Solve each puzzle without printing anything, and return the exit status for the
+// worst outcome; see status.rs. With several puzzles, a count of each outcome goes to
+// stderr.
+fn status_only(args: SolveArgs) -> Result<i32, io::Error> {
+    if let Some(level) = &args.log_level {
+        init_logging(level)?;
+    }
+    let regions = match &args.regions {
+        Some(filename) => Some(read_regions(filename)?),
+        None => None,
+    };
+    let extra_regions = match &args.extra_regions {
+        Some(filename) => read_extra_regions(filename)?,
+        None => Vec::new(),
+    };
+    let mut statuses = Vec::new();
+    let mut many = args.files.len() > 1;
+    for arg in &args.files {
+        let files = match inputs::expand(arg, args.recursive) {
+            Ok(e) => {
+                many |= e.many;
+                e.files
+            }
+            Err(_) => {
+                statuses.push(Status::Unreadable);
+                continue;
+            }
         };
-        if !ps.contains(&value) {
-            return Removal::NotFound;
-        }
-        ps.remove(&value);
-        if ps.len() == 1 {
-            let &v = ps.iter().next().unwrap();
-            *self = Cell::Value(v);
-            return Removal::Fixed(v);
+        for filename in files {
+            let puzzle = match args.index {
+                Some(n) => read_nth_puzzle(&filename, n).map(|s| (s, Rules::default())),
+                None => read_text(&filename).and_then(|text| parse_with_rules(&filename, &text)),
+            };
+            let status = match puzzle {
+                Ok((s, mut rules)) => {
+                    if let Some(variants) = &args.variants {
+                        rules.variants = variants.clone();
+                    }
+                    if regions.is_some() {
+                        rules.regions = regions;
+                    }
+                    rules.units.extend(&extra_regions);
+                    let grid = rules.grid(&s);
+                    let config = solver_config(args.max_guesses);
+                    Status::of(&Solver::with_config(grid, config).solve().result)
+                }
+                Err(_) => Status::Unreadable,
+            };
+            statuses.push(status);
         }
-        return Removal::Removed;
     }
+    if many {
+        eprintln!("{}", status::summary(&statuses));
+    }
+    return Ok(status::worst(&statuses));
 }
 
-impl Default for Cell {
-    fn default() -> Cell {
-        return Cell::Possibilities(collections::BTreeSet::from_iter(1..10));
+// Each solution of s, up to max of them, and then how many there were.
+fn print_solutions(
+    s: &Sudoku,
+    max: usize,
+    name: &str,
+    config: &Config,
+    out: &mut impl Write,
+) -> Result<(), io::Error> {
+    // One past the limit, to tell whether it was reached.
+    let solutions: Vec<Sudoku> = UnsolvedSudoku::from(*s).solutions().take(max + 1).collect();
+    if solutions.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No solution found.",
+        ));
     }
+    for (i, solution) in solutions.iter().take(max).enumerate() {
+        if config.oneline {
+            if config.headers {
+                write!(out, "{} ", name)?;
+            }
+            writeln!(out, "{}", line_text(solution, '.'))?;
+        } else {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}", solution)?;
+        }
+    }
+    if !config.oneline {
+        writeln!(out)?;
+    } else if config.headers {
+        write!(out, "{} ", name)?;
+    }
+    return match solutions.len() {
+        1 => writeln!(out, "found 1 solution"),
+        n if n > max => writeln!(out, "found {}+ solutions (limit reached)", max),
+        n => writeln!(out, "found {} solutions", n),
+    };
 }
 
-#[derive(Clone)]
-pub struct UnsolvedSudoku {
-    rows: [[Cell; 9]; 9],
+// The total, mean, median, and longest of the solve times.
+fn time_summary(times: &mut [Duration]) -> String {
+    times.sort();
+    let total: Duration = times.iter().sum();
+    let n = times.len();
+    let (mean, median) = match n {
+        0 => (Duration::ZERO, Duration::ZERO),
+        _ if n.is_multiple_of(2) => (total / n as u32, (times[n / 2 - 1] + times[n / 2]) / 2),
+        _ => (total / n as u32, times[n / 2]),
+    };
+    return format!(
+        "time: {} puzzles, total {}, mean {}, median {}, max {}",
+        n,
+        duration_text(total),
+        duration_text(mean),
+        duration_text(median),
+        duration_text(times.last().copied().unwrap_or_default())
+    );
 }
 
-#[derive(Clone, Copy)]
-enum PossibleLocations {
-    None,
-    Filled,
-    Single(u8, u8),
-    Many,
+// A duration in µs below a millisecond, ms below a second, and s above.
+fn duration_text(d: Duration) -> String {
+    let secs = d.as_secs_f64();
+    return if secs < 1e-3 {
+        format!("{:.1}µs", secs * 1e6)
+    } else if secs < 1.0 {
+        format!("{:.2}ms", secs * 1e3)
+    } else {
+        format!("{:.2}s", secs)
+    };
 }
 
-pub enum Unsolveable {
-    MultipleSolutions,
-    NoSolution,
+// The puzzle in the file, or in stdin for "-".
+fn read_puzzle(filename: &str) -> Result<Sudoku, io::Error> {
+    return match filename {
+        "-" => Sudoku::from_reader(io::stdin().lock()),
+        _ => Sudoku::from_reader(File::open(filename)?),
+    };
 }
 
-impl UnsolvedSudoku {
-    pub fn empty() -> UnsolvedSudoku {
-        return UnsolvedSudoku {
-            rows: Default::default(),
-        };
-    }
+// The rules a puzzle is solved by on top of the classic ones: those of its variants, which
+// --variant can replace, any other constraints and killer cages its file lists, its
+// jigsaw regions, which --regions can replace, and the units --extra-regions adds.
+#[derive(Clone, Debug, Default)]
+struct Rules {
+    variants: Vec<Variant>,
+    constraints: Vec<Constraint>,
+    cages: Vec<Cage>,
+    // None for the squares.
+    regions: Option<Regions>,
+    units: Vec<Unit>,
+}
 
-    fn get_subarrays(rix: u8, cix: u8) -> (Row, Column, Square) {
-        let qix = ((rix - 1) / 3) * 3 + ((cix - 1) / 3) + 1;
-        return (
-            Row { index: rix },
-            Column { index: cix },
-            Square { index: qix },
-        );
+impl Rules {
+    fn is_classic(&self) -> bool {
+        return self.variants.is_empty()
+            && self.constraints.is_empty()
+            && self.cages.is_empty()
+            && self.regions.is_none()
+            && self.units.is_empty();
     }
 
-    pub fn get(&self, rix: u8, cix: u8) -> &Cell {
-        return &self.rows[(rix - 1) as usize][(cix - 1) as usize];
+    // The puzzle's givens on a grid with these rules, ready to solve.
+    fn grid(&self, puzzle: &Sudoku) -> UnsolvedSudoku {
+        let mut grid = match &self.regions {
+            Some(regions) => UnsolvedSudoku::with_regions(regions, &self.variants),
+            None => UnsolvedSudoku::with_variants(&self.variants),
+        };
+        for &constraint in &self.constraints {
+            grid.add_constraint(constraint);
+        }
+        for cage in &self.cages {
+            grid.add_cage(cage.clone());
+        }
+        for &unit in &self.units {
+            grid.add_unit(unit)
+                .expect("extra regions are checked as they're read");
+        }
+        grid.add_givens(puzzle);
+        return grid;
     }
+}
 
-    pub fn get_mut(&mut self, rix: u8, cix: u8) -> &mut Cell {
-        return &mut self.rows[(rix - 1) as usize][(cix - 1) as usize];
+// The puzzle in text read from the file, and the rules it follows: an .sdk file can
+// name variants with a "#variant:" line and list kropki dots, killer cages, and jigsaw
+// regions, and anything else is classic.
+fn parse_with_rules(filename: &str, text: &str) -> Result<(Sudoku, Rules), io::Error> {
+    if Format::from_extension(filename) == Some(Format::Sdk) {
+        let f = text
+            .parse::<SdkFile>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let rules = Rules {
+            variants: f.variants,
+            constraints: f.constraints,
+            cages: f.cages,
+            regions: f.regions,
+            units: Vec::new(),
+        };
+        return Ok((f.givens, rules));
     }
+    let s = text
+        .parse::<Sudoku>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    return Ok((s, Rules::default()));
+}
 
-    // Set the value at (rix, cix) with value, and recursively remove that possibility
-    // from all cells in the same row, column, and square.
-    fn set(&mut self, rix: u8, cix: u8, value: u8) {
-        self.rows[(rix - 1) as usize][(cix - 1) as usize] = Cell::Value(value);
-        let (r, c, s) = UnsolvedSudoku::get_subarrays(rix, cix);
-        let subarrays = &[
-            Box::new(r) as Box<SubArray>,
-            Box::new(c) as Box<SubArray>,
-            Box::new(s) as Box<SubArray>,
-        ];
+// The puzzle on line n of the file, or of stdin for "-"; see Sudoku::read_nth.
+fn read_nth_puzzle(filename: &str, n: usize) -> Result<Sudoku, io::Error> {
+    let puzzle = match filename {
+        "-" => Sudoku::read_nth(io::stdin().lock(), n)?,
+        _ => Sudoku::read_nth(File::open(filename)?, n)?,
+    };
+    return puzzle.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("There is no puzzle at index {}.", n),
+        )
+    });
+}
 
-        for ix in 1..10 {
-            for a in subarrays {
-                let (i, j) = a.matrix_index(ix);
-                match self.get_mut(i, j).remove_possibility(value) {
-                    Removal::Fixed(w) => self.set(i, j, w),
-                    _ => continue,
-                }
-            }
-        }
-    }
+fn read_text(filename: &str) -> Result<String, io::Error> {
+    let mut text = String::new();
+    match filename {
+        "-" => io::stdin().lock().read_to_string(&mut text)?,
+        _ => File::open(filename)?.read_to_string(&mut text)?,
+    };
+    return Ok(text);
+}
 
-    pub fn solved(&self) -> bool {
-        for r in &self.rows {
-            for c in r {
-                match c {
-                    Cell::Value(_) => continue,
-                    Cell::Possibilities(_) => return false,
-                }
-            }
-        }
-        return true;
-    }
-
-    fn simple_solve(&mut self) {
-        let mut filled = -1;
-        while filled != 0 {
-            filled = 0;
-            for ix in 1..10 {
-                let subarrays = &[
-                    Box::new(Row { index: ix }) as Box<SubArray>,
-                    Box::new(Column { index: ix }) as Box<SubArray>,
-                    Box::new(Square { index: ix }) as Box<SubArray>,
-                ];
-                for a in subarrays {
-                    let mut locs = [PossibleLocations::None; 9];
-                    for inner in 1..10u8 {
-                        let (rix, cix) = a.matrix_index(inner);
-                        let ps = match self.get(rix, cix) {
-                            &Cell::Value(v) => {
-                                locs[(v - 1) as usize] = PossibleLocations::Filled;
-                                continue;
-                            }
-                            &Cell::Possibilities(ref ps) => ps,
-                        };
-
-                        for p in ps {
-                            locs[(p - 1) as usize] = match locs[(p - 1) as usize] {
-                                PossibleLocations::None => PossibleLocations::Single(rix, cix),
-                                PossibleLocations::Single(..) => PossibleLocations::Many,
-                                _ => continue,
-                            }
-                        }
-                    }
+// The region map in the file, for --regions.
+fn read_regions(filename: &str) -> Result<Regions, io::Error> {
+    return read_text(filename)?
+        .parse::<Regions>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", filename, e)));
+}
 
-                    for (ix, p) in locs.iter().enumerate() {
-                        let v = (ix + 1) as u8;
-                        match p {
-                            &PossibleLocations::Single(rix, cix) => {
-                                self.set(rix, cix, v);
-                                filled += 1;
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
-    }
+// The regions in the file, for --extra-regions.
+fn read_extra_regions(filename: &str) -> Result<Vec<Unit>, io::Error> {
+    return parse_extra_regions(&read_text(filename)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", filename, e)));
+}
 
-    // dynamic_solve applies the rules of simple_solve, and then alternates a "guess and check" expansion approach with application of the simple_solve rules to either find a single solution or return no solution.
-    pub fn dynamic_solve(&mut self) -> Result<Sudoku, Unsolveable> {
-        self.simple_solve();
-        if self.solved() {
-            if self.valid() {
-                return Ok((self as &UnsolvedSudoku).into());
-            }
-            return Err(Unsolveable::NoSolution);
-        };
+fn display_name(filename: &str) -> &str {
+    return match filename {
+        "-" => "<stdin>",
+        f => f,
+    };
+}
 
-        let mut to_expand: Option<(u8, u8, collections::BTreeSet<u8>)> = None;
-        for (rix, r) in self.rows.iter().enumerate() {
-            for (cix, c) in r.iter().enumerate() {
-                let ps = match (c, &to_expand) {
-                    (&Cell::Value(_), _) => continue,
-                    (&Cell::Possibilities(ref ps), None) => ps,
-                    (&Cell::Possibilities(ref ps), Some((_, _, ref other_ps)))
-                        if ps.len() < other_ps.len() =>
-                    {
-                        ps
-                    }
-                    (&Cell::Possibilities(_), Some(_)) => continue,
-                };
-                to_expand = Some(((rix + 1) as u8, (cix + 1) as u8, ps.clone()));
-            }
-        }
+// Display leaves blanks as spaces, which reading the file back would trim off the ends
+// of lines, so write them as '-' like the example puzzles.
+fn print_puzzle(s: &Sudoku) {
+    println!("{}", puzzle_text(s));
+}
 
-        let (rix, cix, ps) = match to_expand {
-            None => return Ok((self as &UnsolvedSudoku).into()),
-            Some(v) => v,
-        };
+fn puzzle_text(s: &Sudoku) -> String {
+    return s.to_string().replace(' ', "-");
+}
 
-        let mut found = None;
-        for p in ps {
-            let mut u2 = self.clone();
-            u2.set(rix, cix, p);
-            let solved = match u2.dynamic_solve() {
-                Err(Unsolveable::MultipleSolutions) => return Err(Unsolveable::MultipleSolutions),
-                Err(Unsolveable::NoSolution) => continue,
-                Ok(s) => s,
-            };
-            found = match found {
-                None => Some(solved),
-                Some(_) => return Err(Unsolveable::MultipleSolutions),
-            }
-        }
+// The 81 cells on one line, with blank for the empty ones.
+fn line_text(s: &Sudoku, blank: char) -> String {
+    return s
+        .cells()
+        .map(|(_, v)| match v {
+            0 => blank,
+            v => char::from(b'0' + v),
+        })
+        .collect();
+}
 
-        match found {
-            None => Err(Unsolveable::NoSolution),
-            Some(s) => Ok(s),
+// Print the name of each file whose puzzle is not equivalent, under the moves
+// canonical_form allows, to the puzzle of a file before it.
+fn print_distinct(files: &[String]) -> Result<(), io::Error> {
+    let mut seen = HashSet::new();
+    for filename in files {
+        let s = read_puzzle(filename)?;
+        if seen.insert(s.canonical_form()) {
+            println!("{}", filename);
         }
     }
+    return Ok(());
+}
 
-    pub fn valid(&self) -> bool {
-        for ix in 1..10 {
-            let subarrays = &[
-                Box::new(Row { index: ix }) as Box<SubArray>,
-                Box::new(Column { index: ix }) as Box<SubArray>,
-                Box::new(Square { index: ix }) as Box<SubArray>,
-            ];
-
-            for s in subarrays {
-                let mut seen = collections::BTreeSet::new();
-                for j in 1..10 {
-                    let (rix, cix) = s.matrix_index(j);
-                    let v = match self.get(rix, cix) {
-                        Cell::Value(n) => n,
-                        _ => continue,
-                    };
-                    if !seen.insert(v) {
-                        // insert returns false if the item is already in the set.
-                        // If this happens, that means we have the same value twice in this subarray.
-                        // That's no good.
-                        return false;
-                    }
-                }
-            }
-        }
-
-        return true;
+// Print a new puzzle, or with --solved a complete grid. Without --seed the seed comes
+// from the clock, so each run differs.
+fn generate(args: GenerateArgs) -> Result<(), io::Error> {
+    if let Some((date, difficulty)) = args.daily {
+        let puzzle = generate_daily(&date, difficulty)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        print_puzzle(&puzzle);
+        return Ok(());
     }
+    let seed = args.seed.unwrap_or_else(clock_seed);
+    if let Some(count) = args.count {
+        let report = match args.out {
+            Some(f) => generate_batch(
+                seed,
+                count,
+                args.options,
+                io::BufWriter::new(File::create(f)?),
+            )?,
+            None => generate_batch(seed, count, args.options, io::stdout().lock())?,
+        };
+        eprintln!("{}", report);
+        return Ok(());
+    }
+    if args.solved {
+        println!("{}", Sudoku::generate_solution(seed));
+    } else {
+        print_puzzle(&Sudoku::generate_puzzle(seed, args.options));
+    }
+    return Ok(());
 }
 
-impl From<Sudoku> for UnsolvedSudoku {
-    fn from(s: Sudoku) -> UnsolvedSudoku {
-        let mut u = UnsolvedSudoku::empty();
-        for (rix, row) in s.rows.iter().enumerate() {
-            for (cix, &v) in row.iter().enumerate() {
-                if v != 0 {
-                    u.set((rix + 1) as u8, (cix + 1) as u8, v);
-                    continue;
-                }
-            }
+// Say whether the grid in the file is complete and whether it breaks the rules, listing
+// where it does, and return the exit status for the outcome, by the same statuses as
+// solve --status-only: a grid that breaks the rules has no solution, and one still
+// incomplete has several unless --solvable solves it to find out.
+fn check(args: CheckArgs) -> Result<i32, io::Error> {
+    let s = match read_puzzle(&args.file) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}: {}", display_name(&args.file), e);
+            return Ok(Status::Unreadable.exit_code());
         }
-        return u;
+    };
+    let violations = s.violations();
+    let (message, status) = match (s.is_complete(), violations.is_empty()) {
+        (true, true) => ("complete and valid", Status::Unique),
+        (true, false) => ("complete but invalid", Status::NoSolution),
+        (false, false) => ("incomplete and contradictory", Status::NoSolution),
+        (false, true) if !args.solvable => ("incomplete but consistent", Status::Multiple),
+        (false, true) => match Status::of(&Some(s.solve())) {
+            Status::Unique => (
+                "incomplete but consistent, with one completion",
+                Status::Unique,
+            ),
+            Status::Multiple => (
+                "incomplete but consistent, with several completions",
+                Status::Multiple,
+            ),
+            status => (
+                "incomplete and consistent, but it can't be completed",
+                status,
+            ),
+        },
+    };
+    println!("{}", message);
+    for v in violations {
+        println!("  {}", v);
     }
+    return Ok(status.exit_code());
 }
 
-impl Into<Sudoku> for &UnsolvedSudoku {
-    fn into(self) -> Sudoku {
-        let mut s = Sudoku::empty();
-        for (rix, row) in self.rows.iter().enumerate() {
-            for (cix, cell) in row.iter().enumerate() {
-                s.rows[rix][cix] = match cell {
-                    &Cell::Value(v) => v,
-                    &Cell::Possibilities(_) => 0,
-                };
-            }
+// Print each puzzle's difficulty, score, hardest technique, and guesses.
+fn rate_files(files: &[String]) -> Result<(), io::Error> {
+    return each_file(files, |filename, out| {
+        let rating = rate(&read_puzzle(filename)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if files.len() > 1 {
+            write!(out, "{}: ", display_name(filename))?;
         }
-        return s;
+        let hardest = match rating.hardest {
+            Some(t) => t.to_string(),
+            None => "nothing".to_string(),
+        };
+        writeln!(
+            out,
+            "{}, score {}, hardest {}, {} guesses",
+            rating.difficulty(),
+            rating.score,
+            hardest,
+            rating.guesses
+        )?;
+        return Ok(());
+    });
+}
+
+// Print every placement naked and hidden singles force.
+fn hint(filename: &str) -> Result<(), io::Error> {
+    let forced = read_puzzle(filename)?.forced_placements();
+    if forced.is_empty() {
+        println!("No cell is forced by a single.");
     }
+    for (row, col, value, technique) in forced {
+        println!("r{}c{} = {} ({})", row, col, value, technique);
+    }
+    return Ok(());
 }
 
-fn main() -> Result<(), io::Error> {
-    let args: Vec<String> = env::args().collect();
-    let filename = match args.as_slice() {
-        &[_, ref f] => f,
-        _ => {
-            println!("Usage: sudokusolver file");
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Needs one input.",
-            ));
+// Print the puzzles of one format in another.
+fn convert(args: ConvertArgs) -> Result<(), io::Error> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+    let puzzles = parse_puzzles(&read_text(&args.file)?, args.from)?;
+    let mut out = io::stdout().lock();
+    match (args.to, puzzles.as_slice()) {
+        (Format::Line, _) | (Format::Sdm, _) => {
+            let blank = if args.to == Format::Line { '.' } else { '0' };
+            for s in &puzzles {
+                writeln!(out, "{}", line_text(s, blank))?;
+            }
         }
-    };
-
-    let f = File::open(filename).expect("file not found");
-
-    let s = Sudoku::from_reader(f)?;
-    let mut u: UnsolvedSudoku = s.into();
-    // u.simple_solve();
-    // let solved: Sudoku = (&u).into();
-
-    match u.dynamic_solve() {
-        Ok(s) => println!("{}", s),
-        Err(Unsolveable::MultipleSolutions) => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "Multiple solutions found.",
-            ));
+        (Format::OpenSudoku, _) => write_opensudoku(&mut out, &puzzles)?,
+        (Format::Grid, [s]) => writeln!(out, "{}", puzzle_text(s))?,
+        (Format::Sdk, [s]) => writeln!(out, "{}", s.to_string().replace(' ', "."))?,
+        (Format::Csv, [s]) => {
+            for row in 1..10 {
+                let cells: Vec<String> = s.row_cells(row).map(|(_, v)| v.to_string()).collect();
+                writeln!(out, "{}", cells.join(","))?;
+            }
         }
-        Err(Unsolveable::NoSolution) => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                "No solution found.",
-            ));
+        (_, _) => {
+            return Err(invalid(format!(
+                "That format holds one puzzle, and the input has {}; try line or sdm.",
+                puzzles.len()
+            )))
         }
     }
-
     return Ok(());
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// The puzzles in text of the given format.
+fn parse_puzzles(text: &str, format: Format) -> Result<Vec<Sudoku>, io::Error> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+    return Ok(match format {
+        Format::Grid | Format::Csv => vec![text.parse().map_err(|e| invalid(format!("{}", e)))?],
+        Format::Line | Format::Sdm => text_lines(text)
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| Sudoku::try_from(l.as_bytes()).map_err(|e| invalid(e.to_string())))
+            .collect::<Result<_, _>>()?,
+        Format::Sdk => vec![
+            text.parse::<SdkFile>()
+                .map_err(|e| invalid(e.to_string()))?
+                .givens,
+        ],
+        Format::OpenSudoku => read_opensudoku(text)?,
+    });
+}
 
-    #[test]
-    fn test_rows() {
-        for i in 1..10 {
-            let r = Row { index: i };
-            for j in 1..10 {
-                let (rix, cix) = r.matrix_index(j);
-                assert_eq!(rix, i);
-                assert_eq!(cix, j);
+#[cfg(feature = "quick-xml")]
+fn read_opensudoku(text: &str) -> Result<Vec<Sudoku>, io::Error> {
+    return sudokusolver::read_opensudoku_xml(text.as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+}
 
-                let (r2, _, _) = UnsolvedSudoku::get_subarrays(i, j);
+#[cfg(feature = "quick-xml")]
+fn write_opensudoku(out: &mut impl Write, puzzles: &[Sudoku]) -> Result<(), io::Error> {
+    return sudokusolver::write_opensudoku_xml(out, puzzles);
+}
 
-                assert_eq!(rix, r2.index);
-            }
+#[cfg(not(feature = "quick-xml"))]
+fn read_opensudoku(_text: &str) -> Result<Vec<Sudoku>, io::Error> {
+    return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "The opensudoku format needs the quick-xml feature.",
+    ));
+}
+
+#[cfg(not(feature = "quick-xml"))]
+fn write_opensudoku(_out: &mut impl Write, _puzzles: &[Sudoku]) -> Result<(), io::Error> {
+    return read_opensudoku("").map(|_| ());
+}
+
+// Print the puzzle with each transform asked for applied, in the order the transform
+// usage gives. Without --seed the seed comes from the clock, as for generate.
+fn transform(args: TransformArgs) -> Result<(), io::Error> {
+    let mut s = read_puzzle(&args.file)?;
+    let mut rng = SolverRng::new(args.seed.unwrap_or_else(clock_seed));
+    if args.shuffle {
+        s = s.shuffle_with_rng(&mut rng);
+    }
+    s = match args.rotate {
+        Some(90) => s.rotate90(),
+        Some(180) => s.rotate180(),
+        Some(270) => s.rotate270(),
+        _ => s,
+    };
+    s = match args.mirror {
+        Some(Mirror::Horizontal) => s.mirror_horizontal(),
+        Some(Mirror::Vertical) => s.mirror_vertical(),
+        None => s,
+    };
+    if args.transpose {
+        s = s.transpose();
+    }
+    s = match args.relabel {
+        Some(Relabel::Random) => {
+            let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+            rng.shuffle(&mut digits);
+            s.relabel(digits)
+        }
+        Some(Relabel::To(digits)) => s.relabel(digits),
+        None => s,
+    };
+    print_puzzle(&s);
+    return Ok(());
+}
+
+// Print a puzzle whose clues are the pattern in the given file, '#' for clues and '.'
+// for blanks, trying random grids until one gives a unique puzzle.
+fn mask(args: MaskArgs) -> Result<(), io::Error> {
+    let MaskArgs {
+        pattern,
+        seed,
+        tries,
+    } = args;
+    let pattern = parse_clue_pattern(&read_text(&pattern)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    match Sudoku::generate_for_pattern(seed.unwrap_or_else(clock_seed), &pattern, tries) {
+        Ok(puzzle) => print_puzzle(&puzzle),
+        Err(best) => {
+            let count = match best.solutions {
+                PATTERN_SOLUTION_LIMIT => format!("at least {}", PATTERN_SOLUTION_LIMIT),
+                n => n.to_string(),
+            };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "No grid in {} tries made the pattern unique; the best had {} solutions.",
+                    tries, count
+                ),
+            ));
         }
     }
+    return Ok(());
+}
 
-    #[test]
-    fn test_squares() {
-        for i in 1..10 {
-            let q = Square { index: i };
-            for j in 1..10 {
-                let (rix, cix) = q.matrix_index(j);
-                let (_, _, q2) = UnsolvedSudoku::get_subarrays(rix, cix);
-                println!(
-                    "Square ({}, {}) -> ({}, {}) -> {}",
-                    i, j, rix, cix, q2.index
-                );
-                assert_eq!(i, q2.index);
-            }
+fn print_status(status: SolutionStatus) {
+    match status {
+        SolutionStatus::Unique => println!("unique solution"),
+        SolutionStatus::Multiple(EDITOR_SOLUTION_LIMIT) => {
+            println!("at least {} solutions", EDITOR_SOLUTION_LIMIT)
         }
+        SolutionStatus::Multiple(n) => println!("{} solutions", n),
+        _ => println!("no solution"),
     }
 }
+
+// A seed from the clock, so each run differs.
+fn clock_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    return now.as_nanos() as u64;
+}
+
+#[cfg(feature = "env_logger")]
+fn init_logging(level: &str) -> Result<(), io::Error> {
+    let filter: log::LevelFilter = level.parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Unknown log level {:?}.", level),
+        )
+    })?;
+    env_logger::Builder::new().filter_level(filter).init();
+    return Ok(());
+}
+
+#[cfg(not(feature = "env_logger"))]
+fn init_logging(_level: &str) -> Result<(), io::Error> {
+    return Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "--log-level needs the env_logger feature.",
+    ));
+}
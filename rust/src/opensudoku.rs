@@ -0,0 +1,136 @@
+// Collections from the OpenSudoku Android app. A collection is an XML document with an
+// <opensudoku> root holding some metadata and a <game> element per puzzle, whose data
+// attribute is the 81 cells in row-major order with 0 for blanks:
+//
+//     <opensudoku>
+//       <name>Easy</name>
+//       <game data="530070000600195000..." />
+//     </opensudoku>
+//
+// Only the grids are kept; other elements and attributes are ignored.
+
+use std::io::{self, BufReader, Write};
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use crate::{Coord, ParseError, Sudoku};
+
+// Every game in the collection, in document order. Badly formed XML, or a failure to
+// read it, is ParseError::Markup; a data attribute that isn't 81 digits reports what is
+// wrong with it.
+pub fn read_opensudoku_xml<R: io::Read>(reader: R) -> Result<Vec<Sudoku>, ParseError> {
+    let mut xml = Reader::from_reader(BufReader::new(reader));
+    let mut buf = Vec::new();
+    let mut games = Vec::new();
+    loop {
+        match xml.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"game" => {
+                let data = match e.try_get_attribute("data") {
+                    Ok(Some(attr)) => attr.unescape_value().map_err(|_| ParseError::Markup)?,
+                    Ok(None) => continue,
+                    Err(_) => return Err(ParseError::Markup),
+                };
+                games.push(parse_data(&data)?);
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(_) => return Err(ParseError::Markup),
+        }
+        buf.clear();
+    }
+    return Ok(games);
+}
+
+// Write the puzzles as a collection OpenSudoku can import, which read_opensudoku_xml
+// reads back as the same puzzles.
+pub fn write_opensudoku_xml<W: Write>(mut writer: W, puzzles: &[Sudoku]) -> io::Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "<opensudoku>")?;
+    for s in puzzles {
+        let data: String = s.cells().map(|(_, v)| char::from(b'0' + v)).collect();
+        writeln!(writer, r#"  <game data="{}" />"#, data)?;
+    }
+    writeln!(writer, "</opensudoku>")?;
+    return Ok(());
+}
+
+fn parse_data(data: &str) -> Result<Sudoku, ParseError> {
+    let data = data.trim();
+    let mut s = Sudoku::empty();
+    let mut cells = data.chars();
+    for c in Coord::all() {
+        s[c] = match cells.next() {
+            Some(ch @ '0'..='9') => ch as u8 - b'0',
+            Some(_) => return Err(ParseError::BadField),
+            None => return Err(ParseError::TooFewCharacters),
+        };
+    }
+    if cells.next().is_some() {
+        return Err(ParseError::TooManyCharacters);
+    }
+    return Ok(s);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_read() {
+        let data: String = moderate()
+            .cells()
+            .map(|(_, v)| char::from(b'0' + v))
+            .collect();
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<opensudoku>
+  <name>Mixed</name>
+  <author>someone</author>
+  <game data="{}" />
+  <game data="{}"></game>
+  <game />
+</opensudoku>"#,
+            data,
+            "0".repeat(81)
+        );
+        assert_eq!(
+            read_opensudoku_xml(xml.as_bytes()),
+            Ok(vec![moderate(), Sudoku::empty()])
+        );
+        assert_eq!(read_opensudoku_xml("<opensudoku/>".as_bytes()), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let puzzles = [moderate(), moderate().solve().unwrap(), Sudoku::empty()];
+        let mut out = Vec::new();
+        write_opensudoku_xml(&mut out, &puzzles).unwrap();
+        assert_eq!(read_opensudoku_xml(out.as_slice()), Ok(puzzles.to_vec()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let game = |data: &str| format!(r#"<opensudoku><game data="{}"/></opensudoku>"#, data);
+        assert_eq!(
+            read_opensudoku_xml(game("123").as_bytes()),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(
+            read_opensudoku_xml(game(&"0".repeat(82)).as_bytes()),
+            Err(ParseError::TooManyCharacters)
+        );
+        assert_eq!(
+            read_opensudoku_xml(game(&".".repeat(81)).as_bytes()),
+            Err(ParseError::BadField)
+        );
+        assert_eq!(
+            read_opensudoku_xml(r#"<opensudoku><game data="1></opensudoku>"#.as_bytes()),
+            Err(ParseError::Markup)
+        );
+    }
+}
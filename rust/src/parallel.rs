@@ -0,0 +1,150 @@
+// Searching one puzzle's guess tree on several threads at once, for puzzles hard enough
+// that a single search takes a noticeable time.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+
+use crate::{Sudoku, Unsolveable, UnsolvedSudoku};
+
+// Guesses this deep or deeper are explored one after another on the current thread.
+// The top few levels already give rayon far more branches than it has threads, and
+// splitting further down only adds overhead.
+const PARALLEL_DEPTH: u32 = 3;
+
+// What the branches share: the solutions found so far, and whether the search is over.
+struct Shared {
+    found: Mutex<Vec<Sudoku>>,
+    done: AtomicBool,
+}
+
+impl Shared {
+    fn add(&self, s: Sudoku) {
+        let mut found = self.found.lock().unwrap();
+        if found.len() < 2 {
+            found.push(s);
+        }
+        // Two solutions settle the answer, so every other branch can stop.
+        if found.len() >= 2 {
+            self.done.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl UnsolvedSudoku {
+    // Like dynamic_solve, but exploring the branches of each guess in parallel, and
+    // leaving self untouched. With several solutions, which two are returned depends on
+    // which branches finish first, so it may not be the pair dynamic_solve would give.
+    pub fn dynamic_solve_parallel(&self) -> Result<Sudoku, Unsolveable> {
+        let shared = Shared {
+            found: Mutex::new(Vec::new()),
+            done: AtomicBool::new(false),
+        };
+        explore(self.clone(), 0, &shared);
+        let mut found = shared.found.into_inner().unwrap();
+        return match found.len() {
+            0 => Err(Unsolveable::NoSolution),
+            1 => Ok(found[0]),
+            _ => {
+                let second = found.pop().unwrap();
+                let first = found.pop().unwrap();
+                Err(Unsolveable::MultipleSolutions(
+                    Box::new(first),
+                    Box::new(second),
+                ))
+            }
+        };
+    }
+}
+
+fn explore(mut u: UnsolvedSudoku, depth: u32, shared: &Shared) {
+    // As in dynamic_solve, a conflict can only be in the starting grid.
+    if shared.done.load(Ordering::Relaxed) || (depth == 0 && !u.valid()) {
+        return;
+    }
+    u.simple_solve();
+    if u.is_solved() {
+        if u.valid() {
+            shared.add(u.finished());
+        }
+        return;
+    }
+
+    let (coord, ps) = match u.min_candidates_cell() {
+        None => {
+            shared.add(u.finished());
+            return;
+        }
+        Some(v) => v,
+    };
+    let branch = |p: u8| {
+        let mut u2 = u.clone();
+        u2.set(coord.row(), coord.col(), p);
+        explore(u2, depth + 1, shared);
+    };
+    if depth < PARALLEL_DEPTH {
+        let ps: Vec<u8> = ps.iter().collect();
+        ps.into_par_iter().for_each(branch);
+    } else {
+        ps.iter().for_each(branch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coord;
+
+    fn parse(text: &str) -> Sudoku {
+        return text.parse::<Sudoku>().unwrap();
+    }
+
+    #[test]
+    fn test_matches_sequential() {
+        for text in [
+            include_str!("../../moderate.txt"),
+            include_str!("../../challenge.txt"),
+        ] {
+            let u = UnsolvedSudoku::from(parse(text));
+            let before = u.clone();
+            assert_eq!(u.dynamic_solve_parallel(), u.solved());
+            assert_eq!(u, before);
+        }
+    }
+
+    #[test]
+    fn test_multiple_and_none() {
+        let mut s = parse(include_str!("../../moderate.txt"));
+        s.clear(Coord::new(9, 5).unwrap());
+        let all: Vec<Sudoku> = UnsolvedSudoku::from(s).solutions().collect();
+        match UnsolvedSudoku::from(s).dynamic_solve_parallel() {
+            Err(Unsolveable::MultipleSolutions(a, b)) => {
+                assert_ne!(a, b);
+                assert!(all.contains(&a) && all.contains(&b));
+            }
+            other => panic!("expected two solutions, got {:?}", other),
+        }
+
+        // An empty grid has far too many solutions to enumerate; the search must stop
+        // as soon as it has two.
+        assert!(matches!(
+            UnsolvedSudoku::empty().dynamic_solve_parallel(),
+            Err(Unsolveable::MultipleSolutions(..))
+        ));
+
+        // Row 1 already has a 9 at (1, 8).
+        let broken = include_str!("../../moderate.txt").replacen('-', "9", 1);
+        let u = UnsolvedSudoku::from(parse(&broken));
+        assert_eq!(u.dynamic_solve_parallel(), Err(Unsolveable::NoSolution));
+
+        let mut s = Sudoku::empty();
+        for col in 1..10 {
+            s.set(Coord::new(2, col).unwrap(), 2).unwrap();
+        }
+        assert_eq!(
+            UnsolvedSudoku::from(s).dynamic_solve_parallel(),
+            Err(Unsolveable::NoSolution)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// Puzzles whose clues form a picture. A pattern says which cells are clues; masking a
+// complete grid with it keeps those cells and blanks the rest. Whether the result has a
+// unique solution depends on the grid as well as the pattern, so a designer can try
+// grids until one works, or see how far off a pattern is and adjust it.
+
+use crate::{text_lines, Coord, ParseError, Seed, SolverRng, Sudoku};
+
+// Which cells are clues, by row and then column.
+pub type CluePattern = [[bool; 9]; 9];
+
+// Counting stops here when reporting how ambiguous a masked puzzle is.
+pub const PATTERN_SOLUTION_LIMIT: usize = 100;
+
+// A masked puzzle and how many solutions it has, counting no further than the limit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MaskReport {
+    pub puzzle: Sudoku,
+    pub solutions: usize,
+}
+
+impl MaskReport {
+    pub fn is_unique(&self) -> bool {
+        return self.solutions == 1;
+    }
+}
+
+// Nine lines of nine cells, '#' for a clue and '.' for a blank. Spaces within a line
+// and blank lines are skipped, so patterns can be spaced out to read more easily.
+pub fn parse_clue_pattern(text: &str) -> Result<CluePattern, ParseError> {
+    let mut pattern = [[false; 9]; 9];
+    let mut rix = 0;
+    for line in text_lines(text).map(str::trim).filter(|l| !l.is_empty()) {
+        if rix >= 9 {
+            return Err(ParseError::TooManyCharacters);
+        }
+        let mut cix = 0;
+        for ch in line.chars().filter(|ch| !ch.is_whitespace()) {
+            if cix >= 9 {
+                return Err(ParseError::TooManyCharacters);
+            }
+            pattern[rix][cix] = match ch {
+                '#' => true,
+                '.' => false,
+                _ => return Err(ParseError::BadField),
+            };
+            cix += 1;
+        }
+        if cix < 9 {
+            return Err(ParseError::TooFewCharacters);
+        }
+        rix += 1;
+    }
+    if rix < 9 {
+        return Err(ParseError::TooFewRows);
+    }
+    return Ok(pattern);
+}
+
+// The solution's digits where the pattern has clues, and blanks elsewhere.
+pub fn apply_clue_pattern(solution: &Sudoku, pattern: &CluePattern) -> Sudoku {
+    let mut puzzle = *solution;
+    for c in Coord::all() {
+        let (rix, cix) = c.index();
+        if !pattern[rix][cix] {
+            puzzle.clear(c);
+        }
+    }
+    return puzzle;
+}
+
+// Mask the solution and count the puzzle's solutions, up to PATTERN_SOLUTION_LIMIT.
+pub fn check_clue_pattern(solution: &Sudoku, pattern: &CluePattern) -> MaskReport {
+    let puzzle = apply_clue_pattern(solution, pattern);
+    return MaskReport {
+        puzzle,
+        solutions: puzzle.count_solutions(PATTERN_SOLUTION_LIMIT),
+    };
+}
+
+impl Sudoku {
+    // A puzzle with exactly one solution whose clues are the pattern, from masking
+    // random grids drawn from the seed until one works. After `tries` grids without
+    // success, returns the least ambiguous attempt instead, or the empty pattern's
+    // report if tries is 0.
+    pub fn generate_for_pattern(
+        seed: impl Into<Seed>,
+        pattern: &CluePattern,
+        tries: u32,
+    ) -> Result<Sudoku, MaskReport> {
+        let mut rng = SolverRng::new(seed);
+        let mut best: Option<MaskReport> = None;
+        for _ in 0..tries {
+            let report = check_clue_pattern(&Sudoku::generate_solution_with_rng(&mut rng), pattern);
+            if report.is_unique() {
+                return Ok(report.puzzle);
+            }
+            if best.is_none_or(|b| report.solutions < b.solutions) {
+                best = Some(report);
+            }
+        }
+        return Err(best.unwrap_or_else(|| check_clue_pattern(&Sudoku::empty(), pattern)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every cell but the main diagonal. Each blank is the only one in its row, so it is
+    // forced whatever the grid.
+    fn dense() -> CluePattern {
+        let mut pattern = [[true; 9]; 9];
+        for (i, row) in pattern.iter_mut().enumerate() {
+            row[i] = false;
+        }
+        return pattern;
+    }
+
+    const HEART: &str = "
+        . # # . . . # # .
+        # . . # . # . . #
+        # . . . # . . . #
+        . # . . . . . # .
+        . . # . . . # . .
+        . . . # . # . . .
+        . . . . # . . . .
+        . . . . . . . . .
+        . . . . . . . . .
+    ";
+
+    #[test]
+    fn test_parse() {
+        let heart = parse_clue_pattern(HEART).unwrap();
+        assert_eq!(heart.iter().flatten().filter(|&&b| b).count(), 18);
+        assert!(heart[0][1] && !heart[0][0] && heart[6][4]);
+
+        assert_eq!(
+            parse_clue_pattern(&"#########\n".repeat(8)),
+            Err(ParseError::TooFewRows)
+        );
+        assert_eq!(
+            parse_clue_pattern(&"#########\n".repeat(10)),
+            Err(ParseError::TooManyCharacters)
+        );
+        assert_eq!(
+            parse_clue_pattern(&"########\n".repeat(9)),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(
+            parse_clue_pattern(&"####x####\n".repeat(9)),
+            Err(ParseError::BadField)
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let solution = Sudoku::generate_solution(0);
+        let puzzle = apply_clue_pattern(&solution, &dense());
+        assert_eq!(puzzle.clue_count(), 72);
+        assert!(puzzle.is_empty(Coord::new(4, 4).unwrap()));
+        assert_eq!(
+            puzzle.get(Coord::new(4, 5).unwrap()),
+            solution.get(Coord::new(4, 5).unwrap())
+        );
+        assert_eq!(apply_clue_pattern(&solution, &[[true; 9]; 9]), solution);
+        assert_eq!(
+            apply_clue_pattern(&solution, &[[false; 9]; 9]),
+            Sudoku::empty()
+        );
+    }
+
+    #[test]
+    fn test_dense_always_unique() {
+        for seed in 0..5 {
+            let report = check_clue_pattern(&Sudoku::generate_solution(seed), &dense());
+            assert!(report.is_unique());
+        }
+        let puzzle = Sudoku::generate_for_pattern(3, &dense(), 1).unwrap();
+        assert_eq!(puzzle.count_solutions(2), 1);
+    }
+
+    #[test]
+    fn test_sparse_never_unique() {
+        // Eighteen clues can be enough, but not in this pattern: rows 8 and 9 share a
+        // band and have no clues, so swapping them in any solution gives another.
+        let heart = parse_clue_pattern(HEART).unwrap();
+        let report = Sudoku::generate_for_pattern(3, &heart, 3).unwrap_err();
+        assert!(report.solutions >= 2);
+        assert!(report.solutions <= PATTERN_SOLUTION_LIMIT);
+        assert_eq!(report.puzzle.clue_count(), 18);
+
+        let report = Sudoku::generate_for_pattern(3, &heart, 0).unwrap_err();
+        assert_eq!(report.puzzle, Sudoku::empty());
+    }
+}
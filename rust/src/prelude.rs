@@ -0,0 +1,11 @@
+// The types most programs need, for a single glob import:
+//
+//     use sudokusolver::prelude::*;
+//
+// Everything here is also exported from the crate root. Only stable, public types go in
+// the prelude; helpers that may change shape stay out of it.
+
+pub use crate::{
+    CandidateSet, Cell, Conflict, Coord, GridError, ParseError, SolveError, SolveReport, Solver,
+    SolverConfig, Sudoku, Unit, Unsolveable, UnsolvedSudoku,
+};
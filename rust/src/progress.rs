@@ -0,0 +1,80 @@
+// The spinner solve --progress shows on stderr during a long search, so a hard puzzle
+// doesn't look like a hung program. It only draws to a terminal, and only once a solve
+// has run for a moment, so quick solves and piped or redirected output never see it.
+// Everything it draws is on one line that it wipes before the result is printed.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+// How long a solve runs before the spinner appears, and then how often it redraws.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct Spinner {
+    enabled: bool,
+    last: Instant,
+    frame: usize,
+    drawn: bool,
+}
+
+impl Spinner {
+    // A spinner that draws only if asked to and stderr is a terminal.
+    pub fn new(requested: bool) -> Spinner {
+        return Spinner {
+            enabled: requested && io::stderr().is_terminal(),
+            last: Instant::now(),
+            frame: 0,
+            drawn: false,
+        };
+    }
+
+    // Called after each guess with the guess count.
+    pub fn tick(&mut self, guesses: u64) {
+        if !self.enabled || self.last.elapsed() < INTERVAL {
+            return;
+        }
+        self.last = Instant::now();
+        eprint!("\r{}", line(self.frame, guesses));
+        let _ = io::stderr().flush();
+        self.frame += 1;
+        self.drawn = true;
+    }
+
+    // Wipe the spinner's line, if it drew one.
+    pub fn clear(&mut self) {
+        if self.drawn {
+            eprint!("\r\x1b[K");
+            let _ = io::stderr().flush();
+            self.drawn = false;
+        }
+    }
+}
+
+fn line(frame: usize, guesses: u64) -> String {
+    return format!(
+        "{} solving, guesses: {}",
+        FRAMES[frame % FRAMES.len()],
+        guesses
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(line(0, 12), "| solving, guesses: 12");
+        assert_eq!(line(5, 3000), "/ solving, guesses: 3000");
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut spinner = Spinner::new(false);
+        spinner.last -= INTERVAL;
+        spinner.tick(1);
+        assert!(!spinner.drawn);
+        spinner.clear();
+    }
+}
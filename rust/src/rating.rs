@@ -0,0 +1,386 @@
+// Rating how hard a puzzle is for a person, by which deductions solve it. The rater
+// works like someone solving by hand: it always uses the easiest technique that makes
+// progress, and only reaches for a harder one when the easier ones are stuck. Anything
+// the techniques here can't crack is finished by guessing.
+
+use core::fmt;
+
+use crate::{Strategy, Sudoku, Unsolveable, UnsolvedSudoku};
+
+// The deductions the rater knows, easiest first, in the order of their weights.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum Technique {
+    // A cell with only one candidate left.
+    NakedSingle,
+    // A value with only one place left in a row, column, or box.
+    HiddenSingle,
+    // UnsolvedSudoku::eliminate_pointing and eliminate_naked_pairs.
+    Pointing,
+    NakedPair,
+    // The fish patterns of UnsolvedSudoku::eliminate_fish, of size 2, 3, and 4, with
+    // UnsolvedSudoku::eliminate_xyz_wing between the swordfish and the jellyfish.
+    XWing,
+    Swordfish,
+    XyzWing,
+    Jellyfish,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 8] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::Pointing,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::Swordfish,
+        Technique::XyzWing,
+        Technique::Jellyfish,
+    ];
+
+    // Points each use adds to the score. A single is routine, and pointing and pairs
+    // only need a unit looked over; a fish or a wing takes some searching, and more the
+    // more cells it spans.
+    pub fn weight(self) -> u32 {
+        return match self {
+            Technique::NakedSingle => 1,
+            Technique::HiddenSingle => 2,
+            Technique::Pointing => 6,
+            Technique::NakedPair => 8,
+            Technique::XWing => 20,
+            Technique::Swordfish => 40,
+            Technique::XyzWing => 50,
+            Technique::Jellyfish => 60,
+        };
+    }
+
+    // The technique's name as it would appear in a hint.
+    pub fn name(self) -> &'static str {
+        return match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::Pointing => "pointing",
+            Technique::NakedPair => "naked pair",
+            Technique::XWing => "X-wing",
+            Technique::Swordfish => "swordfish",
+            Technique::XyzWing => "XYZ-wing",
+            Technique::Jellyfish => "jellyfish",
+        };
+    }
+
+    fn index(self) -> usize {
+        return self as usize;
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.name());
+    }
+}
+
+// Points each guess adds to the score, more than any single deduction.
+pub const GUESS_WEIGHT: u32 = 100;
+
+// A coarse band for a rating.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum Difficulty {
+    // Singles are enough.
+    Easy,
+    // Needs pointing or a naked pair, or singles are enough but only with many hidden
+    // singles, which are harder to spot.
+    Medium,
+    // Needs a fish or an XYZ-wing.
+    Hard,
+    // Needs guessing.
+    Expert,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+// Hidden singles beyond this many make a singles-only puzzle Medium.
+const EASY_HIDDEN_SINGLES: u32 = 20;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Rating {
+    // The hardest technique used, or None if the givens alone fill the grid.
+    pub hardest: Option<Technique>,
+    // Guesses the search made once the techniques were stuck; 0 if they never were.
+    pub guesses: u64,
+    // The sum over techniques of uses times weight, plus GUESS_WEIGHT per guess.
+    pub score: u64,
+    uses: [u32; 8],
+}
+
+impl Rating {
+    // How many times the technique made progress: cells filled for singles, and passes
+    // that removed candidates for the rest.
+    pub fn uses(&self, technique: Technique) -> u32 {
+        return self.uses[technique.index()];
+    }
+
+    // Every deduction counted by uses, plus every guess: how many steps the solve took.
+    pub fn steps(&self) -> u64 {
+        return self.uses.iter().map(|&n| n as u64).sum::<u64>() + self.guesses;
+    }
+
+    pub fn needed_guessing(&self) -> bool {
+        return self.guesses > 0;
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        if self.needed_guessing() {
+            return Difficulty::Expert;
+        }
+        return match self.hardest {
+            Some(t) if t >= Technique::XWing => Difficulty::Hard,
+            Some(t) if t >= Technique::Pointing => Difficulty::Medium,
+            _ if self.uses(Technique::HiddenSingle) > EASY_HIDDEN_SINGLES => Difficulty::Medium,
+            _ => Difficulty::Easy,
+        };
+    }
+
+    fn record(&mut self, technique: Technique, uses: usize) {
+        if uses == 0 {
+            return;
+        }
+        self.uses[technique.index()] += uses as u32;
+        self.hardest = self.hardest.max(Some(technique));
+    }
+}
+
+// Rate a puzzle with exactly one solution; any other puzzle is refused with the reason.
+pub fn rate(puzzle: &Sudoku) -> Result<Rating, Unsolveable> {
+    puzzle.solve()?;
+    let mut rating = Rating {
+        hardest: None,
+        guesses: 0,
+        score: 0,
+        uses: [0; 8],
+    };
+    let mut u = UnsolvedSudoku::from(*puzzle);
+    rating.record(
+        Technique::NakedSingle,
+        u.solved_count() - puzzle.clue_count(),
+    );
+
+    'deduce: while !u.is_solved() {
+        let before = u.solved_count();
+        let hidden = u.hidden_singles();
+        if hidden > 0 {
+            rating.record(Technique::HiddenSingle, hidden);
+            // Each placement can fix more cells as naked singles.
+            let naked = (u.solved_count() - before).saturating_sub(hidden);
+            rating.record(Technique::NakedSingle, naked);
+            continue;
+        }
+        // Past the singles, the eliminations in order, easiest first.
+        for technique in &Technique::ALL[2..] {
+            if technique.apply(&mut u) > 0 {
+                rating.record(*technique, 1);
+                rating.record(Technique::NakedSingle, u.solved_count() - before);
+                continue 'deduce;
+            }
+        }
+        rating.guesses = u.budgeted_solve(u64::MAX).1;
+        break;
+    }
+
+    rating.score = Technique::ALL
+        .iter()
+        .map(|&t| rating.uses(t) as u64 * t.weight() as u64)
+        .sum::<u64>()
+        + rating.guesses * GUESS_WEIGHT as u64;
+    return Ok(rating);
+}
+
+impl Sudoku {
+    // How many deductions and guesses it takes to solve the puzzle, as Rating::steps,
+    // or None unless it has exactly one solution. A finer measure of difficulty than
+    // the rating's band.
+    pub fn solve_path_length(&self) -> Option<usize> {
+        return rate(self).ok().map(|r| r.steps() as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coord;
+
+    // One row per line of nine, 0 for blanks.
+    fn grid(rows: [&str; 9]) -> Sudoku {
+        return rows.join("\n").parse().unwrap();
+    }
+
+    // The example puzzle from Wikipedia's Sudoku article, of the kind newspapers print
+    // as easy.
+    fn easy() -> Sudoku {
+        return grid([
+            "530070000",
+            "600195000",
+            "098000060",
+            "800060003",
+            "400803001",
+            "700020006",
+            "060000280",
+            "000419005",
+            "000080079",
+        ]);
+    }
+
+    // Singles stall partway, and pointing once gets them going again.
+    fn pointing() -> Sudoku {
+        return grid([
+            "000700900",
+            "800050001",
+            "060402000",
+            "000020006",
+            "001000850",
+            "007006003",
+            "000603000",
+            "500000008",
+            "009000300",
+        ]);
+    }
+
+    // Singles stall twice more after pointing and a naked pair, and an X-wing gets them
+    // going again.
+    fn x_wing() -> Sudoku {
+        return grid([
+            "020060087",
+            "006120009",
+            "007003000",
+            "600000745",
+            "030000000",
+            "002005000",
+            "000704096",
+            "018000002",
+            "000000050",
+        ]);
+    }
+
+    // A 17-clue puzzle, the fewest a unique puzzle can have, that singles barely dent.
+    fn seventeen() -> Sudoku {
+        return grid([
+            "000000014",
+            "000000203",
+            "800050000",
+            "000207000",
+            "031000000",
+            "000000650",
+            "600000700",
+            "000140000",
+            "000300000",
+        ]);
+    }
+
+    fn expected_score(r: &Rating) -> u64 {
+        let deductions: u64 = Technique::ALL
+            .iter()
+            .map(|&t| (r.uses(t) * t.weight()) as u64)
+            .sum();
+        return deductions + r.guesses * GUESS_WEIGHT as u64;
+    }
+
+    #[test]
+    fn test_reference_bands() {
+        let r = rate(&easy()).unwrap();
+        assert_eq!(r.difficulty(), Difficulty::Easy, "{:?}", r);
+        assert!(r.hardest <= Some(Technique::HiddenSingle));
+        assert!(!r.needed_guessing());
+
+        let r = rate(&pointing()).unwrap();
+        assert_eq!(r.difficulty(), Difficulty::Medium, "{:?}", r);
+        assert_eq!(r.hardest, Some(Technique::Pointing));
+        assert_eq!(r.uses(Technique::Pointing), 1);
+        assert!(!r.needed_guessing());
+
+        let r = rate(&x_wing()).unwrap();
+        assert_eq!(r.difficulty(), Difficulty::Hard, "{:?}", r);
+        assert_eq!(r.hardest, Some(Technique::XWing));
+        for t in [Technique::Pointing, Technique::NakedPair, Technique::XWing] {
+            assert_eq!(r.uses(t), 1, "{}", t);
+        }
+        assert!(!r.needed_guessing());
+
+        let puzzle = seventeen();
+        assert_eq!(puzzle.clue_count(), 17);
+        let r = rate(&puzzle).unwrap();
+        assert_eq!(r.difficulty(), Difficulty::Expert, "{:?}", r);
+        assert!(r.needed_guessing());
+
+        let scores: Vec<u64> = [easy(), pointing(), x_wing(), seventeen()]
+            .iter()
+            .map(|p| rate(p).unwrap().score)
+            .collect();
+        assert!(scores.windows(2).all(|w| w[0] < w[1]), "{:?}", scores);
+    }
+
+    #[test]
+    fn test_counts() {
+        for puzzle in [easy(), pointing(), x_wing(), seventeen()] {
+            let r = rate(&puzzle).unwrap();
+            assert_eq!(r.score, expected_score(&r));
+            // Without guessing, every blank was filled by some single.
+            if !r.needed_guessing() {
+                let singles = r.uses(Technique::NakedSingle) + r.uses(Technique::HiddenSingle);
+                assert_eq!(singles as usize, puzzle.empty_count(), "{:?}", r);
+            }
+        }
+
+        let solution = easy().solve().unwrap();
+        let r = rate(&solution).unwrap();
+        assert_eq!((r.hardest, r.score), (None, 0));
+        assert_eq!(r.difficulty(), Difficulty::Easy);
+        // One blank is always a naked single.
+        let mut one = solution;
+        one.clear(Coord::new(5, 5).unwrap());
+        let r = rate(&one).unwrap();
+        assert_eq!(r.hardest, Some(Technique::NakedSingle));
+        assert_eq!(r.uses(Technique::NakedSingle), 1);
+    }
+
+    #[test]
+    fn test_path_length() {
+        // Singles fill each blank once.
+        assert_eq!(easy().solve_path_length(), Some(easy().empty_count()));
+        // The pointing, the naked pair, and the X-wing are a step each.
+        assert_eq!(
+            x_wing().solve_path_length(),
+            Some(x_wing().empty_count() + 3)
+        );
+        let r = rate(&seventeen()).unwrap();
+        assert_eq!(seventeen().solve_path_length(), Some(r.steps() as usize));
+        assert!(r.steps() > r.guesses);
+        assert_eq!(easy().solve().unwrap().solve_path_length(), Some(0));
+
+        let mut ambiguous: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        ambiguous.clear(Coord::new(9, 5).unwrap());
+        assert_eq!(ambiguous.solve_path_length(), None);
+    }
+
+    #[test]
+    fn test_refuses() {
+        let mut ambiguous: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        ambiguous.clear(Coord::new(9, 5).unwrap());
+        assert!(matches!(
+            rate(&ambiguous),
+            Err(Unsolveable::MultipleSolutions(..))
+        ));
+        let broken = include_str!("../../moderate.txt").replacen('-', "9", 1);
+        assert_eq!(rate(&broken.parse().unwrap()), Err(Unsolveable::NoSolution));
+    }
+}
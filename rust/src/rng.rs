@@ -0,0 +1,97 @@
+// The one source of randomness in the crate, so nothing needs a random number crate and
+// every random choice can be replayed from its seed.
+//
+// SolverRng is splitmix64 (Steele, Lea, and Flood, "Fast splittable pseudorandom number
+// generators", 2014): the state steps by a fixed odd constant, and each output is that
+// state put through a mixing function. It is tiny, accepts any seed including 0, and is
+// plenty random enough for shuffling cells and candidates, though not for anything
+// that needs to be unpredictable.
+//
+// The output for a given seed is part of the crate's interface. It uses only wrapping
+// 64-bit arithmetic, so it is the same on every platform, and the golden values in the
+// tests below must not change between releases: a seed that makes some puzzle today
+// should make the same puzzle with every later version.
+
+// A seed for SolverRng. Any u64 will do.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Seed(pub u64);
+
+impl From<u64> for Seed {
+    fn from(seed: u64) -> Seed {
+        return Seed(seed);
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SolverRng {
+    state: u64,
+}
+
+impl SolverRng {
+    pub fn new(seed: impl Into<Seed>) -> SolverRng {
+        return SolverRng {
+            state: seed.into().0,
+        };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+
+    // A number in 0..n, by scaling a 64-bit output rather than taking a remainder. The
+    // bias for n that doesn't divide 2^64 is far too small to matter here. Panics if n
+    // is 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "below: empty range");
+        return ((self.next_u64() as u128 * n as u128) >> 64) as usize;
+    }
+
+    // Put items in a random order (Fisher-Yates), every order equally likely.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_outputs() {
+        let mut rng = SolverRng::new(0);
+        assert_eq!(
+            [rng.next_u64(), rng.next_u64(), rng.next_u64()],
+            [
+                0xE220_A839_7B1D_CDAF,
+                0x6E78_9E6A_A1B9_65F4,
+                0x06C4_5D18_8009_454F
+            ]
+        );
+        let mut rng = SolverRng::new(Seed(12345));
+        let below: Vec<usize> = (0..8).map(|_| rng.below(10)).collect();
+        assert_eq!(below, vec![1, 2, 1, 1, 5, 3, 1, 4]);
+        let mut items = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        SolverRng::new(1).shuffle(&mut items);
+        assert_eq!(items, [1, 2, 5, 4, 8, 3, 7, 9, 6]);
+    }
+
+    #[test]
+    fn test_seeds() {
+        assert_eq!(SolverRng::new(7), SolverRng::new(Seed(7)));
+        let a: Vec<u64> = (0..4).map(|s| SolverRng::new(s).next_u64()).collect();
+        let mut b = a.clone();
+        b.sort();
+        b.dedup();
+        assert_eq!(a.len(), b.len());
+        for n in [1, 2, 3, 81] {
+            let mut rng = SolverRng::new(n as u64);
+            assert!((0..100).all(|_| rng.below(n) < n));
+        }
+    }
+}
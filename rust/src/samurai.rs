@@ -0,0 +1,379 @@
+// Samurai sudoku: five classic grids overlapping in a 21x21 square, one in each corner
+// and one in the middle. Each corner box of the middle grid is also the inner corner
+// box of a corner grid: rows and columns 7-9 of the top-left grid, for one.
+//
+// A shared box is one set of nine cells in both grids' units, so 369 cells make up the
+// five grids and a value placed in an overlap counts in both. The text form is 21
+// lines of 21 characters, with digits 1-9 for clues and '.', '0', '-', 'x', or a space
+// for blanks. The four gaps between the corner grids, columns 10-12 of rows 1-6 and
+// 16-21 and columns 1-6 and 16-21 of rows 10-12, hold no cells and are written as
+// spaces.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::shape::solve_units;
+use crate::{text_lines, Coord, ParseError, Sudoku};
+
+// The square is this many cells on a side.
+const SIDE: usize = 21;
+// Cells on the five grids, each shared one counted once.
+const CELLS: usize = 369;
+
+// One of the five grids.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum SamuraiGrid {
+    TopLeft,
+    TopRight,
+    Center,
+    BottomLeft,
+    BottomRight,
+}
+
+impl SamuraiGrid {
+    pub const ALL: [SamuraiGrid; 5] = [
+        SamuraiGrid::TopLeft,
+        SamuraiGrid::TopRight,
+        SamuraiGrid::Center,
+        SamuraiGrid::BottomLeft,
+        SamuraiGrid::BottomRight,
+    ];
+
+    // The 1-based row and column in the square of the grid's top-left cell.
+    pub fn origin(self) -> (u8, u8) {
+        return match self {
+            SamuraiGrid::TopLeft => (1, 1),
+            SamuraiGrid::TopRight => (1, 13),
+            SamuraiGrid::Center => (7, 7),
+            SamuraiGrid::BottomLeft => (13, 1),
+            SamuraiGrid::BottomRight => (13, 13),
+        };
+    }
+
+    fn contains(self, r: usize, c: usize) -> bool {
+        let (row, col) = self.origin();
+        let (row, col) = (row as usize - 1, col as usize - 1);
+        return (row..row + 9).contains(&r) && (col..col + 9).contains(&c);
+    }
+}
+
+// Why Samurai::solve failed: the counterpart of Unsolveable for samurai grids.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SamuraiUnsolveable {
+    // Two of the solutions, which differ in at least one cell.
+    MultipleSolutions(Box<Samurai>, Box<Samurai>),
+    NoSolution,
+}
+
+impl fmt::Display for SamuraiUnsolveable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SamuraiUnsolveable::MultipleSolutions(..) => {
+                write!(f, "puzzle has multiple solutions")
+            }
+            SamuraiUnsolveable::NoSolution => write!(f, "puzzle has no solution"),
+        }
+    }
+}
+
+impl core::error::Error for SamuraiUnsolveable {}
+
+// The five grids' cells, with 0 for blanks.
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Samurai {
+    // Row-major over the square, skipping the gaps.
+    cells: [u8; CELLS],
+}
+
+// Each position of the square, 0-based, with the index in Samurai::cells of its cell,
+// or None in the gaps.
+fn cell_indices() -> [[Option<usize>; SIDE]; SIDE] {
+    let mut indices = [[None; SIDE]; SIDE];
+    let mut next = 0;
+    for (r, row) in indices.iter_mut().enumerate() {
+        for (c, index) in row.iter_mut().enumerate() {
+            if SamuraiGrid::ALL.iter().any(|g| g.contains(r, c)) {
+                *index = Some(next);
+                next += 1;
+            }
+        }
+    }
+    return indices;
+}
+
+// Every row, column, and box of the five grids as indices into Samurai::cells. The
+// corner boxes of the center grid are listed once, with the corner grid's units.
+fn units() -> Vec<Vec<usize>> {
+    let indices = cell_indices();
+    let mut units: Vec<Vec<usize>> = Vec::new();
+    for grid in SamuraiGrid::ALL {
+        let (row, col) = grid.origin();
+        let cell = |c: Coord| {
+            let (r, c) = c.index();
+            return indices[r + row as usize - 1][c + col as usize - 1].unwrap();
+        };
+        for ix in 1..10 {
+            for unit in [
+                crate::Unit::Row(ix),
+                crate::Unit::Column(ix),
+                crate::Unit::Square(ix),
+            ] {
+                let cells: Vec<usize> = unit.coords().iter().map(|&c| cell(c)).collect();
+                if !units.contains(&cells) {
+                    units.push(cells);
+                }
+            }
+        }
+    }
+    return units;
+}
+
+impl Samurai {
+    pub fn empty() -> Samurai {
+        return Samurai { cells: [0; CELLS] };
+    }
+
+    // The value at 1-based (row, col) of the square, 0 for a blank, or None in the gaps
+    // or outside the square.
+    pub fn get(&self, row: u8, col: u8) -> Option<u8> {
+        if !(1..=SIDE as u8).contains(&row) || !(1..=SIDE as u8).contains(&col) {
+            return None;
+        }
+        let index = cell_indices()[row as usize - 1][col as usize - 1]?;
+        return Some(self.cells[index]);
+    }
+
+    // One of the five grids as a classic puzzle of its own.
+    pub fn grid(&self, which: SamuraiGrid) -> Sudoku {
+        let (row, col) = which.origin();
+        let mut s = Sudoku::empty();
+        for c in Coord::all() {
+            s[c] = self.get(row + c.row() - 1, col + c.col() - 1).unwrap();
+        }
+        return s;
+    }
+
+    pub fn clue_count(&self) -> usize {
+        return self.cells.iter().filter(|&&v| v != 0).count();
+    }
+
+    // Find the unique solution, filling every grid at once, as SizedSudoku::solve does
+    // with the units of all five.
+    pub fn solve(&self) -> Result<Samurai, SamuraiUnsolveable> {
+        let mut found = solve_units(9, &units(), &self.cells)
+            .into_iter()
+            .map(|cells| Samurai {
+                cells: cells.try_into().unwrap(),
+            });
+        return match (found.next(), found.next()) {
+            (None, _) => Err(SamuraiUnsolveable::NoSolution),
+            (Some(s), None) => Ok(s),
+            (Some(a), Some(b)) => Err(SamuraiUnsolveable::MultipleSolutions(
+                Box::new(a),
+                Box::new(b),
+            )),
+        };
+    }
+}
+
+impl FromStr for Samurai {
+    type Err = ParseError;
+
+    // Lines may stop short, as when trailing spaces have been trimmed; the cells they
+    // leave off are blank. Blank lines after the 21st are skipped. Any digit 1-9 in
+    // a gap is a BadField, as is any other character on a grid.
+    fn from_str(text: &str) -> Result<Samurai, ParseError> {
+        let mut lines: Vec<&str> = text_lines(text).collect();
+        while lines.len() > SIDE && lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        if lines.len() < SIDE {
+            return Err(ParseError::TooFewRows);
+        }
+        let indices = cell_indices();
+        let mut s = Samurai::empty();
+        for (r, line) in lines.iter().enumerate() {
+            let line = line.trim_end();
+            if r >= SIDE || line.chars().count() > SIDE {
+                return Err(ParseError::TooManyCharacters);
+            }
+            for (c, ch) in line.chars().enumerate() {
+                let value = match ch {
+                    '1'..='9' => ch as u8 - b'0',
+                    '0' | '.' | '-' | 'x' | ' ' => continue,
+                    _ => return Err(ParseError::BadField),
+                };
+                match indices[r][c] {
+                    Some(index) => s.cells[index] = value,
+                    None => return Err(ParseError::BadField),
+                }
+            }
+        }
+        return Ok(s);
+    }
+}
+
+// The text form, with '.' for blanks and no spaces at the ends of lines.
+impl fmt::Display for Samurai {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let indices = cell_indices();
+        for (r, row) in indices.iter().enumerate() {
+            if r > 0 {
+                writeln!(f)?;
+            }
+            let line: String = row
+                .iter()
+                .map(|index| match index.map(|i| self.cells[i]) {
+                    None => ' ',
+                    Some(0) => '.',
+                    Some(v) => (b'0' + v) as char,
+                })
+                .collect();
+            write!(f, "{}", line.trim_end())?;
+        }
+        return Ok(());
+    }
+}
+
+// The five grids' Debug forms, one after another in SamuraiGrid::ALL order.
+impl fmt::Debug for Samurai {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut list = f.debug_list();
+        for grid in SamuraiGrid::ALL {
+            list.entry(&self.grid(grid));
+        }
+        return list.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnsolvedSudoku;
+
+    const PUZZLE: &str = include_str!("../tests/fixtures/variants/samurai.txt");
+    const SOLUTION: &str = include_str!("../tests/fixtures/variants/samurai-solution.txt");
+
+    #[test]
+    fn test_layout() {
+        let indices = cell_indices();
+        let cells: Vec<usize> = indices.iter().flatten().flatten().cloned().collect();
+        assert_eq!(cells, (0..CELLS).collect::<Vec<_>>());
+        assert_eq!(indices[0][8], Some(8));
+        assert_eq!((indices[0][9], indices[5][11]), (None, None));
+        assert_eq!(indices[0][12], Some(9));
+        assert_eq!(indices[6][6], Some(6 * 18 + 6));
+
+        // Five grids of 27 units, less the four boxes the center grid shares.
+        let units = units();
+        assert_eq!(units.len(), 5 * 27 - 4);
+        let mut counts = [0; CELLS];
+        for unit in &units {
+            assert_eq!(unit.len(), 9);
+            for &i in unit {
+                counts[i] += 1;
+            }
+        }
+        // A shared cell is in a row and column of each grid, and their one box.
+        let shared = counts.iter().filter(|&&n| n == 5).count();
+        assert_eq!(shared, 4 * 9);
+        assert!(counts.iter().all(|&n| n == 3 || n == 5));
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let puzzle: Samurai = PUZZLE.parse().unwrap();
+        assert_eq!(puzzle.to_string(), PUZZLE.trim_end());
+        assert_eq!(puzzle.to_string().parse(), Ok(puzzle));
+        assert_eq!(puzzle.get(1, 10), None);
+        assert_eq!(puzzle.get(22, 1), None);
+        assert_eq!(puzzle.get(0, 1), None);
+        let lines: Vec<&str> = PUZZLE.lines().collect();
+        assert_eq!(lines.len(), SIDE);
+        for (r, line) in lines.iter().enumerate() {
+            for (c, ch) in line.chars().enumerate() {
+                let value = puzzle.get(r as u8 + 1, c as u8 + 1);
+                match ch {
+                    ' ' => assert!(matches!(value, None | Some(0))),
+                    '.' => assert_eq!(value, Some(0)),
+                    _ => assert_eq!(value, Some(ch as u8 - b'0')),
+                }
+            }
+        }
+
+        // The shared box is the same cells seen from either grid.
+        let center = puzzle.grid(SamuraiGrid::Center);
+        let corner = puzzle.grid(SamuraiGrid::BottomRight);
+        for c in crate::Unit::Square(9).coords() {
+            let (r, col) = (c.row() - 6, c.col() - 6);
+            assert_eq!(center[c], corner[Coord::new(r, col).unwrap()]);
+        }
+
+        let padded = PUZZLE.replace('.', "0").replace("\n", "   \n");
+        assert_eq!(padded.parse(), Ok(puzzle));
+        let short: String = PUZZLE.lines().take(20).collect::<Vec<_>>().join("\n");
+        assert_eq!(short.parse::<Samurai>(), Err(ParseError::TooFewRows));
+        let extra = format!("{}\n1\n", PUZZLE);
+        assert_eq!(extra.parse::<Samurai>(), Err(ParseError::TooManyCharacters));
+        let wide = PUZZLE.replacen('\n', "..\n", 1);
+        assert_eq!(wide.parse::<Samurai>(), Err(ParseError::TooManyCharacters));
+        // A clue in the gap of the first row.
+        let mut gap: Vec<String> = PUZZLE.lines().map(String::from).collect();
+        gap[0].replace_range(9..10, "5");
+        let gap = gap.join("\n");
+        assert_eq!(gap.parse::<Samurai>(), Err(ParseError::BadField));
+        let letter = PUZZLE.replacen('.', "a", 1);
+        assert_eq!(letter.parse::<Samurai>(), Err(ParseError::BadField));
+        assert_eq!(Samurai::empty().clue_count(), 0);
+        assert_eq!("".parse::<Samurai>(), Err(ParseError::TooFewRows));
+    }
+
+    #[test]
+    fn test_solve() {
+        let puzzle: Samurai = PUZZLE.parse().unwrap();
+        let solution: Samurai = SOLUTION.parse().unwrap();
+        assert_eq!(solution.clue_count(), CELLS);
+        assert_eq!(puzzle.solve(), Ok(solution));
+        for grid in SamuraiGrid::ALL {
+            let s = solution.grid(grid);
+            assert!(
+                s.is_complete() && UnsolvedSudoku::from(s).valid(),
+                "{:?}: {}",
+                grid,
+                s
+            );
+            // No corner grid is fixed by its own clues alone.
+            if grid != SamuraiGrid::Center {
+                assert!(puzzle.grid(grid).count_solutions(2) > 1, "{:?}", grid);
+            }
+        }
+        for (cell, &v) in puzzle.cells.iter().enumerate() {
+            if v != 0 {
+                assert_eq!(solution.cells[cell], v);
+            }
+        }
+
+        // A clash between two grids goes through the shared box: a 5 in the center
+        // grid's top-left box and in the same row of the top-left grid.
+        let mut clash = Samurai::empty();
+        let indices = cell_indices();
+        clash.cells[indices[6][6].unwrap()] = 5;
+        clash.cells[indices[6][0].unwrap()] = 5;
+        assert_eq!(clash.solve(), Err(SamuraiUnsolveable::NoSolution));
+
+        match Samurai::empty().solve() {
+            Err(SamuraiUnsolveable::MultipleSolutions(a, b)) => {
+                assert_ne!(a, b);
+                for grid in SamuraiGrid::ALL {
+                    assert!(UnsolvedSudoku::from(a.grid(grid)).valid());
+                    assert!(UnsolvedSudoku::from(b.grid(grid)).valid());
+                }
+            }
+            other => panic!("expected two solutions, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,553 @@
+// SadMan Sudoku's .sdk files. A file starts with optional metadata lines such as
+// "#A author" or "#D description", followed by the puzzle as nine lines with '.' for
+// blanks. A "#variant: NAME" line, our own addition, names the rules when they aren't
+// classic, like "x" for X-sudoku or "hyper" for windoku, or several at once separated by
+// commas; see Variant. Saved games add sections, each starting with a header line:
+//
+//     [Puzzle]       the givens, as above (also assumed before any header)
+//     [State]        the same grid with the player's entries filled in
+//     [PencilMarks]  nine lines of nine space-separated cells, each the digits still
+//                    pencilled into that cell, or '.' for none
+//     [Consecutive]  our own addition: one line per white kropki dot, the two
+//                    orthogonally adjacent cells it joins, like "r1c1 r1c2", which
+//                    must hold consecutive values
+//     [Cages]        our own addition: one line per killer cage, its sum, a colon,
+//                    and its cells, like "15: r1c1 r1c2 r2c1"; see Cage. A pure
+//                    killer's grid is all blanks
+//     [Regions]      our own addition: a jigsaw puzzle's nine lines of nine letters
+//                    A-I, each the region of its cell, which stand in for the
+//                    squares; see Regions
+//
+// Any other section is skipped.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{
+    text_lines, Cage, Conflict, Constraint, Coord, ParseError, RegionError, Regions, Sudoku,
+    UnsolvedSudoku, Variant,
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SdkFile {
+    pub givens: Sudoku,
+    // The givens and the player's entries, with the pencil marks applied as
+    // eliminations.
+    pub grid: UnsolvedSudoku,
+    // Each "#X text" line as (X, text), in file order, without the variant line.
+    pub metadata: Vec<(char, String)>,
+    // The variants whose rules the puzzle follows on top of the classic ones, which grid
+    // is solved against; empty for a classic puzzle.
+    pub variants: Vec<Variant>,
+    // The constraints the file lists beyond its variants', such as the white dots of a
+    // [Consecutive] section. grid is solved against these too.
+    pub constraints: Vec<Constraint>,
+    // The killer cages of a [Cages] section, which grid is solved against too.
+    pub cages: Vec<Cage>,
+    // The jigsaw regions of a [Regions] section, which grid uses in place of the
+    // squares; None for square regions.
+    pub regions: Option<Regions>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SdkError {
+    // The [Puzzle] or [State] section is not a grid.
+    Grid(ParseError),
+    // A [State] entry disagrees with the givens or an earlier entry.
+    Conflict(Conflict),
+    // The [PencilMarks] line for this row doesn't have nine cells, or lists something
+    // other than digits 1-9. A missing line reports the first row not given.
+    PencilMarks(u8),
+    // The "#variant:" line names a variant Variant::from_name doesn't know.
+    UnknownVariant,
+    // This line of the [Consecutive] section, counting from 1, isn't two orthogonally
+    // adjacent cells.
+    Consecutive(usize),
+    // This line of the [Cages] section, counting from 1, isn't a sum and a colon
+    // followed by cells that make a valid Cage.
+    Cage(usize),
+    // The cage on this line of the [Cages] section shares the cell with an earlier one.
+    CageOverlap { line: usize, cell: Coord },
+    // The [Regions] section isn't a valid region map.
+    Regions(RegionError),
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SdkError::Grid(e) => write!(f, "{}", e),
+            SdkError::Conflict(e) => write!(f, "{}", e),
+            SdkError::PencilMarks(row) => write!(f, "bad pencil marks in row {}", row),
+            SdkError::UnknownVariant => write!(f, "unknown variant"),
+            SdkError::Consecutive(line) => write!(f, "bad consecutive pair on line {}", line),
+            SdkError::Cage(line) => write!(f, "bad cage on line {}", line),
+            SdkError::CageOverlap { line, cell } => {
+                write!(
+                    f,
+                    "cage on line {} shares {} with an earlier cage",
+                    line, cell
+                )
+            }
+            SdkError::Regions(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for SdkError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        return match self {
+            SdkError::Grid(e) => Some(e),
+            SdkError::Conflict(e) => Some(e),
+            SdkError::Regions(e) => Some(e),
+            SdkError::PencilMarks(_)
+            | SdkError::UnknownVariant
+            | SdkError::Consecutive(_)
+            | SdkError::Cage(_)
+            | SdkError::CageOverlap { .. } => None,
+        };
+    }
+}
+
+impl From<ParseError> for SdkError {
+    fn from(e: ParseError) -> SdkError {
+        return SdkError::Grid(e);
+    }
+}
+
+impl From<RegionError> for SdkError {
+    fn from(e: RegionError) -> SdkError {
+        return SdkError::Regions(e);
+    }
+}
+
+impl From<Conflict> for SdkError {
+    fn from(e: Conflict) -> SdkError {
+        return SdkError::Conflict(e);
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Section {
+    Puzzle,
+    State,
+    PencilMarks,
+    Consecutive,
+    Cages,
+    Regions,
+    Other,
+}
+
+impl FromStr for SdkFile {
+    type Err = SdkError;
+
+    fn from_str(text: &str) -> Result<SdkFile, SdkError> {
+        let mut metadata = Vec::new();
+        let mut variants = Vec::new();
+        let mut puzzle = Vec::new();
+        let mut state = Vec::new();
+        let mut marks = Vec::new();
+        let mut pairs = Vec::new();
+        let mut cage_lines = Vec::new();
+        let mut region_lines = Vec::new();
+        let mut section = Section::Puzzle;
+        for line in text_lines(text).map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match name.trim() {
+                    "Puzzle" => Section::Puzzle,
+                    "State" => Section::State,
+                    "PencilMarks" => Section::PencilMarks,
+                    "Consecutive" => Section::Consecutive,
+                    "Cages" => Section::Cages,
+                    "Regions" => Section::Regions,
+                    _ => Section::Other,
+                };
+                continue;
+            }
+            match section {
+                Section::Puzzle => match line.strip_prefix('#') {
+                    Some(meta) if meta.starts_with("variant:") => {
+                        variants = Variant::from_names(&meta["variant:".len()..])
+                            .ok_or(SdkError::UnknownVariant)?;
+                    }
+                    Some(meta) => {
+                        let mut chars = meta.chars();
+                        if let Some(key) = chars.next() {
+                            metadata.push((key, chars.as_str().trim().to_string()));
+                        }
+                    }
+                    None => puzzle.push(line),
+                },
+                Section::State => state.push(line),
+                Section::PencilMarks => marks.push(line),
+                Section::Consecutive => pairs.push(line),
+                Section::Cages => cage_lines.push(line),
+                Section::Regions => region_lines.push(line),
+                Section::Other => {}
+            }
+        }
+
+        let givens = parse_grid(&puzzle)?;
+        let constraints = parse_pairs(&pairs)?;
+        let cages = parse_cages(&cage_lines)?;
+        let regions = match region_lines.is_empty() {
+            true => None,
+            false => Some(region_lines.join("\n").parse::<Regions>()?),
+        };
+        let mut grid = match &regions {
+            Some(regions) => UnsolvedSudoku::with_regions(regions, &variants),
+            None => UnsolvedSudoku::with_variants(&variants),
+        };
+        for &constraint in &constraints {
+            grid.add_constraint(constraint);
+        }
+        for cage in &cages {
+            grid.add_cage(cage.clone());
+        }
+        grid.add_givens(&givens);
+        if !state.is_empty() {
+            let entries: Vec<(u8, u8, u8)> = parse_grid(&state)?
+                .cells()
+                .filter(|&(c, v)| v != 0 && givens.is_empty(c))
+                .map(|(c, v)| (c.row(), c.col(), v))
+                .collect();
+            grid = grid.with_user_entries(&entries)?;
+        }
+        if !marks.is_empty() {
+            apply_marks(&mut grid, &marks)?;
+        }
+        return Ok(SdkFile {
+            givens,
+            grid,
+            metadata,
+            variants,
+            constraints,
+            cages,
+            regions,
+        });
+    }
+}
+
+// The grid lines use '.' for blanks, which Sudoku's own parser skips over.
+fn parse_grid(lines: &[&str]) -> Result<Sudoku, ParseError> {
+    return lines.join("\n").replace('.', "-").parse::<Sudoku>();
+}
+
+// Each line's two cells as a Consecutive constraint, the first in row-major order first.
+fn parse_pairs(lines: &[&str]) -> Result<Vec<Constraint>, SdkError> {
+    let mut constraints = Vec::new();
+    for (ix, line) in lines.iter().enumerate() {
+        let cells: Option<Vec<Coord>> = line.split_whitespace().map(parse_coord).collect();
+        let (a, b) = match cells.as_deref() {
+            Some(&[a, b]) if a.row().abs_diff(b.row()) + a.col().abs_diff(b.col()) == 1 => {
+                (a.min(b), a.max(b))
+            }
+            _ => return Err(SdkError::Consecutive(ix + 1)),
+        };
+        constraints.push(Constraint::Consecutive(a, b));
+    }
+    return Ok(constraints);
+}
+
+// Each line as a Cage, written as Cage displays it. No cell can be in two cages.
+fn parse_cages(lines: &[&str]) -> Result<Vec<Cage>, SdkError> {
+    let mut cages: Vec<Cage> = Vec::new();
+    for (ix, line) in lines.iter().enumerate() {
+        let cage = line.split_once(':').and_then(|(sum, cells)| {
+            let cells: Option<Vec<Coord>> = cells.split_whitespace().map(parse_coord).collect();
+            return Cage::new(sum.trim().parse().ok()?, cells?).ok();
+        });
+        let cage = match cage {
+            Some(cage) => cage,
+            None => return Err(SdkError::Cage(ix + 1)),
+        };
+        let mut earlier = cages.iter().flat_map(|other| other.cells().iter().copied());
+        if let Some(cell) = earlier.find(|c| cage.cells().contains(c)) {
+            return Err(SdkError::CageOverlap { line: ix + 1, cell });
+        }
+        cages.push(cage);
+    }
+    return Ok(cages);
+}
+
+// A cell written as Coord displays it, like "r3c7".
+pub fn parse_coord(text: &str) -> Option<Coord> {
+    let (row, col) = text.strip_prefix('r')?.split_once('c')?;
+    return Coord::new(row.parse().ok()?, col.parse().ok()?).ok();
+}
+
+// Remove every candidate not pencilled in. Cells with no marks at all are left alone,
+// since that means the player hasn't marked them yet rather than that nothing fits.
+fn apply_marks(grid: &mut UnsolvedSudoku, lines: &[&str]) -> Result<(), SdkError> {
+    for rix in 1..10u8 {
+        let cells: Vec<&str> = match lines.get(rix as usize - 1) {
+            Some(line) => line.split_whitespace().collect(),
+            None => return Err(SdkError::PencilMarks(rix)),
+        };
+        if cells.len() != 9 {
+            return Err(SdkError::PencilMarks(rix));
+        }
+        for (cix, cell) in (1..10u8).zip(cells) {
+            if cell == "." {
+                continue;
+            }
+            let mut marked = [false; 10];
+            for ch in cell.chars() {
+                match ch.to_digit(10) {
+                    Some(d @ 1..=9) => marked[d as usize] = true,
+                    _ => return Err(SdkError::PencilMarks(rix)),
+                }
+            }
+            let c = Coord::new(rix, cix).expect("rows and columns run 1-9");
+            if grid.candidates(c).is_none() {
+                continue;
+            }
+            for v in 1..10u8 {
+                if !marked[v as usize] {
+                    grid.eliminate(c, v).expect("values run 1-9");
+                }
+            }
+        }
+    }
+    if lines.len() > 9 {
+        return Err(SdkError::PencilMarks(10));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "\
+#AWendell
+#DThe moderate example
+#L3
+...1.3.95
+.8....7..
+.94..7..3
+......5..
+.3...1...
+6..5.9...
+.4..351.7
+.....486.
+....6....
+";
+
+    #[test]
+    fn test_plain() {
+        let f = PUZZLE.parse::<SdkFile>().unwrap();
+        let moderate = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        assert_eq!(f.givens, moderate);
+        assert_eq!(f.grid, UnsolvedSudoku::from(moderate));
+        assert_eq!(
+            f.metadata,
+            vec![
+                ('A', "Wendell".to_string()),
+                ('D', "The moderate example".to_string()),
+                ('L', "3".to_string()),
+            ]
+        );
+        for ending in ["\r\n", "\r"] {
+            assert_eq!(PUZZLE.replace('\n', ending).parse(), Ok(f.clone()));
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        let mut text = String::from("[Puzzle]\n");
+        text.push_str(PUZZLE);
+        // The player has put a 2 at (1, 1).
+        text.push_str("[State]\n2..1.3.95\n");
+        text.push_str(&PUZZLE.lines().skip(4).collect::<Vec<_>>().join("\n"));
+        text.push_str("\n[Timer]\n12:34\n[PencilMarks]\n");
+        // Pencil marks only at (1, 2): 4 and 6.
+        text.push_str(". 46 . . . . . . .\n");
+        for _ in 0..8 {
+            text.push_str(". . . . . . . . .\n");
+        }
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(f.givens.get(Coord::new(1, 1).unwrap()), 0);
+        assert_eq!(f.grid.fixed_value(Coord::new(1, 1).unwrap()), Some(2));
+        // Column 2 already has a 4, so the marks leave only 6.
+        assert_eq!(f.grid.fixed_value(Coord::new(1, 2).unwrap()), Some(6));
+        // Nothing else was touched.
+        let mut expected = UnsolvedSudoku::from(f.givens)
+            .with_user_entries(&[(1, 1, 2)])
+            .unwrap();
+        for v in [1, 2, 3, 5, 7, 8, 9] {
+            expected.eliminate(Coord::new(1, 2).unwrap(), v).unwrap();
+        }
+        assert_eq!(f.grid, expected);
+        assert_eq!(f.metadata.len(), 3);
+    }
+
+    #[test]
+    fn test_variant() {
+        assert_eq!(PUZZLE.parse::<SdkFile>().unwrap().variants, []);
+        let text = format!("#variant: x\n{}", PUZZLE);
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(f.variants, [Variant::Diagonal]);
+        assert_eq!(f.metadata.len(), 3);
+        assert_eq!(f.grid.units().count(), 29);
+        let mut expected = UnsolvedSudoku::with_variant(Variant::Diagonal);
+        expected.add_givens(&f.givens);
+        assert_eq!(f.grid, expected);
+
+        let text = format!("#variant: anti-king, Anti-Knight\n{}", PUZZLE);
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(f.variants, [Variant::AntiKing, Variant::AntiKnight]);
+        assert_eq!(f.grid.constraints().count(), 2);
+        assert_eq!(
+            format!("#variant: killer\n{}", PUZZLE).parse::<SdkFile>(),
+            Err(SdkError::UnknownVariant)
+        );
+        assert_eq!(
+            format!("#variant: x,\n{}", PUZZLE).parse::<SdkFile>(),
+            Err(SdkError::UnknownVariant)
+        );
+    }
+
+    #[test]
+    fn test_consecutive() {
+        assert_eq!(PUZZLE.parse::<SdkFile>().unwrap().constraints, []);
+        let c = |r, c| Coord::new(r, c).unwrap();
+        let text = format!("{}[Consecutive]\nr1c4 r1c3\n\n r4c5  r5c5\n", PUZZLE);
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(
+            f.constraints,
+            [
+                Constraint::Consecutive(c(1, 3), c(1, 4)),
+                Constraint::Consecutive(c(4, 5), c(5, 5)),
+            ]
+        );
+        assert_eq!(f.grid.constraints().count(), 2);
+        assert_eq!(f.variants, []);
+        // r1c3 sits beside r1c4's given 1, so 2 is all that's left for it.
+        assert_eq!(f.grid.fixed_value(c(1, 3)), Some(2));
+
+        for pairs in [
+            "r1c1 r1c3",
+            "r1c1 r2c2",
+            "r1c1",
+            "r1c1 r1c2 r1c3",
+            "r0c1 r1c1",
+        ] {
+            let text = format!("{}[Consecutive]\nr2c2 r2c3\n{}\n", PUZZLE, pairs);
+            assert_eq!(
+                text.parse::<SdkFile>(),
+                Err(SdkError::Consecutive(2)),
+                "{}",
+                pairs
+            );
+        }
+        assert_eq!(
+            SdkError::Consecutive(2).to_string(),
+            "bad consecutive pair on line 2"
+        );
+    }
+
+    #[test]
+    fn test_cages() {
+        assert_eq!(PUZZLE.parse::<SdkFile>().unwrap().cages, []);
+        let c = |r, c| Coord::new(r, c).unwrap();
+        let text = format!("{}[Cages]\n3: r1c1 r1c2\n 17 :r2c1  r3c1\n", PUZZLE);
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(
+            f.cages,
+            [
+                Cage::new(3, vec![c(1, 1), c(1, 2)]).unwrap(),
+                Cage::new(17, vec![c(2, 1), c(3, 1)]).unwrap(),
+            ]
+        );
+        assert_eq!(f.grid.cages().count(), 2);
+
+        for cage in [
+            "3 r1c1 r1c2",
+            "x: r1c1",
+            "3: r1c1 r1",
+            "3: r1c1 r1c1",
+            "2: r1c1 r1c2",
+            "3:",
+        ] {
+            let text = format!("{}[Cages]\n4: r9c9 r8c8\n{}\n", PUZZLE, cage);
+            assert_eq!(text.parse::<SdkFile>(), Err(SdkError::Cage(2)), "{}", cage);
+        }
+        assert_eq!(SdkError::Cage(2).to_string(), "bad cage on line 2");
+
+        let text = format!(
+            "{}[Cages]\n3: r1c1 r1c2\n10: r2c1 r2c2\n9: r2c2 r3c2\n",
+            PUZZLE
+        );
+        let e = text.parse::<SdkFile>().unwrap_err();
+        assert_eq!(
+            e,
+            SdkError::CageOverlap {
+                line: 3,
+                cell: c(2, 2)
+            }
+        );
+        assert_eq!(
+            e.to_string(),
+            "cage on line 3 shares r2c2 with an earlier cage"
+        );
+    }
+
+    #[test]
+    fn test_regions() {
+        assert_eq!(PUZZLE.parse::<SdkFile>().unwrap().regions, None);
+        let classic = Regions::classic().to_string();
+        let text = format!("{}[Regions]\n{}\n", PUZZLE, classic);
+        let f = text.parse::<SdkFile>().unwrap();
+        assert_eq!(f.regions, Some(Regions::classic()));
+        // Square regions make the classic grid.
+        assert_eq!(f.grid, PUZZLE.parse::<SdkFile>().unwrap().grid);
+
+        let text = format!("{}[Regions]\n{}\n", PUZZLE, classic.replacen('A', "B", 1));
+        let e = text.parse::<SdkFile>().unwrap_err();
+        assert_eq!(
+            e,
+            SdkError::Regions(RegionError::Size {
+                label: 'A',
+                cells: 8
+            })
+        );
+        assert_eq!(e.to_string(), "region A has 8 cells, not 9");
+        assert!(core::error::Error::source(&e).is_some());
+        let text = format!("{}[Regions]\nAAABBBCCC\n", PUZZLE);
+        assert_eq!(
+            text.parse::<SdkFile>(),
+            Err(SdkError::Regions(RegionError::RowCount(1)))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "...1.3.95\n".parse::<SdkFile>(),
+            Err(SdkError::Grid(ParseError::TooFewRows))
+        );
+        let mut conflict = String::from(PUZZLE);
+        // 1 is already a given in row 1.
+        conflict.push_str("[State]\n1..1.3.95\n");
+        conflict.push_str(&PUZZLE.lines().skip(4).collect::<Vec<_>>().join("\n"));
+        assert!(matches!(
+            conflict.parse::<SdkFile>(),
+            Err(SdkError::Conflict(_))
+        ));
+        let mut marks = String::from(PUZZLE);
+        marks.push_str("[PencilMarks]\n. . .\n");
+        assert_eq!(marks.parse::<SdkFile>(), Err(SdkError::PencilMarks(1)));
+        let mut marks = String::from(PUZZLE);
+        marks.push_str("[PencilMarks]\n. 4x . . . . . . .\n");
+        assert_eq!(marks.parse::<SdkFile>(), Err(SdkError::PencilMarks(1)));
+        assert_eq!(
+            SdkError::PencilMarks(3).to_string(),
+            "bad pencil marks in row 3"
+        );
+    }
+}
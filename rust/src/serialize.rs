@@ -0,0 +1,254 @@
+// serde support, enabled with the "serde" feature.
+//
+// A Sudoku is written as its nine rows of digits, with 0 for blanks. An UnsolvedSudoku
+// keeps its full candidate state: nine rows of cells, each either {"value": n} or
+// {"candidates": [...]}, so an in-progress solve including pencil marks can be restored.
+// Only the cells are written: a grid with extra units comes back with the classic 27.
+//
+// A SolveReport is only written, not read back, as an object of its figures: the
+// solution if there is just one, the techniques by name, and the time in seconds.
+
+use std::collections;
+use std::fmt;
+
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use crate::{Cell, SolveReport, Sudoku, UnsolvedSudoku};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CellRepr {
+    Value(u8),
+    Candidates(Vec<u8>),
+}
+
+fn check_len<E: de::Error>(what: &str, len: usize) -> Result<(), E> {
+    if len != 9 {
+        return Err(E::custom(format_args!(
+            "expected 9 {}, found {}",
+            what, len
+        )));
+    }
+    return Ok(());
+}
+
+fn check_digit<E: de::Error>(v: u8, lowest: u8) -> Result<u8, E> {
+    if v < lowest || v > 9 {
+        return Err(E::custom(format_args!(
+            "value {} out of range {}-9",
+            v, lowest
+        )));
+    }
+    return Ok(v);
+}
+
+impl Serialize for Sudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return self.rows.serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for Sudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sudoku, D::Error> {
+        let rows: Vec<Vec<u8>> = Deserialize::deserialize(deserializer)?;
+        check_len("rows", rows.len())?;
+        let mut s = Sudoku::empty();
+        for (rix, row) in rows.into_iter().enumerate() {
+            check_len("cells in a row", row.len())?;
+            for (cix, v) in row.into_iter().enumerate() {
+                s.rows[rix][cix] = check_digit(v, 0)?;
+            }
+        }
+        return Ok(s);
+    }
+}
+
+impl Serialize for UnsolvedSudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Vec<CellRepr>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        &Cell::Value(v) => CellRepr::Value(v),
+                        Cell::Possibilities(ps) => {
+                            CellRepr::Candidates(ps.iter().cloned().collect())
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        return rows.serialize(serializer);
+    }
+}
+
+impl<'de> Deserialize<'de> for UnsolvedSudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<UnsolvedSudoku, D::Error> {
+        let rows: Vec<Vec<CellRepr>> = Deserialize::deserialize(deserializer)?;
+        check_len("rows", rows.len())?;
+        let mut u = UnsolvedSudoku::empty();
+        for (rix, row) in rows.into_iter().enumerate() {
+            check_len("cells in a row", row.len())?;
+            for (cix, repr) in row.into_iter().enumerate() {
+                u.rows[rix][cix] = match repr {
+                    CellRepr::Value(v) => Cell::Value(check_digit(v, 1)?),
+                    CellRepr::Candidates(vs) => {
+                        let mut ps = collections::BTreeSet::new();
+                        for v in vs {
+                            ps.insert(check_digit(v, 1)?);
+                        }
+                        match ps.len() {
+                            0 => {
+                                return Err(de::Error::custom(EmptyCell(rix + 1, cix + 1)));
+                            }
+                            // A lone candidate is a fixed value, as the solver would
+                            // have stored it.
+                            1 => Cell::Value(*ps.iter().next().unwrap()),
+                            _ => Cell::Possibilities(ps),
+                        }
+                    }
+                };
+            }
+        }
+        return Ok(u);
+    }
+}
+
+#[derive(Serialize)]
+struct ReportRepr<'a> {
+    solved: bool,
+    unique: bool,
+    // The guess budget ran out, leaving the rest unknown.
+    stopped: bool,
+    solution: Option<&'a Sudoku>,
+    guesses: u64,
+    max_depth: u32,
+    techniques_used: Vec<&'static str>,
+    elapsed_secs: f64,
+}
+
+impl Serialize for SolveReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = ReportRepr {
+            solved: self.solved(),
+            unique: self.unique(),
+            stopped: self.result.is_none(),
+            solution: match &self.result {
+                Some(Ok(s)) => Some(s),
+                _ => None,
+            },
+            guesses: self.guesses,
+            max_depth: self.max_depth,
+            techniques_used: self.techniques_used.iter().map(|t| t.name()).collect(),
+            elapsed_secs: self.elapsed.as_secs_f64(),
+        };
+        return repr.serialize(serializer);
+    }
+}
+
+struct EmptyCell(usize, usize);
+
+impl fmt::Display for EmptyCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cell ({}, {}) has no candidates", self.0, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sudoku_round_trip() {
+        let s = Sudoku::from_reader(include_str!("../../moderate.txt").as_bytes()).unwrap();
+        let json = serde_json::to_string(&s).unwrap();
+        assert!(json.starts_with("[[0,0,0,1,0,3,0,9,5],"));
+        let back: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, s);
+
+        assert!(serde_json::from_str::<Sudoku>("[[0,0,0]]").is_err());
+        let bad = json.replacen("[0,0,0,1", "[0,0,10,1", 1);
+        assert!(serde_json::from_str::<Sudoku>(&bad).is_err());
+    }
+
+    #[test]
+    fn test_unsolved_round_trip() {
+        let s = Sudoku::from_reader(include_str!("../../challenge.txt").as_bytes()).unwrap();
+        let mut u: UnsolvedSudoku = s.into();
+        u.simple_solve();
+        let json = serde_json::to_string(&u).unwrap();
+        assert!(json.contains(r#"{"value":"#));
+        assert!(json.contains(r#"{"candidates":["#));
+
+        let back: UnsolvedSudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, u);
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn test_report() {
+        let s = Sudoku::from_reader(include_str!("../../moderate.txt").as_bytes()).unwrap();
+        let report = UnsolvedSudoku::from(s).solve_report();
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["solved"], true);
+        assert_eq!(json["unique"], true);
+        assert_eq!(json["stopped"], false);
+        assert_eq!(
+            json["solution"],
+            serde_json::to_value(s.solve().unwrap()).unwrap()
+        );
+        assert_eq!(json["guesses"], report.guesses);
+        assert_eq!(json["techniques_used"][0], "naked single");
+        assert!(json["elapsed_secs"].as_f64().unwrap() >= 0.0);
+
+        let empty = UnsolvedSudoku::empty().solve_report();
+        let json: serde_json::Value = serde_json::to_value(&empty).unwrap();
+        assert_eq!(
+            (&json["solved"], &json["unique"]),
+            (&true.into(), &false.into())
+        );
+        assert_eq!(json["solution"], serde_json::Value::Null);
+    }
+
+    fn single_cell_grid(first: &str) -> String {
+        let full = r#"{"candidates":[1,2,3,4,5,6,7,8,9]}"#;
+        let mut cells = vec![full; 81];
+        cells[0] = first;
+        let rows: Vec<String> = cells
+            .chunks(9)
+            .map(|r| format!("[{}]", r.join(",")))
+            .collect();
+        return format!("[{}]", rows.join(","));
+    }
+
+    #[test]
+    fn test_unsolved_validation() {
+        let ok: UnsolvedSudoku =
+            serde_json::from_str(&single_cell_grid(r#"{"candidates":[2,7,7]}"#)).unwrap();
+        assert_eq!(
+            *ok.get(1, 1),
+            Cell::Possibilities([2, 7].iter().cloned().collect())
+        );
+        let fixed: UnsolvedSudoku =
+            serde_json::from_str(&single_cell_grid(r#"{"candidates":[4]}"#)).unwrap();
+        assert_eq!(*fixed.get(1, 1), Cell::Value(4));
+
+        for bad in &[
+            r#"{"candidates":[]}"#,
+            r#"{"candidates":[0,1]}"#,
+            r#"{"candidates":[1,10]}"#,
+            r#"{"value":0}"#,
+            r#"{"value":10}"#,
+            r#"{"other":1}"#,
+        ] {
+            assert!(serde_json::from_str::<UnsolvedSudoku>(&single_cell_grid(bad)).is_err());
+        }
+        let err = serde_json::from_str::<UnsolvedSudoku>(&single_cell_grid(r#"{"candidates":[]}"#))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("cell (1, 1) has no candidates"));
+    }
+}
@@ -0,0 +1,719 @@
+// Grids with rectangular boxes. A grid of side rows * cols is tiled by boxes `rows` tall
+// and `cols` wide: the classic grid is 3x3, 4x4 uses 2x2 boxes, 6x6 uses 2x3, 8x8 uses
+// 2x4, 12x12 uses 3x4, and 16x16 uses 4x4.
+//
+// The main solver is still built around the 9x9 grid, so other sizes go through
+// SizedSudoku, which has its own small search. Digits above 9 are written with letters,
+// either going on from A for 10 or, on 16x16 grids, as the hex digits 0-F; see
+// Alphabet.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{text_lines, GridError, ParseError};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BoxShape {
+    rows: u8,
+    cols: u8,
+}
+
+// Digits run 1-9 and then A-G, so 16 is the largest side that can be written down.
+const MAX_SIZE: u8 = 16;
+
+impl Default for BoxShape {
+    fn default() -> BoxShape {
+        return BoxShape::CLASSIC;
+    }
+}
+
+impl BoxShape {
+    pub const CLASSIC: BoxShape = BoxShape { rows: 3, cols: 3 };
+
+    pub fn new(rows: u8, cols: u8) -> Result<BoxShape, GridError> {
+        if rows == 0 || cols == 0 || rows as u16 * cols as u16 > MAX_SIZE as u16 {
+            return Err(GridError::BoxShapeOutOfRange(rows, cols));
+        }
+        return Ok(BoxShape { rows, cols });
+    }
+
+    // The boxes of a grid this many cells on a side, as near square as they go: 2x2 for
+    // 4x4, 3x3 for 9x9, and 4x4 for 16x16, and where the side isn't a square, boxes
+    // wider than they are tall, like 2x3 for 6x6 and 3x4 for 12x12. Only whole rows
+    // tile a prime side, so it has none.
+    pub fn for_size(size: u8) -> Result<BoxShape, GridError> {
+        if size > MAX_SIZE {
+            return Err(GridError::SizeOutOfRange(size));
+        }
+        let rows = (1..=size)
+            .rev()
+            .find(|&r| r <= size / r && size.is_multiple_of(r));
+        return match rows {
+            Some(rows) if rows > 1 || size == 1 => BoxShape::new(rows, size / rows),
+            _ => Err(GridError::SizeOutOfRange(size)),
+        };
+    }
+
+    // The height of each box.
+    pub fn rows(self) -> u8 {
+        return self.rows;
+    }
+
+    // The width of each box.
+    pub fn cols(self) -> u8 {
+        return self.cols;
+    }
+
+    // The side of the grid, which is also the number of boxes and of digits.
+    pub fn size(self) -> u8 {
+        return self.rows * self.cols;
+    }
+
+    // The 1-based index of the box containing (row, col), counting left to right and
+    // then top to bottom.
+    pub fn box_of(self, row: u8, col: u8) -> u8 {
+        let per_band = self.size() / self.cols;
+        return ((row - 1) / self.rows) * per_band + (col - 1) / self.cols + 1;
+    }
+
+    // The (row, col) of the index-th cell of box ix, both 1-based, with the box's cells
+    // in reading order.
+    pub fn box_cell(self, ix: u8, index: u8) -> (u8, u8) {
+        let per_band = self.size() / self.cols;
+        let band = (ix - 1) / per_band;
+        let stack = (ix - 1) % per_band;
+        let row = band * self.rows + (index - 1) / self.cols + 1;
+        let col = stack * self.cols + (index - 1) % self.cols + 1;
+        return (row, col);
+    }
+
+    // Every unit as a list of 0-based row-major cell indices: for each index in turn,
+    // that row, column, and box, the same order as Unit::classic.
+    fn units(self) -> Vec<Vec<usize>> {
+        let n = self.size();
+        let cell = |(r, c): (u8, u8)| (r as usize - 1) * n as usize + c as usize - 1;
+        let mut units = Vec::with_capacity(3 * n as usize);
+        for ix in 1..=n {
+            units.push((1..=n).map(|j| cell((ix, j))).collect());
+            units.push((1..=n).map(|j| cell((j, ix))).collect());
+            units.push((1..=n).map(|j| cell(self.box_cell(ix, j))).collect());
+        }
+        return units;
+    }
+}
+
+// Why SizedSudoku::solve failed: the counterpart of Unsolveable for other sizes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SizedUnsolveable {
+    // Two of the solutions, which differ in at least one cell.
+    MultipleSolutions(Box<SizedSudoku>, Box<SizedSudoku>),
+    NoSolution,
+}
+
+impl fmt::Display for SizedUnsolveable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SizedUnsolveable::MultipleSolutions(..) => write!(f, "puzzle has multiple solutions"),
+            SizedUnsolveable::NoSolution => write!(f, "puzzle has no solution"),
+        }
+    }
+}
+
+impl core::error::Error for SizedUnsolveable {}
+
+// A grid of any BoxShape, with 0 for blanks.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct SizedSudoku {
+    shape: BoxShape,
+    // Row-major.
+    cells: Vec<u8>,
+}
+
+// How a SizedSudoku's digits are written.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Alphabet {
+    // 1-9 as themselves and then A for 10, B for 11, up to G for 16.
+    #[default]
+    Letters,
+    // Each digit as the hex digit one below it, 0 for 1 up to F for 16, as hexadoku
+    // is usually printed.
+    Hex,
+}
+
+impl Alphabet {
+    // The digit c stands for, or None if it isn't one. Case doesn't matter.
+    pub fn digit(self, c: char) -> Option<u8> {
+        return match self {
+            Alphabet::Letters => c.to_digit(MAX_SIZE as u32 + 1).filter(|&v| v > 0),
+            Alphabet::Hex => c.to_digit(16).map(|v| v + 1),
+        }
+        .map(|v| v as u8);
+    }
+
+    // Panics if v is outside 1-16.
+    pub fn char(self, v: u8) -> char {
+        assert!(
+            (1..=MAX_SIZE).contains(&v),
+            "digit {} out of range 1-{}",
+            v,
+            MAX_SIZE
+        );
+        let ix = match self {
+            Alphabet::Letters => v as u32,
+            Alphabet::Hex => v as u32 - 1,
+        };
+        return char::from_digit(ix, MAX_SIZE as u32 + 1)
+            .unwrap()
+            .to_ascii_uppercase();
+    }
+
+    // Whether c marks a blank: '-', 'x', or '0' where it isn't a digit.
+    fn is_blank(self, c: char) -> bool {
+        return c == '-' || c == 'x' || (c == '0' && self == Alphabet::Letters);
+    }
+}
+
+fn digit_char(v: u8) -> char {
+    return match v {
+        0 => ' ',
+        _ => Alphabet::Letters.char(v),
+    };
+}
+
+impl SizedSudoku {
+    pub fn empty(shape: BoxShape) -> SizedSudoku {
+        let n = shape.size() as usize;
+        return SizedSudoku {
+            shape,
+            cells: vec![0; n * n],
+        };
+    }
+
+    // Read a grid in the same format as Sudoku::from_reader.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: io::Read>(shape: BoxShape, mut reader: R) -> io::Result<SizedSudoku> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        return SizedSudoku::parse(shape, &text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+    }
+
+    // Parse a grid in the same format as Sudoku's FromStr, with one row per line. On
+    // grids larger than 9x9, the digits from 10 up are written A, B, C, and so on.
+    pub fn parse(shape: BoxShape, text: &str) -> Result<SizedSudoku, ParseError> {
+        return SizedSudoku::parse_with(shape, Alphabet::Letters, text);
+    }
+
+    // Parse a grid as parse does, with its digits written in the alphabet. With Hex, '0'
+    // is the digit 1, so only '-' and 'x' are blanks.
+    pub fn parse_with(
+        shape: BoxShape,
+        alphabet: Alphabet,
+        text: &str,
+    ) -> Result<SizedSudoku, ParseError> {
+        let n = shape.size() as usize;
+        let mut s = SizedSudoku::empty(shape);
+        let mut rix = 0;
+        for line in text_lines(text) {
+            let mut cix = 0;
+            for c in line.trim().chars() {
+                let v = match alphabet.digit(c) {
+                    Some(v) if (v as usize) <= n => v,
+                    Some(_) => return Err(ParseError::DigitOutOfRange),
+                    None if alphabet.is_blank(c) => 0,
+                    None => continue,
+                };
+                if cix >= n || rix >= n {
+                    return Err(ParseError::TooManyCharacters);
+                }
+                s.cells[rix * n + cix] = v;
+                cix += 1;
+            }
+            if cix < n {
+                return Err(ParseError::TooFewCharacters);
+            }
+            rix += 1;
+        }
+        if rix < n {
+            return Err(ParseError::TooFewRows);
+        }
+        return Ok(s);
+    }
+
+    pub fn shape(&self) -> BoxShape {
+        return self.shape;
+    }
+
+    // The grid as Display writes it, but with '-' for blanks and the digits in the
+    // alphabet, so parse_with reads it back.
+    pub fn to_text(&self, alphabet: Alphabet) -> String {
+        let n = self.shape.size() as usize;
+        let rows: Vec<String> = self
+            .cells
+            .chunks(n)
+            .map(|row| {
+                row.iter()
+                    .map(|&v| if v == 0 { '-' } else { alphabet.char(v) })
+                    .collect()
+            })
+            .collect();
+        return rows.join("\n");
+    }
+
+    // The digit at 1-based (row, col), or 0 if it is blank. Panics if either is off
+    // the grid.
+    pub fn get(&self, row: u8, col: u8) -> u8 {
+        let n = self.shape.size();
+        assert!(
+            (1..=n).contains(&row) && (1..=n).contains(&col),
+            "cell ({}, {}) out of range 1-{}",
+            row,
+            col,
+            n
+        );
+        return self.cells[(row as usize - 1) * n as usize + col as usize - 1];
+    }
+
+    // Find the unique solution by backtracking, filling in naked and hidden singles
+    // before each guess and guessing at the blank with the fewest candidates. That's
+    // enough for 16x16 puzzles, though far slower than Sudoku's own solver on 9x9.
+    pub fn solve(&self) -> Result<SizedSudoku, SizedUnsolveable> {
+        let found = solve_units(self.shape.size(), &self.shape.units(), &self.cells);
+        let mut found = found.into_iter().map(|cells| SizedSudoku {
+            shape: self.shape,
+            cells,
+        });
+        return match (found.next(), found.next()) {
+            (None, _) => Err(SizedUnsolveable::NoSolution),
+            (Some(s), None) => Ok(s),
+            (Some(a), Some(b)) => Err(SizedUnsolveable::MultipleSolutions(
+                Box::new(a),
+                Box::new(b),
+            )),
+        };
+    }
+}
+
+// The first two solutions, in search order, of cells under units, each a list of cell
+// indices that must hold the digits 1-size once each. A cell may be in any number of
+// units, so this solves grids of any layout. A conflict in the givens has none.
+pub(crate) fn solve_units(size: u8, units: &[Vec<usize>], cells: &[u8]) -> Vec<Vec<u8>> {
+    let mut peers: Vec<Vec<usize>> = vec![Vec::new(); cells.len()];
+    for unit in units {
+        for &i in unit {
+            peers[i].extend(unit.iter().filter(|&&j| j != i));
+        }
+    }
+    for p in &mut peers {
+        p.sort_unstable();
+        p.dedup();
+    }
+
+    for (i, &v) in cells.iter().enumerate() {
+        if v != 0 && peers[i].iter().any(|&j| cells[j] == v) {
+            return Vec::new();
+        }
+    }
+
+    let mut found = Vec::new();
+    search(size, units, &peers, cells.to_vec(), &mut found);
+    return found;
+}
+
+// Fill the blanks of cells, adding each solution to found. Every forced value goes in
+// first: a blank with one candidate left, or a digit with one place left in a unit.
+// Then each candidate of the blank with the fewest is tried in turn. Stops, returning
+// false, as soon as a second solution turns up.
+fn search(
+    size: u8,
+    units: &[Vec<usize>],
+    peers: &[Vec<usize>],
+    mut cells: Vec<u8>,
+    found: &mut Vec<Vec<u8>>,
+) -> bool {
+    // Candidate bitmasks use bit v for digit v.
+    let full: u32 = ((1 << size) - 1) << 1;
+    let candidates = |cells: &[u8], i: usize| {
+        return full & !peers[i].iter().fold(0u32, |m, &j| m | (1 << cells[j]));
+    };
+    let best = loop {
+        let mut forced = false;
+        let mut best: Option<(usize, u32)> = None;
+        for i in 0..cells.len() {
+            if cells[i] != 0 {
+                continue;
+            }
+            let ps = candidates(&cells, i);
+            match ps.count_ones() {
+                0 => return true,
+                1 => {
+                    cells[i] = ps.trailing_zeros() as u8;
+                    forced = true;
+                }
+                n => {
+                    if best.is_none_or(|(_, b)| n < b.count_ones()) {
+                        best = Some((i, ps));
+                    }
+                }
+            }
+        }
+        if forced {
+            continue;
+        }
+        // Each unit's digits with a place in one blank, once, or in more, twice.
+        for unit in units {
+            let (mut placed, mut once, mut twice) = (0u32, 0u32, 0u32);
+            for &i in unit {
+                match cells[i] {
+                    0 => {
+                        let ps = candidates(&cells, i);
+                        twice |= once & ps;
+                        once |= ps;
+                    }
+                    v => placed |= 1 << v,
+                }
+            }
+            if full & !placed & !once != 0 {
+                return true;
+            }
+            let single = once & !twice & !placed;
+            if single != 0 {
+                let v = single.trailing_zeros();
+                let &i = unit
+                    .iter()
+                    .find(|&&i| cells[i] == 0 && candidates(&cells, i) & (1 << v) != 0)
+                    .unwrap();
+                cells[i] = v as u8;
+                forced = true;
+                break;
+            }
+        }
+        if !forced {
+            break best;
+        }
+    };
+
+    let (i, candidates) = match best {
+        None => {
+            found.push(cells);
+            return found.len() < 2;
+        }
+        Some(b) => b,
+    };
+    for v in 1..=size {
+        if candidates & (1 << v) == 0 {
+            continue;
+        }
+        let mut next = cells.clone();
+        next[i] = v;
+        if !search(size, units, peers, next, found) {
+            return false;
+        }
+    }
+    return true;
+}
+
+impl fmt::Display for SizedSudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.shape.size() as usize;
+        for (rix, row) in self.cells.chunks(n).enumerate() {
+            if rix > 0 {
+                writeln!(f)?;
+            }
+            for &v in row {
+                write!(f, "{}", digit_char(v))?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+// Rows separated by '/', with '.' for blanks, as Sudoku's Debug does.
+impl fmt::Debug for SizedSudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = self.shape.size() as usize;
+        let rows: Vec<String> = self
+            .cells
+            .chunks(n)
+            .map(|row| {
+                row.iter()
+                    .map(|&v| if v == 0 { '.' } else { digit_char(v) })
+                    .collect()
+            })
+            .collect();
+        return write!(
+            f,
+            "SizedSudoku({}x{}: {})",
+            self.shape.rows,
+            self.shape.cols,
+            rows.join("/")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{box_coords, Coord};
+
+    const SIX: &str = include_str!("../tests/fixtures/sizes/six.txt");
+    const SIX_SOLVED: &str = "123456\n456123\n231564\n564231\n312645\n645312";
+
+    #[test]
+    fn test_classic_shape() {
+        for ix in 1..10 {
+            for (j, c) in box_coords(ix).iter().enumerate() {
+                assert_eq!(
+                    BoxShape::CLASSIC.box_cell(ix, j as u8 + 1),
+                    (c.row(), c.col())
+                );
+            }
+        }
+        for c in Coord::all() {
+            assert_eq!(BoxShape::CLASSIC.box_of(c.row(), c.col()), c.square());
+        }
+        assert_eq!(BoxShape::new(3, 3), Ok(BoxShape::CLASSIC));
+    }
+
+    #[test]
+    fn test_rectangular_boxes() {
+        let shape = BoxShape::new(2, 3).unwrap();
+        assert_eq!(shape.size(), 6);
+        // Boxes are two rows tall and three columns wide, two to a band.
+        let cells: Vec<(u8, u8)> = (1..7).map(|j| shape.box_cell(4, j)).collect();
+        assert_eq!(cells, vec![(3, 4), (3, 5), (3, 6), (4, 4), (4, 5), (4, 6)]);
+        assert_eq!(shape.box_of(4, 4), 4);
+        assert_eq!(shape.box_of(6, 1), 5);
+
+        let tall = BoxShape::new(3, 2).unwrap();
+        let cells: Vec<(u8, u8)> = (1..7).map(|j| tall.box_cell(2, j)).collect();
+        assert_eq!(cells, vec![(1, 3), (1, 4), (2, 3), (2, 4), (3, 3), (3, 4)]);
+
+        for (rows, cols) in [(2, 3), (3, 2), (2, 4), (3, 4), (4, 3)] {
+            let shape = BoxShape::new(rows, cols).unwrap();
+            let n = shape.size();
+            for ix in 1..=n {
+                for j in 1..=n {
+                    let (r, c) = shape.box_cell(ix, j);
+                    assert!(r <= n && c <= n);
+                    assert_eq!(shape.box_of(r, c), ix);
+                }
+            }
+            // Each cell is in exactly one row, column, and box.
+            let units = shape.units();
+            assert_eq!(units.len(), 3 * n as usize);
+            let mut counts = vec![0; n as usize * n as usize];
+            for unit in &units {
+                for &i in unit {
+                    counts[i] += 1;
+                }
+            }
+            assert!(counts.iter().all(|&k| k == 3));
+        }
+
+        assert_eq!(
+            BoxShape::new(0, 3),
+            Err(GridError::BoxShapeOutOfRange(0, 3))
+        );
+        assert_eq!(
+            BoxShape::new(4, 5),
+            Err(GridError::BoxShapeOutOfRange(4, 5))
+        );
+    }
+
+    #[test]
+    fn test_rectangular_units() {
+        // 2x3 boxes: three columns wide, so two boxes to a band and three bands.
+        let units = BoxShape::new(2, 3).unwrap().units();
+        assert_eq!(units[0], [0, 1, 2, 3, 4, 5]);
+        assert_eq!(units[1], [0, 6, 12, 18, 24, 30]);
+        assert_eq!(units[2], [0, 1, 2, 6, 7, 8]);
+        assert_eq!(units[5], [3, 4, 5, 9, 10, 11]);
+        assert_eq!(units[8], [12, 13, 14, 18, 19, 20]);
+        assert_eq!(units[17], [27, 28, 29, 33, 34, 35]);
+
+        // 3x2 boxes: two columns wide, so three boxes to a band and two bands.
+        let units = BoxShape::new(3, 2).unwrap().units();
+        assert_eq!(units[2], [0, 1, 6, 7, 12, 13]);
+        assert_eq!(units[5], [2, 3, 8, 9, 14, 15]);
+        assert_eq!(units[8], [4, 5, 10, 11, 16, 17]);
+        assert_eq!(units[11], [18, 19, 24, 25, 30, 31]);
+        assert_eq!(units[17], [22, 23, 28, 29, 34, 35]);
+    }
+
+    #[test]
+    fn test_solve_six() {
+        let shape = BoxShape::new(2, 3).unwrap();
+        let s = SizedSudoku::parse(shape, SIX).unwrap();
+        assert_eq!(s.get(1, 3), 3);
+        assert_eq!(s.get(1, 2), 0);
+        let solved = s.solve().unwrap();
+        assert_eq!(solved.to_string(), SIX_SOLVED);
+        assert_eq!(
+            format!("{:?}", s),
+            "SizedSudoku(2x3: 1.3.5./.....3/....6./5..2.1/3....5/.4....)"
+        );
+
+        match SizedSudoku::empty(shape).solve() {
+            Err(SizedUnsolveable::MultipleSolutions(a, b)) => {
+                assert_ne!(a, b);
+                assert_eq!(a.solve().as_ref(), Ok(&*a));
+            }
+            other => panic!("expected multiple solutions, got {:?}", other),
+        }
+        let clash = SIX.replacen("1-3", "1-1", 1);
+        let clash = SizedSudoku::parse(shape, &clash).unwrap();
+        assert_eq!(clash.solve(), Err(SizedUnsolveable::NoSolution));
+    }
+
+    const FOUR: &str = include_str!("../tests/fixtures/sizes/four.txt");
+    const SIXTEEN: &str = include_str!("../tests/fixtures/sizes/sixteen.txt");
+
+    #[test]
+    fn test_for_size() {
+        assert_eq!(BoxShape::for_size(9), Ok(BoxShape::CLASSIC));
+        assert_eq!(BoxShape::for_size(4), BoxShape::new(2, 2));
+        assert_eq!(BoxShape::for_size(16), BoxShape::new(4, 4));
+        assert_eq!(BoxShape::for_size(6), BoxShape::new(2, 3));
+        assert_eq!(BoxShape::for_size(8), BoxShape::new(2, 4));
+        assert_eq!(BoxShape::for_size(12), BoxShape::new(3, 4));
+        assert_eq!(BoxShape::for_size(1), BoxShape::new(1, 1));
+        for size in [0, 2, 5, 7, 13, 18, 25] {
+            assert_eq!(
+                BoxShape::for_size(size),
+                Err(GridError::SizeOutOfRange(size))
+            );
+        }
+        assert_eq!(
+            GridError::SizeOutOfRange(5).to_string(),
+            "no box shape fits a 5x5 grid"
+        );
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let letters: String = (1..=16).map(|v| Alphabet::Letters.char(v)).collect();
+        assert_eq!(letters, "123456789ABCDEFG");
+        let hex: String = (1..=16).map(|v| Alphabet::Hex.char(v)).collect();
+        assert_eq!(hex, "0123456789ABCDEF");
+        for v in 1..=16 {
+            for alphabet in [Alphabet::Letters, Alphabet::Hex] {
+                let c = alphabet.char(v);
+                assert_eq!(alphabet.digit(c), Some(v));
+                assert_eq!(alphabet.digit(c.to_ascii_lowercase()), Some(v));
+            }
+        }
+        assert_eq!(Alphabet::Letters.digit('0'), None);
+        assert_eq!(Alphabet::Hex.digit('G'), None);
+        assert_eq!(Alphabet::Letters.digit('-'), None);
+    }
+
+    #[test]
+    fn test_solve_four_and_sixteen() {
+        let four = BoxShape::for_size(4).unwrap();
+        let s = SizedSudoku::parse(four, FOUR).unwrap();
+        let solved = s.solve().unwrap();
+        assert_eq!(solved.to_string(), "3214\n1432\n2341\n4123");
+        assert_eq!(
+            SizedSudoku::parse(four, &s.to_text(Alphabet::Letters)),
+            Ok(s)
+        );
+
+        let sixteen = BoxShape::for_size(16).unwrap();
+        let s = SizedSudoku::parse(sixteen, SIXTEEN).unwrap();
+        assert_eq!(s.get(1, 3), 10);
+        assert_eq!(s.get(1, 10), 16);
+        let solved = s.solve().unwrap();
+        assert!(solved.to_string().starts_with("C1A795BE4GDF3862\n"));
+        for unit in sixteen.units() {
+            let mut values: Vec<u8> = unit.iter().map(|&i| solved.cells[i]).collect();
+            values.sort();
+            assert_eq!(values, (1..=16).collect::<Vec<u8>>());
+        }
+        // The same grid written in hex, where '0' is 1 and 'F' is 16.
+        let hex = s.to_text(Alphabet::Hex);
+        assert!(hex.starts_with("--9-8----F--27--\n"));
+        assert_eq!(
+            SizedSudoku::parse_with(sixteen, Alphabet::Hex, &hex),
+            Ok(s.clone())
+        );
+        // Read as hex, the letters grid's G is skipped, leaving its first row short.
+        assert_eq!(
+            SizedSudoku::parse_with(sixteen, Alphabet::Hex, SIXTEEN),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(solved.solve(), Ok(solved.clone()));
+    }
+
+    #[test]
+    fn test_inconsistent_dimensions() {
+        let four = BoxShape::for_size(4).unwrap();
+        let cases = [
+            // A row too long and a row too short.
+            ("3--4-\n---2\n--4-\n41--\n", ParseError::TooManyCharacters),
+            ("3--4\n--2\n--4-\n41--\n", ParseError::TooFewCharacters),
+            // A row missing, and one too many.
+            ("3--4\n---2\n--4-\n", ParseError::TooFewRows),
+            (
+                "3--4\n---2\n--4-\n41--\n----\n",
+                ParseError::TooManyCharacters,
+            ),
+            // A 9x9 grid read as 4x4, and a 4x4 one with a digit too large.
+            (
+                include_str!("../../moderate.txt"),
+                ParseError::TooManyCharacters,
+            ),
+            ("3--4\n---2\n--5-\n41--\n", ParseError::DigitOutOfRange),
+        ];
+        for (text, e) in cases {
+            assert_eq!(SizedSudoku::parse(four, text), Err(e), "{:?}", text);
+        }
+        // A 4x4 grid read as 16x16.
+        assert_eq!(
+            SizedSudoku::parse(BoxShape::for_size(16).unwrap(), FOUR),
+            Err(ParseError::TooFewCharacters)
+        );
+    }
+
+    #[test]
+    fn test_sized_from_reader() {
+        let shape = BoxShape::new(2, 3).unwrap();
+        assert_eq!(
+            SizedSudoku::parse(shape, "1-3-5\n"),
+            Err(ParseError::TooFewCharacters)
+        );
+        assert_eq!(
+            SizedSudoku::parse(shape, "1-3-5-\n"),
+            Err(ParseError::TooFewRows)
+        );
+        let seven = SIX.replacen("1-3", "7-3", 1);
+        assert_eq!(
+            SizedSudoku::parse(shape, &seven),
+            Err(ParseError::DigitOutOfRange)
+        );
+        #[cfg(feature = "std")]
+        assert_eq!(
+            SizedSudoku::from_reader(shape, seven.as_bytes())
+                .unwrap_err()
+                .to_string(),
+            "Digit out of range for the grid"
+        );
+
+        // Letters stand for 10 and up on larger grids.
+        let twelve = BoxShape::new(3, 4).unwrap();
+        let mut text = String::from("1-3-5-7-9ABC\n");
+        for _ in 1..12 {
+            text.push_str("------------\n");
+        }
+        let s = SizedSudoku::parse(twelve, &text).unwrap();
+        assert_eq!(s.get(1, 12), 12);
+        assert!(s.to_string().starts_with("1 3 5 7 9ABC\n"));
+    }
+}
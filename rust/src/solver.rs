@@ -0,0 +1,356 @@
+// A Solver owns one puzzle's working grid along with the options and running totals
+// for solving it, so settings don't have to be threaded through every call.
+//
+// Thread safety: Sudoku, UnsolvedSudoku, Solver, and the config and report types are all
+// Send + Sync. There is no global mutable state; the one shared value is the classic
+// unit layout, which is built once behind a OnceLock and never changed. So separate
+// Solvers (or separate grids) can be used from as many threads at once as you like. A
+// Solver's methods that take &self are safe to call from several threads through a
+// shared reference; solve takes &mut self, so Rust already stops two threads running
+// it on the same Solver.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::{Solutions, Strategy, Sudoku, Technique, Unsolveable, UnsolvedSudoku};
+
+// Which cell the search guesses in once deductions run out. Every choice finds the same
+// solutions; they differ in how many guesses it takes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Branching {
+    // The first cell in row-major order with the fewest candidates, as
+    // UnsolvedSudoku::min_candidates_cell.
+    #[default]
+    FewestCandidates,
+    // Of the cells with the fewest candidates, the one whose candidates are rarest
+    // among its peers, as UnsolvedSudoku::rarest_candidates_cell. This makes fewer
+    // guesses on most hard puzzles, but scoring the tied cells costs more than it
+    // saves on typical ones, so it isn't the default.
+    RarestCandidates,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SolverConfig {
+    // Give up once this many guesses have been made. The default is no limit.
+    pub guess_budget: u64,
+    pub branching: Branching,
+}
+
+impl Default for SolverConfig {
+    fn default() -> SolverConfig {
+        return SolverConfig {
+            guess_budget: u64::MAX,
+            branching: Branching::default(),
+        };
+    }
+}
+
+// The outcome of one Solver::solve call, or of UnsolvedSudoku::solve_report.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SolveReport {
+    // None if the guess budget ran out first.
+    pub result: Option<Result<Sudoku, Unsolveable>>,
+    pub guesses: u64,
+    // The most guesses stacked on one branch at once; 0 if none were needed.
+    pub max_depth: u32,
+    // The deductions that filled at least one cell, easiest first. Guesses aren't
+    // among them.
+    pub techniques_used: Vec<Technique>,
+    // Time spent in the search. Without std there is no clock to measure it by.
+    #[cfg(feature = "std")]
+    pub elapsed: std::time::Duration,
+}
+
+impl SolveReport {
+    // Whether the search found a solution, unique or not.
+    pub fn solved(&self) -> bool {
+        return matches!(
+            self.result,
+            Some(Ok(_)) | Some(Err(Unsolveable::MultipleSolutions(..)))
+        );
+    }
+
+    // Whether it found exactly one.
+    pub fn unique(&self) -> bool {
+        return matches!(self.result, Some(Ok(_)));
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Solver {
+    grid: UnsolvedSudoku,
+    config: SolverConfig,
+    // Run before each guess, in order; see UnsolvedSudoku::apply_strategies.
+    strategies: Vec<Arc<dyn Strategy>>,
+    // Totals over every solve call so far.
+    runs: u64,
+    guesses: u64,
+}
+
+impl Solver {
+    pub fn new(grid: UnsolvedSudoku) -> Solver {
+        return Solver::with_config(grid, SolverConfig::default());
+    }
+
+    pub fn with_config(grid: UnsolvedSudoku, config: SolverConfig) -> Solver {
+        return Solver::with_strategies(grid, config, Vec::new());
+    }
+
+    // A solver that runs the strategies to a fixpoint, along with the hidden singles it
+    // always looks for, before every guess. A strategy that makes deductions the search
+    // would otherwise guess its way to saves guesses, though it may cost more time
+    // than it saves.
+    pub fn with_strategies(
+        grid: UnsolvedSudoku,
+        config: SolverConfig,
+        strategies: Vec<Box<dyn Strategy>>,
+    ) -> Solver {
+        return Solver {
+            grid,
+            config,
+            strategies: strategies.into_iter().map(Arc::from).collect(),
+            runs: 0,
+            guesses: 0,
+        };
+    }
+
+    // The working grid. After solve it holds whatever was deduced before the search
+    // started guessing, just as dynamic_solve leaves its grid.
+    pub fn grid(&self) -> &UnsolvedSudoku {
+        return &self.grid;
+    }
+
+    pub fn config(&self) -> SolverConfig {
+        return self.config;
+    }
+
+    pub fn set_config(&mut self, config: SolverConfig) {
+        self.config = config;
+    }
+
+    pub fn strategies(&self) -> &[Arc<dyn Strategy>] {
+        return &self.strategies;
+    }
+
+    // Search for the unique solution within the configured guess budget.
+    pub fn solve(&mut self) -> SolveReport {
+        return self.run(None);
+    }
+
+    // Like solve, but calls on_guess with the running guess count after every guess, so
+    // a caller can show progress. Returning false stops the search, and then the
+    // result is None, as when the budget runs out.
+    pub fn solve_with_callback(&mut self, mut on_guess: impl FnMut(u64) -> bool) -> SolveReport {
+        return self.run(Some(&mut on_guess));
+    }
+
+    fn run(&mut self, on_guess: Option<&mut dyn FnMut(u64) -> bool>) -> SolveReport {
+        let report = self
+            .grid
+            .configured_report(self.config, on_guess, &self.strategies);
+        self.runs += 1;
+        self.guesses += report.guesses;
+        return report;
+    }
+
+    // Every solution of the working grid; see UnsolvedSudoku::solutions. This doesn't
+    // touch the grid, so it can run alongside other readers.
+    pub fn solutions(&self) -> Solutions {
+        return self.grid.solutions();
+    }
+
+    // How many times solve has been called.
+    pub fn runs(&self) -> u64 {
+        return self.runs;
+    }
+
+    // Guesses made over all calls to solve.
+    pub fn total_guesses(&self) -> u64 {
+        return self.guesses;
+    }
+
+    pub fn into_grid(self) -> UnsolvedSudoku {
+        return self.grid;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_send_sync() {
+        assert_send_sync::<Sudoku>();
+        assert_send_sync::<UnsolvedSudoku>();
+        assert_send_sync::<Solver>();
+        assert_send_sync::<SolverConfig>();
+        assert_send_sync::<SolveReport>();
+        assert_send_sync::<Solutions>();
+    }
+
+    fn challenge() -> Sudoku {
+        return include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_solver() {
+        let s = challenge();
+        let mut solver = Solver::new(s.into());
+        assert_eq!(solver.config(), SolverConfig::default());
+        let report = solver.solve();
+        assert_eq!(report.result, Some(s.solve()));
+        assert_eq!(report.guesses, 12);
+        assert_eq!((solver.runs(), solver.total_guesses()), (1, 12));
+        assert_eq!(solver.solutions().count(), 1);
+
+        let mut limited = Solver::with_config(
+            s.into(),
+            SolverConfig {
+                guess_budget: 3,
+                ..Default::default()
+            },
+        );
+        let report = limited.solve();
+        assert_eq!(report.result, None);
+        assert_eq!(report.guesses, 3);
+        limited.set_config(SolverConfig::default());
+        assert_eq!(limited.solve().result, Some(s.solve()));
+        assert_eq!(limited.runs(), 2);
+        assert!(limited.total_guesses() > 3);
+    }
+
+    #[test]
+    fn test_solve_with_callback() {
+        let s = challenge();
+        let mut seen = Vec::new();
+        let report = Solver::new(s.into()).solve_with_callback(|n| {
+            seen.push(n);
+            return true;
+        });
+        assert_eq!(report.result, Some(s.solve()));
+        assert_eq!(seen, (1..=12).collect::<Vec<u64>>());
+
+        // Stopping from the callback, and the budget still applying alongside it.
+        let report = Solver::new(s.into()).solve_with_callback(|n| n < 5);
+        assert_eq!((report.result, report.guesses), (None, 5));
+        let mut limited = Solver::with_config(
+            s.into(),
+            SolverConfig {
+                guess_budget: 3,
+                ..Default::default()
+            },
+        );
+        assert_eq!(limited.solve_with_callback(|_| true).guesses, 3);
+    }
+
+    #[test]
+    fn test_solve_report() {
+        let s = challenge();
+        let report = UnsolvedSudoku::from(s).solve_report();
+        assert!(report.solved() && report.unique());
+        assert_eq!(report.result, Some(s.solve()));
+        assert_eq!((report.guesses, report.max_depth), (12, 4));
+        assert_eq!(
+            report.techniques_used,
+            [Technique::NakedSingle, Technique::HiddenSingle]
+        );
+        let mut solver = Solver::new(s.into());
+        let from_solver = solver.solve();
+        assert_eq!(from_solver.max_depth, report.max_depth);
+        assert_eq!(from_solver.techniques_used, report.techniques_used);
+
+        // Nothing left to deduce or guess.
+        let full = UnsolvedSudoku::from(s.solve().unwrap()).solve_report();
+        assert!(full.unique());
+        assert_eq!((full.guesses, full.max_depth), (0, 0));
+        assert_eq!(full.techniques_used, []);
+
+        let empty = UnsolvedSudoku::empty().solve_report();
+        assert!(empty.solved() && !empty.unique());
+        assert!(empty.max_depth > 0 && u64::from(empty.max_depth) <= empty.guesses);
+
+        let mut clash = s;
+        clash[crate::Coord::new(1, 1).unwrap()] = s[crate::Coord::new(1, 9).unwrap()];
+        let none = UnsolvedSudoku::from(clash).solve_report();
+        assert!(!none.solved() && !none.unique());
+        assert_eq!(none.result, Some(Err(Unsolveable::NoSolution)));
+
+        let mut limited = Solver::with_config(
+            s.into(),
+            SolverConfig {
+                guess_budget: 1,
+                ..Default::default()
+            },
+        );
+        let stopped = limited.solve();
+        assert_eq!(stopped.result, None);
+        assert!(!stopped.solved());
+    }
+
+    // Guesses over a fixed set of generated puzzles, the challenge puzzle among them, with
+    // each branching. Rarest-candidates branching loses on some puzzles, but saves far
+    // more on the puzzles that take the most guessing.
+    #[test]
+    fn test_branching() {
+        let mut rng = crate::SolverRng::new(11);
+        let mut puzzles = vec![challenge()];
+        for _ in 0..20 {
+            puzzles.push(Sudoku::generate_puzzle_with_rng(
+                &mut rng,
+                crate::GeneratorOptions::default(),
+            ));
+        }
+        let mut totals = Vec::new();
+        for branching in [Branching::FewestCandidates, Branching::RarestCandidates] {
+            let config = SolverConfig {
+                branching,
+                ..Default::default()
+            };
+            let mut guesses = Vec::new();
+            for &p in &puzzles {
+                let report = Solver::with_config(p.into(), config).solve();
+                assert_eq!(report.result, Some(p.solve()));
+                guesses.push(report.guesses);
+            }
+            totals.push(guesses);
+        }
+        assert_eq!((totals[0][0], totals[1][0]), (12, 14));
+        let sums: Vec<u64> = totals.iter().map(|g| g.iter().sum()).collect();
+        assert_eq!(sums, [188, 108]);
+    }
+
+    #[test]
+    fn test_concurrent_solves() {
+        let s = challenge();
+        let expected = s.solve().unwrap();
+        let shared = Solver::new(s.into());
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let shared = &shared;
+                    scope.spawn(move || {
+                        let mut own = Solver::new(s.into());
+                        let report = own.solve();
+                        let first = shared.solutions().next();
+                        return (report, first);
+                    })
+                })
+                .collect();
+            for h in handles {
+                let (report, first) = h.join().unwrap();
+                assert_eq!(report.result, Some(Ok(expected)));
+                assert_eq!(report.guesses, 12);
+                assert_eq!(first, Some(expected));
+            }
+        });
+    }
+}
@@ -0,0 +1,132 @@
+// The exit statuses of solve --status-only, which reports through them alone, and of
+// check. The numbers are a contract with scripts and must not change:
+//
+//   0  the puzzle has exactly one solution
+//   2  it has no solution
+//   3  it has more than one
+//   4  it couldn't be read or parsed
+//   5  the search gave up at --max-guesses
+//
+// With several puzzles the run exits with the worst of them, the highest number.
+
+use std::fmt;
+
+use sudokusolver::{SizedSudoku, SizedUnsolveable, Sudoku, Unsolveable};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Status {
+    Unique,
+    NoSolution,
+    Multiple,
+    Unreadable,
+    Stopped,
+}
+
+impl Status {
+    pub const ALL: [Status; 5] = [
+        Status::Unique,
+        Status::NoSolution,
+        Status::Multiple,
+        Status::Unreadable,
+        Status::Stopped,
+    ];
+
+    pub fn exit_code(self) -> i32 {
+        return match self {
+            Status::Unique => 0,
+            Status::NoSolution => 2,
+            Status::Multiple => 3,
+            Status::Unreadable => 4,
+            Status::Stopped => 5,
+        };
+    }
+
+    // The status of a search's result, None meaning it ran out of guesses.
+    pub fn of(result: &Option<Result<Sudoku, Unsolveable>>) -> Status {
+        return match result {
+            Some(Ok(_)) => Status::Unique,
+            Some(Err(Unsolveable::MultipleSolutions(..))) => Status::Multiple,
+            Some(Err(_)) => Status::NoSolution,
+            None => Status::Stopped,
+        };
+    }
+
+    // The status of a SizedSudoku's solve, which always runs to the end.
+    pub fn of_sized(result: &Result<SizedSudoku, SizedUnsolveable>) -> Status {
+        return match result {
+            Ok(_) => Status::Unique,
+            Err(SizedUnsolveable::MultipleSolutions(..)) => Status::Multiple,
+            Err(_) => Status::NoSolution,
+        };
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Unique => "unique",
+            Status::NoSolution => "no solution",
+            Status::Multiple => "multiple solutions",
+            Status::Unreadable => "unreadable",
+            Status::Stopped => "gave up",
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+// One line counting each status that came up, like "4 puzzles: 2 unique, 1 no
+// solution, 1 unreadable".
+pub fn summary(statuses: &[Status]) -> String {
+    let counts: Vec<String> = Status::ALL
+        .iter()
+        .map(|&s| (s, statuses.iter().filter(|&&t| t == s).count()))
+        .filter(|&(_, n)| n > 0)
+        .map(|(s, n)| format!("{} {}", n, s))
+        .collect();
+    return format!("{} puzzles: {}", statuses.len(), counts.join(", "));
+}
+
+// The status to exit with: the worst, or 0 if there were no puzzles.
+pub fn worst(statuses: &[Status]) -> i32 {
+    return statuses.iter().max().map_or(0, |s| s.exit_code());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let codes: Vec<i32> = Status::ALL.iter().map(|s| s.exit_code()).collect();
+        assert_eq!(codes, [0, 2, 3, 4, 5]);
+        // Worse statuses sort later, so the worst is the maximum.
+        assert!(Status::ALL.windows(2).all(|w| w[0] < w[1]));
+
+        let s = Sudoku::empty();
+        assert_eq!(Status::of(&Some(Ok(s))), Status::Unique);
+        assert_eq!(
+            Status::of(&Some(Err(Unsolveable::NoSolution))),
+            Status::NoSolution
+        );
+        let multiple = Unsolveable::MultipleSolutions(Box::new(s), Box::new(s));
+        assert_eq!(Status::of(&Some(Err(multiple))), Status::Multiple);
+        assert_eq!(Status::of(&None), Status::Stopped);
+    }
+
+    #[test]
+    fn test_worst_and_summary() {
+        let statuses = [
+            Status::Unique,
+            Status::Unreadable,
+            Status::NoSolution,
+            Status::Unique,
+        ];
+        assert_eq!(worst(&statuses), 4);
+        assert_eq!(worst(&[Status::Unique]), 0);
+        assert_eq!(worst(&[]), 0);
+        assert_eq!(
+            summary(&statuses),
+            "4 puzzles: 2 unique, 1 no solution, 1 unreadable"
+        );
+    }
+}
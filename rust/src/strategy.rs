@@ -0,0 +1,137 @@
+// Deductions the solver can be given to run before it guesses. Each Technique is one,
+// and any type implementing Strategy can join them, so a caller can add a technique of
+// their own without touching the search:
+//
+//     let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(Technique::XWing)];
+//     let mut solver = Solver::with_strategies(grid, SolverConfig::default(), strategies);
+//     let report = solver.solve();
+//
+// Hidden singles and cage sums always run as well, between strategies, so a strategy
+// only has to make progress the search couldn't already.
+
+use alloc::sync::Arc;
+use core::fmt;
+
+use crate::{Technique, UnsolvedSudoku};
+
+// Send + Sync so a Solver holding strategies stays shareable between threads.
+pub trait Strategy: Send + Sync {
+    // Make whatever deductions the strategy can, returning how many cells it filled or
+    // candidates it removed; 0 means it found nothing. It must only remove candidates
+    // the grid rules out, or the search can lose solutions.
+    fn apply(&self, grid: &mut UnsolvedSudoku) -> usize;
+
+    // A short name for messages and logs.
+    fn name(&self) -> &str;
+}
+
+impl fmt::Debug for dyn Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "Strategy({:?})", self.name());
+    }
+}
+
+impl Strategy for Technique {
+    // Naked singles have nothing to do: a cell left with one candidate is fixed as soon
+    // as the last other is removed.
+    fn apply(&self, grid: &mut UnsolvedSudoku) -> usize {
+        return match self {
+            Technique::NakedSingle => 0,
+            Technique::HiddenSingle => grid.hidden_singles(),
+            Technique::XWing => grid.eliminate_fish(2),
+            Technique::Swordfish => grid.eliminate_fish(3),
+            Technique::Jellyfish => grid.eliminate_fish(4),
+            Technique::XyzWing => grid.eliminate_xyz_wing(),
+            Technique::NakedPair => grid.eliminate_naked_pairs(),
+            Technique::Pointing => grid.eliminate_pointing(),
+        };
+    }
+
+    fn name(&self) -> &str {
+        return Technique::name(*self);
+    }
+}
+
+impl UnsolvedSudoku {
+    // Run the strategies until none of them makes progress, trying each in order and
+    // going back to the first after any that does, so the easier ones listed first are
+    // always tried before a harder one. Returns the total progress they reported.
+    pub fn apply_strategies(&mut self, strategies: &[Arc<dyn Strategy>]) -> usize {
+        let mut total = 0;
+        'outer: loop {
+            for strategy in strategies {
+                let progress = strategy.apply(self);
+                if progress > 0 {
+                    log_trace!("{} made {} deductions", strategy.name(), progress);
+                    total += progress;
+                    continue 'outer;
+                }
+            }
+            return total;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Solver, SolverConfig, Sudoku};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    fn challenge() -> Sudoku {
+        return include_str!("../../challenge.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_techniques() {
+        let s = challenge();
+        let mut u = UnsolvedSudoku::from(s);
+        let mut copy = u.clone();
+        assert_eq!(Technique::HiddenSingle.apply(&mut u), copy.hidden_singles());
+        assert_eq!(u, copy);
+        assert_eq!(Technique::NakedSingle.apply(&mut u), 0);
+        assert_eq!(Technique::XWing.apply(&mut u), copy.eliminate_fish(2));
+        assert_eq!(
+            Technique::NakedPair.apply(&mut u),
+            copy.eliminate_naked_pairs()
+        );
+        assert_eq!(Technique::Pointing.apply(&mut u), copy.eliminate_pointing());
+        assert_eq!(u, copy);
+        assert_eq!(Strategy::name(&Technique::XyzWing), "XYZ-wing");
+
+        let all: Vec<Arc<dyn Strategy>> = Technique::ALL
+            .iter()
+            .map(|&t| Arc::new(t) as Arc<dyn Strategy>)
+            .collect();
+        let mut u = UnsolvedSudoku::from(s);
+        assert!(u.apply_strategies(&all) > 0);
+        assert_eq!(u.apply_strategies(&all), 0, "already at a fixpoint");
+        assert_eq!(format!("{:?}", all[4]), "Strategy(\"X-wing\")");
+    }
+
+    #[test]
+    fn test_pairs_and_pointing() {
+        let s = challenge();
+        let plain = Solver::new(s.into()).solve();
+        let strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(Technique::Pointing),
+            Box::new(Technique::NakedPair),
+        ];
+        let report = Solver::with_strategies(s.into(), SolverConfig::default(), strategies).solve();
+        assert_eq!(report.result, plain.result);
+        assert!(report.guesses < plain.guesses, "{}", report.guesses);
+
+        // Every solution is still found with strategies at work, as on an empty grid.
+        let strategies: Vec<Box<dyn Strategy>> = Technique::ALL
+            .iter()
+            .map(|&t| Box::new(t) as Box<dyn Strategy>)
+            .collect();
+        let mut solver =
+            Solver::with_strategies(UnsolvedSudoku::empty(), SolverConfig::default(), strategies);
+        assert!(solver.solve().solved());
+        assert!(!solver.solve().unique());
+    }
+}
@@ -0,0 +1,288 @@
+// Moves that turn one valid grid into another: rotations, reflections, swapping rows
+// within a band or whole bands (and likewise columns and stacks), and relabelling the
+// digits. Each returns a new grid, so they chain: s.rotate90().relabel(p).transpose().
+// Blank cells stay blank, so a puzzle and its solution can be transformed alike.
+//
+// Bands are the three horizontal strips of three rows, numbered 1-3 from the top; stacks
+// are the three vertical strips of columns, numbered 1-3 from the left. Rows and columns
+// within a band or stack are numbered 1-3 too.
+//
+// shuffle strings these moves together at random. Like the seeds in rng.rs, the grid a
+// seed shuffles to is part of the crate's interface and must not change between
+// releases.
+
+use crate::{Coord, Seed, SolverRng, Sudoku};
+
+impl Sudoku {
+    // Build a grid whose cell (r, c) comes from cell from(r, c) of this one.
+    fn remap(&self, from: impl Fn(u8, u8) -> (u8, u8)) -> Sudoku {
+        let mut out = Sudoku::empty();
+        for c in Coord::all() {
+            let (r0, c0) = from(c.row(), c.col());
+            out[c] = self[Coord::new(r0, c0).expect("transforms stay on the grid")];
+        }
+        return out;
+    }
+
+    // A quarter turn clockwise.
+    pub fn rotate90(&self) -> Sudoku {
+        return self.remap(|r, c| (10 - c, r));
+    }
+
+    pub fn rotate180(&self) -> Sudoku {
+        return self.remap(|r, c| (10 - r, 10 - c));
+    }
+
+    // A quarter turn anticlockwise.
+    pub fn rotate270(&self) -> Sudoku {
+        return self.remap(|r, c| (c, 10 - r));
+    }
+
+    // Swap rows and columns, reflecting in the main diagonal.
+    pub fn transpose(&self) -> Sudoku {
+        return self.remap(|r, c| (c, r));
+    }
+
+    // Swap left and right.
+    pub fn mirror_horizontal(&self) -> Sudoku {
+        return self.remap(|r, c| (r, 10 - c));
+    }
+
+    // Swap top and bottom.
+    pub fn mirror_vertical(&self) -> Sudoku {
+        return self.remap(|r, c| (10 - r, c));
+    }
+
+    // Swap rows a and b of the given band. Panics if any index is outside 1-3.
+    pub fn swap_rows_within_band(&self, band: u8, a: u8, b: u8) -> Sudoku {
+        let (a, b) = (line_in(band, a), line_in(band, b));
+        return self.remap(|r, c| (swap(r, a, b), c));
+    }
+
+    // Swap columns a and b of the given stack. Panics if any index is outside 1-3.
+    pub fn swap_cols_within_stack(&self, stack: u8, a: u8, b: u8) -> Sudoku {
+        let (a, b) = (line_in(stack, a), line_in(stack, b));
+        return self.remap(|r, c| (r, swap(c, a, b)));
+    }
+
+    // Swap bands a and b, keeping the order of rows within each. Panics if either is
+    // outside 1-3.
+    pub fn swap_bands(&self, a: u8, b: u8) -> Sudoku {
+        check_third(a);
+        check_third(b);
+        return self.remap(|r, c| (swap_strip(r, a, b), c));
+    }
+
+    // Swap stacks a and b. Panics if either is outside 1-3.
+    pub fn swap_stacks(&self, a: u8, b: u8) -> Sudoku {
+        check_third(a);
+        check_third(b);
+        return self.remap(|r, c| (r, swap_strip(c, a, b)));
+    }
+
+    // Replace each digit d with permutation[d - 1]. Panics unless permutation holds
+    // each of 1-9 exactly once.
+    pub fn relabel(&self, permutation: [u8; 9]) -> Sudoku {
+        let mut sorted = permutation;
+        sorted.sort();
+        assert!(
+            sorted == [1, 2, 3, 4, 5, 6, 7, 8, 9],
+            "relabel: {:?} is not a permutation of 1-9",
+            permutation
+        );
+        let mut out = *self;
+        for c in Coord::all() {
+            if self[c] != 0 {
+                out[c] = permutation[self[c] as usize - 1];
+            }
+        }
+        return out;
+    }
+
+    // A random mix of all the moves above, the same for the same seed: the bands and the
+    // rows within each put in a random order, likewise the stacks and columns, then a
+    // transpose or not, then a random relabelling. Each arrangement is equally likely,
+    // and shuffling a puzzle and its solution with one seed gives a puzzle and its
+    // solution.
+    pub fn shuffle(&self, seed: impl Into<Seed>) -> Sudoku {
+        return self.shuffle_with_rng(&mut SolverRng::new(seed));
+    }
+
+    // Like shuffle, drawing from rng.
+    pub fn shuffle_with_rng(&self, rng: &mut SolverRng) -> Sudoku {
+        let rows = shuffled_lines(rng);
+        let cols = shuffled_lines(rng);
+        let moved = self.remap(|r, c| (rows[r as usize - 1], cols[c as usize - 1]));
+        let moved = match rng.below(2) {
+            0 => moved,
+            _ => moved.transpose(),
+        };
+        let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        rng.shuffle(&mut digits);
+        return moved.relabel(digits);
+    }
+}
+
+// The lines 1-9 in a random order that keeps each strip of three together: the strips
+// shuffled, and the lines within each.
+fn shuffled_lines(rng: &mut SolverRng) -> [u8; 9] {
+    let mut strips = [1, 2, 3];
+    rng.shuffle(&mut strips);
+    let mut lines = [0; 9];
+    for (i, &strip) in strips.iter().enumerate() {
+        let mut within = [1, 2, 3];
+        rng.shuffle(&mut within);
+        for (j, &ix) in within.iter().enumerate() {
+            lines[i * 3 + j] = line_in(strip, ix);
+        }
+    }
+    return lines;
+}
+
+fn check_third(ix: u8) {
+    assert!(
+        (1..=3).contains(&ix),
+        "band, stack, or line {} out of range 1-3",
+        ix
+    );
+}
+
+// The grid row (or column) of line ix within band (or stack) strip.
+fn line_in(strip: u8, ix: u8) -> u8 {
+    check_third(strip);
+    check_third(ix);
+    return (strip - 1) * 3 + ix;
+}
+
+fn swap(x: u8, a: u8, b: u8) -> u8 {
+    return match x {
+        _ if x == a => b,
+        _ if x == b => a,
+        _ => x,
+    };
+}
+
+// Where row (or column) x comes from once strips a and b trade places.
+fn swap_strip(x: u8, a: u8, b: u8) -> u8 {
+    let strip = (x - 1) / 3 + 1;
+    return (swap(strip, a, b) - 1) * 3 + (x - 1) % 3 + 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puzzles() -> [Sudoku; 2] {
+        return [
+            include_str!("../../moderate.txt").parse().unwrap(),
+            include_str!("../../challenge.txt").parse().unwrap(),
+        ];
+    }
+
+    const RELABEL: [u8; 9] = [3, 1, 4, 5, 9, 2, 6, 8, 7];
+
+    type Transform = fn(&Sudoku) -> Sudoku;
+
+    // Each transform, paired with its inverse.
+    fn transforms() -> Vec<(&'static str, Transform, Transform)> {
+        return vec![
+            ("rotate90", |s| s.rotate90(), |s| s.rotate270()),
+            ("rotate180", |s| s.rotate180(), |s| s.rotate180()),
+            ("rotate270", |s| s.rotate270(), |s| s.rotate90()),
+            ("transpose", |s| s.transpose(), |s| s.transpose()),
+            (
+                "mirror_h",
+                |s| s.mirror_horizontal(),
+                |s| s.mirror_horizontal(),
+            ),
+            ("mirror_v", |s| s.mirror_vertical(), |s| s.mirror_vertical()),
+            (
+                "rows",
+                |s| s.swap_rows_within_band(2, 1, 3),
+                |s| s.swap_rows_within_band(2, 3, 1),
+            ),
+            (
+                "cols",
+                |s| s.swap_cols_within_stack(3, 1, 2),
+                |s| s.swap_cols_within_stack(3, 1, 2),
+            ),
+            ("bands", |s| s.swap_bands(1, 3), |s| s.swap_bands(3, 1)),
+            ("stacks", |s| s.swap_stacks(1, 2), |s| s.swap_stacks(1, 2)),
+            (
+                "relabel",
+                |s| s.relabel(RELABEL),
+                |s| {
+                    let mut inverse = [0; 9];
+                    for (i, &d) in RELABEL.iter().enumerate() {
+                        inverse[d as usize - 1] = i as u8 + 1;
+                    }
+                    return s.relabel(inverse);
+                },
+            ),
+        ];
+    }
+
+    #[test]
+    fn test_solve_commutes() {
+        for s in puzzles() {
+            let solution = s.solve().unwrap();
+            for (name, forward, back) in transforms() {
+                let t = forward(&s);
+                assert_eq!(t.solve(), Ok(forward(&solution)), "{}", name);
+                assert_eq!(back(&t), s, "{}", name);
+                assert_eq!(t.clue_count(), s.clue_count(), "{}", name);
+                assert!(Sudoku::empty().verifies(&forward(&solution)), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compositions() {
+        let [s, _] = puzzles();
+        assert_eq!(s.rotate90().rotate90(), s.rotate180());
+        assert_eq!(s.rotate90().rotate180(), s.rotate270());
+        assert_eq!(s.rotate180(), s.mirror_horizontal().mirror_vertical());
+        assert_eq!(s.transpose().mirror_horizontal(), s.rotate90());
+        assert_eq!(s.rotate90().rotate90().rotate90().rotate90(), s);
+
+        let c = |r, c| Coord::new(r, c).unwrap();
+        assert_eq!(s.rotate90()[c(1, 9)], s[c(1, 1)]);
+        assert_eq!(s.swap_rows_within_band(3, 1, 2)[c(8, 4)], s[c(7, 4)]);
+        assert_eq!(s.swap_bands(1, 2)[c(4, 5)], s[c(1, 5)]);
+        assert_eq!(s.swap_stacks(2, 3)[c(6, 9)], s[c(6, 6)]);
+        assert_eq!(s.swap_bands(2, 2), s);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        for s in puzzles() {
+            let solution = s.solve().unwrap();
+            for seed in 0..20 {
+                let t = s.shuffle(seed);
+                assert_eq!(t.clue_count(), s.clue_count());
+                assert_eq!(t.solve(), Ok(solution.shuffle(seed)));
+            }
+            assert_ne!(s.shuffle(1), s.shuffle(2));
+            assert!(crate::are_isomorphic(&s, &s.shuffle(3)));
+        }
+    }
+
+    // The same grid for the same seed in every release; see the top of this file.
+    #[test]
+    fn test_golden_shuffle() {
+        let [s, _] = puzzles();
+        assert_eq!(s.shuffle(5).to_code(), "QABxRgljIIIYdbCSsaihRJykSJobTCg");
+    }
+
+    #[test]
+    #[should_panic(expected = "not a permutation")]
+    fn test_relabel_checks() {
+        Sudoku::empty().relabel([1, 1, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range 1-3")]
+    fn test_band_range() {
+        Sudoku::empty().swap_bands(0, 1);
+    }
+}
@@ -0,0 +1,1002 @@
+// Units are the groups of nine cells that must each hold 1-9 exactly once. A classic
+// grid has 27 of them (9 rows, 9 columns, and 9 squares); variants add more. Some
+// variants instead add a Constraint, which relates pairs of cells anywhere in the grid.
+
+use alloc::collections;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::{
+    box_coords, col_coords, row_coords, Cage, Column, Coord, GridError, Row, Square, SubArray,
+};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub enum Unit {
+    Row(u8),
+    Column(u8),
+    Square(u8),
+    // Any nine distinct cells.
+    Custom([Coord; 9]),
+}
+
+impl Unit {
+    // The 27 classic units: for each index 1-9 in turn, that row, column, and square.
+    pub fn classic() -> impl Iterator<Item = Unit> {
+        return (1..10).flat_map(|ix| [Unit::Row(ix), Unit::Column(ix), Unit::Square(ix)]);
+    }
+
+    // The two main diagonals of X-sudoku: top left to bottom right, then top right to
+    // bottom left.
+    pub fn diagonals() -> [Unit; 2] {
+        let cell = |row, col| Coord { row, col };
+        return [
+            Unit::Custom(core::array::from_fn(|i| cell(i as u8 + 1, i as u8 + 1))),
+            Unit::Custom(core::array::from_fn(|i| cell(i as u8 + 1, 9 - i as u8))),
+        ];
+    }
+
+    // The four windows of hyper sudoku (windoku): the 3x3 blocks with top left corners
+    // (2, 2), (2, 6), (6, 2), and (6, 6), each in row-major order.
+    pub fn windows() -> [Unit; 4] {
+        let window = |top: u8, left: u8| {
+            return Unit::Custom(core::array::from_fn(|i| Coord {
+                row: top + i as u8 / 3,
+                col: left + i as u8 % 3,
+            }));
+        };
+        return [window(2, 2), window(2, 6), window(6, 2), window(6, 6)];
+    }
+
+    // The nine cells of this unit, in the order given by row_coords, col_coords, and
+    // box_coords for the built-in units. Panics if a built-in unit's index is outside 1-9.
+    pub fn coords(&self) -> [Coord; 9] {
+        return match *self {
+            Unit::Row(ix) => row_coords(ix),
+            Unit::Column(ix) => col_coords(ix),
+            Unit::Square(ix) => box_coords(ix),
+            Unit::Custom(coords) => coords,
+        };
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        return self.coords().contains(&coord);
+    }
+
+    // Check that this is a usable unit: built-in indices in 1-9, and no cell repeated in
+    // a custom unit.
+    pub fn check(&self) -> Result<(), GridError> {
+        let coords = match *self {
+            Unit::Row(ix) | Unit::Column(ix) | Unit::Square(ix) => {
+                if !(1..=9).contains(&ix) {
+                    return Err(GridError::UnitOutOfRange(ix));
+                }
+                return Ok(());
+            }
+            Unit::Custom(coords) => coords,
+        };
+        let mut seen = collections::BTreeSet::new();
+        for c in coords {
+            if !seen.insert(c) {
+                return Err(GridError::RepeatedCell(c));
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Row(ix) => write!(f, "{}{}", Row { index: *ix }.name(), ix),
+            Unit::Column(ix) => write!(f, "{}{}", Column { index: *ix }.name(), ix),
+            Unit::Square(ix) => write!(f, "{}{}", Square { index: *ix }.name(), ix),
+            Unit::Custom(coords) => {
+                write!(f, "unit")?;
+                for c in coords {
+                    write!(f, " {}", c)?;
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+// A rule on the values of pairs of cells picked out by how they lie relative to each
+// other rather than by sharing a unit: neighbors says which cells it pairs with one, and
+// allows which values such a pair may hold.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[non_exhaustive]
+pub enum Constraint {
+    // No two cells a chess knight's move apart hold the same value.
+    AntiKnight,
+    // No two cells a chess king's move apart hold the same value. Only the diagonal
+    // steps add anything, as the others stay within a row or column.
+    AntiKing,
+    // No two orthogonally adjacent cells hold consecutive values.
+    NonConsecutive,
+    // These two cells hold consecutive values, as a white kropki dot between them says.
+    Consecutive(Coord, Coord),
+}
+
+impl Constraint {
+    // The cells whose values this constraint limits given coord's, in row-major order.
+    pub fn neighbors(self, coord: Coord) -> Vec<Coord> {
+        let steps: &[(i8, i8)] = match self {
+            Constraint::AntiKnight => &KNIGHT_STEPS,
+            Constraint::AntiKing => &KING_STEPS,
+            Constraint::NonConsecutive => &ORTHOGONAL_STEPS,
+            Constraint::Consecutive(a, b) if a == b => return vec![],
+            Constraint::Consecutive(a, b) if coord == a => return vec![b],
+            Constraint::Consecutive(a, b) if coord == b => return vec![a],
+            Constraint::Consecutive(..) => return vec![],
+        };
+        let mut found: Vec<Coord> = steps
+            .iter()
+            .filter_map(|&(dr, dc)| {
+                let row = coord.row() as i8 + dr;
+                let col = coord.col() as i8 + dc;
+                return Coord::new(row as u8, col as u8).ok();
+            })
+            .collect();
+        found.sort();
+        return found;
+    }
+
+    // Whether two cells this constraint pairs may hold a and b, in either order.
+    pub fn allows(self, a: u8, b: u8) -> bool {
+        return match self {
+            Constraint::AntiKnight | Constraint::AntiKing => a != b,
+            Constraint::NonConsecutive => a.abs_diff(b) != 1,
+            Constraint::Consecutive(..) => a.abs_diff(b) == 1,
+        };
+    }
+}
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const ORTHOGONAL_STEPS: [(i8, i8); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Constraint::AntiKnight => write!(f, "anti-knight"),
+            Constraint::AntiKing => write!(f, "anti-king"),
+            Constraint::NonConsecutive => write!(f, "non-consecutive"),
+            Constraint::Consecutive(a, b) => write!(f, "consecutive {} {}", a, b),
+        };
+    }
+}
+
+// A named set of rules: the classic units, plus whatever the variant adds.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Variant {
+    #[default]
+    Classic,
+    // X-sudoku: both main diagonals also hold 1-9 once each.
+    Diagonal,
+    // Hyper sudoku, or windoku: so do the four windows of Unit::windows.
+    Hyper,
+    // No two cells a knight's move apart hold the same value.
+    AntiKnight,
+    // No two cells a king's move apart, diagonally adjacent, hold the same value.
+    AntiKing,
+    // No two orthogonally adjacent cells hold consecutive values.
+    NonConsecutive,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 6] = [
+        Variant::Classic,
+        Variant::Diagonal,
+        Variant::Hyper,
+        Variant::AntiKnight,
+        Variant::AntiKing,
+        Variant::NonConsecutive,
+    ];
+
+    // The name files and the command line use for it.
+    pub fn name(self) -> &'static str {
+        return match self {
+            Variant::Classic => "classic",
+            Variant::Diagonal => "x",
+            Variant::Hyper => "hyper",
+            Variant::AntiKnight => "anti-knight",
+            Variant::AntiKing => "anti-king",
+            Variant::NonConsecutive => "non-consecutive",
+        };
+    }
+
+    // The variant with this name, also taking "diagonal" for x and "windoku" for hyper.
+    pub fn from_name(name: &str) -> Option<Variant> {
+        return match name.trim().to_ascii_lowercase().as_str() {
+            "diagonal" => Some(Variant::Diagonal),
+            "windoku" => Some(Variant::Hyper),
+            n => Variant::ALL.into_iter().find(|v| v.name() == n),
+        };
+    }
+
+    // The variants in a comma-separated list of names, like "anti-knight, anti-king",
+    // for rules that combine. Each is listed once, in the order first given, and classic
+    // is left out, as every variant includes its rules; so "classic" alone is empty.
+    pub fn from_names(list: &str) -> Option<Vec<Variant>> {
+        let mut variants = Vec::new();
+        for name in list.split(',') {
+            let v = Variant::from_name(name)?;
+            if v != Variant::Classic && !variants.contains(&v) {
+                variants.push(v);
+            }
+        }
+        return Some(variants);
+    }
+
+    // Every unit of the variant, the classic 27 first.
+    pub fn units(self) -> Vec<Unit> {
+        let mut units: Vec<Unit> = Unit::classic().collect();
+        match self {
+            Variant::Classic
+            | Variant::AntiKnight
+            | Variant::AntiKing
+            | Variant::NonConsecutive => {}
+            Variant::Diagonal => units.extend(Unit::diagonals()),
+            Variant::Hyper => units.extend(Unit::windows()),
+        }
+        return units;
+    }
+
+    // The constraints the variant adds to its units.
+    pub fn constraints(self) -> Vec<Constraint> {
+        return match self {
+            Variant::AntiKnight => vec![Constraint::AntiKnight],
+            Variant::AntiKing => vec![Constraint::AntiKing],
+            Variant::NonConsecutive => vec![Constraint::NonConsecutive],
+            Variant::Classic | Variant::Diagonal | Variant::Hyper => vec![],
+        };
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.name());
+    }
+}
+
+// The units a grid is solved against, with each unit's cells and the units each cell
+// belongs to worked out up front, along with any constraints and killer cages. Copies
+// of a grid share one Layout.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Layout {
+    pub(crate) units: Vec<Unit>,
+    pub(crate) coords: Vec<[Coord; 9]>,
+    // For each cell in row-major order, the indices of the units containing it.
+    pub(crate) cell_units: Vec<Vec<usize>>,
+    pub(crate) constraints: Vec<Constraint>,
+    // For each cell in row-major order, the cells the constraints pair it with, each
+    // with the constraint, whether or not they also share a unit with it.
+    pub(crate) cell_neighbors: Vec<Vec<(Coord, Constraint)>>,
+    pub(crate) cages: Vec<Cage>,
+    // For each cell in row-major order, the indices of the cages containing it.
+    pub(crate) cell_cages: Vec<Vec<usize>>,
+    // More sums the cages imply, for pruning with alongside theirs; see innies.
+    pub(crate) innies: Vec<Cage>,
+}
+
+impl Layout {
+    // Units must already have been checked.
+    pub(crate) fn new(units: Vec<Unit>) -> Layout {
+        return Layout::with_rules(units, Vec::new(), Vec::new());
+    }
+
+    pub(crate) fn with_rules(
+        units: Vec<Unit>,
+        constraints: Vec<Constraint>,
+        cages: Vec<Cage>,
+    ) -> Layout {
+        let coords: Vec<[Coord; 9]> = units.iter().map(|u| u.coords()).collect();
+        let mut cell_units = vec![Vec::new(); 81];
+        for (ix, unit) in coords.iter().enumerate() {
+            for &c in unit {
+                cell_units[cell_index(c)].push(ix);
+            }
+        }
+        let mut cell_neighbors = vec![Vec::new(); 81];
+        for c in Coord::all() {
+            let neighbors: &mut Vec<(Coord, Constraint)> = &mut cell_neighbors[cell_index(c)];
+            for &constraint in &constraints {
+                neighbors.extend(constraint.neighbors(c).into_iter().map(|n| (n, constraint)));
+            }
+            neighbors.sort();
+        }
+        let mut cell_cages = vec![Vec::new(); 81];
+        for (ix, cage) in cages.iter().enumerate() {
+            for &c in cage.cells() {
+                cell_cages[cell_index(c)].push(ix);
+            }
+        }
+        let innies = innies(&coords, &cages, &cell_cages);
+        return Layout {
+            units,
+            coords,
+            cell_units,
+            constraints,
+            cell_neighbors,
+            cages,
+            cell_cages,
+            innies,
+        };
+    }
+
+    // Built once and shared by every classic grid. Without std there is no OnceLock, so
+    // each grid builds its own.
+    #[cfg(feature = "std")]
+    pub(crate) fn classic() -> Arc<Layout> {
+        static CLASSIC: OnceLock<Arc<Layout>> = OnceLock::new();
+        return Arc::clone(
+            CLASSIC.get_or_init(|| Arc::new(Layout::new(Unit::classic().collect()))),
+        );
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn classic() -> Arc<Layout> {
+        return Arc::new(Layout::new(Unit::classic().collect()));
+    }
+
+    // The indices of the units containing coord.
+    pub(crate) fn units_of(&self, coord: Coord) -> &[usize] {
+        return &self.cell_units[cell_index(coord)];
+    }
+
+    // Whether the two cells are different and share a unit, so they can't hold the same
+    // value. Unlike Coord::sees, this goes by the grid's own units, jigsaw regions and
+    // all.
+    pub(crate) fn sees(&self, a: Coord, b: Coord) -> bool {
+        let units = self.units_of(b);
+        return a != b && self.units_of(a).iter().any(|u| units.contains(u));
+    }
+
+    // The other cells sharing a unit with coord, in row-major order.
+    pub(crate) fn peers(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        return Coord::all().filter(move |&c| self.sees(coord, c));
+    }
+
+    // The cells the constraints pair with coord, and the constraint pairing each.
+    pub(crate) fn neighbors_of(&self, coord: Coord) -> &[(Coord, Constraint)] {
+        return &self.cell_neighbors[cell_index(coord)];
+    }
+
+    // The indices of the cages containing coord.
+    pub(crate) fn cages_of(&self, coord: Coord) -> &[usize] {
+        return &self.cell_cages[cell_index(coord)];
+    }
+}
+
+// The rule of 45: with no cell in two cages, the cells of a unit outside the cages
+// lying wholly inside it must add up to 45 less those cages' sums, and being in one
+// unit they can't repeat a value, so they make one more cage. Units with no cage inside
+// them, or tiled by their cages, add nothing.
+fn innies(units: &[[Coord; 9]], cages: &[Cage], cell_cages: &[Vec<usize>]) -> Vec<Cage> {
+    if cell_cages.iter().any(|ks| ks.len() > 1) {
+        return Vec::new();
+    }
+    let mut found = Vec::new();
+    for unit in units {
+        let inside: Vec<&Cage> = cages
+            .iter()
+            .filter(|cage| cage.cells().iter().all(|c| unit.contains(c)))
+            .collect();
+        let rest: Vec<Coord> = unit
+            .iter()
+            .cloned()
+            .filter(|c| !inside.iter().any(|cage| cage.cells().contains(c)))
+            .collect();
+        if inside.is_empty() || rest.is_empty() {
+            continue;
+        }
+        let covered: u32 = inside.iter().map(|cage| cage.sum() as u32).sum();
+        // A sum the rest can't reach means the cages contradict each other, which
+        // solving finds on its own.
+        let rest_sum = 45u32
+            .checked_sub(covered)
+            .and_then(|s| u8::try_from(s).ok());
+        if let Some(cage) = rest_sum.and_then(|sum| Cage::new(sum, rest).ok()) {
+            found.push(cage);
+        }
+    }
+    return found;
+}
+
+fn cell_index(c: Coord) -> usize {
+    let (i, j) = c.index();
+    return i * 9 + j;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SolverRng, Sudoku, Unsolveable, UnsolvedSudoku};
+
+    fn coords(cells: &[(u8, u8)]) -> [Coord; 9] {
+        let mut out = [Coord::from_index(0, 0); 9];
+        for (o, &(r, c)) in out.iter_mut().zip(cells) {
+            *o = Coord::new(r, c).unwrap();
+        }
+        return out;
+    }
+
+    #[test]
+    fn test_classic() {
+        let units: Vec<Unit> = Unit::classic().collect();
+        assert_eq!(units.len(), 27);
+        assert_eq!(
+            &units[..4],
+            &[Unit::Row(1), Unit::Column(1), Unit::Square(1), Unit::Row(2)]
+        );
+
+        let layout = Layout::classic();
+        assert_eq!(layout.units, units);
+        for c in Coord::all() {
+            let mut containing: Vec<Unit> = layout
+                .units_of(c)
+                .iter()
+                .map(|&u| layout.units[u])
+                .collect();
+            containing.sort();
+            assert_eq!(
+                containing,
+                vec![
+                    Unit::Row(c.row()),
+                    Unit::Column(c.col()),
+                    Unit::Square(c.square())
+                ]
+            );
+        }
+        #[cfg(feature = "std")]
+        assert!(Arc::ptr_eq(&layout, &Layout::classic()));
+        #[cfg(feature = "std")]
+        assert!(Arc::ptr_eq(&UnsolvedSudoku::empty().layout, &layout));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Unit::Row(3).to_string(), "R3");
+        assert_eq!(Unit::Column(4).to_string(), "C4");
+        assert_eq!(Unit::Square(9).to_string(), "S9");
+        let diagonal: Vec<(u8, u8)> = (1..10).map(|i| (i, i)).collect();
+        assert_eq!(
+            Unit::Custom(coords(&diagonal)).to_string(),
+            "unit r1c1 r2c2 r3c3 r4c4 r5c5 r6c6 r7c7 r8c8 r9c9"
+        );
+    }
+
+    #[test]
+    fn test_with_units() {
+        let u = UnsolvedSudoku::with_units(Unit::classic()).unwrap();
+        assert_eq!(u, UnsolvedSudoku::empty());
+
+        // With only the rows, columns are free to repeat values.
+        let mut rows = UnsolvedSudoku::with_units((1..10).map(Unit::Row)).unwrap();
+        rows.try_set(1, 1, 5).unwrap();
+        assert!(rows.can_place(2, 1, 5));
+        assert!(!rows.can_place(1, 2, 5));
+        assert_eq!(rows.units().count(), 9);
+
+        assert_eq!(
+            UnsolvedSudoku::with_units([Unit::Row(1), Unit::Square(0)]).err(),
+            Some(GridError::UnitOutOfRange(0))
+        );
+    }
+
+    // valid and simple_solve see only the grid's units, never a fixed set of 27.
+    #[test]
+    fn test_units_drive_checks() {
+        let mut rows = UnsolvedSudoku::with_units((1..10).map(Unit::Row)).unwrap();
+        rows[Coord::new(1, 1).unwrap()] = crate::Cell::Value(5);
+        rows[Coord::new(2, 1).unwrap()] = crate::Cell::Value(5);
+        assert!(rows.valid());
+        let mut classic = UnsolvedSudoku::empty();
+        classic[Coord::new(1, 1).unwrap()] = crate::Cell::Value(5);
+        classic[Coord::new(2, 1).unwrap()] = crate::Cell::Value(5);
+        assert!(!classic.valid());
+
+        // With rows only, a value missing from all but one cell of a row is placed there,
+        // but nothing is deduced from columns.
+        let mut rows = UnsolvedSudoku::with_units((1..10).map(Unit::Row)).unwrap();
+        for col in 2..10 {
+            rows.eliminate(Coord::new(1, col).unwrap(), 7).unwrap();
+        }
+        rows.simple_solve();
+        assert_eq!(rows.fixed_value(Coord::new(1, 1).unwrap()), Some(7));
+        assert!(rows.can_place(2, 1, 7));
+    }
+
+    #[test]
+    fn test_classic_unchanged() {
+        for text in [
+            include_str!("../../moderate.txt"),
+            include_str!("../../challenge.txt"),
+        ] {
+            let s = text.parse::<Sudoku>().unwrap();
+            let mut explicit = UnsolvedSudoku::with_units(Unit::classic()).unwrap();
+            explicit.add_givens(&s);
+            let mut u = UnsolvedSudoku::from(s);
+            assert_eq!(explicit, u);
+            assert_eq!(
+                explicit.budgeted_solve(u64::MAX),
+                u.budgeted_solve(u64::MAX)
+            );
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        for ix in 1..10 {
+            assert_eq!(Unit::Row(ix).check(), Ok(()));
+            assert_eq!(Unit::Column(ix).check(), Ok(()));
+            assert_eq!(Unit::Square(ix).check(), Ok(()));
+        }
+        assert_eq!(Unit::Row(0).check(), Err(GridError::UnitOutOfRange(0)));
+        assert_eq!(Unit::Column(10).check(), Err(GridError::UnitOutOfRange(10)));
+        assert_eq!(Unit::Square(10).check(), Err(GridError::UnitOutOfRange(10)));
+
+        let diagonal: Vec<(u8, u8)> = (1..10).map(|i| (i, i)).collect();
+        assert_eq!(Unit::Custom(coords(&diagonal)).check(), Ok(()));
+        let mut repeated = diagonal.clone();
+        repeated[8] = (2, 2);
+        assert_eq!(
+            Unit::Custom(coords(&repeated)).check(),
+            Err(GridError::RepeatedCell(Coord::new(2, 2).unwrap()))
+        );
+
+        let mut u = UnsolvedSudoku::empty();
+        assert_eq!(
+            u.add_unit(Unit::Custom(coords(&repeated))),
+            Err(GridError::RepeatedCell(Coord::new(2, 2).unwrap()))
+        );
+        assert_eq!(
+            u.add_unit(Unit::Row(10)),
+            Err(GridError::UnitOutOfRange(10))
+        );
+        assert_eq!(u, UnsolvedSudoku::empty());
+    }
+
+    // The moderate puzzle without its (9, 5) clue, which has exactly two solutions.
+    fn ambiguous() -> Sudoku {
+        let mut s = include_str!("../../moderate.txt")
+            .parse::<Sudoku>()
+            .unwrap();
+        s.clear(Coord::new(9, 5).unwrap());
+        return s;
+    }
+
+    fn parse_solution(digits: &str) -> Sudoku {
+        let lines: Vec<&str> = (0..9).map(|r| &digits[r * 9..r * 9 + 9]).collect();
+        return lines.join("\n").parse::<Sudoku>().unwrap();
+    }
+
+    #[test]
+    fn test_custom_unit_changes_solution() {
+        let first = parse_solution(
+            "267183495583496712194257683921378546435621978678549231846935127352714869719862354",
+        );
+        let second = parse_solution(
+            "762183495583496712194257683419362578235871946678549231846935127351724869927618354",
+        );
+        let s = ambiguous();
+        assert_eq!(
+            UnsolvedSudoku::from(s).dynamic_solve(),
+            Err(Unsolveable::MultipleSolutions(
+                Box::new(first),
+                Box::new(second)
+            ))
+        );
+
+        // Row 1 plus one more cell. In each case the extra cell repeats one of the
+        // solutions' values at (1, 1) and so rules that solution out.
+        let mut row1: Vec<(u8, u8)> = (1..10).filter(|&c| c != 3).map(|c| (1, c)).collect();
+        row1.push((2, 7));
+        let picks_first = Unit::Custom(coords(&row1));
+        row1[8] = (2, 9);
+        let picks_second = Unit::Custom(coords(&row1));
+
+        for (unit, expected) in [(picks_first, first), (picks_second, second)] {
+            let mut u = UnsolvedSudoku::empty();
+            u.add_unit(unit).unwrap();
+            u.add_givens(&s);
+            assert_eq!(u.units().count(), 28);
+            assert_eq!(u.units().last(), Some(unit));
+            assert!(u.clone().dynamic_solve() == Ok(expected));
+
+            // Adding the unit after the givens propagates them into it just the same.
+            let mut later = UnsolvedSudoku::from(s);
+            later.add_unit(unit).unwrap();
+            assert_eq!(later, u);
+        }
+    }
+
+    // Unique only with the anti-knight rule; classically it has many solutions.
+    const ANTI_KNIGHT: &str =
+        "000006080900000006400000000001000004030000000000000030500009000000000000000000820";
+
+    #[test]
+    fn test_knight_neighbors() {
+        let c = |r, c| Coord::new(r, c).unwrap();
+        assert_eq!(
+            Constraint::AntiKnight.neighbors(c(1, 1)),
+            vec![c(2, 3), c(3, 2)]
+        );
+        assert_eq!(Constraint::AntiKnight.neighbors(c(5, 5)).len(), 8);
+        assert_eq!(
+            Constraint::AntiKnight.neighbors(c(9, 8)),
+            vec![c(7, 7), c(7, 9), c(8, 6)]
+        );
+        assert_eq!(Constraint::AntiKnight.to_string(), "anti-knight");
+    }
+
+    #[test]
+    fn test_anti_knight() {
+        let puzzle = parse_solution(ANTI_KNIGHT);
+        assert_eq!(puzzle.count_solutions(2), 2);
+
+        let mut u = UnsolvedSudoku::empty();
+        u.add_constraint(Constraint::AntiKnight);
+        u.add_constraint(Constraint::AntiKnight);
+        assert_eq!(
+            u.constraints().collect::<Vec<_>>(),
+            [Constraint::AntiKnight]
+        );
+        u.add_givens(&puzzle);
+        let solution = u.clone().dynamic_solve().unwrap();
+        assert_eq!(
+            solution,
+            parse_solution(
+                "123456789987312456465798213251983674734621598896574132512849367378265941649137825"
+            )
+        );
+        assert!(puzzle.verifies(&solution));
+        for c in Coord::all() {
+            for n in Constraint::AntiKnight.neighbors(c) {
+                assert_ne!(solution.get(c), solution.get(n), "{} and {}", c, n);
+            }
+        }
+
+        // Adding the constraint after the givens propagates them just the same.
+        let mut later = UnsolvedSudoku::from(puzzle);
+        later.add_constraint(Constraint::AntiKnight);
+        assert_eq!(later, u);
+        // Units added later keep the constraint.
+        later.add_unit(Unit::Row(1)).unwrap();
+        assert_eq!(later.constraints().count(), 1);
+
+        // r5c2 holds 3, so a knight's move away a 3 can't go in, and isn't valid there.
+        assert!(!u.can_place(4, 4, 3));
+        assert!(UnsolvedSudoku::from(puzzle).can_place(4, 4, 3));
+        let mut broken = UnsolvedSudoku::empty();
+        broken.add_constraint(Constraint::AntiKnight);
+        broken[Coord::new(5, 2).unwrap()] = crate::Cell::Value(3);
+        broken[Coord::new(4, 4).unwrap()] = crate::Cell::Value(3);
+        assert!(!broken.valid());
+        assert!(broken.violations().is_empty());
+        assert_eq!(broken.dynamic_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    // Unique only with the anti-king rule, and with both rules together; each puzzle
+    // has many solutions without its rules.
+    const ANTI_KING: &str =
+        "000000080700003000400000000012530000070000010000008000600005900000000800250300001";
+    const ANTI_KNIGHT_KING: &str =
+        "000006700400009000700000000000007001000000234000230000005008002008000000000000000";
+
+    #[test]
+    fn test_anti_king() {
+        let c = |r, c| Coord::new(r, c).unwrap();
+        assert_eq!(
+            Constraint::AntiKing.neighbors(c(1, 1)),
+            vec![c(1, 2), c(2, 1), c(2, 2)]
+        );
+        assert_eq!(Constraint::AntiKing.neighbors(c(5, 5)).len(), 8);
+        assert_eq!(Constraint::AntiKing.to_string(), "anti-king");
+        assert_eq!(Variant::AntiKing.constraints(), [Constraint::AntiKing]);
+        assert_eq!(Variant::AntiKing.units(), Variant::Classic.units());
+
+        let puzzle = parse_solution(ANTI_KING);
+        assert_eq!(puzzle.count_solutions(2), 2);
+        let mut u = UnsolvedSudoku::with_variant(Variant::AntiKing);
+        u.add_givens(&puzzle);
+        assert_eq!(u.solutions().count(), 1);
+
+        // Diagonal neighbors from different boxes are only a conflict under the rule.
+        let mut clash = Sudoku::empty();
+        clash[c(3, 3)] = 4;
+        clash[c(4, 4)] = 4;
+        assert!(UnsolvedSudoku::from(clash).valid());
+        let mut u = UnsolvedSudoku::with_variant(Variant::AntiKing);
+        u.add_givens(&clash);
+        assert!(!u.valid());
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    // Unique when no two cells side by side hold consecutive digits, and not otherwise.
+    const NON_CONSECUTIVE: &str =
+        "035000000460000090700000005000000579000000013800000000001000000000130007000000000";
+
+    #[test]
+    fn test_non_consecutive() {
+        let c = |r, c| Coord::new(r, c).unwrap();
+        assert_eq!(
+            Constraint::NonConsecutive.neighbors(c(1, 1)),
+            vec![c(1, 2), c(2, 1)]
+        );
+        assert_eq!(Constraint::NonConsecutive.neighbors(c(5, 5)).len(), 4);
+        assert_eq!(Constraint::NonConsecutive.to_string(), "non-consecutive");
+        assert!(Constraint::NonConsecutive.allows(3, 5));
+        assert!(Constraint::NonConsecutive.allows(4, 4));
+        assert!(!Constraint::NonConsecutive.allows(4, 3));
+        assert!(Constraint::AntiKing.allows(4, 3));
+        assert!(!Constraint::AntiKing.allows(4, 4));
+
+        let puzzle = parse_solution(NON_CONSECUTIVE);
+        assert_eq!(puzzle.count_solutions(2), 2);
+        let mut u = UnsolvedSudoku::with_variant(Variant::NonConsecutive);
+        u.add_givens(&puzzle);
+        assert_eq!(u.solutions().count(), 1);
+        let solution = u.dynamic_solve().unwrap();
+        assert!(puzzle.verifies(&solution));
+
+        // Digits one apart in neighboring cells are only a conflict under the rule.
+        let mut clash = Sudoku::empty();
+        clash[c(3, 3)] = 4;
+        clash[c(3, 4)] = 5;
+        assert!(UnsolvedSudoku::from(clash).valid());
+        let mut u = UnsolvedSudoku::with_variant(Variant::NonConsecutive);
+        u.add_givens(&clash);
+        assert!(!u.valid());
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    #[test]
+    fn test_consecutive() {
+        let c = |r, c| Coord::new(r, c).unwrap();
+        let dot = Constraint::Consecutive(c(2, 2), c(2, 3));
+        assert_eq!(dot.neighbors(c(2, 2)), vec![c(2, 3)]);
+        assert_eq!(dot.neighbors(c(2, 3)), vec![c(2, 2)]);
+        assert_eq!(dot.neighbors(c(2, 4)), vec![]);
+        assert_eq!(
+            Constraint::Consecutive(c(2, 2), c(2, 2)).neighbors(c(2, 2)),
+            vec![]
+        );
+        assert_eq!(dot.to_string(), "consecutive r2c2 r2c3");
+        assert!(dot.allows(7, 6));
+        assert!(!dot.allows(7, 5));
+
+        // One given leaves its partner only the two values beside it.
+        let mut u = UnsolvedSudoku::empty();
+        u.add_constraint(dot);
+        let mut given = Sudoku::empty();
+        given[c(2, 2)] = 5;
+        u.add_givens(&given);
+        assert_eq!(
+            u.candidates(c(2, 3)).unwrap().iter().collect::<Vec<_>>(),
+            [4, 6]
+        );
+        given[c(2, 3)] = 7;
+        let mut u = UnsolvedSudoku::empty();
+        u.add_constraint(dot);
+        u.add_givens(&given);
+        assert!(!u.valid());
+    }
+
+    #[test]
+    fn test_combined_variants() {
+        let both = [Variant::AntiKnight, Variant::AntiKing];
+        let u = UnsolvedSudoku::with_variants(&both);
+        assert_eq!(
+            u.constraints().collect::<Vec<_>>(),
+            [Constraint::AntiKnight, Constraint::AntiKing]
+        );
+        assert_eq!(u.units().count(), 27);
+        assert_eq!(UnsolvedSudoku::with_variants(&[]), UnsolvedSudoku::empty());
+        let x_hyper = UnsolvedSudoku::with_variants(&[Variant::Diagonal, Variant::Hyper]);
+        assert_eq!(x_hyper.units().count(), 33);
+
+        let puzzle = parse_solution(ANTI_KNIGHT_KING);
+        for variants in [&both[..1], &both[1..]] {
+            let mut u = UnsolvedSudoku::with_variants(variants);
+            u.add_givens(&puzzle);
+            assert_eq!(u.solutions().take(2).count(), 2, "{:?}", variants);
+        }
+        let mut u = UnsolvedSudoku::with_variants(&both);
+        u.add_givens(&puzzle);
+        assert_eq!(u.solutions().count(), 1);
+        let solution = u.dynamic_solve().unwrap();
+        assert!(puzzle.verifies(&solution));
+    }
+
+    // Whatever the puzzle, a solution never puts equal values a knight's or king's move
+    // apart, or consecutive ones side by side, if the rules forbid it. The rules look
+    // the same after turning or flipping the grid and relabelling the digits, so each
+    // puzzle is tried under random combinations of those, which keep it solvable. Only
+    // reversing the digits keeps consecutive ones consecutive, though.
+    #[test]
+    fn test_constraints_hold() {
+        let symmetries: [fn(&Sudoku) -> Sudoku; 8] = [
+            |s| *s,
+            Sudoku::rotate90,
+            Sudoku::rotate180,
+            Sudoku::rotate270,
+            Sudoku::transpose,
+            Sudoku::mirror_horizontal,
+            Sudoku::mirror_vertical,
+            |s| s.transpose().rotate180(),
+        ];
+        let cases = [
+            (ANTI_KNIGHT, &[Variant::AntiKnight][..], true),
+            (ANTI_KING, &[Variant::AntiKing], true),
+            (
+                ANTI_KNIGHT_KING,
+                &[Variant::AntiKnight, Variant::AntiKing],
+                true,
+            ),
+            (NON_CONSECUTIVE, &[Variant::NonConsecutive], false),
+        ];
+        for (text, variants, shuffle) in cases {
+            let base = parse_solution(text);
+            let mut rng = SolverRng::new(85);
+            for _ in 0..4 {
+                let mut digits: [u8; 9] = core::array::from_fn(|i| i as u8 + 1);
+                if shuffle {
+                    rng.shuffle(&mut digits);
+                } else if rng.below(2) == 1 {
+                    digits.reverse();
+                }
+                let puzzle = symmetries[rng.below(8)](&base).relabel(digits);
+                let mut u = UnsolvedSudoku::with_variants(variants);
+                u.add_givens(&puzzle);
+                let solution = u.solutions().next().expect("the puzzle has a solution");
+                assert!(puzzle.verifies(&solution));
+                for constraint in variants.iter().flat_map(|v| v.constraints()) {
+                    for c in Coord::all() {
+                        for n in constraint.neighbors(c) {
+                            assert!(
+                                constraint.allows(solution.get(c), solution.get(n)),
+                                "{} {}",
+                                constraint,
+                                c
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Unique under X-sudoku's rules, but not under the classic ones.
+    const X_SUDOKU: &str =
+        "000000789000000000000120006000000000610008004800000030000300000000060040060970000";
+
+    // A hyper sudoku with many solutions by the classic rules alone.
+    const HYPER_SUDOKU: &str =
+        "020000000000000020700023006504000800000030000800000000000600040000015000000000008";
+
+    #[test]
+    fn test_variant() {
+        assert_eq!(Variant::default(), Variant::Classic);
+        for v in Variant::ALL {
+            assert_eq!(Variant::from_name(v.name()), Some(v));
+            assert_eq!(v.to_string(), v.name());
+        }
+        assert_eq!(Variant::from_name(" Diagonal"), Some(Variant::Diagonal));
+        assert_eq!(Variant::from_name("X"), Some(Variant::Diagonal));
+        assert_eq!(Variant::from_name("Windoku"), Some(Variant::Hyper));
+        assert_eq!(Variant::from_name("killer"), None);
+        assert_eq!(
+            Variant::from_names("anti-king, X,anti-king,classic"),
+            Some(vec![Variant::AntiKing, Variant::Diagonal])
+        );
+        assert_eq!(Variant::from_names(" classic "), Some(vec![]));
+        assert_eq!(Variant::from_names("x,killer"), None);
+        assert_eq!(Variant::from_names(""), None);
+        assert_eq!(
+            Variant::Classic.units(),
+            Unit::classic().collect::<Vec<_>>()
+        );
+        assert_eq!(Variant::Diagonal.units().len(), 29);
+        let [down, up] = Unit::diagonals();
+        assert!(down.check().is_ok() && up.check().is_ok());
+        let c = |r, c| Coord::new(r, c).unwrap();
+        assert!(down.contains(c(5, 5)) && up.contains(c(5, 5)));
+        assert!(down.contains(c(9, 9)) && up.contains(c(9, 1)) && !down.contains(c(9, 1)));
+
+        let puzzle = parse_solution(X_SUDOKU);
+        assert_eq!(puzzle.count_solutions(2), 2);
+        let mut u = UnsolvedSudoku::with_variant(Variant::Diagonal);
+        u.add_givens(&puzzle);
+        assert_eq!(u.solutions().count(), 1);
+        let solution = u.dynamic_solve().unwrap();
+        assert!(puzzle.verifies(&solution));
+        for unit in Unit::diagonals() {
+            let mut values: Vec<u8> = unit.coords().iter().map(|&c| solution.get(c)).collect();
+            values.sort();
+            assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        }
+        // A repeat on a diagonal is a conflict only under the variant.
+        let mut clash = Sudoku::empty();
+        clash[c(1, 1)] = 5;
+        clash[c(9, 9)] = 5;
+        assert!(UnsolvedSudoku::from(clash).valid());
+        let mut u = UnsolvedSudoku::with_variant(Variant::Diagonal);
+        u.add_givens(&clash);
+        assert!(!u.valid());
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+    }
+
+    #[test]
+    fn test_hyper() {
+        let windows = Unit::windows();
+        assert_eq!(Variant::Hyper.units()[27..], windows);
+        let c = |r, c| Coord::new(r, c).unwrap();
+        for (w, corner) in windows.iter().zip([c(2, 2), c(2, 6), c(6, 2), c(6, 6)]) {
+            assert!(w.check().is_ok());
+            assert_eq!(w.coords()[0], corner);
+            assert_eq!(w.coords()[8], c(corner.row() + 2, corner.col() + 2));
+        }
+        // Row 5 and column 5 are in no window.
+        assert!(Coord::all()
+            .filter(|c| c.row() == 5 || c.col() == 5)
+            .all(|c| windows.iter().all(|w| !w.contains(c))));
+
+        let puzzle = parse_solution(HYPER_SUDOKU);
+        assert!(puzzle.count_solutions(2) > 1);
+        let mut u = UnsolvedSudoku::with_variant(Variant::Hyper);
+        u.add_givens(&puzzle);
+        assert_eq!(u.solutions().count(), 1);
+        let solution = u.dynamic_solve().unwrap();
+        assert!(puzzle.verifies(&solution));
+        for unit in windows {
+            let mut values: Vec<u8> = unit.coords().iter().map(|&c| solution.get(c)).collect();
+            values.sort();
+            assert_eq!(values, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        }
+
+        // (2, 2) and (4, 4) share a window, though no row, column, or box.
+        let mut clash = Sudoku::empty();
+        clash[c(2, 2)] = 5;
+        clash[c(4, 4)] = 5;
+        assert!(UnsolvedSudoku::from(clash).valid());
+        let mut u = UnsolvedSudoku::with_variant(Variant::Hyper);
+        u.add_givens(&clash);
+        assert!(!u.valid());
+        assert_eq!(u.violations().len(), 1);
+        assert_eq!(u.violations()[0].unit, windows[0]);
+        assert_eq!(u.dynamic_solve(), Err(Unsolveable::NoSolution));
+        // The diagonals are no units here, so (2, 2) and (9, 9) may match.
+        clash[c(4, 4)] = 0;
+        clash[c(9, 9)] = 5;
+        let mut u = UnsolvedSudoku::with_variant(Variant::Hyper);
+        u.add_givens(&clash);
+        assert!(u.valid());
+    }
+}
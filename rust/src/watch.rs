@@ -0,0 +1,174 @@
+// solve --watch: solve a file again each time it changes, for designing a puzzle by
+// editing it. The file is polled, not watched through the OS, so this needs nothing
+// beyond std. An editor may write a file in pieces, so a change is only reported once
+// the file has looked the same for a whole interval. Nothing here changes the
+// terminal's state, and each report is flushed whole, so Ctrl-C's usual exit stops it
+// cleanly at any point.
+
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::thread;
+use std::time::SystemTime;
+
+use sudokusolver::{Cell, Coord, PuzzleEditor, SolutionStatus, Sudoku, UnsolvedSudoku};
+
+use crate::cli::WatchArgs;
+use crate::{print_status, read_puzzle};
+
+// What's compared between looks at the file: its modification time and length, or None
+// while it can't be read, as when an editor has moved it aside to save.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(filename: &str) -> Stamp {
+    let metadata = fs::metadata(filename).ok()?;
+    return Some((metadata.modified().ok()?, metadata.len()));
+}
+
+// Decides when a stamp is settled enough to report: the first at once, and after that
+// a new one once it has been seen twice in a row.
+#[derive(Default)]
+struct Debounce {
+    shown: Option<Stamp>,
+    seen: Option<Stamp>,
+}
+
+impl Debounce {
+    fn settled(&mut self, stamp: Stamp) -> bool {
+        let first = self.shown.is_none();
+        if self.shown == Some(stamp) {
+            self.seen = None;
+            return false;
+        }
+        if first || self.seen == Some(stamp) {
+            self.shown = Some(stamp);
+            self.seen = None;
+            return true;
+        }
+        self.seen = Some(stamp);
+        return false;
+    }
+}
+
+pub fn watch(args: WatchArgs) -> Result<(), io::Error> {
+    let terminal = io::stdout().is_terminal();
+    let mut debounce = Debounce::default();
+    let mut reports = 0;
+    loop {
+        if debounce.settled(stamp(&args.file)) {
+            if terminal {
+                // Clear the screen and move to its top.
+                print!("\x1b[2J\x1b[H");
+            } else if reports > 0 {
+                println!();
+            }
+            println!("{} (watching; Ctrl-C stops)", args.file);
+            report(&args.file);
+            io::stdout().flush()?;
+            reports += 1;
+        }
+        thread::sleep(args.interval);
+    }
+}
+
+// The puzzle's status, and its candidates if singles alone don't solve it. A file that
+// can't be read or parsed is reported, not fatal, as it is likely half edited.
+fn report(filename: &str) {
+    let puzzle = match read_puzzle(filename) {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let status = PuzzleEditor::new(puzzle).solution_status();
+    print_status(status);
+    if status == SolutionStatus::NoSolution {
+        return;
+    }
+    let u = UnsolvedSudoku::from(after_singles(puzzle));
+    if !u.is_solved() {
+        print!("{}", candidate_grid(&u));
+    }
+}
+
+// The puzzle with every cell naked and hidden singles reach filled in.
+fn after_singles(mut puzzle: Sudoku) -> Sudoku {
+    loop {
+        let before = puzzle;
+        for (row, col, value, _) in puzzle.forced_placements() {
+            let coord = Coord::new(row, col).expect("forced cells are on the grid");
+            // A conflict means there's no solution; leave the cell for the search.
+            puzzle = puzzle.with_clue(coord, value).unwrap_or(puzzle);
+        }
+        if puzzle == before {
+            return puzzle;
+        }
+    }
+}
+
+// The grid with each unsolved cell as its candidates, padded to the same width.
+fn candidate_grid(u: &UnsolvedSudoku) -> String {
+    let text = |c: Coord| -> String {
+        return match u.get(c.row(), c.col()) {
+            Cell::Value(v) => v.to_string(),
+            Cell::Possibilities(ps) => ps.iter().map(|v| v.to_string()).collect(),
+        };
+    };
+    let width = Coord::all().map(|c| text(c).len()).max().unwrap_or(1);
+    let cells: Vec<String> = Coord::all()
+        .map(|c| format!("{:width$}", text(c), width = width))
+        .collect();
+    let mut out = String::new();
+    for (ix, row) in cells.chunks(9).enumerate() {
+        if ix == 3 || ix == 6 {
+            let (side, middle) = ("-".repeat(3 * width + 3), "-".repeat(3 * width + 4));
+            out.push_str(&format!("{}+{}+{}\n", side, middle, side));
+        }
+        let stacks: Vec<String> = row.chunks(3).map(|s| s.join(" ")).collect();
+        out.push_str(stacks.join(" | ").trim_end());
+        out.push('\n');
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_debounce() {
+        let at = |secs, len| Some((SystemTime::UNIX_EPOCH + Duration::from_secs(secs), len));
+        let mut d = Debounce::default();
+        // The first look is shown at once, and is then old news.
+        assert!(d.settled(at(1, 10)));
+        assert!(!d.settled(at(1, 10)));
+        // A write in two parts is shown once it stops changing.
+        assert!(!d.settled(at(2, 5)));
+        assert!(!d.settled(at(3, 10)));
+        assert!(d.settled(at(3, 10)));
+        assert!(!d.settled(at(3, 10)));
+        // A file that goes missing is a change like any other.
+        assert!(!d.settled(None));
+        assert!(d.settled(None));
+        // A change that's undone before it settles is never shown.
+        assert!(!d.settled(at(4, 10)));
+        assert!(!d.settled(None));
+    }
+
+    #[test]
+    fn test_candidate_grid() {
+        let puzzle: Sudoku = include_str!("../../moderate.txt").parse().unwrap();
+        let mut u = UnsolvedSudoku::from(puzzle);
+        let grid = candidate_grid(&u);
+        assert_eq!(grid.lines().count(), 11);
+        // Every line of cells is as wide as the separators.
+        let width = grid.lines().nth(3).unwrap().len();
+        assert!(grid.lines().all(|l| l.len() <= width));
+        let solution = u.dynamic_solve().unwrap();
+        assert_eq!(after_singles(puzzle), solution);
+        let solved = candidate_grid(&UnsolvedSudoku::from(solution));
+        assert_eq!(solved.lines().next(), Some("2 6 7 | 1 8 3 | 4 9 5"));
+        assert_eq!(solved.lines().nth(3), Some("------+-------+------"));
+    }
+}
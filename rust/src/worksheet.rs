@@ -0,0 +1,110 @@
+// Grids drawn for printing: each with a border around it and lines between its bands
+// and stacks, '.' for blanks, and several side by side for a worksheet.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::Sudoku;
+
+// The border above and below each band, as wide as every row of a bordered grid.
+const RULE: &str = "+-------+-------+-------+";
+
+impl Sudoku {
+    // The grid in 13 lines of 25 characters, boxed in and split into its squares:
+    //
+    //     +-------+-------+-------+
+    //     | 5 3 . | . 7 . | . . . |
+    //     ...
+    //     +-------+-------+-------+
+    pub fn bordered(&self) -> String {
+        let mut out = String::new();
+        for (rix, row) in self.rows.iter().enumerate() {
+            if rix % 3 == 0 {
+                out.push_str(RULE);
+                out.push('\n');
+            }
+            for (cix, &v) in row.iter().enumerate() {
+                if cix % 3 == 0 {
+                    out.push_str("| ");
+                }
+                out.push(match v {
+                    0 => '.',
+                    v => (b'0' + v) as char,
+                });
+                out.push(' ');
+            }
+            out.push_str("|\n");
+        }
+        out.push_str(RULE);
+        return out;
+    }
+}
+
+// The puzzles' bordered grids laid out in rows of `columns`, left to right and then top
+// to bottom, two spaces between grids in a row and a blank line between rows. A last
+// row with fewer grids starts at the left like the others, and no line has trailing
+// spaces. A columns of 0 is taken as 1; no puzzles give an empty string.
+pub fn format_grid_row(puzzles: &[&Sudoku], columns: usize) -> String {
+    let rows: Vec<String> = puzzles
+        .chunks(columns.max(1))
+        .map(|row| {
+            let grids: Vec<String> = row.iter().map(|s| s.bordered()).collect();
+            let mut lines: Vec<core::str::Lines> = grids.iter().map(|g| g.lines()).collect();
+            let mut out: Vec<String> = Vec::new();
+            for _ in 0..13 {
+                let parts: Vec<&str> = lines.iter_mut().filter_map(|l| l.next()).collect();
+                out.push(parts.join("  "));
+            }
+            return out.join("\n");
+        })
+        .collect();
+    return rows.join("\n\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderate() -> Sudoku {
+        return include_str!("../../moderate.txt").parse().unwrap();
+    }
+
+    #[test]
+    fn test_bordered() {
+        let text = moderate().bordered();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 13);
+        assert!(lines.iter().all(|l| l.len() == 25), "{}", text);
+        for ix in [0, 4, 8, 12] {
+            assert_eq!(lines[ix], RULE);
+        }
+        assert_eq!(lines[1], "| . . . | 1 . 3 | . 9 5 |");
+        assert_eq!(
+            Sudoku::empty().bordered().lines().nth(5),
+            Some("| . . . | . . . | . . . |")
+        );
+        assert!(!text.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_format_grid_row() {
+        let (a, b) = (moderate(), moderate().rotate90());
+        let c = Sudoku::empty();
+        let text = format_grid_row(&[&a, &b, &c], 2);
+        let lines: Vec<&str> = text.lines().collect();
+        // Two rows of 13 lines with a blank line between them.
+        assert_eq!(lines.len(), 27);
+        assert_eq!(lines[13], "");
+        assert_eq!(lines[0], format!("{}  {}", RULE, RULE));
+        let (left, right) = lines[1].split_at(25);
+        assert_eq!(left, a.bordered().lines().nth(1).unwrap());
+        assert_eq!(right, format!("  {}", b.bordered().lines().nth(1).unwrap()));
+        // The ragged last row holds only c, with nothing after it.
+        assert_eq!(lines[14..].join("\n"), c.bordered());
+
+        assert_eq!(format_grid_row(&[&a, &b], 5), format_grid_row(&[&a, &b], 2));
+        assert_eq!(format_grid_row(&[&a, &b], 0), format_grid_row(&[&a, &b], 1));
+        assert_eq!(format_grid_row(&[&a], 1), a.bordered());
+        assert_eq!(format_grid_row(&[], 3), "");
+    }
+}
@@ -0,0 +1,30 @@
+// The shape of the public API, as downstream code sees it.
+
+use sudokusolver::prelude::*;
+
+#[test]
+fn test_prelude() {
+    let s = include_str!("../../moderate.txt")
+        .parse::<Sudoku>()
+        .unwrap();
+    let mut config = SolverConfig::default();
+    config.guess_budget = 0;
+    let mut solver = Solver::with_config(UnsolvedSudoku::from(s), config);
+    let report = solver.solve();
+    assert_eq!(report.guesses, 0);
+    assert!(matches!(report.result, Some(Ok(_))));
+
+    let message = match Coord::new(0, 1) {
+        Ok(_) => "ok",
+        Err(GridError::RowOutOfRange(_)) => "row",
+        // Error enums are non_exhaustive, so there must be a catch-all arm.
+        Err(_) => "other",
+    };
+    assert_eq!(message, "row");
+}
+
+#[test]
+fn test_sealed() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
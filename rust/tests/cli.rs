@@ -0,0 +1,1052 @@
+// The command-line program, run as a user would over the puzzles in tests/fixtures/cli.
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use sudokusolver::{
+    generate_daily, parse_clue_pattern, Difficulty, GeneratorOptions, Sudoku, Symmetry,
+};
+
+fn fixtures() -> Vec<String> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cli");
+    let mut files: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path().to_str().unwrap().to_string())
+        .collect();
+    files.sort();
+    return files;
+}
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudokusolver"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    return child.wait_with_output().unwrap();
+}
+
+// The fixtures' names without their directory, so output doesn't depend on where the
+// crate is checked out.
+fn relative(text: &[u8], files: &[String]) -> String {
+    let dir = files[0].rsplit_once('/').unwrap().0;
+    return String::from_utf8_lossy(text).replace(&format!("{}/", dir), "");
+}
+
+const MODERATE: &str =
+    "267183495583496712194257683921378546435621978678549231846935127352714869719862354";
+const CHALLENGE: &str =
+    "651847932324965178879123456132598764485276319967314825518639247246781593793452681";
+
+#[test]
+fn test_many_files() {
+    let files = fixtures();
+    let args: Vec<&str> = files.iter().map(String::as_str).collect();
+    let output = run(&args, "");
+    assert!(!output.status.success());
+    let grid = |line: &str| {
+        let rows: Vec<&str> = (0..9).map(|r| &line[r * 9..r * 9 + 9]).collect();
+        return rows.join("\n");
+    };
+    let expected = format!(
+        "== 1-moderate.txt ==\n{}\n== 2-ambiguous.txt ==\n== 3-broken.txt ==\n== 4-challenge.txt ==\n{}\n",
+        grid(MODERATE),
+        grid(CHALLENGE)
+    );
+    assert_eq!(relative(&output.stdout, &files), expected);
+    assert_eq!(
+        relative(&output.stderr, &files),
+        "2-ambiguous.txt: Multiple solutions found.\n\
+         3-broken.txt: No solution found.\n\
+         Error: Custom { kind: InvalidInput, error: \"2 of 4 puzzles failed.\" }\n"
+    );
+}
+
+#[test]
+fn test_directory_and_pattern() {
+    let files = fixtures();
+    let dir = files[0].rsplit_once('/').unwrap().0;
+    let args: Vec<&str> = files.iter().map(String::as_str).collect();
+    let listed = run(&args, "");
+    let output = run(&[dir], "");
+    assert_eq!(output.stdout, listed.stdout);
+    assert_eq!(output.stderr, listed.stderr);
+
+    // One file matched still gets a header, and a pattern that matches nothing fails
+    // the run once the rest are solved.
+    let matched = format!("{}/*-m*.txt", dir);
+    let unmatched = format!("{}/*.xml", dir);
+    let output = run(&["--oneline", &matched, &unmatched], "");
+    assert!(!output.status.success());
+    assert_eq!(
+        relative(&output.stdout, &files),
+        format!("1-moderate.txt {}\n", MODERATE)
+    );
+    assert!(relative(&output.stderr, &files).starts_with("*.xml: No puzzle files found.\n"));
+}
+
+#[test]
+fn test_oneline_and_stdin() {
+    let files = fixtures();
+    let moderate = fs::read_to_string(&files[0]).unwrap();
+    let output = run(&["--oneline", &files[0], &files[3], "-"], &moderate);
+    assert!(output.status.success());
+    assert_eq!(
+        relative(&output.stdout, &files),
+        format!(
+            "1-moderate.txt {}\n4-challenge.txt {}\n<stdin> {}\n",
+            MODERATE, CHALLENGE, MODERATE
+        )
+    );
+    assert_eq!(output.stderr, b"");
+
+    // A single file has no label, and its failure is the program's.
+    let output = run(&["--oneline", "-"], &moderate);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", MODERATE)
+    );
+    let output = run(&[&files[1]], "");
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"");
+
+    // A grid saved with its borders reads back the same.
+    let bordered = format!("{:#}\n", moderate.parse::<Sudoku>().unwrap());
+    let output = run(&["--oneline", "-"], &bordered);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", MODERATE)
+    );
+}
+
+#[test]
+fn test_all_solutions() {
+    let files = fixtures();
+    // The ambiguous fixture is moderate with one clue removed, and has two solutions.
+    let output = run(&["--all", &files[1]], "");
+    assert!(output.status.success());
+    let stdout = text(&output);
+    let blocks: Vec<&str> = stdout.split("\n\n").collect();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].replace('\n', ""), MODERATE);
+    let other: Sudoku = blocks[1].parse().unwrap();
+    assert!(other.is_complete() && other.to_string() != blocks[0]);
+    let puzzle: Sudoku = fs::read_to_string(&files[1]).unwrap().parse().unwrap();
+    assert!(puzzle.verifies(&other));
+    assert_eq!(blocks[2], "found 2 solutions\n");
+
+    let output = run(
+        &[
+            "--all",
+            "--max-solutions",
+            "1",
+            "--oneline",
+            &files[1],
+            &files[0],
+        ],
+        "",
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        relative(&output.stdout, &files),
+        format!(
+            "2-ambiguous.txt {}\n2-ambiguous.txt found 1+ solutions (limit reached)\n\
+             1-moderate.txt {}\n1-moderate.txt found 1 solution\n",
+            MODERATE, MODERATE
+        )
+    );
+
+    // Without a solution it still fails.
+    let output = run(&["--all", &files[2]], "");
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"");
+}
+
+// A duration as --time prints it, in seconds.
+fn seconds(text: &str) -> f64 {
+    let (number, scale) = if let Some(n) = text.strip_suffix("µs") {
+        (n, 1e-6)
+    } else if let Some(n) = text.strip_suffix("ms") {
+        (n, 1e-3)
+    } else {
+        (text.strip_suffix('s').unwrap(), 1.0)
+    };
+    return number.parse::<f64>().unwrap() * scale;
+}
+
+#[test]
+fn test_time() {
+    let files = fixtures();
+    let output = run(
+        &["--oneline", "--time", &files[0], &files[1], &files[3]],
+        "",
+    );
+    assert!(!output.status.success());
+    let stdout = relative(&output.stdout, &files);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], format!("1-moderate.txt {}", MODERATE));
+    assert_eq!(lines[2], format!("4-challenge.txt {}", CHALLENGE));
+    for line in [lines[1], lines[3]] {
+        let rest = line.strip_prefix("time: parse ").unwrap();
+        let (parse, solve) = rest.split_once(", solve ").unwrap();
+        assert!(seconds(parse) >= 0.0 && seconds(solve) > 0.0);
+    }
+    // The ambiguous puzzle's solve counts too, though it has no solution to print.
+    let summary = lines[4].strip_prefix("time: 3 puzzles, ").unwrap();
+    let values: Vec<f64> = ["total ", "mean ", "median ", "max "]
+        .iter()
+        .zip(summary.split(", "))
+        .map(|(label, field)| seconds(field.strip_prefix(label).unwrap()))
+        .collect();
+    assert_eq!(values.len(), 4);
+    assert!(values[1] <= values[3] && values[2] <= values[3] && values[3] <= values[0]);
+
+    // A single file has no summary.
+    let output = run(&["--time", &files[0]], "");
+    assert!(output.status.success());
+    let stdout = text(&output);
+    let last: Vec<&str> = stdout.lines().skip(9).collect();
+    assert_eq!(last.len(), 1);
+    assert!(last[0].starts_with("time: parse "));
+}
+
+#[test]
+fn test_status_only() {
+    let files = fixtures();
+    let code = |args: &[&str], stdin: &str| {
+        let output = run(args, stdin);
+        assert_eq!(output.stdout, b"");
+        return (
+            output.status.code(),
+            String::from_utf8(output.stderr).unwrap(),
+        );
+    };
+    for (file, status) in files.iter().zip([0, 3, 2, 0]) {
+        assert_eq!(
+            code(&["--status-only", file], ""),
+            (Some(status), String::new())
+        );
+    }
+    assert_eq!(
+        code(&["--status-only", "-"], "not a puzzle"),
+        (Some(4), String::new())
+    );
+    let challenge = files[3].as_str();
+    assert_eq!(
+        code(&["--status-only", "--max-guesses", "1", challenge], "").0,
+        Some(5)
+    );
+
+    // The worst status, and a count of each on stderr.
+    let mut args = vec!["--status-only"];
+    args.extend(files.iter().map(String::as_str));
+    args.push("missing.txt");
+    assert_eq!(
+        code(&args, ""),
+        (
+            Some(4),
+            "5 puzzles: 2 unique, 1 no solution, 1 multiple solutions, 1 unreadable\n".to_string()
+        )
+    );
+
+    // Without --status-only, running out of guesses is an error.
+    let output = run(&["--max-guesses", "1", challenge], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Gave up after 1 guesses."));
+}
+
+#[test]
+fn test_variant() {
+    // Unique with both diagonals as units, but not by the classic rules alone.
+    let puzzle = [
+        "------789",
+        "---------",
+        "---12---6",
+        "---------",
+        "61---8--4",
+        "8------3-",
+        "---3-----",
+        "----6--4-",
+        "-6-97----",
+    ]
+    .join("\n");
+    let puzzle = puzzle.as_str();
+    let classic = run(&["--status-only", "-"], puzzle);
+    assert_eq!(classic.status.code(), Some(3));
+    let x = run(&["--variant", "x", "--oneline", "-"], puzzle);
+    assert!(x.status.success(), "{}", String::from_utf8_lossy(&x.stderr));
+    let solution = text(&x);
+    let cells = solution.trim().as_bytes();
+    assert_eq!(cells.len(), 81);
+    let down: Vec<u8> = (0..9).map(|i| cells[10 * i]).collect();
+    let up: Vec<u8> = (0..9).map(|i| cells[8 * i + 8]).collect();
+    for mut diagonal in [down, up] {
+        diagonal.sort();
+        assert_eq!(diagonal, b"123456789");
+    }
+
+    // An .sdk file names its variant itself, and --variant overrides it.
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("x.sdk");
+    fs::write(
+        &path,
+        format!("#variant: x\n{}\n", puzzle.replace('-', ".")),
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(text(&run(&["--oneline", path], "")), solution);
+    assert_eq!(run(&["--status-only", path], "").status.code(), Some(0));
+    let overridden = run(&["--status-only", "--variant", "classic", path], "");
+    assert_eq!(overridden.status.code(), Some(3));
+    let all = run(&["--all", "2", path], "");
+    assert!(!all.status.success());
+
+    // The hyper fixture has many solutions until its windows count.
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/variants/hyper.sdk");
+    let path = path.to_str().unwrap();
+    assert_eq!(run(&["--status-only", path], "").status.code(), Some(0));
+    let classic = run(&["--status-only", "--variant", "classic", path], "");
+    assert_eq!(classic.status.code(), Some(3));
+    let hyper = run(&["--oneline", "--variant", "windoku", path], "");
+    assert_eq!(text(&hyper), text(&run(&["--oneline", path], "")));
+
+    // So does the non-consecutive one until neighbors can't hold consecutive digits,
+    // and the kropki one until its dots count; --variant leaves the dots alone.
+    let fixture = |name: &str| {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/variants")
+            .join(name);
+        return path.to_str().unwrap().to_string();
+    };
+    let path = fixture("non-consecutive.sdk");
+    assert_eq!(run(&["--status-only", &path], "").status.code(), Some(0));
+    let classic = run(&["--status-only", "--variant", "classic", &path], "");
+    assert_eq!(classic.status.code(), Some(3));
+    let path = fixture("kropki.sdk");
+    assert_eq!(
+        text(&run(&["--oneline", "--variant", "classic", &path], "")).trim(),
+        "679128534581347269324569178936274815712985346845613927268791453193456782457832691"
+    );
+    let all = run(&["--all", "2", &path], "");
+    assert!(!all.status.success());
+
+    // A pure killer has nothing but its cages to go on, and --variant keeps them too.
+    let path = fixture("killer.sdk");
+    let killer = run(&["--oneline", "--variant", "classic", &path], "");
+    assert!(
+        killer.status.success(),
+        "{}",
+        String::from_utf8_lossy(&killer.stderr)
+    );
+    assert_eq!(
+        text(&killer).trim(),
+        "571963824482157963963248715146892537827435196359716248798324651614589372235671489"
+    );
+    assert!(!run(&["--canonical", &path], "").status.success());
+
+    // Rules combine, given as a list.
+    let grid: Vec<String> =
+        "000006700400009000700000000000007001000000234000230000005008002008000000000000000"
+            .replace('0', "-")
+            .as_bytes()
+            .chunks(9)
+            .map(|row| String::from_utf8(row.to_vec()).unwrap())
+            .collect();
+    let grid = grid.join("\n");
+    let status = |variants: &str| {
+        return run(&["--status-only", "--variant", variants, "-"], &grid)
+            .status
+            .code();
+    };
+    assert_eq!(status("anti-knight,anti-king"), Some(0));
+    assert_eq!(status("anti-knight"), Some(3));
+    assert_eq!(status("anti-king"), Some(3));
+    assert_eq!(status("anti-bishop"), Some(1));
+}
+
+#[test]
+fn test_regions() {
+    // Unique with the fixture's regions, but not with the squares.
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/variants/jigsaw.sdk");
+    let path = path.to_str().unwrap();
+    let jigsaw = run(&["--oneline", path], "");
+    assert!(
+        jigsaw.status.success(),
+        "{}",
+        String::from_utf8_lossy(&jigsaw.stderr)
+    );
+    // Without --oneline the solution is drawn with the regions' walls, and reads back.
+    let drawn = text(&run(&[path], ""));
+    assert!(
+        drawn.starts_with("+---+---+---+---+---+---+---+---+---+\n"),
+        "{}",
+        drawn
+    );
+    let solution: Sudoku = drawn.parse().unwrap();
+    let digits: String = solution
+        .cells()
+        .map(|(_, v)| char::from(b'0' + v))
+        .collect();
+    assert_eq!(text(&jigsaw).trim(), digits);
+    assert!(!run(&["--canonical", path], "").status.success());
+
+    // The same givens as a plain grid, with the map from its own file.
+    let sdk = fs::read_to_string(path).unwrap();
+    let (grid, map) = sdk.split_once("[Regions]").unwrap();
+    let grid: Vec<&str> = grid.lines().skip(1).collect();
+    let grid = grid.join("\n").replace('.', "-");
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let map_path = dir.join("regions.txt");
+    fs::write(&map_path, map).unwrap();
+    let map_path = map_path.to_str().unwrap();
+    assert_eq!(run(&["--status-only", "-"], &grid).status.code(), Some(3));
+    let regions = run(&["--oneline", "--regions", map_path, "-"], &grid);
+    assert_eq!(text(&regions), text(&jigsaw));
+    let status = run(&["--status-only", "--regions", map_path, "-"], &grid);
+    assert_eq!(status.status.code(), Some(0));
+
+    // A region with a cell too many is refused before anything is read or solved, so
+    // the puzzle comes from a file rather than stdin.
+    let bad_path = dir.join("bad-regions.txt");
+    fs::write(&bad_path, map.replacen('A', "B", 1)).unwrap();
+    let bad_path = bad_path.to_str().unwrap();
+    let grid_path = dir.join("jigsaw.txt");
+    fs::write(&grid_path, &grid).unwrap();
+    let bad = run(&["--regions", bad_path, grid_path.to_str().unwrap()], "");
+    assert_eq!(bad.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&bad.stderr);
+    assert!(stderr.contains("region A has 8 cells, not 9"), "{}", stderr);
+    let bad = run(&["--regions", bad_path, path], "");
+    assert!(!bad.status.success());
+}
+
+#[test]
+fn test_extra_regions() {
+    // Two solutions as classic sudoku, but only one has 1-9 in the asterisk.
+    let variants = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/variants");
+    let puzzle = variants.join("asterisk.txt");
+    let puzzle = puzzle.to_str().unwrap();
+    let star = variants.join("asterisk-regions.txt");
+    let star = star.to_str().unwrap();
+    assert_eq!(run(&["--status-only", puzzle], "").status.code(), Some(3));
+    let status = run(&["--status-only", "--extra-regions", star, puzzle], "");
+    assert_eq!(status.status.code(), Some(0));
+    let solved = run(&["--oneline", "--extra-regions", star, puzzle], "");
+    assert!(solved.status.success(), "{}", text(&solved));
+    let solution = String::from_utf8_lossy(&solved.stdout);
+    let solution = Sudoku::try_from(solution.trim().as_bytes()).unwrap();
+    assert!(solution.is_complete());
+    assert!(!run(&["--canonical", "--extra-regions", star, puzzle], "")
+        .status
+        .success());
+
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let bad_path = dir.join("bad-extra-regions.txt");
+    fs::write(&bad_path, "r1c1 r1c2 r1c3\n").unwrap();
+    let bad = run(&["--extra-regions", bad_path.to_str().unwrap(), puzzle], "");
+    assert_eq!(bad.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&bad.stderr);
+    assert!(
+        stderr.contains("bad-extra-regions.txt: line 1: region has 3 cells, not 9"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_size() {
+    let fixture = |name: &str| {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/sizes")
+            .join(name);
+        return path.to_str().unwrap().to_string();
+    };
+    let (four, sixteen) = (fixture("four.txt"), fixture("sixteen.txt"));
+    let solved = run(&["--size", "4", &four], "");
+    assert!(
+        solved.status.success(),
+        "{}",
+        String::from_utf8_lossy(&solved.stderr)
+    );
+    assert_eq!(text(&solved), "3214\n1432\n2341\n4123\n");
+    let oneline = run(&["--size", "16", "--oneline", &sixteen], "");
+    let cells = text(&oneline);
+    assert_eq!(cells.trim().len(), 256);
+    assert!(cells.starts_with("C1A795BE4GDF3862"));
+
+    // Hex puzzles get hex solutions.
+    let hex = fs::read_to_string(&sixteen).unwrap();
+    let hex: String = hex
+        .chars()
+        .map(|c| match c.to_digit(17) {
+            Some(v) => char::from_digit(v - 1, 16).unwrap().to_ascii_uppercase(),
+            None => c,
+        })
+        .collect();
+    let solved = run(&["--size", "16", "--hex", "--oneline", "-"], &hex);
+    assert!(text(&solved).starts_with("B09684AD3FCE2751"));
+
+    let status = |args: &[&str], stdin: &str| run(args, stdin).status.code();
+    assert_eq!(
+        status(&["--size", "4", "--status-only", &four], ""),
+        Some(0)
+    );
+    assert_eq!(
+        status(
+            &["--size", "4", "--status-only", "-"],
+            "----\n----\n----\n----\n"
+        ),
+        Some(3)
+    );
+    assert_eq!(
+        status(
+            &["--size", "4", "--status-only", "-"],
+            "11--\n----\n----\n----\n"
+        ),
+        Some(2)
+    );
+    // The wrong size doesn't parse.
+    assert_eq!(
+        status(&["--size", "16", "--status-only", &four], ""),
+        Some(4)
+    );
+    assert_eq!(status(&["--size", "4", &sixteen], ""), Some(1));
+    assert_eq!(status(&["--size", "7", &four], ""), Some(1));
+    assert_eq!(status(&["--hex", &four], ""), Some(1));
+
+    // 6x6 takes 2x3 boxes unless --box says otherwise.
+    let six = fixture("six.txt");
+    let solved = run(&["--size", "6", "--oneline", &six], "");
+    assert_eq!(text(&solved), "123456456123231564564231312645645312\n");
+    let boxed = run(&["--box", "2x3", "--oneline", &six], "");
+    assert_eq!(text(&boxed), text(&solved));
+    // In 3x2 boxes, the same givens have no solution.
+    assert_eq!(
+        status(&["--box", "3x2", "--status-only", &six], ""),
+        Some(2)
+    );
+    assert_eq!(status(&["--size", "6", "--box", "3x3", &six], ""), Some(1));
+}
+
+// The fixtures as one puzzle per line.
+fn corpus() -> String {
+    return fixtures()
+        .iter()
+        .map(|f| {
+            let cells: String = fs::read_to_string(f).unwrap().lines().collect();
+            return cells.replace('-', "0") + "\n";
+        })
+        .collect();
+}
+
+#[test]
+fn test_index() {
+    let corpus = format!("\n{}", corpus());
+    let output = run(&["--index", "3", "--oneline", "-"], &corpus);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{}\n", CHALLENGE)
+    );
+    let output = run(&["--status-only", "--index", "1", "-"], &corpus);
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = run(&["--index", "4", "-"], &corpus);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("There is no puzzle at index 4."));
+}
+
+#[test]
+fn test_bench() {
+    let corpus = corpus();
+    let output = run(&["bench", "--iterations", "3", "-"], &corpus);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "4 puzzles, 3 iterations after 1 warm-up, engine fewest"
+    );
+    assert!(lines[1].starts_with("time: fastest "));
+    assert_eq!(
+        lines[2],
+        "each iteration: 2 unique, 1 multiple solutions, 1 no solution; 18 guesses, at most 12 on one puzzle"
+    );
+
+    // --branching is another name for --engine.
+    let output = run(
+        &[
+            "bench",
+            "--json",
+            "--branching",
+            "rarest",
+            "--warmup",
+            "0",
+            "-",
+        ],
+        &corpus,
+    );
+    if cfg!(feature = "json") {
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            stdout.starts_with(r#"{"file":"-","puzzles":4,"iterations":5,"warmup":0,"engine":"#),
+            "{}",
+            stdout
+        );
+        assert_eq!(json["file"], "-");
+        assert_eq!(json["puzzles"], 4);
+        assert_eq!(json["iterations"], 5);
+        assert_eq!(json["engine"], "rarest");
+        assert_eq!(json["unique"], 2);
+        assert!(json["fastest_ns"].as_u64().unwrap() <= json["mean_ns"].as_u64().unwrap());
+    } else {
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("--json needs the json feature."),
+            "{}",
+            stderr
+        );
+    }
+
+    let output = run(&["bench", "--log-level", "debug", "-"], "");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with("--log-level would skew the timings.")
+    );
+}
+
+#[test]
+fn test_compare() {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let dir = fixtures.join("compare").display().to_string();
+    let output = run(&["compare", &dir], "");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).replace(&format!("{}/", dir), ""),
+        "FAIL ambiguous.txt: multiple solutions found\n\
+         PASS challenge.txt\n\
+         FAIL missing.txt: missing.expected.txt: No such file or directory (os error 2)\n\
+         PASS moderate.txt\n\
+         FAIL wrong.txt: 2 cells differ\n  \
+         r1c1: expected 6, got 2\n  \
+         r1c2: expected 2, got 6\n\
+         5 compared: 2 passed, 3 failed\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("3 of 5 comparisons failed."));
+
+    // An explicit pair, with the expected grid in another format.
+    let puzzle = fixtures.join("cli/4-challenge.txt").display().to_string();
+    let expected = fixtures
+        .join("challenge-solution.sdm")
+        .display()
+        .to_string();
+    let output = run(&["compare", &puzzle, &expected], "");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("1 compared: 1 passed, 0 failed\n"));
+}
+
+#[test]
+fn test_progress() {
+    // stderr isn't a terminal here, so the spinner stays out of the output entirely.
+    let files = fixtures();
+    let challenge = files[3].as_str();
+    let plain = run(&[challenge], "");
+    let output = run(&["--progress", challenge], "");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, plain.stdout);
+    assert_eq!(output.stderr, b"");
+}
+
+#[test]
+fn test_transform() {
+    let files = fixtures();
+    let grid = |line: &str| {
+        let rows: Vec<&str> = (0..9).map(|r| &line[r * 9..r * 9 + 9]).collect();
+        return rows.join("\n") + "\n";
+    };
+    let flags = [
+        "transform",
+        "--shuffle",
+        "--rotate",
+        "90",
+        "--mirror",
+        "horizontal",
+        "--relabel",
+        "random",
+        "--seed",
+        "5",
+    ];
+    let mut args = flags.to_vec();
+    args.push(&files[0]);
+    let puzzle = run(&args, "");
+    assert!(puzzle.status.success());
+    assert_eq!(run(&args, "").stdout, puzzle.stdout);
+    let clues = |text: &[u8]| text.iter().filter(|b| b.is_ascii_digit()).count();
+    let original = fs::read(&files[0]).unwrap();
+    assert_eq!(clues(&puzzle.stdout), clues(&original));
+    assert_ne!(puzzle.stdout, original);
+
+    // Solving the transformed puzzle gives the transformed solution.
+    let solved = run(&["-"], &String::from_utf8(puzzle.stdout).unwrap());
+    let mut args = flags.to_vec();
+    args.push("-");
+    let transformed = run(&args, &grid(MODERATE));
+    assert!(solved.status.success());
+    assert_eq!(solved.stdout, transformed.stdout);
+
+    // Explicit transforms need no seed. Relabelling with 912345678 writes 1 as 9 and
+    // every other digit as the one below it.
+    let output = run(
+        &["transform", "--relabel", "912345678", "-"],
+        &grid(MODERATE),
+    );
+    let relabelled: String = MODERATE
+        .bytes()
+        .map(|b| if b == b'1' { '9' } else { (b - 1) as char })
+        .collect();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), grid(&relabelled));
+}
+
+fn text(output: &Output) -> String {
+    return String::from_utf8(output.stdout.clone()).unwrap();
+}
+
+#[test]
+fn test_check() {
+    let files = fixtures();
+    let check = |args: &[&str], stdin: &str| {
+        let output = run(args, stdin);
+        return (output.status.code(), text(&output));
+    };
+    let grid: String = (0..9)
+        .map(|r| format!("{}\n", &MODERATE[r * 9..r * 9 + 9]))
+        .collect();
+    assert_eq!(
+        check(&["check", "-"], &grid),
+        (Some(0), "complete and valid\n".to_string())
+    );
+    // Swapping the first two cells breaks both columns.
+    let swapped = format!("{}{}{}", &grid[1..2], &grid[..1], &grid[2..]);
+    assert_eq!(
+        check(&["check", "-"], &swapped),
+        (
+            Some(2),
+            "complete but invalid\n  \
+             r1c1 and r6c1 both hold 6 in C1\n  \
+             r1c2 and r4c2 both hold 2 in C2\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        check(&["check", &files[0]], ""),
+        (Some(3), "incomplete but consistent\n".to_string())
+    );
+    assert_eq!(
+        check(&["check", "--solvable", &files[0]], ""),
+        (
+            Some(0),
+            "incomplete but consistent, with one completion\n".to_string()
+        )
+    );
+    let empty = "---------\n".repeat(9);
+    assert_eq!(
+        check(&["check", "--solvable", "-"], &empty),
+        (
+            Some(3),
+            "incomplete but consistent, with several completions\n".to_string()
+        )
+    );
+    // The broken fixture's extra 9 shares a row with another 9.
+    let (status, out) = check(&["check", &files[2]], "");
+    assert_eq!(status, Some(2));
+    assert!(out.starts_with("incomplete and contradictory\n  r1c1 and r1c8 both hold 9 in R1\n"));
+    // Consistent, but nothing fits in the top right corner.
+    let stuck = "12345678-\n--------9\n".to_string() + &"---------\n".repeat(7);
+    assert_eq!(
+        check(&["check", "--solvable", "-"], &stuck),
+        (
+            Some(2),
+            "incomplete and consistent, but it can't be completed\n".to_string()
+        )
+    );
+    // Unreadable, with the same status solve --status-only gives it.
+    for args in [&["check", "-"][..], &["solve", "--status-only", "-"]] {
+        let output = run(args, "not a sudoku\n");
+        assert_eq!(output.status.code(), Some(4), "{:?}", args);
+        assert_eq!(text(&output), "");
+    }
+    assert_eq!(
+        check(&["check", "no-such-file.txt"], ""),
+        (Some(4), String::new())
+    );
+}
+
+#[test]
+fn test_generate() {
+    let output = run(&["generate", "--seed", "1", "--symmetry", "rotational"], "");
+    assert!(output.status.success());
+    let mut options = GeneratorOptions::default();
+    options.symmetry = Symmetry::Rotational;
+    let puzzle = Sudoku::generate_puzzle(1, options);
+    assert_eq!(text(&output).parse::<Sudoku>(), Ok(puzzle));
+
+    let output = run(
+        &["generate", "--daily", "2024-06-01", "--difficulty", "easy"],
+        "",
+    );
+    assert_eq!(
+        text(&output).parse::<Sudoku>(),
+        Ok(generate_daily("2024-06-01", Difficulty::Easy).unwrap())
+    );
+
+    let output = run(&["generate", "--seed", "1", "--bogus"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: sudokusolver generate"));
+}
+
+#[test]
+fn test_rate() {
+    let files = fixtures();
+    let output = run(&["rate", &files[0], &files[3]], "");
+    assert!(output.status.success());
+    assert_eq!(
+        relative(&output.stdout, &files),
+        "1-moderate.txt: easy, score 68, hardest hidden single, 0 guesses\n\
+         4-challenge.txt: expert, score 1235, hardest hidden single, 12 guesses\n"
+    );
+    let output = run(&["rate", &files[1]], "");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_convert() {
+    let files = fixtures();
+    let sdm = run(&["convert", "--to", "sdm", &files[0]], "");
+    assert!(sdm.status.success());
+    let line = text(&sdm);
+    assert_eq!(line.len(), 82);
+
+    // Two puzzles on two lines go to a per-line format, but not to a grid.
+    let two = format!("{}{}", line, line.replace('0', "."));
+    let output = run(&["convert", "--from", "line", "--to", "line", "-"], &two);
+    assert_eq!(text(&output), line.replace('0', ".").repeat(2));
+    let output = run(&["convert", "--from", "sdm", "--to", "grid", "-"], &two);
+    assert!(!output.status.success());
+
+    let moderate = fs::read_to_string(&files[0]).unwrap();
+    for format in ["grid", "csv", "sdk"] {
+        let output = run(&["convert", "--from", "sdm", "--to", format, "-"], &line);
+        assert!(output.status.success(), "{}", format);
+        let back = run(
+            &["convert", "--from", format, "--to", "grid", "-"],
+            &text(&output),
+        );
+        assert_eq!(text(&back).trim_end(), moderate.trim_end(), "{}", format);
+    }
+}
+
+#[test]
+fn test_hint() {
+    let files = fixtures();
+    let output = run(&["hint", &files[0]], "");
+    assert!(output.status.success());
+    let moderate: Sudoku = fs::read_to_string(&files[0]).unwrap().parse().unwrap();
+    let expected: String = moderate
+        .forced_placements()
+        .iter()
+        .map(|(r, c, v, t)| format!("r{}c{} = {} ({})\n", r, c, v, t))
+        .collect();
+    assert_eq!(text(&output), expected);
+    assert!(expected.contains("r7c8 = 2 (naked single)\n"));
+}
+
+#[test]
+fn test_mask() {
+    let pattern: String = (0..9)
+        .map(|r| {
+            let row: String = (0..9).map(|c| if r == c { '.' } else { '#' }).collect();
+            return row + "\n";
+        })
+        .collect();
+    let output = run(
+        &["mask", "--pattern", "-", "--seed", "3", "--tries", "1"],
+        &pattern,
+    );
+    assert!(output.status.success());
+    let parsed = parse_clue_pattern(&pattern).unwrap();
+    assert_eq!(
+        text(&output).parse::<Sudoku>(),
+        Ok(Sudoku::generate_for_pattern(3, &parsed, 1).unwrap())
+    );
+}
+
+// edit runs the same commands as interactive.
+#[test]
+fn test_edit() {
+    let files = fixtures();
+    let output = run(
+        &["edit", &files[1]],
+        "set r9c5 6\nset r9c5 7\nclear r9c5\nshow\nquit\n",
+    );
+    assert!(output.status.success());
+    let lines: Vec<String> = text(&output).lines().map(String::from).collect();
+    assert_eq!(
+        lines[..4],
+        [
+            "2 solutions",
+            "unique solution",
+            "cell (9, 5) is already filled with 6",
+            "2 solutions"
+        ]
+    );
+    let puzzle: Sudoku = fs::read_to_string(&files[1]).unwrap().parse().unwrap();
+    assert_eq!(lines[4..].join("\n"), format!("{:#}", puzzle));
+}
+
+#[test]
+fn test_interactive() {
+    let files = fixtures();
+    let script = "set r9c5 6\nhint\nset r0c1 3\nset r1c1 9\nset r1c1 x\nclear r1c1\n\
+                  clear r9c5\nundo\nsolve\nundo\nundo\nsolve\nreset\nload nothere.txt\n\
+                  frob\nset r1c1\nquit\nshow\n";
+    let output = run(&["interactive", &files[1]], script);
+    assert!(output.status.success());
+    let expected = "\
+2 solutions
+unique solution
+r1c2 = 6 (hidden single)
+Expected a cell like r4c7, not \"r0c1\".
+9 cannot go in cell (1, 1)
+Expected a value from 1 to 9, not \"x\".
+r1c1 is already blank.
+2 solutions
+unique solution
++-------+-------+-------+
+| 2 6 7 | 1 8 3 | 4 9 5 |
+| 5 8 3 | 4 9 6 | 7 1 2 |
+| 1 9 4 | 2 5 7 | 6 8 3 |
++-------+-------+-------+
+| 9 2 1 | 3 7 8 | 5 4 6 |
+| 4 3 5 | 6 2 1 | 9 7 8 |
+| 6 7 8 | 5 4 9 | 2 3 1 |
++-------+-------+-------+
+| 8 4 6 | 9 3 5 | 1 2 7 |
+| 3 5 2 | 7 1 4 | 8 6 9 |
+| 7 1 9 | 8 6 2 | 3 5 4 |
++-------+-------+-------+
+unique solution
+2 solutions
+The puzzle has more than one solution.
+2 solutions
+nothere.txt: No such file or directory (os error 2)
+Unknown command \"frob\"; help lists the commands.
+Usage: set r4c7 3
+";
+    assert_eq!(text(&output), expected);
+
+    let output = run(
+        &["interactive", &files[0]],
+        "solve\nclear r1c1\nclear r1c2\ncandidates\n",
+    );
+    let stdout = text(&output);
+    let last: Vec<&str> = stdout.lines().rev().take(2).collect();
+    assert_eq!(last, ["r1c2: 6", "r1c1: 2"]);
+}
+
+// The fish script lists every option of every subcommand from the parser's table, so
+// it doubles as a way to check each subcommand's help mentions all of them.
+#[test]
+fn test_completions_and_help() {
+    let output = run(&["completions", "fish"], "");
+    assert!(output.status.success());
+    let fish = text(&output);
+    let mut flags: Vec<(String, String)> = Vec::new();
+    for line in fish
+        .lines()
+        .filter(|l| l.contains(" -l ") && !l.contains(" -l help "))
+    {
+        let condition = line.split('\'').nth(1).unwrap();
+        let sub = condition
+            .strip_prefix("__fish_seen_subcommand_from ")
+            .unwrap_or("solve");
+        let name = line
+            .split(" -l ")
+            .nth(1)
+            .unwrap()
+            .split(' ')
+            .next()
+            .unwrap();
+        flags.push((sub.to_string(), format!("--{}", name)));
+    }
+    assert!(flags.contains(&("solve".to_string(), "--max-guesses".to_string())));
+    assert!(flags.contains(&("transform".to_string(), "--relabel".to_string())));
+    for (sub, flag) in &flags {
+        let help = text(&run(&["help", sub], ""));
+        assert!(help.contains(flag.as_str()), "help {} lacks {}", sub, flag);
+        // And the parser takes it, rather than calling it unknown.
+        let output = run(&[sub, flag, "1", "nothere.txt"], "");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!stderr.contains("Unknown option"), "{} {}", sub, stderr);
+    }
+
+    let bash = text(&run(&["completions", "bash"], ""));
+    assert!(bash.contains("complete -o filenames -F _sudokusolver sudokusolver"));
+    assert!(bash.contains("--symmetry) COMPREPLY=($(compgen -W \"none rotational mirror\""));
+    let zsh = text(&run(&["completions", "zsh"], ""));
+    assert!(zsh.starts_with("#compdef sudokusolver\n"));
+    assert!(zsh
+        .contains("'--rotate[turn it clockwise by 90, 180, or 270 degrees]:DEGREES:(90 180 270)'"));
+
+    let output = run(&["completions", "tcsh"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Unknown shell \"tcsh\"."));
+}
+
+// --watch reports the file as it starts, and again after each edit, carrying on past
+// one that doesn't parse.
+#[test]
+fn test_watch() {
+    let files = fixtures();
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("watch.txt");
+    fs::copy(&files[0], &path).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudokusolver"))
+        .args(["--watch", "--interval", "20", path.to_str().unwrap()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let pause = || std::thread::sleep(std::time::Duration::from_millis(400));
+    pause();
+    fs::write(&path, "not a puzzle\n").unwrap();
+    pause();
+    fs::copy(&files[1], &path).unwrap();
+    pause();
+    child.kill().unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reports: Vec<&str> = stdout.split("\n\n").collect();
+    assert_eq!(reports.len(), 3, "{}", stdout);
+    assert!(reports[0].ends_with("(watching; Ctrl-C stops)\nunique solution"));
+    assert_eq!(reports[1].lines().count(), 2);
+    let ambiguous: Vec<&str> = reports[2].lines().collect();
+    assert!(ambiguous[1].ends_with("solutions"));
+    // Singles don't finish it, so its candidates follow: nine rows and two separators.
+    // The moderate puzzle needed nothing more, so showed none.
+    assert_eq!(ambiguous.len(), 2 + 11);
+    assert!(ambiguous[5].contains('+'));
+}
@@ -0,0 +1,6 @@
+// The solver's internals are not part of the API.
+use sudokusolver::Layout;
+use sudokusolver::unit::Unit;
+use sudokusolver::Square;
+
+fn main() {}
@@ -0,0 +1,19 @@
+// Errors and config structs can grow without breaking downstream code, so they can't be
+// matched exhaustively or built field by field.
+use sudokusolver::{GridError, SolverConfig};
+
+fn describe(e: GridError) -> &'static str {
+    match e {
+        GridError::RowOutOfRange(_) => "row",
+        GridError::ColumnOutOfRange(_) => "column",
+        GridError::ValueOutOfRange(_) => "value",
+        GridError::UnitOutOfRange(_) => "unit",
+        GridError::RepeatedCell(_) => "repeated",
+        GridError::BoxShapeOutOfRange(..) => "box shape",
+    }
+}
+
+fn main() {
+    let _ = SolverConfig { guess_budget: 10 };
+    let _ = describe(GridError::ValueOutOfRange(0));
+}
@@ -0,0 +1,53 @@
+// Solver events seen through a capturing logger. The logger is global, so this file
+// holds a single test.
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use sudokusolver::{Sudoku, UnsolvedSudoku};
+
+struct Capture {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        return true;
+    }
+
+    fn log(&self, record: &Record) {
+        let mut records = self.records.lock().unwrap();
+        records.push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture {
+    records: Mutex::new(Vec::new()),
+};
+
+fn has(records: &[(Level, String)], level: Level, prefix: &str) -> bool {
+    return records
+        .iter()
+        .any(|(l, msg)| *l == level && msg.starts_with(prefix));
+}
+
+#[test]
+fn test_events() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let s = include_str!("../../challenge.txt")
+        .parse::<Sudoku>()
+        .unwrap();
+    UnsolvedSudoku::from(s).dynamic_solve().unwrap();
+
+    let records = CAPTURE.records.lock().unwrap();
+    assert!(has(&records, Level::Debug, "parsed puzzle with 23 clues"));
+    assert!(has(&records, Level::Trace, "propagation settled"));
+    assert!(has(&records, Level::Trace, "guess "));
+    assert!(has(&records, Level::Trace, "backtrack from "));
+    assert!(has(&records, Level::Debug, "solution found at depth "));
+}
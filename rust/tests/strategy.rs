@@ -0,0 +1,125 @@
+// A strategy written purely against the public candidate API, as a downstream crate would.
+
+use sudokusolver::{
+    CandidateSet, Coord, Elimination, Solver, SolverConfig, Strategy, Sudoku, Technique, Unit,
+    UnsolvedSudoku,
+};
+
+// Naked pairs: if two cells in a unit have the same two candidates, those two values
+// must go in those two cells, so they can be removed from the rest of the unit.
+fn naked_pairs(u: &mut UnsolvedSudoku) -> usize {
+    let mut removed = 0;
+    let units: Vec<Unit> = u.units().collect();
+    for unit in units {
+        let unit = unit.coords();
+        let pairs: Vec<(Coord, CandidateSet)> = unit
+            .iter()
+            .filter_map(|&c| u.candidates(c).map(|ps| (c, ps)))
+            .filter(|(_, ps)| ps.len() == 2)
+            .collect();
+        for (i, &(a, ps)) in pairs.iter().enumerate() {
+            for &(b, other) in &pairs[i + 1..] {
+                if ps != other {
+                    continue;
+                }
+                for &c in &unit {
+                    if c == a || c == b {
+                        continue;
+                    }
+                    for v in ps.iter() {
+                        if u.eliminate(c, v).unwrap() != Elimination::NotCandidate {
+                            removed += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    return removed;
+}
+
+// naked_pairs as a Strategy, for a Solver to run.
+struct NakedPairs;
+
+impl Strategy for NakedPairs {
+    fn apply(&self, grid: &mut UnsolvedSudoku) -> usize {
+        return naked_pairs(grid);
+    }
+
+    fn name(&self) -> &str {
+        return "naked pair";
+    }
+}
+
+fn coord(r: u8, c: u8) -> Coord {
+    return Coord::new(r, c).unwrap();
+}
+
+#[test]
+fn test_naked_pairs() {
+    let mut u = UnsolvedSudoku::empty();
+    for v in 3..10 {
+        u.eliminate(coord(1, 1), v).unwrap();
+        u.eliminate(coord(1, 2), v).unwrap();
+    }
+
+    // Both cells share row 1 and square 1: 7 more cells in the row, and 6 in the square
+    // outside it, each lose two candidates.
+    assert_eq!(naked_pairs(&mut u), 26);
+    assert_eq!(naked_pairs(&mut u), 0);
+    for c in [coord(1, 9), coord(3, 3)] {
+        let ps = u.candidates(c).unwrap();
+        assert!(!ps.contains(1) && !ps.contains(2));
+        assert_eq!(ps.len(), 7);
+    }
+    assert_eq!(u.candidates(coord(4, 1)), Some(CandidateSet::full()));
+    assert_eq!(u.fixed_value(coord(1, 1)), None);
+
+    // The crate's own naked pairs find the same.
+    let mut builtin = UnsolvedSudoku::empty();
+    for v in 3..10 {
+        builtin.eliminate(coord(1, 1), v).unwrap();
+        builtin.eliminate(coord(1, 2), v).unwrap();
+    }
+    assert_eq!(builtin.eliminate_naked_pairs(), 26);
+    assert_eq!(builtin, u);
+}
+
+#[test]
+fn test_naked_pairs_keeps_solution() {
+    let s = challenge();
+    let expected = UnsolvedSudoku::from(s).dynamic_solve().unwrap();
+
+    let mut u = UnsolvedSudoku::from(s);
+    while naked_pairs(&mut u) > 0 {}
+    assert_eq!(u.dynamic_solve(), Ok(expected));
+}
+
+fn challenge() -> Sudoku {
+    return include_str!("../../challenge.txt")
+        .parse::<Sudoku>()
+        .unwrap();
+}
+
+#[test]
+fn test_custom_strategy() {
+    let s = challenge();
+    let plain = Solver::new(s.into()).solve();
+    let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(NakedPairs)];
+    let mut solver = Solver::with_strategies(s.into(), SolverConfig::default(), strategies);
+    assert_eq!(solver.strategies()[0].name(), "naked pair");
+    let report = solver.solve();
+    assert_eq!(report.result, plain.result);
+    assert!(report.guesses < plain.guesses, "{}", report.guesses);
+
+    // Alongside the built-in strategies, every solution of an empty grid is still found.
+    let strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(Technique::XWing),
+        Box::new(NakedPairs),
+        Box::new(Technique::XyzWing),
+    ];
+    let mut solver =
+        Solver::with_strategies(UnsolvedSudoku::empty(), SolverConfig::default(), strategies);
+    assert!(solver.solve().solved());
+    assert!(!solver.solve().unique());
+}